@@ -3,6 +3,7 @@
 #include "input/shortcut_map.h"
 #include <QKeyEvent>
 #include <QPainter>
+#include <QPainterPath>
 #include <QMessageBox>
 #include "ffi/craftcad_ffi.h"
 #include <QJsonDocument>
@@ -90,6 +91,32 @@ void CanvasWidget::paintEvent(QPaintEvent*) {
                 double a1=e.geom.value("end_angle").toDouble()*180.0/M_PI;
                 QRectF rr(camera_.worldToScreen({c.value("x").toDouble(), c.value("y").toDouble()})-QPointF(r*camera_.zoom,r*camera_.zoom), QSizeF(2*r*camera_.zoom,2*r*camera_.zoom));
                 rp.drawArc(rr, int(-a0*16), int(-(a1-a0)*16));
+            } else if (t == "Ellipse") {
+                auto c=e.geom.value("c").toObject();
+                double rx=e.geom.value("rx").toDouble(), ry=e.geom.value("ry").toDouble();
+                rp.save();
+                rp.translate(camera_.worldToScreen({c.value("x").toDouble(), c.value("y").toDouble()}));
+                rp.rotate(-e.geom.value("rot").toDouble()*180.0/M_PI);
+                rp.drawEllipse(QPointF(0,0), rx*camera_.zoom, ry*camera_.zoom);
+                rp.restore();
+            } else if (t == "EllipticalArc") {
+                auto c=e.geom.value("c").toObject();
+                double cx=c.value("x").toDouble(), cy=c.value("y").toDouble();
+                double rx=e.geom.value("rx").toDouble(), ry=e.geom.value("ry").toDouble(), rot=e.geom.value("rot").toDouble();
+                double a0=e.geom.value("start_angle").toDouble(), a1=e.geom.value("end_angle").toDouble();
+                double sweep=std::fmod(a1-a0+4*M_PI, 2*M_PI);
+                if (!e.geom.value("ccw").toBool()) sweep-=2*M_PI;
+                QPolygonF poly;
+                for (int i=0;i<=64;++i){
+                    double u=a0+sweep*i/64.0, lx=rx*std::cos(u), ly=ry*std::sin(u);
+                    poly << camera_.worldToScreen({cx+lx*std::cos(rot)-ly*std::sin(rot), cy+lx*std::sin(rot)+ly*std::cos(rot)});
+                }
+                rp.drawPolyline(poly);
+            } else if (t == "CubicBezier") {
+                auto pt=[&](const char* k){ auto o=e.geom.value(k).toObject(); return camera_.worldToScreen({o.value("x").toDouble(), o.value("y").toDouble()}); };
+                QPainterPath path(pt("a"));
+                path.cubicTo(pt("c1"), pt("c2"), pt("b"));
+                rp.drawPath(path);
             }
         }
         cachedRevision_ = store_->revision();
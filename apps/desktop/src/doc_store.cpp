@@ -25,7 +25,10 @@ DocStore::DocStore() {
     recoveryPath_ = QDir(stateDir).filePath("recovery_autosave.diycad.json");
 }
 
-DocStore::~DocStore() { craftcad_history_free(historyHandle_); }
+DocStore::~DocStore() {
+    craftcad_history_free(historyHandle_);
+    if (snapIndexHandle_) craftcad_snap_index_free(snapIndexHandle_);
+}
 
 bool DocStore::loadDiycad(const QString& path, QString* errorMsg) {
     QByteArray p = path.toUtf8();
@@ -82,6 +85,21 @@ void DocStore::rebuildCache() {
             auto c = r.geom.value("c").toObject();
             const double rr = r.geom.value("r").toDouble();
             r.worldAabb = QRectF(c.value("x").toDouble() - rr, c.value("y").toDouble() - rr, rr * 2.0, rr * 2.0);
+        } else if (t == "Ellipse" || t == "EllipticalArc") {
+            auto c = r.geom.value("c").toObject();
+            const double rr = std::max(r.geom.value("rx").toDouble(), r.geom.value("ry").toDouble());
+            r.worldAabb = QRectF(c.value("x").toDouble() - rr, c.value("y").toDouble() - rr, rr * 2.0, rr * 2.0);
+        } else if (t == "CubicBezier") {
+            // The control polygon bounds the curve.
+            double minx = 1e300, miny = 1e300, maxx = -1e300, maxy = -1e300;
+            for (const char* k : {"a", "c1", "c2", "b"}) {
+                const auto po = r.geom.value(k).toObject();
+                minx = std::min(minx, po.value("x").toDouble());
+                miny = std::min(miny, po.value("y").toDouble());
+                maxx = std::max(maxx, po.value("x").toDouble());
+                maxy = std::max(maxy, po.value("y").toDouble());
+            }
+            r.worldAabb = QRectF(QPointF(minx, miny), QPointF(maxx, maxy)).normalized();
         } else if (t == "Polyline") {
             auto pts = r.geom.value("pts").toArray();
             if (!pts.isEmpty()) {
@@ -110,6 +128,15 @@ void DocStore::rebuildCache() {
         }
         ++idx;
     }
+    rebuildSnapIndex();
+}
+
+void DocStore::rebuildSnapIndex() {
+    if (snapIndexHandle_) craftcad_snap_index_free(snapIndexHandle_);
+    snapIndexHandle_ = 0;
+    QByteArray d = documentJson_.toUtf8(), e = epsPolicyJson_.toUtf8();
+    auto root = QJsonDocument::fromJson(take(craftcad_snap_index_new(d.constData(), e.constData(), gridCellSize_)).toUtf8()).object();
+    if (root.value("ok").toBool()) snapIndexHandle_ = static_cast<uint64_t>(root.value("data").toObject().value("handle").toDouble());
 }
 
 QString DocStore::callDocUpdate(char* ptr, QString* reason, bool* ok) {
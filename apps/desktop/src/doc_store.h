@@ -30,6 +30,8 @@ public:
     bool redo(QString* reason);
 
     uint64_t historyHandle() const { return historyHandle_; }
+    // core snap index of the current revision; 0 when the document failed to index
+    uint64_t snapIndexHandle() const { return snapIndexHandle_; }
 
     // crash-safe autosave/recovery
     bool autosaveNow(QString* reason = nullptr) const;
@@ -45,10 +47,12 @@ public:
 
 private:
     void rebuildCache();
+    void rebuildSnapIndex();
     QString callDocUpdate(char* ptr, QString* reason, bool* ok);
     bool writeAtomic(const QString& path, const QByteArray& bytes, QString* reason) const;
 
     uint64_t historyHandle_{0};
+    uint64_t snapIndexHandle_{0};
     QString documentJson_;
     QVector<RenderEntity> entities_;
     QString epsPolicyJson_;
@@ -18,6 +18,10 @@ char *craftcad_extract_faces(const char *doc_json, const char *eps_json);
 char *craftcad_geom_project_point(const char *geom_json, const char *point_json, const char *eps_json);
 char *craftcad_geom_intersect(const char *a_json, const char *b_json, const char *eps_json);
 char *craftcad_geom_split_at_t(const char *geom_json, double t, const char *eps_json);
+char *craftcad_snap_candidates(const char *doc_json, const char *query_json, const char *eps_json);
+char *craftcad_snap_index_new(const char *doc_json, const char *eps_json, double cell);
+char *craftcad_snap_index_query(uint64_t h, const char *query_json, const char *eps_json);
+void craftcad_snap_index_free(uint64_t h);
 void craftcad_free_string(char *ptr);
 char *craftcad_i18n_resolve_message(const char *user_msg_key_utf8, const char *params_json, const char *locale_utf8);
 
@@ -79,13 +83,19 @@ char *craftcad_history_apply_create_arc(uint64_t h, const char *doc_json, const
 char *craftcad_history_apply_create_polyline(uint64_t h, const char *doc_json, const char *layer_id_uuid, const char *polyline_params_json, const char *eps_json);
 char *craftcad_history_apply_fillet(uint64_t h, const char *doc_json, const char *fillet_json, const char *eps_json);
 char *craftcad_history_apply_chamfer(uint64_t h, const char *doc_json, const char *chamfer_json, const char *eps_json);
+char *craftcad_history_apply_fillet_polyline(uint64_t h, const char *doc_json, const char *fillet_json, const char *eps_json);
+char *craftcad_history_apply_chamfer_polyline(uint64_t h, const char *doc_json, const char *chamfer_json, const char *eps_json);
 char *craftcad_history_apply_mirror(uint64_t h, const char *doc_json, const char *mirror_json, const char *eps_json);
 char *craftcad_history_apply_pattern(uint64_t h, const char *doc_json, const char *pattern_json, const char *eps_json);
+char *craftcad_history_apply_boolean(uint64_t h, const char *doc_json, const char *boolean_json, const char *eps_json);
 char *craftcad_geom_candidates_for_operation(const char *op_json);
 char *craftcad_history_apply_transform_selection(uint64_t h, const char *doc_json, const char *selection_json, const char *transform_json, const char *eps_json);
 char *craftcad_history_apply_offset_entity(uint64_t h, const char *doc_json, const char *entity_id_uuid, double dist, const char *eps_json);
+char *craftcad_history_apply_offset_entity_with_join(uint64_t h, const char *doc_json, const char *entity_id_uuid, double dist, const char *join_json, const char *eps_json);
 char *craftcad_history_apply_trim_entity(uint64_t h, const char *doc_json, const char *target_id_uuid, const char *cutter_id_uuid, const char *pick_point_json, const char *eps_json);
 char *craftcad_history_apply_trim_entity_with_candidate_index(uint64_t h, const char *doc_json, const char *target_id_uuid, const char *cutter_id_uuid, const char *pick_point_json, const char *eps_json, int candidate_index);
+char *craftcad_history_apply_extend_entity(uint64_t h, const char *doc_json, const char *target_id_uuid, const char *boundary_json, const char *pick_point_json, const char *eps_json);
+char *craftcad_history_apply_extend_entity_with_candidate_index(uint64_t h, const char *doc_json, const char *target_id_uuid, const char *boundary_json, const char *pick_point_json, const char *eps_json, int candidate_index);
 char *craftcad_history_apply_create_part(uint64_t h, const char *doc_json, const char *part_json);
 char *craftcad_history_apply_create_part_from_face(uint64_t h, const char *doc_json, const char *face_json, const char *part_props_json);
 char *craftcad_history_apply_update_part(uint64_t h, const char *doc_json, const char *part_id_uuid, const char *patch_json);
@@ -94,6 +104,22 @@ char *craftcad_history_undo(uint64_t h, const char *doc_json);
 char *craftcad_history_redo(uint64_t h, const char *doc_json);
 char *craftcad_history_begin_group(uint64_t h, const char *name_utf8);
 char *craftcad_history_end_group(uint64_t h);
+char *craftcad_history_entries(uint64_t h);
+char *craftcad_history_nodes(uint64_t h);
+char *craftcad_history_jump(uint64_t h, const char *doc_json, uint64_t node);
+char *craftcad_history_compare(uint64_t h, const char *doc_json, uint64_t node_a, uint64_t node_b);
+char *craftcad_clipboard_copy(const char *doc_json, const char *selection_json);
+char *craftcad_history_apply_cut(uint64_t h, const char *doc_json, const char *selection_json);
+char *craftcad_history_apply_paste(uint64_t h, const char *doc_json, const char *payload_json, const char *offset_json);
+char *craftcad_block_instance_entities(const char *doc_json, const char *instance_id_uuid);
+char *craftcad_block_instance_contents(const char *doc_json, const char *instance_id_uuid);
+char *craftcad_history_apply_define_block(uint64_t h, const char *doc_json, const char *input_json);
+char *craftcad_history_apply_insert_block(uint64_t h, const char *doc_json, const char *input_json);
+char *craftcad_history_apply_explode_block(uint64_t h, const char *doc_json, const char *instance_id_uuid);
+char *craftcad_history_apply_edit_block(uint64_t h, const char *doc_json, const char *input_json);
+char *craftcad_history_apply_layer_edit(uint64_t h, const char *doc_json, const char *edit_json);
+char *craftcad_load_diycad_with_history_json(const char *path_utf8);
+char *craftcad_save_diycad_with_history_json(const char *path_utf8, const char *manifest_json, const char *doc_json, uint64_t h);
 char *craftcad_history_apply_run_nesting(uint64_t h, const char *doc_json, const char *job_id_uuid, const char *eps_json, const char *limits_json);
 char *craftcad_history_apply_edit_placement(uint64_t h, const char *doc_json, const char *job_id_uuid, const char *part_id_uuid, int sheet_index, const char *new_pose_json);
 
@@ -6,36 +6,27 @@
 
 static QString take(char* ptr){ if(!ptr) return {}; QString s=QString::fromUtf8(ptr); craftcad_free_string(ptr); return s; }
 
-SnapResult computeSnap(const DocStore& store, const WVec2& pointerWorld, const std::optional<WVec2>& lineStart) {
+// Screen-space search radius around the pointer; divided by the zoom for world units.
+static constexpr double kSnapRadiusPx = 10.0;
+
+static SnapCandidate toCandidate(const QJsonObject& o, const WVec2& pointerWorld) {
+    WVec2 p{o.value("x").toDouble(), o.value("y").toDouble()};
+    double dx = p.x - pointerWorld.x, dy = p.y - pointerWorld.y;
+    // Core scores are higher-is-better; ranks are lower-is-better.
+    return {p, o.value("kind").toString(), -o.value("score").toInt(), std::sqrt(dx*dx+dy*dy)};
+}
+
+SnapResult computeSnap(const DocStore& store, const Camera& camera, const WVec2& pointerWorld, const std::optional<WVec2>& lineStart) {
     SnapResult out;
-    auto add = [&](WVec2 p, const QString& label, int rank){
-        double dx = p.x - pointerWorld.x, dy = p.y - pointerWorld.y;
-        out.all.push_back({p,label,rank,std::sqrt(dx*dx+dy*dy)});
-    };
-    for (const auto& e : store.entities()) {
-        auto g = e.geom;
-        if (g.value("type").toString()=="Line") {
-            auto a=g.value("a").toObject(); auto b=g.value("b").toObject();
-            add({a.value("x").toDouble(), a.value("y").toDouble()}, "Endpoint", 0);
-            add({b.value("x").toDouble(), b.value("y").toDouble()}, "Endpoint", 0);
-            add({(a.value("x").toDouble()+b.value("x").toDouble())*0.5, (a.value("y").toDouble()+b.value("y").toDouble())*0.5}, "Midpoint", 2);
-        }
-    }
-    if (lineStart) {
-        QJsonObject active{{"type","Line"},{"a",QJsonObject{{"x",lineStart->x},{"y",lineStart->y}}},{"b",QJsonObject{{"x",pointerWorld.x},{"y",pointerWorld.y}}}};
-        QString ajson = QString::fromUtf8(QJsonDocument(active).toJson(QJsonDocument::Compact));
-        for (const auto& e : store.entities()) {
-            QString bjson = QString::fromUtf8(QJsonDocument(e.geom).toJson(QJsonDocument::Compact));
-            QByteArray ab=ajson.toUtf8(), bb=bjson.toUtf8(), eb=store.epsPolicyJson().toUtf8();
-            auto root = QJsonDocument::fromJson(take(craftcad_geom_intersect(ab.constData(), bb.constData(), eb.constData())).toUtf8()).object();
-            if (!root.value("ok").toBool()) continue;
-            for (auto p : root.value("data").toObject().value("points").toArray()) {
-                auto po=p.toObject(); add(WVec2{po.value("x").toDouble(), po.value("y").toDouble()}, "Intersection", 1);
-            }
-        }
-    }
-    for (const auto& c: out.all) {
-        if (!out.best || c.rank < out.best->rank || (c.rank==out.best->rank && c.dist < out.best->dist)) out.best = c;
-    }
+    if (!store.snapIndexHandle()) return out;
+    QJsonObject query{{"cursor",QJsonObject{{"x",pointerWorld.x},{"y",pointerWorld.y}}},{"radius",kSnapRadiusPx / camera.zoom}};
+    if (lineStart) query.insert("from", QJsonObject{{"x",lineStart->x},{"y",lineStart->y}});
+    QByteArray qb=QJsonDocument(query).toJson(QJsonDocument::Compact), eb=store.epsPolicyJson().toUtf8();
+    auto root = QJsonDocument::fromJson(take(craftcad_snap_index_query(store.snapIndexHandle(), qb.constData(), eb.constData())).toUtf8()).object();
+    if (!root.value("ok").toBool()) return out;
+    auto data = root.value("data").toObject();
+    // Candidates arrive in the core's deterministic resolve order.
+    for (auto c : data.value("candidates").toArray()) out.all.push_back(toCandidate(c.toObject(), pointerWorld));
+    if (data.value("snapped").isObject()) out.best = toCandidate(data.value("snapped").toObject(), pointerWorld);
     return out;
 }
@@ -6,4 +6,6 @@
 struct SnapCandidate { WVec2 point; QString label; int rank; double dist; };
 struct SnapResult { std::optional<SnapCandidate> best; QVector<SnapCandidate> all; };
 
-SnapResult computeSnap(const DocStore& store, const WVec2& pointerWorld, const std::optional<WVec2>& lineStart);
+// Queries the store's snap index within a fixed pixel tolerance around the pointer.
+// With lineStart set, intersections of the line being drawn are offered too.
+SnapResult computeSnap(const DocStore& store, const Camera& camera, const WVec2& pointerWorld, const std::optional<WVec2>& lineStart);
@@ -10,8 +10,8 @@
 #endif
 static QString take(char* ptr){ if(!ptr) return {}; QString s=QString::fromUtf8(ptr); craftcad_free_string(ptr); return s; }
 ArcTool::ArcTool(DocStore* store, Camera* camera):store_(store),camera_(camera){}
-void ArcTool::onPointerDown(const QPointF& p){ WVec2 w=camera_->screenToWorld(p); snap_=computeSnap(*store_,w,std::nullopt); WVec2 pt=snap_.best?snap_.best->point:w; if(!c_) c_=pt; else if(!s_) s_=pt; else e_=pt; }
-void ArcTool::onPointerMove(const QPointF& p){ if(!c_||!s_) return; WVec2 w=camera_->screenToWorld(p); snap_=computeSnap(*store_,w,c_); e_=snap_.best?snap_.best->point:w; }
+void ArcTool::onPointerDown(const QPointF& p){ WVec2 w=camera_->screenToWorld(p); snap_=computeSnap(*store_,*camera_,w,std::nullopt); WVec2 pt=snap_.best?snap_.best->point:w; if(!c_) c_=pt; else if(!s_) s_=pt; else e_=pt; }
+void ArcTool::onPointerMove(const QPointF& p){ if(!c_||!s_) return; WVec2 w=camera_->screenToWorld(p); snap_=computeSnap(*store_,*camera_,w,c_); e_=snap_.best?snap_.best->point:w; }
 void ArcTool::onPointerUp(const QPointF&){ if(!c_||!s_||!e_) return; double sx=s_->x-c_->x, sy=s_->y-c_->y; double ex=e_->x-c_->x, ey=e_->y-c_->y; double r=std::sqrt(sx*sx+sy*sy); if(r<=0){ QMessageBox::warning(nullptr,"Arc","GEOM_DEGENERATE"); c_.reset(); s_.reset(); e_.reset(); return; }
     double a0=std::atan2(sy,sx), a1=std::atan2(ey,ex); if(angleLock_){ double step=M_PI/12.0; a1=std::round(a1/step)*step; }
     if(auto deg=numeric_.value()) a1=a0 + (*deg)*M_PI/180.0;
@@ -7,8 +7,8 @@
 #include <QJsonObject>
 static QString take(char* ptr){ if(!ptr) return {}; QString s=QString::fromUtf8(ptr); craftcad_free_string(ptr); return s; }
 CircleTool::CircleTool(DocStore* store, Camera* camera):store_(store),camera_(camera){}
-void CircleTool::onPointerDown(const QPointF& s){ WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,w,std::nullopt); if(!c_) c_=snap_.best?snap_.best->point:w; else edge_=snap_.best?snap_.best->point:w; }
-void CircleTool::onPointerMove(const QPointF& s){ if(!c_) return; WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,w,c_); edge_=snap_.best?snap_.best->point:w; }
+void CircleTool::onPointerDown(const QPointF& s){ WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,*camera_,w,std::nullopt); if(!c_) c_=snap_.best?snap_.best->point:w; else edge_=snap_.best?snap_.best->point:w; }
+void CircleTool::onPointerMove(const QPointF& s){ if(!c_) return; WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,*camera_,w,c_); edge_=snap_.best?snap_.best->point:w; }
 void CircleTool::onPointerUp(const QPointF&){ if(!c_||!edge_) return; double dx=edge_->x-c_->x,dy=edge_->y-c_->y,r=std::sqrt(dx*dx+dy*dy); if(auto n=numeric_.value()) r=*n; if(r<=0){ QMessageBox::warning(nullptr,"Circle","DRAW_INVALID_NUMERIC"); c_.reset(); edge_.reset(); return; }
     QByteArray doc=store_->documentJson().toUtf8(); auto d=QJsonDocument::fromJson(doc).object(); QString layer=d.value("layers").toArray().first().toObject().value("id").toString();
     QJsonObject params{{"mode","CenterRadius"},{"c",QJsonObject{{"x",c_->x},{"y",c_->y}}},{"r",r}};
@@ -18,7 +18,7 @@ WVec2 LineTool::applyConstraint(const WVec2& start, const WVec2& end) const {
 
 void LineTool::onPointerDown(const QPointF& s) {
     WVec2 w = camera_->screenToWorld(s);
-    snap_ = computeSnap(*store_, w, std::nullopt);
+    snap_ = computeSnap(*store_, *camera_, w, std::nullopt);
     start_ = snap_.best ? snap_.best->point : w;
     end_ = start_;
     if (!groupActive_) {
@@ -31,7 +31,7 @@ void LineTool::onPointerDown(const QPointF& s) {
 void LineTool::onPointerMove(const QPointF& s) {
     if (!start_) return;
     WVec2 w = camera_->screenToWorld(s);
-    snap_ = computeSnap(*store_, w, start_);
+    snap_ = computeSnap(*store_, *camera_, w, start_);
     WVec2 snapped = snap_.best ? snap_.best->point : w;
     end_ = applyConstraint(*start_, snapped); // precedence: snap first, then constraint
 }
@@ -8,8 +8,8 @@
 
 static QString take(char* ptr){ if(!ptr) return {}; QString s=QString::fromUtf8(ptr); craftcad_free_string(ptr); return s; }
 RectTool::RectTool(DocStore* store, Camera* camera):store_(store),camera_(camera){}
-void RectTool::onPointerDown(const QPointF& s){ WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,w,std::nullopt); p0_=snap_.best?snap_.best->point:w; p1_=p0_; }
-void RectTool::onPointerMove(const QPointF& s){ if(!p0_) return; WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,w,p0_); p1_=snap_.best?snap_.best->point:w; if(lockH_) p1_->y=p0_->y; if(lockV_) p1_->x=p0_->x; }
+void RectTool::onPointerDown(const QPointF& s){ WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,*camera_,w,std::nullopt); p0_=snap_.best?snap_.best->point:w; p1_=p0_; }
+void RectTool::onPointerMove(const QPointF& s){ if(!p0_) return; WVec2 w=camera_->screenToWorld(s); snap_=computeSnap(*store_,*camera_,w,p0_); p1_=snap_.best?snap_.best->point:w; if(lockH_) p1_->y=p0_->y; if(lockV_) p1_->x=p0_->x; }
 void RectTool::onPointerUp(const QPointF&){ if(!p0_||!p1_) return; if(lockH_&&lockV_){ QMessageBox::warning(nullptr,"Rect","DRAW_CONSTRAINT_CONFLICT"); p0_.reset(); p1_.reset(); return; }
     auto len=numeric_.value(); if(len){ double dx=p1_->x-p0_->x,dy=p1_->y-p0_->y; double n=std::sqrt(dx*dx+dy*dy); if(n>0){ dx/=n; dy/=n; p1_={p0_->x+dx*(*len),p0_->y+dy*(*len)}; }}
     QByteArray doc=store_->documentJson().toUtf8(); auto d=QJsonDocument::fromJson(doc).object(); QString layer=d.value("layers").toArray().first().toObject().value("id").toString();
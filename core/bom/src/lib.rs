@@ -194,6 +194,9 @@ mod tests {
                 visible: true,
                 locked: false,
                 editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
             }],
             entities: vec![],
             jobs: vec![],
@@ -202,6 +205,9 @@ mod tests {
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
             parts: vec![
                 Part {
                     id: Uuid::new_v4(),
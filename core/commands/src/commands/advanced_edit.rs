@@ -1,6 +1,11 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_faces::extract_faces;
+use craftcad_serialize::Polygon2D;
 use craftcad_serialize::{Document, Entity, Geom2D, Reason, ReasonCode, Result, Vec2};
-use diycad_geom::{chamfer_lines, mirror_geom};
+use diycad_geom::{
+    chamfer_curves, chamfer_polyline, fillet_curves, fillet_polyline, mirror_geom, polygon_boolean,
+    BooleanOp, EpsilonPolicy,
+};
 use std::collections::BTreeMap;
 use std::sync::Mutex;
 use uuid::Uuid;
@@ -20,8 +25,8 @@ fn from_op(g: &diycad_geom::Geom2D) -> Result<Geom2D> {
     .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))
 }
 
-#[derive(Debug)]
-struct EntitiesDelta {
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct EntitiesDelta {
     before: Mutex<Option<Vec<Entity>>>,
     after: Mutex<Option<Vec<Entity>>>,
     op: EditOp,
@@ -57,9 +62,18 @@ impl Delta for EntitiesDelta {
             .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Entities(*self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+enum CornerCut {
+    Fillet(f64),
+    Chamfer(f64),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 enum EditOp {
     Fillet {
         e1: Uuid,
@@ -71,6 +85,11 @@ enum EditOp {
         e2: Uuid,
         distance: f64,
     },
+    PolylineCorners {
+        entity_id: Uuid,
+        vertex: Option<usize>,
+        cut: CornerCut,
+    },
     Mirror {
         selection_ids: Vec<Uuid>,
         axis_a: Vec2,
@@ -80,141 +99,42 @@ enum EditOp {
         selection_ids: Vec<Uuid>,
         params: PatternParams,
     },
+    Boolean {
+        a_ids: Vec<Uuid>,
+        b_ids: Vec<Uuid>,
+        op: BooleanOp,
+        eps: EpsilonPolicy,
+    },
 }
 impl EditOp {
     fn apply_to(&self, entities: &mut Vec<Entity>) -> Result<()> {
         match self {
             EditOp::Fillet { e1, e2, radius } => {
-                let a = entities
-                    .iter()
-                    .find(|e| &e.id == e1)
-                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
-                    .clone();
-                let b = entities
-                    .iter()
-                    .find(|e| &e.id == e2)
-                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
-                    .clone();
-                let (a0, a1, b0, b1) = match (&a.geom, &b.geom) {
-                    (Geom2D::Line { a: a0, b: a1 }, Geom2D::Line { a: b0, b: b1 }) => {
-                        (a0.clone(), a1.clone(), b0.clone(), b1.clone())
-                    }
-                    _ => return Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported)),
-                };
-                let (g1, g2, ga) = match chamfer_lines(
-                    diycad_geom::Vec2 { x: a0.x, y: a0.y },
-                    diycad_geom::Vec2 { x: a1.x, y: a1.y },
-                    diycad_geom::Vec2 { x: b0.x, y: b0.y },
-                    diycad_geom::Vec2 { x: b1.x, y: b1.y },
-                    *radius,
-                ) {
-                    Ok((g1, g2, _)) => {
-                        let pa = match &g1 {
-                            diycad_geom::Geom2D::Line { b, .. } => *b,
-                            _ => diycad_geom::Vec2 { x: a0.x, y: a0.y },
-                        };
-                        let pb = match &g2 {
-                            diycad_geom::Geom2D::Line { b, .. } => *b,
-                            _ => diycad_geom::Vec2 { x: b0.x, y: b0.y },
-                        };
-                        let center = diycad_geom::Vec2 {
-                            x: 0.5 * (pa.x + pb.x),
-                            y: 0.5 * (pa.y + pb.y),
-                        };
-                        let ga = diycad_geom::Geom2D::Arc {
-                            c: center,
-                            r: *radius,
-                            start_angle: (pa.y - center.y).atan2(pa.x - center.x),
-                            end_angle: (pb.y - center.y).atan2(pb.x - center.x),
-                            ccw: true,
-                        };
-                        (g1, g2, ga)
-                    }
-                    Err(_) => {
-                        let g1 = diycad_geom::Geom2D::Line {
-                            a: diycad_geom::Vec2 { x: a0.x, y: a0.y },
-                            b: diycad_geom::Vec2 { x: a1.x, y: a1.y },
-                        };
-                        let g2 = diycad_geom::Geom2D::Line {
-                            a: diycad_geom::Vec2 { x: b0.x, y: b0.y },
-                            b: diycad_geom::Vec2 { x: b1.x, y: b1.y },
-                        };
-                        let c = diycad_geom::Vec2 {
-                            x: 0.5 * (a0.x + b0.x),
-                            y: 0.5 * (a0.y + b0.y),
-                        };
-                        let ga = diycad_geom::Geom2D::Arc {
-                            c,
-                            r: *radius,
-                            start_angle: 0.0,
-                            end_angle: 1.57,
-                            ccw: true,
-                        };
-                        (g1, g2, ga)
-                    }
-                };
-                let layer_id = a.layer_id;
-                entities.retain(|e| e.id != a.id && e.id != b.id);
-                entities.push(Entity {
-                    geom: from_op(&g1)?,
-                    ..a
-                });
-                entities.push(Entity {
-                    geom: from_op(&g2)?,
-                    ..b
-                });
-                entities.push(Entity {
-                    id: Uuid::new_v4(),
-                    layer_id,
-                    geom: from_op(&ga)?,
-                    style: serde_json::json!({}),
-                    tags: vec!["fillet".into()],
-                    meta: BTreeMap::new(),
-                });
+                corner_edit(entities, *e1, *e2, "fillet", |g1, g2| {
+                    fillet_curves(g1, g2, *radius, &EpsilonPolicy::default())
+                })?;
             }
             EditOp::Chamfer { e1, e2, distance } => {
-                let a = entities
-                    .iter()
-                    .find(|e| &e.id == e1)
-                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
-                    .clone();
-                let b = entities
-                    .iter()
-                    .find(|e| &e.id == e2)
-                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
-                    .clone();
-                let (a0, a1, b0, b1) = match (&a.geom, &b.geom) {
-                    (Geom2D::Line { a: a0, b: a1 }, Geom2D::Line { a: b0, b: b1 }) => {
-                        (a0.clone(), a1.clone(), b0.clone(), b1.clone())
-                    }
-                    _ => return Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported)),
+                corner_edit(entities, *e1, *e2, "chamfer", |g1, g2| {
+                    chamfer_curves(g1, g2, *distance, &EpsilonPolicy::default())
+                })?;
+            }
+            EditOp::PolylineCorners {
+                entity_id,
+                vertex,
+                cut,
+            } => {
+                let e = entities
+                    .iter_mut()
+                    .find(|e| &e.id == entity_id)
+                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+                let eps = EpsilonPolicy::default();
+                let g = to_op(&e.geom)?;
+                let out = match cut {
+                    CornerCut::Fillet(r) => fillet_polyline(&g, *vertex, *r, &eps)?,
+                    CornerCut::Chamfer(d) => chamfer_polyline(&g, *vertex, *d, &eps)?,
                 };
-                let (g1, g2, gc) = chamfer_lines(
-                    diycad_geom::Vec2 { x: a0.x, y: a0.y },
-                    diycad_geom::Vec2 { x: a1.x, y: a1.y },
-                    diycad_geom::Vec2 { x: b0.x, y: b0.y },
-                    diycad_geom::Vec2 { x: b1.x, y: b1.y },
-                    *distance,
-                )
-                .map_err(|_| Reason::from_code(ReasonCode::EditChamferDistanceTooLarge))?;
-                let layer_id = a.layer_id;
-                entities.retain(|e| e.id != a.id && e.id != b.id);
-                entities.push(Entity {
-                    geom: from_op(&g1)?,
-                    ..a
-                });
-                entities.push(Entity {
-                    geom: from_op(&g2)?,
-                    ..b
-                });
-                entities.push(Entity {
-                    id: Uuid::new_v4(),
-                    layer_id,
-                    geom: from_op(&gc)?,
-                    style: serde_json::json!({}),
-                    tags: vec!["chamfer".into()],
-                    meta: BTreeMap::new(),
-                });
+                e.geom = from_op(&out)?;
             }
             EditOp::Mirror {
                 selection_ids,
@@ -293,11 +213,117 @@ impl EditOp {
                     }
                 }
             }
+            EditOp::Boolean {
+                a_ids,
+                b_ids,
+                op,
+                eps,
+            } => {
+                let a = operand_polygons(entities, a_ids, eps)?;
+                let b = operand_polygons(entities, b_ids, eps)?;
+                let result = polygon_boolean(&a, &b, *op, eps)?;
+                if result.is_empty() {
+                    return Err(Reason::from_code(ReasonCode::GeomBooleanEmptyResult));
+                }
+                let template = entities
+                    .iter()
+                    .find(|e| e.id == a_ids[0])
+                    .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
+                    .clone();
+                entities.retain(|e| !a_ids.contains(&e.id) && !b_ids.contains(&e.id));
+                for poly in result {
+                    for ring in std::iter::once(poly.outer).chain(poly.holes) {
+                        entities.push(Entity {
+                            id: Uuid::new_v4(),
+                            geom: Geom2D::Polyline {
+                                pts: ring,
+                                closed: true,
+                            },
+                            ..template.clone()
+                        });
+                    }
+                }
+            }
         }
         Ok(())
     }
 }
 
+// Replaces two corner curves with their trimmed versions plus the new corner
+// piece (fillet arc or chamfer line) on the first curve's layer.
+fn corner_edit(
+    entities: &mut Vec<Entity>,
+    e1: Uuid,
+    e2: Uuid,
+    tag: &str,
+    op: impl FnOnce(
+        &diycad_geom::Geom2D,
+        &diycad_geom::Geom2D,
+    ) -> Result<(
+        diycad_geom::Geom2D,
+        diycad_geom::Geom2D,
+        diycad_geom::Geom2D,
+    )>,
+) -> Result<()> {
+    let a = entities
+        .iter()
+        .find(|e| e.id == e1)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
+        .clone();
+    let b = entities
+        .iter()
+        .find(|e| e.id == e2)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?
+        .clone();
+    let (g1, g2, corner) = op(&to_op(&a.geom)?, &to_op(&b.geom)?)?;
+    let layer_id = a.layer_id;
+    entities.retain(|e| e.id != a.id && e.id != b.id);
+    entities.push(Entity {
+        geom: from_op(&g1)?,
+        ..a
+    });
+    entities.push(Entity {
+        geom: from_op(&g2)?,
+        ..b
+    });
+    entities.push(Entity {
+        id: Uuid::new_v4(),
+        layer_id,
+        geom: from_op(&corner)?,
+        style: serde_json::json!({}),
+        tags: vec![tag.into()],
+        meta: BTreeMap::new(),
+    });
+    Ok(())
+}
+
+// Closed polylines of one boolean operand, nested into outlines with holes.
+fn operand_polygons(
+    entities: &[Entity],
+    ids: &[Uuid],
+    eps: &EpsilonPolicy,
+) -> Result<Vec<Polygon2D>> {
+    let mut geoms = Vec::with_capacity(ids.len());
+    for id in ids {
+        let e = entities
+            .iter()
+            .find(|e| &e.id == id)
+            .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+        if !matches!(e.geom, Geom2D::Polyline { closed: true, .. }) {
+            return Err(Reason::from_code(ReasonCode::FaceNoClosedLoop));
+        }
+        geoms.push(e.geom.clone());
+    }
+    Ok(extract_faces(&geoms, eps)?
+        .faces
+        .into_iter()
+        .map(|f| Polygon2D {
+            outer: f.outer.pts,
+            holes: f.holes.into_iter().map(|h| h.pts).collect(),
+        })
+        .collect())
+}
+
 #[derive(Debug, Clone)]
 pub struct FilletInput {
     pub e1: Uuid,
@@ -404,6 +430,116 @@ impl Command for ChamferCommand {
     }
 }
 
+/// Fillet (`radius`) one vertex of a polyline, or every corner when `vertex` is
+/// `None`. The polyline is edited in place.
+#[derive(Debug, Clone)]
+pub struct FilletPolylineInput {
+    pub entity_id: Uuid,
+    pub vertex: Option<usize>,
+    pub radius: f64,
+}
+pub struct FilletPolylineCommand {
+    preview: Option<FilletPolylineInput>,
+}
+impl FilletPolylineCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for FilletPolylineCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Command for FilletPolylineCommand {
+    type Input = FilletPolylineInput;
+    fn begin(&mut self, _: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if !input.radius.is_finite() || input.radius <= 0.0 {
+            return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let p = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(EntitiesDelta {
+            before: Mutex::new(None),
+            after: Mutex::new(None),
+            op: EditOp::PolylineCorners {
+                entity_id: p.entity_id,
+                vertex: p.vertex,
+                cut: CornerCut::Fillet(p.radius),
+            },
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// Chamfer (`distance` along both edges) one vertex of a polyline, or every
+/// corner when `vertex` is `None`.
+#[derive(Debug, Clone)]
+pub struct ChamferPolylineInput {
+    pub entity_id: Uuid,
+    pub vertex: Option<usize>,
+    pub distance: f64,
+}
+pub struct ChamferPolylineCommand {
+    preview: Option<ChamferPolylineInput>,
+}
+impl ChamferPolylineCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for ChamferPolylineCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Command for ChamferPolylineCommand {
+    type Input = ChamferPolylineInput;
+    fn begin(&mut self, _: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if !input.distance.is_finite() || input.distance <= 0.0 {
+            return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let p = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(EntitiesDelta {
+            before: Mutex::new(None),
+            after: Mutex::new(None),
+            op: EditOp::PolylineCorners {
+                entity_id: p.entity_id,
+                vertex: p.vertex,
+                cut: CornerCut::Chamfer(p.distance),
+            },
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MirrorInput {
     pub selection_ids: Vec<Uuid>,
@@ -522,6 +658,64 @@ impl Command for PatternCommand {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct BooleanInput {
+    pub a_ids: Vec<Uuid>,
+    pub b_ids: Vec<Uuid>,
+    pub op: BooleanOp,
+    pub eps: EpsilonPolicy,
+}
+pub struct BooleanCommand {
+    preview: Option<BooleanInput>,
+}
+impl BooleanCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for BooleanCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Command for BooleanCommand {
+    type Input = BooleanInput;
+    fn begin(&mut self, _: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if input.a_ids.is_empty() || input.b_ids.is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditNoSelection));
+        }
+        if input.a_ids.iter().any(|id| input.b_ids.contains(id)) {
+            return Err(Reason::from_code(ReasonCode::EditAmbiguousTarget));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let p = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(EntitiesDelta {
+            before: Mutex::new(None),
+            after: Mutex::new(None),
+            op: EditOp::Boolean {
+                a_ids: p.a_ids,
+                b_ids: p.b_ids,
+                op: p.op,
+                eps: p.eps,
+            },
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
 fn translate_geom(g: &Geom2D, dx: f64, dy: f64) -> Geom2D {
     match g {
         Geom2D::Line { a, b } => Geom2D::Line {
@@ -567,6 +761,47 @@ fn translate_geom(g: &Geom2D, dx: f64, dy: f64) -> Geom2D {
                 .collect(),
             closed: *closed,
         },
+        Geom2D::Ellipse { c, rx, ry, rot } => Geom2D::Ellipse {
+            c: Vec2 {
+                x: c.x + dx,
+                y: c.y + dy,
+            },
+            rx: *rx,
+            ry: *ry,
+            rot: *rot,
+        },
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => Geom2D::EllipticalArc {
+            c: Vec2 {
+                x: c.x + dx,
+                y: c.y + dy,
+            },
+            rx: *rx,
+            ry: *ry,
+            rot: *rot,
+            start_angle: *start_angle,
+            end_angle: *end_angle,
+            ccw: *ccw,
+        },
+        Geom2D::CubicBezier { a, c1, c2, b } => {
+            let mv = |p: &Vec2| Vec2 {
+                x: p.x + dx,
+                y: p.y + dy,
+            };
+            Geom2D::CubicBezier {
+                a: mv(a),
+                c1: mv(c1),
+                c2: mv(c2),
+                b: mv(b),
+            }
+        }
     }
 }
 fn rotate_geom(g: &Geom2D, c: Vec2, a: f64) -> Geom2D {
@@ -597,5 +832,39 @@ fn rotate_geom(g: &Geom2D, c: Vec2, a: f64) -> Geom2D {
             pts: pts.iter().map(rot).collect(),
             closed: *closed,
         },
+        Geom2D::Ellipse {
+            c: c0,
+            rx,
+            ry,
+            rot: r0,
+        } => Geom2D::Ellipse {
+            c: rot(c0),
+            rx: *rx,
+            ry: *ry,
+            rot: *r0 + a,
+        },
+        Geom2D::EllipticalArc {
+            c: c0,
+            rx,
+            ry,
+            rot: r0,
+            start_angle,
+            end_angle,
+            ccw,
+        } => Geom2D::EllipticalArc {
+            c: rot(c0),
+            rx: *rx,
+            ry: *ry,
+            rot: *r0 + a,
+            start_angle: *start_angle,
+            end_angle: *end_angle,
+            ccw: *ccw,
+        },
+        Geom2D::CubicBezier { a: p0, c1, c2, b } => Geom2D::CubicBezier {
+            a: rot(p0),
+            c1: rot(c1),
+            c2: rot(c2),
+            b: rot(b),
+        },
     }
 }
@@ -0,0 +1,692 @@
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_edit_ops::blocks::block_def as block;
+pub use craftcad_edit_ops::blocks::{drawn_entities, instance_entities, place_geom};
+use craftcad_serialize::{
+    BlockDef, BlockInstance, BlockTransform, Document, Entity, Reason, ReasonCode, Result, Vec2,
+};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const EPS: f64 = 1e-9;
+
+/// `x' = m * (x - base) + t` in matrix form, used to compose and invert
+/// block transforms.
+#[derive(Clone, Copy, Debug)]
+struct Affine {
+    m: [[f64; 2]; 2],
+    t: [f64; 2],
+}
+
+impl Affine {
+    fn of(tr: &BlockTransform, base: &Vec2) -> Self {
+        let (s, c) = tr.rotate_rad.sin_cos();
+        let sx = if tr.mirror { -tr.scale.x } else { tr.scale.x };
+        let sy = tr.scale.y;
+        let m = [[c * sx, -s * sy], [s * sx, c * sy]];
+        let t = [
+            tr.translate.x - (m[0][0] * base.x + m[0][1] * base.y),
+            tr.translate.y - (m[1][0] * base.x + m[1][1] * base.y),
+        ];
+        Self { m, t }
+    }
+
+    fn apply(&self, p: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.m[0][0] * p.x + self.m[0][1] * p.y + self.t[0],
+            y: self.m[1][0] * p.x + self.m[1][1] * p.y + self.t[1],
+        }
+    }
+
+    /// `self` after `inner`.
+    fn then(&self, inner: &Affine) -> Self {
+        let a = &self.m;
+        let b = &inner.m;
+        let m = [
+            [
+                a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                a[0][0] * b[0][1] + a[0][1] * b[1][1],
+            ],
+            [
+                a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                a[1][0] * b[0][1] + a[1][1] * b[1][1],
+            ],
+        ];
+        let t = self.apply(&Vec2 {
+            x: inner.t[0],
+            y: inner.t[1],
+        });
+        Self { m, t: [t.x, t.y] }
+    }
+
+    fn inverse(&self) -> Result<Self> {
+        let [[a, b], [c, d]] = self.m;
+        let det = a * d - b * c;
+        if !det.is_finite() || det.abs() <= EPS {
+            return Err(Reason::from_code(ReasonCode::EditTransformWouldDegenerate));
+        }
+        let m = [[d / det, -b / det], [-c / det, a / det]];
+        let t = [
+            -(m[0][0] * self.t[0] + m[0][1] * self.t[1]),
+            -(m[1][0] * self.t[0] + m[1][1] * self.t[1]),
+        ];
+        Ok(Self { m, t })
+    }
+
+    /// Splits the matrix back into mirror, scale and rotation for a block
+    /// whose base point is `base`. Shear, e.g. a rotated non-uniform scale
+    /// inside another rotation, has no such form.
+    fn decompose(&self, base: &Vec2) -> Result<BlockTransform> {
+        let c0 = (self.m[0][0], self.m[1][0]);
+        let c1 = (self.m[0][1], self.m[1][1]);
+        let sx = c0.0.hypot(c0.1);
+        let sy = c1.0.hypot(c1.1);
+        let det = c0.0 * c1.1 - c1.0 * c0.1;
+        if sx <= EPS || sy <= EPS || det.abs() <= EPS {
+            return Err(Reason::from_code(ReasonCode::EditTransformWouldDegenerate));
+        }
+        if (c0.0 * c1.0 + c0.1 * c1.1).abs() > EPS * sx * sy {
+            return Err(Reason::from_code(ReasonCode::EditTransformWouldDegenerate));
+        }
+        let mirror = det < 0.0;
+        let rotate_rad = if mirror {
+            (-c0.1).atan2(-c0.0)
+        } else {
+            c0.1.atan2(c0.0)
+        };
+        Ok(BlockTransform {
+            translate: self.apply(base),
+            rotate_rad,
+            scale: Vec2 { x: sx, y: sy },
+            mirror,
+        })
+    }
+}
+
+fn check_transform(tr: &BlockTransform) -> Result<()> {
+    let finite = [
+        tr.translate.x,
+        tr.translate.y,
+        tr.rotate_rad,
+        tr.scale.x,
+        tr.scale.y,
+    ]
+    .iter()
+    .all(|v| v.is_finite());
+    if !finite {
+        return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
+    }
+    if tr.scale.x <= EPS || tr.scale.y <= EPS {
+        return Err(Reason::from_code(ReasonCode::EditTransformWouldDegenerate));
+    }
+    Ok(())
+}
+
+fn check_editable(doc: &Document, layer_id: Uuid) -> Result<()> {
+    let layer = doc
+        .layers
+        .iter()
+        .find(|l| l.id == layer_id)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+    if !layer.visible || layer.locked || !layer.editable {
+        return Err(Reason::from_code(ReasonCode::EditTargetLockedOrHidden));
+    }
+    Ok(())
+}
+
+/// Whether `target` is placed, at any depth, by one of `instances`. Each
+/// block is expanded once, so shared sub-blocks and cycles that do not pass
+/// through `target` cost no more than one visit.
+fn reaches(blocks: &[BlockDef], instances: &[BlockInstance], target: Uuid) -> Result<bool> {
+    let mut visited = HashSet::new();
+    let mut stack: Vec<Uuid> = instances.iter().map(|i| i.block_id).collect();
+    while let Some(id) = stack.pop() {
+        if id == target {
+            return Ok(true);
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+        let def = block(blocks, id)?;
+        stack.extend(def.instances.iter().map(|i| i.block_id));
+    }
+    Ok(false)
+}
+
+/// One level of a placed block in world coordinates: the definition's own
+/// entities and the transforms of its nested instances. Edit these and
+/// pass them back through [`EditBlockInput::via_instance`] to edit the
+/// block in place.
+pub fn instance_contents(
+    doc: &Document,
+    instance_id: Uuid,
+) -> Result<(Vec<Entity>, Vec<BlockInstance>)> {
+    let instance = doc
+        .block_instances
+        .iter()
+        .find(|i| i.id == instance_id)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+    let def = block(&doc.blocks, instance.block_id)?;
+    let entities = def
+        .entities
+        .iter()
+        .map(|e| {
+            Ok(Entity {
+                geom: place_geom(&instance.transform, &def.base_point, &e.geom)?,
+                ..e.clone()
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let outer = Affine::of(&instance.transform, &def.base_point);
+    let instances = def
+        .instances
+        .iter()
+        .map(|n| {
+            let base = &block(&doc.blocks, n.block_id)?.base_point;
+            Ok(BlockInstance {
+                transform: outer
+                    .then(&Affine::of(&n.transform, base))
+                    .decompose(base)?,
+                ..n.clone()
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((entities, instances))
+}
+
+/// Moves entities and placed instances into a new block named `name` and
+/// places one instance of it, at `base_point`, on `layer_id`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct DefineBlockInput {
+    pub name: String,
+    pub base_point: Vec2,
+    #[serde(default)]
+    pub entity_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub instance_ids: Vec<Uuid>,
+    pub layer_id: Uuid,
+}
+
+pub struct DefineBlockCommand {
+    preview: Option<DefineBlockInput>,
+}
+
+impl DefineBlockCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for DefineBlockCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for DefineBlockCommand {
+    type Input = DefineBlockInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if input.name.trim().is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditBlockNameInvalid));
+        }
+        if input.entity_ids.is_empty() && input.instance_ids.is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditNoSelection));
+        }
+        if !input.base_point.x.is_finite() || !input.base_point.y.is_finite() {
+            return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditNoSelection))?;
+        Ok(Box::new(BlocksDelta::new(BlockOp::Define {
+            block_id: Uuid::new_v4(),
+            instance_id: Uuid::new_v4(),
+            name: input.name,
+            base_point: input.base_point,
+            entity_ids: input.entity_ids,
+            instance_ids: input.instance_ids,
+            layer_id: input.layer_id,
+        })))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// Places a block with a named instance.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct InsertBlockInput {
+    pub block_id: Uuid,
+    #[serde(default)]
+    pub name: String,
+    pub layer_id: Uuid,
+    pub transform: BlockTransform,
+}
+
+pub struct InsertBlockCommand {
+    preview: Option<InsertBlockInput>,
+}
+
+impl InsertBlockCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for InsertBlockCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for InsertBlockCommand {
+    type Input = InsertBlockInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        check_transform(&input.transform)?;
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(BlocksDelta::new(BlockOp::Insert {
+            instance: BlockInstance {
+                id: Uuid::new_v4(),
+                block_id: input.block_id,
+                name: input.name,
+                layer_id: input.layer_id,
+                transform: input.transform,
+            },
+        })))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// Replaces an instance with one level of its block: the entities become
+/// plain entities and nested blocks become instances of their own.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ExplodeBlockInput {
+    pub instance_id: Uuid,
+}
+
+pub struct ExplodeBlockCommand {
+    preview: Option<ExplodeBlockInput>,
+}
+
+impl ExplodeBlockCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for ExplodeBlockCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ExplodeBlockCommand {
+    type Input = ExplodeBlockInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditNoSelection))?;
+        Ok(Box::new(BlocksDelta::new(BlockOp::Explode {
+            instance_id: input.instance_id,
+        })))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// Replaces the contents of a block definition, updating every instance.
+/// Contents are in block coordinates, or in the world coordinates of
+/// `via_instance` as returned by [`instance_contents`].
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct EditBlockInput {
+    pub block_id: Uuid,
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub instances: Vec<BlockInstance>,
+    #[serde(default)]
+    pub via_instance: Option<Uuid>,
+}
+
+pub struct EditBlockCommand {
+    preview: Option<EditBlockInput>,
+}
+
+impl EditBlockCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for EditBlockCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for EditBlockCommand {
+    type Input = EditBlockInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        for i in &input.instances {
+            check_transform(&i.transform)?;
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(BlocksDelta::new(BlockOp::Edit {
+            block_id: input.block_id,
+            entities: input.entities,
+            instances: input.instances,
+            via_instance: input.via_instance,
+        })))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+enum BlockOp {
+    Define {
+        block_id: Uuid,
+        instance_id: Uuid,
+        name: String,
+        base_point: Vec2,
+        entity_ids: Vec<Uuid>,
+        instance_ids: Vec<Uuid>,
+        layer_id: Uuid,
+    },
+    Insert {
+        instance: BlockInstance,
+    },
+    Explode {
+        instance_id: Uuid,
+    },
+    Edit {
+        block_id: Uuid,
+        entities: Vec<Entity>,
+        instances: Vec<BlockInstance>,
+        via_instance: Option<Uuid>,
+    },
+}
+
+/// What a block edit changed: removed items with their original indices,
+/// added items, and the definition it touched before and after.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+struct BlockChange {
+    removed_entities: Vec<(usize, Entity)>,
+    added_entities: Vec<Entity>,
+    removed_instances: Vec<(usize, BlockInstance)>,
+    added_instances: Vec<BlockInstance>,
+    block_before: Option<BlockDef>,
+    block_after: Option<BlockDef>,
+}
+
+/// Replaces, adds or removes the definition with `id`.
+fn set_block(blocks: &mut Vec<BlockDef>, id: Uuid, def: &Option<BlockDef>) {
+    match (blocks.iter().position(|b| b.id == id), def) {
+        (Some(at), Some(d)) => blocks[at] = d.clone(),
+        (Some(at), None) => {
+            blocks.remove(at);
+        }
+        (None, Some(d)) => blocks.push(d.clone()),
+        (None, None) => {}
+    }
+}
+
+impl BlockChange {
+    fn block_id(&self) -> Option<Uuid> {
+        self.block_after
+            .as_ref()
+            .or(self.block_before.as_ref())
+            .map(|b| b.id)
+    }
+
+    fn redo(&self, doc: &mut Document) {
+        doc.entities
+            .retain(|e| !self.removed_entities.iter().any(|(_, r)| r.id == e.id));
+        doc.entities.extend(self.added_entities.iter().cloned());
+        doc.block_instances
+            .retain(|i| !self.removed_instances.iter().any(|(_, r)| r.id == i.id));
+        doc.block_instances
+            .extend(self.added_instances.iter().cloned());
+        if let Some(id) = self.block_id() {
+            set_block(&mut doc.blocks, id, &self.block_after);
+        }
+    }
+
+    fn undo(&self, doc: &mut Document) {
+        doc.entities
+            .retain(|e| !self.added_entities.iter().any(|a| a.id == e.id));
+        for (i, e) in &self.removed_entities {
+            doc.entities.insert((*i).min(doc.entities.len()), e.clone());
+        }
+        doc.block_instances
+            .retain(|i| !self.added_instances.iter().any(|a| a.id == i.id));
+        for (i, inst) in &self.removed_instances {
+            doc.block_instances
+                .insert((*i).min(doc.block_instances.len()), inst.clone());
+        }
+        if let Some(id) = self.block_id() {
+            set_block(&mut doc.blocks, id, &self.block_before);
+        }
+    }
+}
+
+/// Positions of `ids` in `items`, each checked editable, in id order.
+fn take<T: Clone>(
+    doc: &Document,
+    items: &[T],
+    ids: &[Uuid],
+    key: impl Fn(&T) -> (Uuid, Uuid),
+) -> Result<Vec<(usize, T)>> {
+    let mut out: Vec<(usize, T)> = vec![];
+    for id in ids {
+        let at = items
+            .iter()
+            .position(|t| key(t).0 == *id)
+            .filter(|at| !out.iter().any(|(i, _)| i == at))
+            .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+        check_editable(doc, key(&items[at]).1)?;
+        out.push((at, items[at].clone()));
+    }
+    Ok(out)
+}
+
+impl BlockOp {
+    /// Validates the op against `doc` and works out what it changes,
+    /// without touching the document.
+    fn resolve(&self, doc: &Document) -> Result<BlockChange> {
+        let mut c = BlockChange::default();
+        match self {
+            BlockOp::Define {
+                block_id,
+                instance_id,
+                name,
+                base_point,
+                entity_ids,
+                instance_ids,
+                layer_id,
+            } => {
+                if doc.blocks.iter().any(|b| b.name == *name) {
+                    return Err(Reason::from_code(ReasonCode::EditBlockNameInvalid));
+                }
+                check_editable(doc, *layer_id)?;
+                let entities = take(doc, &doc.entities, entity_ids, |e| (e.id, e.layer_id))?;
+                let instances = take(doc, &doc.block_instances, instance_ids, |i| {
+                    (i.id, i.layer_id)
+                })?;
+                c.block_after = Some(BlockDef {
+                    id: *block_id,
+                    name: name.clone(),
+                    base_point: base_point.clone(),
+                    entities: entities.iter().map(|(_, e)| e.clone()).collect(),
+                    instances: instances.iter().map(|(_, i)| i.clone()).collect(),
+                });
+                c.added_instances.push(BlockInstance {
+                    id: *instance_id,
+                    block_id: *block_id,
+                    name: name.clone(),
+                    layer_id: *layer_id,
+                    transform: BlockTransform {
+                        translate: base_point.clone(),
+                        ..BlockTransform::default()
+                    },
+                });
+                c.removed_entities = entities;
+                c.removed_entities.sort_by_key(|(i, _)| *i);
+                c.removed_instances = instances;
+                c.removed_instances.sort_by_key(|(i, _)| *i);
+            }
+            BlockOp::Insert { instance } => {
+                block(&doc.blocks, instance.block_id)?;
+                check_editable(doc, instance.layer_id)?;
+                c.added_instances.push(instance.clone());
+            }
+            BlockOp::Explode { instance_id } => {
+                let removed = take(doc, &doc.block_instances, &[*instance_id], |i| {
+                    (i.id, i.layer_id)
+                })?;
+                let (entities, instances) = instance_contents(doc, *instance_id)?;
+                c.removed_instances = removed;
+                c.added_entities = entities
+                    .into_iter()
+                    .map(|e| Entity {
+                        id: Uuid::new_v4(),
+                        ..e
+                    })
+                    .collect();
+                c.added_instances = instances
+                    .into_iter()
+                    .map(|i| BlockInstance {
+                        id: Uuid::new_v4(),
+                        ..i
+                    })
+                    .collect();
+            }
+            BlockOp::Edit {
+                block_id,
+                entities,
+                instances,
+                via_instance,
+            } => {
+                let before = block(&doc.blocks, *block_id)?;
+                let base = before.base_point.clone();
+                let (mut entities, mut instances) = (entities.clone(), instances.clone());
+                if let Some(via) = via_instance {
+                    let placed = doc
+                        .block_instances
+                        .iter()
+                        .find(|i| i.id == *via && i.block_id == *block_id)
+                        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+                    let back = Affine::of(&placed.transform, &base).inverse()?;
+                    let to_block = back.decompose(&placed.transform.translate)?;
+                    for e in &mut entities {
+                        e.geom = place_geom(&to_block, &placed.transform.translate, &e.geom)?;
+                    }
+                    for i in &mut instances {
+                        let b = &block(&doc.blocks, i.block_id)?.base_point;
+                        i.transform = back.then(&Affine::of(&i.transform, b)).decompose(b)?;
+                    }
+                }
+                if reaches(&doc.blocks, &instances, *block_id)? {
+                    return Err(Reason::from_code(ReasonCode::EditBlockCycle));
+                }
+                c.block_after = Some(BlockDef {
+                    entities,
+                    instances,
+                    ..before.clone()
+                });
+                c.block_before = Some(before.clone());
+            }
+        }
+        Ok(c)
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BlocksDelta {
+    op: BlockOp,
+    /// Resolved on the first `apply` so redo reuses the same ids.
+    change: Mutex<Option<BlockChange>>,
+}
+
+impl BlocksDelta {
+    fn new(op: BlockOp) -> Self {
+        Self {
+            op,
+            change: Mutex::new(None),
+        }
+    }
+}
+
+impl Delta for BlocksDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        let mut change = self
+            .change
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        if change.is_none() {
+            *change = Some(self.op.resolve(doc)?);
+        }
+        change
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .redo(doc);
+        Ok(())
+    }
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let change = self
+            .change
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        change
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .undo(doc);
+        Ok(())
+    }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Blocks(*self)
+    }
+}
@@ -0,0 +1,452 @@
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_edit_ops::translate;
+use craftcad_serialize::{
+    Document, Entity, Layer, Material, Part, Reason, ReasonCode, Result, Vec2,
+};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub const CLIPBOARD_VERSION: u32 = 1;
+
+/// Copied content with the layers and materials it references, so it can
+/// be pasted into any document.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ClipboardPayload {
+    pub version: u32,
+    pub layers: Vec<Layer>,
+    pub entities: Vec<Entity>,
+    pub materials: Vec<Material>,
+    pub parts: Vec<Part>,
+}
+
+impl ClipboardPayload {
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.parts.is_empty()
+    }
+}
+
+/// Copies the given entities and parts of `doc` in document order.
+pub fn copy_selection(
+    doc: &Document,
+    entity_ids: &[Uuid],
+    part_ids: &[Uuid],
+) -> Result<ClipboardPayload> {
+    if entity_ids.is_empty() && part_ids.is_empty() {
+        return Err(Reason::from_code(ReasonCode::EditNoSelection));
+    }
+    if entity_ids
+        .iter()
+        .any(|id| !doc.entities.iter().any(|e| e.id == *id))
+        || part_ids
+            .iter()
+            .any(|id| !doc.parts.iter().any(|p| p.id == *id))
+    {
+        return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
+    }
+    let entities: Vec<Entity> = doc
+        .entities
+        .iter()
+        .filter(|e| entity_ids.contains(&e.id))
+        .cloned()
+        .collect();
+    let parts: Vec<Part> = doc
+        .parts
+        .iter()
+        .filter(|p| part_ids.contains(&p.id))
+        .cloned()
+        .collect();
+    let mut layers = vec![];
+    for e in &entities {
+        if !layers.iter().any(|l: &Layer| l.id == e.layer_id) {
+            let layer = doc
+                .layers
+                .iter()
+                .find(|l| l.id == e.layer_id)
+                .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+            layers.push(layer.clone());
+        }
+    }
+    // Parts may name a material the document does not define; such ids are
+    // pasted unchanged.
+    let materials = doc
+        .materials
+        .iter()
+        .filter(|m| parts.iter().any(|p| p.material_id == m.id))
+        .cloned()
+        .collect();
+    Ok(ClipboardPayload {
+        version: CLIPBOARD_VERSION,
+        layers,
+        entities,
+        materials,
+        parts,
+    })
+}
+
+fn check_editable(doc: &Document, layer_id: Uuid) -> Result<()> {
+    let layer = doc
+        .layers
+        .iter()
+        .find(|l| l.id == layer_id)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+    if !layer.visible || layer.locked || !layer.editable {
+        return Err(Reason::from_code(ReasonCode::EditTargetLockedOrHidden));
+    }
+    Ok(())
+}
+
+/// Removes entities and parts; copy them with [`copy_selection`] first.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CutInput {
+    pub entity_ids: Vec<Uuid>,
+    pub part_ids: Vec<Uuid>,
+}
+
+pub struct CutCommand {
+    preview: Option<CutInput>,
+}
+
+impl CutCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for CutCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for CutCommand {
+    type Input = CutInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if input.entity_ids.is_empty() && input.part_ids.is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditNoSelection));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditNoSelection))?;
+        Ok(Box::new(CutDelta {
+            entity_ids: input.entity_ids,
+            part_ids: input.part_ids,
+            removed: Mutex::new(None),
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Removed {
+    entities: Vec<(usize, Entity)>,
+    parts: Vec<(usize, Part)>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CutDelta {
+    entity_ids: Vec<Uuid>,
+    part_ids: Vec<Uuid>,
+    /// Items removed by the last `apply`, with their original indices.
+    removed: Mutex<Option<Removed>>,
+}
+
+impl Delta for CutDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        for id in &self.entity_ids {
+            let e = doc
+                .entities
+                .iter()
+                .find(|e| e.id == *id)
+                .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+            check_editable(doc, e.layer_id)?;
+        }
+        if self
+            .part_ids
+            .iter()
+            .any(|id| !doc.parts.iter().any(|p| p.id == *id))
+        {
+            return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
+        }
+        let mut removed = Removed {
+            entities: vec![],
+            parts: vec![],
+        };
+        let mut i = 0;
+        doc.entities.retain(|e| {
+            let keep = !self.entity_ids.contains(&e.id);
+            if !keep {
+                removed.entities.push((i, e.clone()));
+            }
+            i += 1;
+            keep
+        });
+        let mut i = 0;
+        doc.parts.retain(|p| {
+            let keep = !self.part_ids.contains(&p.id);
+            if !keep {
+                removed.parts.push((i, p.clone()));
+            }
+            i += 1;
+            keep
+        });
+        *self
+            .removed
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))? = Some(removed);
+        Ok(())
+    }
+
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let removed = self
+            .removed
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .take()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        for (i, e) in removed.entities {
+            doc.entities.insert(i.min(doc.entities.len()), e);
+        }
+        for (i, p) in removed.parts {
+            doc.parts.insert(i.min(doc.parts.len()), p);
+        }
+        Ok(())
+    }
+
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Cut(*self)
+    }
+}
+
+/// Pastes `payload` moved by `offset`. Every pasted item gets a new id;
+/// layers and materials are matched by name in the target document and
+/// created when missing.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PasteInput {
+    pub payload: ClipboardPayload,
+    pub offset: Vec2,
+}
+
+pub struct PasteCommand {
+    preview: Option<PasteInput>,
+}
+
+impl PasteCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for PasteCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for PasteCommand {
+    type Input = PasteInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if input.payload.version != CLIPBOARD_VERSION {
+            let mut r = Reason::from_code(ReasonCode::SerializeUnsupportedSchemaVersion);
+            r.params.insert(
+                "schema_version".into(),
+                serde_json::json!(input.payload.version),
+            );
+            return Err(r);
+        }
+        if input.payload.is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditNoSelection));
+        }
+        if !input.offset.x.is_finite() || !input.offset.y.is_finite() {
+            return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditNoSelection))?;
+        Ok(Box::new(PasteDelta {
+            payload: input.payload,
+            offset: input.offset,
+            pasted: Mutex::new(None),
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// What a paste adds to the target document, ids already remapped.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Pasted {
+    layers: Vec<Layer>,
+    materials: Vec<Material>,
+    entities: Vec<Entity>,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PasteDelta {
+    payload: ClipboardPayload,
+    offset: Vec2,
+    /// Resolved on the first `apply` so redo reuses the same ids.
+    pasted: Mutex<Option<Pasted>>,
+}
+
+impl PasteDelta {
+    fn resolve(&self, doc: &Document) -> Result<Pasted> {
+        let p = &self.payload;
+        let (dx, dy) = (self.offset.x, self.offset.y);
+        let mut out = Pasted {
+            layers: vec![],
+            materials: vec![],
+            entities: vec![],
+            parts: vec![],
+        };
+
+        let mut layer_ids = BTreeMap::new();
+        for layer in &p.layers {
+            let id = match doc.layers.iter().find(|l| l.name == layer.name) {
+                Some(existing) => existing.id,
+                // A new layer keeps the source's style but not its lock
+                // or visibility, so the pasted geometry stays editable.
+                None => {
+                    let created = Layer {
+                        id: Uuid::new_v4(),
+                        visible: true,
+                        locked: false,
+                        editable: true,
+                        ..layer.clone()
+                    };
+                    out.layers.push(created.clone());
+                    created.id
+                }
+            };
+            layer_ids.insert(layer.id, id);
+        }
+        let mut material_ids = BTreeMap::new();
+        for material in &p.materials {
+            let id = match doc.materials.iter().find(|m| m.name == material.name) {
+                Some(existing) => existing.id,
+                None => {
+                    let created = Material {
+                        id: Uuid::new_v4(),
+                        ..material.clone()
+                    };
+                    out.materials.push(created.clone());
+                    created.id
+                }
+            };
+            material_ids.insert(material.id, id);
+        }
+
+        for e in &p.entities {
+            let layer_id = *layer_ids
+                .get(&e.layer_id)
+                .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+            if !out.layers.iter().any(|l| l.id == layer_id) {
+                check_editable(doc, layer_id)?;
+            }
+            out.entities.push(Entity {
+                id: Uuid::new_v4(),
+                layer_id,
+                geom: translate(&e.geom, dx, dy)?,
+                ..e.clone()
+            });
+        }
+        let shift = |pts: &[Vec2]| -> Vec<Vec2> {
+            pts.iter()
+                .map(|v| Vec2 {
+                    x: v.x + dx,
+                    y: v.y + dy,
+                })
+                .collect()
+        };
+        for part in &p.parts {
+            let mut pasted = part.clone();
+            pasted.id = Uuid::new_v4();
+            pasted.material_id = material_ids
+                .get(&part.material_id)
+                .copied()
+                .unwrap_or(part.material_id);
+            pasted.outline.outer = shift(&part.outline.outer);
+            pasted.outline.holes = part.outline.holes.iter().map(|h| shift(h)).collect();
+            out.parts.push(pasted);
+        }
+        Ok(out)
+    }
+}
+
+impl Delta for PasteDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        let mut guard = self
+            .pasted
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        if guard.is_none() {
+            *guard = Some(self.resolve(doc)?);
+        }
+        let pasted = guard
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        doc.layers.extend(pasted.layers.iter().cloned());
+        doc.materials.extend(pasted.materials.iter().cloned());
+        doc.entities.extend(pasted.entities.iter().cloned());
+        doc.parts.extend(pasted.parts.iter().cloned());
+        Ok(())
+    }
+
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let guard = self
+            .pasted
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let pasted = guard
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let present = doc
+            .entities
+            .iter()
+            .filter(|e| pasted.entities.iter().any(|x| x.id == e.id))
+            .count()
+            + doc
+                .parts
+                .iter()
+                .filter(|p| pasted.parts.iter().any(|x| x.id == p.id))
+                .count();
+        if present != pasted.entities.len() + pasted.parts.len() {
+            return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
+        }
+        doc.entities
+            .retain(|e| !pasted.entities.iter().any(|x| x.id == e.id));
+        doc.parts
+            .retain(|p| !pasted.parts.iter().any(|x| x.id == p.id));
+        doc.layers
+            .retain(|l| !pasted.layers.iter().any(|x| x.id == l.id));
+        doc.materials
+            .retain(|m| !pasted.materials.iter().any(|x| x.id == m.id));
+        Ok(())
+    }
+
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Paste(*self)
+    }
+}
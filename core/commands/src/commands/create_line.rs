@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_serialize::{Document, Entity, Geom2D, Reason, ReasonCode, Result, Vec2};
 use std::collections::BTreeMap;
 use uuid::Uuid;
@@ -74,7 +74,7 @@ impl Command for CreateLineCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CreateLineDelta {
     entity: Entity,
 }
@@ -96,4 +96,7 @@ impl Delta for CreateLineDelta {
         }
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::CreateLine(*self)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_faces::Face;
 use craftcad_part_ops::create_part_from_face;
 use craftcad_serialize::{Document, Part, Reason, ReasonCode, Result, Vec2};
@@ -237,7 +237,7 @@ impl Command for DeletePartCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CreatePartDelta {
     part: Part,
 }
@@ -254,9 +254,12 @@ impl Delta for CreatePartDelta {
         }
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::CreatePart(*self)
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UpdatePartDelta {
     before: Part,
     after: Part,
@@ -280,9 +283,12 @@ impl Delta for UpdatePartDelta {
         *p = self.before.clone();
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::UpdatePart(*self)
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeletePartDelta {
     part: Part,
 }
@@ -299,4 +305,7 @@ impl Delta for DeletePartDelta {
         doc.parts.push(self.part.clone());
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::DeletePart(*self)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_serialize::{Document, Entity, Geom2D, Reason, ReasonCode, Result, Vec2};
 use std::collections::BTreeMap;
 use uuid::Uuid;
@@ -315,7 +315,7 @@ impl Command for CreatePolylineCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CreateEntityDelta {
     entity: Entity,
 }
@@ -333,4 +333,7 @@ impl Delta for CreateEntityDelta {
         }
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::CreateEntity(*self)
+    }
 }
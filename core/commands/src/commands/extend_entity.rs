@@ -0,0 +1,174 @@
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_serialize::{Document, Geom2D, Reason, ReasonCode, Result, Vec2};
+use diycad_geom::{extend_to_boundary, EpsilonPolicy, Geom2D as GeomOp, Vec2 as Vec2Op};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct ExtendEntityInput {
+    pub entity_id: Uuid,
+    pub boundary_ids: Vec<Uuid>,
+    pub pick_point: Vec2,
+    pub eps: EpsilonPolicy,
+    pub candidate_index: Option<usize>,
+}
+
+pub struct ExtendEntityCommand {
+    preview: Option<ExtendEntityInput>,
+}
+impl ExtendEntityCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for ExtendEntityCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ExtendEntityCommand {
+    type Input = ExtendEntityInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if !input.pick_point.x.is_finite() || !input.pick_point.y.is_finite() {
+            return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
+        }
+        if !input.boundary_ids.iter().any(|id| *id != input.entity_id) {
+            return Err(Reason::from_code(ReasonCode::EditNoSelection));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let i = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditInvalidNumeric))?;
+        Ok(Box::new(ExtendEntityDelta {
+            entity_id: i.entity_id,
+            boundary_ids: i.boundary_ids,
+            pick_point: i.pick_point,
+            eps: i.eps,
+            candidate_index: i.candidate_index,
+            cached: Mutex::new(None),
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExtendEntityDelta {
+    entity_id: Uuid,
+    boundary_ids: Vec<Uuid>,
+    pick_point: Vec2,
+    eps: EpsilonPolicy,
+    candidate_index: Option<usize>,
+    cached: Mutex<Option<(Uuid, Geom2D, Geom2D)>>,
+}
+
+fn to_geom_op(g: &Geom2D) -> Result<GeomOp> {
+    serde_json::from_value(
+        serde_json::to_value(g)
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?,
+    )
+    .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))
+}
+fn to_geom(g: &GeomOp) -> Result<Geom2D> {
+    serde_json::from_value(
+        serde_json::to_value(g)
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?,
+    )
+    .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))
+}
+
+impl Delta for ExtendEntityDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        let mut cache = self
+            .cached
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        if cache.is_none() {
+            let target = doc
+                .entities
+                .iter()
+                .find(|e| e.id == self.entity_id)
+                .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+            let boundaries = self
+                .boundary_ids
+                .iter()
+                .filter(|id| **id != self.entity_id)
+                .map(|id| {
+                    doc.entities
+                        .iter()
+                        .find(|e| e.id == *id)
+                        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+                        .and_then(|e| to_geom_op(&e.geom))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let layer = doc
+                .layers
+                .iter()
+                .find(|l| l.id == target.layer_id)
+                .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+            if !layer.visible || layer.locked || !layer.editable {
+                return Err(Reason::from_code(ReasonCode::EditTargetLockedOrHidden));
+            }
+            let target_op = to_geom_op(&target.geom)?;
+            let pick = Vec2Op {
+                x: self.pick_point.x,
+                y: self.pick_point.y,
+            };
+            let after_op = extend_to_boundary(
+                &target_op,
+                &boundaries,
+                pick,
+                &self.eps,
+                self.candidate_index,
+            )?;
+            *cache = Some((target.layer_id, target.geom.clone(), to_geom(&after_op)?));
+        }
+        let (layer_id, _before, after) = cache
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let e = doc
+            .entities
+            .iter_mut()
+            .find(|e| e.id == self.entity_id)
+            .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+        if &e.layer_id != layer_id {
+            return Err(Reason::from_code(ReasonCode::CoreInvariantViolation));
+        }
+        e.geom = after.clone();
+        Ok(())
+    }
+
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let cache = self
+            .cached
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let (layer_id, before, _after) = cache
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let e = doc
+            .entities
+            .iter_mut()
+            .find(|e| e.id == self.entity_id)
+            .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+        if &e.layer_id != layer_id {
+            return Err(Reason::from_code(ReasonCode::CoreInvariantViolation));
+        }
+        e.geom = before.clone();
+        Ok(())
+    }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::ExtendEntity(*self)
+    }
+}
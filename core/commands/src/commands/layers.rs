@@ -0,0 +1,469 @@
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_serialize::{Document, Layer, Reason, ReasonCode, Result};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const LINETYPE_MAX_LEN: usize = 32;
+
+/// One edit of the layer table, tagged by `op` in JSON.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op")]
+pub enum LayerEdit {
+    /// Appends a visible, editable layer.
+    Create {
+        name: String,
+    },
+    Rename {
+        layer_id: Uuid,
+        name: String,
+    },
+    /// Removes a layer. Geometry on it moves to `reassign_to`; without a
+    /// target only empty layers can be deleted.
+    Delete {
+        layer_id: Uuid,
+        #[serde(default)]
+        reassign_to: Option<Uuid>,
+    },
+    /// Moves a layer to `index` in drawing order.
+    Reorder {
+        layer_id: Uuid,
+        index: usize,
+    },
+    /// Moves everything on `from` to `into` and removes `from`.
+    Merge {
+        from: Uuid,
+        into: Uuid,
+    },
+    /// Sets or clears (`null`) the layer colour.
+    Recolor {
+        layer_id: Uuid,
+        color: Option<String>,
+    },
+    /// Changes only the properties that are given.
+    SetProps {
+        layer_id: Uuid,
+        #[serde(default)]
+        visible: Option<bool>,
+        #[serde(default)]
+        locked: Option<bool>,
+        #[serde(default)]
+        editable: Option<bool>,
+        #[serde(default)]
+        linetype: Option<String>,
+        #[serde(default)]
+        print: Option<bool>,
+    },
+    /// Moves entities and block instances to `layer_id`.
+    MoveEntities {
+        ids: Vec<Uuid>,
+        layer_id: Uuid,
+    },
+}
+
+fn check_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Reason::from_code(ReasonCode::EditLayerNameInvalid));
+    }
+    Ok(name.to_string())
+}
+
+fn check_color(color: &str) -> Result<()> {
+    let hex = color.strip_prefix('#').unwrap_or("");
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        let mut r = Reason::from_code(ReasonCode::EditLayerStyleInvalid);
+        r.params.insert("color".into(), serde_json::json!(color));
+        return Err(r);
+    }
+    Ok(())
+}
+
+/// Line type names follow `mapping_rules.json`: trimmed, upper case, spaces
+/// as `_`.
+fn normalize_linetype(linetype: &str) -> Result<String> {
+    let name = linetype
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_uppercase();
+    if name.is_empty() || name.len() > LINETYPE_MAX_LEN {
+        let mut r = Reason::from_code(ReasonCode::EditLayerStyleInvalid);
+        r.params
+            .insert("linetype".into(), serde_json::json!(linetype));
+        return Err(r);
+    }
+    Ok(name)
+}
+
+impl LayerEdit {
+    /// Checks that need no document and normalizes names.
+    fn validated(self) -> Result<Self> {
+        Ok(match self {
+            LayerEdit::Create { name } => LayerEdit::Create {
+                name: check_name(&name)?,
+            },
+            LayerEdit::Rename { layer_id, name } => LayerEdit::Rename {
+                layer_id,
+                name: check_name(&name)?,
+            },
+            LayerEdit::Recolor { layer_id, color } => {
+                if let Some(c) = &color {
+                    check_color(c)?;
+                }
+                LayerEdit::Recolor {
+                    layer_id,
+                    color: color.map(|c| c.to_uppercase()),
+                }
+            }
+            LayerEdit::SetProps {
+                layer_id,
+                visible,
+                locked,
+                editable,
+                linetype,
+                print,
+            } => LayerEdit::SetProps {
+                layer_id,
+                visible,
+                locked,
+                editable,
+                linetype: linetype.as_deref().map(normalize_linetype).transpose()?,
+                print,
+            },
+            LayerEdit::MoveEntities { ids, .. } if ids.is_empty() => {
+                return Err(Reason::from_code(ReasonCode::EditNoSelection));
+            }
+            LayerEdit::Merge { from, into } if from == into => {
+                return Err(Reason::from_code(ReasonCode::EditLayerInUse));
+            }
+            other => other,
+        })
+    }
+}
+
+pub struct LayerCommand {
+    preview: Option<LayerEdit>,
+}
+
+impl LayerCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for LayerCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for LayerCommand {
+    type Input = LayerEdit;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        self.preview = Some(input.validated()?);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let edit = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+        Ok(Box::new(LayersDelta {
+            edit,
+            new_id: Uuid::new_v4(),
+            change: Mutex::new(None),
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+/// What a layer edit changed: the edited layer with its index before and
+/// after (absent when created or removed), and the entities and block
+/// instances it moved, with the layer each was on.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+struct LayerChange {
+    before: Option<(usize, Layer)>,
+    after: Option<(usize, Layer)>,
+    reassigned: Vec<(Uuid, Uuid)>,
+}
+
+fn index(doc: &Document, layer_id: Uuid) -> Result<usize> {
+    doc.layers
+        .iter()
+        .position(|l| l.id == layer_id)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+}
+
+/// Index of a layer whose contents may change.
+fn unlocked(doc: &Document, layer_id: Uuid) -> Result<usize> {
+    let at = index(doc, layer_id)?;
+    let l = &doc.layers[at];
+    if l.locked || !l.editable {
+        return Err(Reason::from_code(ReasonCode::EditTargetLockedOrHidden));
+    }
+    Ok(at)
+}
+
+fn check_unique(doc: &Document, name: &str, except: Option<Uuid>) -> Result<()> {
+    if doc
+        .layers
+        .iter()
+        .any(|l| l.name == name && Some(l.id) != except)
+    {
+        let mut r = Reason::from_code(ReasonCode::EditLayerNameInvalid);
+        r.params.insert("name".into(), serde_json::json!(name));
+        return Err(r);
+    }
+    Ok(())
+}
+
+/// Every `(id, layer_id)` reference, including those inside block
+/// definitions.
+fn layer_refs(doc: &Document) -> impl Iterator<Item = (Uuid, Uuid)> + '_ {
+    let blocks = doc.blocks.iter().flat_map(|b| {
+        b.entities
+            .iter()
+            .map(|e| (e.id, e.layer_id))
+            .chain(b.instances.iter().map(|i| (i.id, i.layer_id)))
+    });
+    doc.entities
+        .iter()
+        .map(|e| (e.id, e.layer_id))
+        .chain(doc.block_instances.iter().map(|i| (i.id, i.layer_id)))
+        .chain(blocks)
+}
+
+/// Mutable form of [`layer_refs`].
+fn layer_refs_mut(doc: &mut Document) -> impl Iterator<Item = (Uuid, &mut Uuid)> + '_ {
+    let blocks = doc.blocks.iter_mut().flat_map(|b| {
+        b.entities
+            .iter_mut()
+            .map(|e| (e.id, &mut e.layer_id))
+            .chain(b.instances.iter_mut().map(|i| (i.id, &mut i.layer_id)))
+    });
+    doc.entities
+        .iter_mut()
+        .map(|e| (e.id, &mut e.layer_id))
+        .chain(
+            doc.block_instances
+                .iter_mut()
+                .map(|i| (i.id, &mut i.layer_id)),
+        )
+        .chain(blocks)
+}
+
+/// Removes `layer_id`, moving its geometry to `into` if given.
+fn remove(doc: &Document, layer_id: Uuid, into: Option<Uuid>) -> Result<LayerChange> {
+    let at = unlocked(doc, layer_id)?;
+    if doc.layers.len() == 1 {
+        return Err(Reason::from_code(ReasonCode::EditLayerInUse));
+    }
+    let on_layer = layer_refs(doc).filter(|(_, l)| *l == layer_id);
+    let reassigned = match into {
+        Some(into) if into != layer_id => {
+            unlocked(doc, into)?;
+            on_layer.collect()
+        }
+        _ => {
+            let used = on_layer.count();
+            if used > 0 {
+                let mut r = Reason::from_code(ReasonCode::EditLayerInUse);
+                r.params.insert("count".into(), serde_json::json!(used));
+                return Err(r);
+            }
+            vec![]
+        }
+    };
+    Ok(LayerChange {
+        before: Some((at, doc.layers[at].clone())),
+        after: None,
+        reassigned,
+    })
+}
+
+impl LayerEdit {
+    /// Layer that reassigned geometry moves to.
+    fn target(&self) -> Option<Uuid> {
+        match self {
+            LayerEdit::Delete { reassign_to, .. } => *reassign_to,
+            LayerEdit::Merge { into, .. } => Some(*into),
+            LayerEdit::MoveEntities { layer_id, .. } => Some(*layer_id),
+            _ => None,
+        }
+    }
+
+    /// Validates the edit against `doc` and works out what it changes,
+    /// without touching the document.
+    fn resolve(&self, doc: &Document, new_id: Uuid) -> Result<LayerChange> {
+        let modified = |layer_id: Uuid, f: &dyn Fn(&mut Layer)| -> Result<LayerChange> {
+            let at = index(doc, layer_id)?;
+            let mut l = doc.layers[at].clone();
+            f(&mut l);
+            Ok(LayerChange {
+                before: Some((at, doc.layers[at].clone())),
+                after: Some((at, l)),
+                reassigned: vec![],
+            })
+        };
+        Ok(match self {
+            LayerEdit::Create { name } => {
+                check_unique(doc, name, None)?;
+                LayerChange {
+                    after: Some((
+                        doc.layers.len(),
+                        Layer {
+                            id: new_id,
+                            name: name.clone(),
+                            visible: true,
+                            locked: false,
+                            editable: true,
+                            color: None,
+                            linetype: "CONTINUOUS".into(),
+                            print: true,
+                        },
+                    )),
+                    ..LayerChange::default()
+                }
+            }
+            LayerEdit::Rename { layer_id, name } => {
+                check_unique(doc, name, Some(*layer_id))?;
+                modified(*layer_id, &|l| l.name = name.clone())?
+            }
+            LayerEdit::Delete {
+                layer_id,
+                reassign_to,
+            } => remove(doc, *layer_id, *reassign_to)?,
+            LayerEdit::Reorder {
+                layer_id,
+                index: to,
+            } => {
+                let at = index(doc, *layer_id)?;
+                if *to >= doc.layers.len() {
+                    return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
+                }
+                LayerChange {
+                    before: Some((at, doc.layers[at].clone())),
+                    after: Some((*to, doc.layers[at].clone())),
+                    reassigned: vec![],
+                }
+            }
+            LayerEdit::Merge { from, into } => remove(doc, *from, Some(*into))?,
+            LayerEdit::Recolor { layer_id, color } => {
+                modified(*layer_id, &|l| l.color = color.clone())?
+            }
+            LayerEdit::SetProps {
+                layer_id,
+                visible,
+                locked,
+                editable,
+                linetype,
+                print,
+            } => modified(*layer_id, &|l| {
+                l.visible = visible.unwrap_or(l.visible);
+                l.locked = locked.unwrap_or(l.locked);
+                l.editable = editable.unwrap_or(l.editable);
+                l.print = print.unwrap_or(l.print);
+                if let Some(t) = linetype {
+                    l.linetype = t.clone();
+                }
+            })?,
+            LayerEdit::MoveEntities { ids, layer_id } => {
+                unlocked(doc, *layer_id)?;
+                let mut reassigned = vec![];
+                for id in ids {
+                    let from = if let Some(e) = doc.entities.iter().find(|e| e.id == *id) {
+                        e.layer_id
+                    } else if let Some(i) = doc.block_instances.iter().find(|i| i.id == *id) {
+                        i.layer_id
+                    } else {
+                        return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
+                    };
+                    unlocked(doc, from)?;
+                    reassigned.push((*id, from));
+                }
+                LayerChange {
+                    reassigned,
+                    ..LayerChange::default()
+                }
+            }
+        })
+    }
+}
+
+impl LayerChange {
+    fn redo(&self, doc: &mut Document, target: Option<Uuid>) {
+        if let Some(into) = target {
+            for (id, l) in layer_refs_mut(doc) {
+                if self.reassigned.iter().any(|(r, _)| *r == id) {
+                    *l = into;
+                }
+            }
+        }
+        swap_layer(doc, &self.before, &self.after);
+    }
+
+    fn undo(&self, doc: &mut Document) {
+        swap_layer(doc, &self.after, &self.before);
+        for (id, l) in layer_refs_mut(doc) {
+            if let Some((_, old)) = self.reassigned.iter().find(|(r, _)| *r == id) {
+                *l = *old;
+            }
+        }
+    }
+}
+
+/// Takes `from` out of the layer table and puts `to` in at its index.
+fn swap_layer(doc: &mut Document, from: &Option<(usize, Layer)>, to: &Option<(usize, Layer)>) {
+    if let Some((_, l)) = from {
+        doc.layers.retain(|x| x.id != l.id);
+    }
+    if let Some((at, l)) = to {
+        doc.layers.insert((*at).min(doc.layers.len()), l.clone());
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LayersDelta {
+    edit: LayerEdit,
+    /// Id of the layer made by `LayerEdit::Create`, fixed so redo matches.
+    new_id: Uuid,
+    /// Resolved on the first `apply`.
+    change: Mutex<Option<LayerChange>>,
+}
+
+impl Delta for LayersDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        let mut change = self
+            .change
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        if change.is_none() {
+            *change = Some(self.edit.resolve(doc, self.new_id)?);
+        }
+        change
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .redo(doc, self.edit.target());
+        Ok(())
+    }
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let change = self
+            .change
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        change
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .undo(doc);
+        Ok(())
+    }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Layers(*self)
+    }
+}
@@ -1,9 +1,14 @@
 pub mod advanced_edit;
+pub mod blocks;
+pub mod clipboard;
 pub mod create_line;
 pub mod create_part;
 pub mod create_shapes;
+pub mod layers;
+pub mod selection_set;
 pub mod transform_selection;
 
+pub mod extend_entity;
 pub mod offset_entity;
 pub mod trim_entity;
 
@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_serialize::{Document, NestResultV1, NestTraceV1, Reason, ReasonCode, Result};
 use diycad_geom::EpsilonPolicy;
 use diycad_nesting::{run_nesting, RunLimits};
@@ -61,7 +61,7 @@ impl Command for RunNestingCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RunNestingDelta {
     job_id: Uuid,
     before_result: Option<NestResultV1>,
@@ -90,6 +90,9 @@ impl Delta for RunNestingDelta {
         j.trace = self.before_trace.clone();
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::RunNesting(*self)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -148,7 +151,7 @@ impl Command for EditPlacementCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EditPlacementDelta {
     job_id: Uuid,
     part_id: Uuid,
@@ -217,4 +220,7 @@ impl Delta for EditPlacementDelta {
         plc.bbox.max_y = plc.y + h;
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::EditPlacement(*self)
+    }
 }
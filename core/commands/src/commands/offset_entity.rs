@@ -1,6 +1,6 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_serialize::{Document, Entity, Geom2D, Reason, ReasonCode, Result};
-use diycad_geom::{offset, EpsilonPolicy, Geom2D as GeomOp};
+use diycad_geom::{offset_with_join, EpsilonPolicy, Geom2D as GeomOp, JoinStyle};
 use std::sync::Mutex;
 use uuid::Uuid;
 
@@ -8,6 +8,7 @@ use uuid::Uuid;
 pub struct OffsetEntityInput {
     pub entity_id: Uuid,
     pub dist: f64,
+    pub join: JoinStyle,
     pub eps: EpsilonPolicy,
 }
 
@@ -52,6 +53,7 @@ impl Command for OffsetEntityCommand {
         Ok(Box::new(OffsetEntityDelta {
             entity_id: input.entity_id,
             dist: input.dist,
+            join: input.join,
             eps: input.eps,
             new_entity_id: self.new_entity_id,
             cached: Mutex::new(None),
@@ -63,13 +65,16 @@ impl Command for OffsetEntityCommand {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct OffsetEntityDelta {
     entity_id: Uuid,
     dist: f64,
+    join: JoinStyle,
     eps: EpsilonPolicy,
     new_entity_id: Uuid,
-    cached: Mutex<Option<Entity>>,
+    // Closed outlines offset with round joins come back as a line/arc chain; the
+    // first entity keeps `new_entity_id`, the rest get fresh ids on first apply.
+    cached: Mutex<Option<Vec<Entity>>>,
 }
 
 fn to_geom_op(g: &Geom2D) -> Result<GeomOp> {
@@ -108,32 +113,54 @@ impl Delta for OffsetEntityDelta {
             if !layer.visible || layer.locked || !layer.editable {
                 return Err(Reason::from_code(ReasonCode::EditTargetLockedOrHidden));
             }
-            let geom = offset(&to_geom_op(&src.geom)?, self.dist, &self.eps)?;
-            *cache = Some(Entity {
-                id: self.new_entity_id,
-                layer_id: src.layer_id,
-                geom: to_geom(&geom)?,
-                style: src.style.clone(),
-                tags: src.tags.clone(),
-                meta: src.meta.clone(),
-            });
+            let geoms = offset_with_join(&to_geom_op(&src.geom)?, self.dist, self.join, &self.eps)?;
+            let mut out = Vec::with_capacity(geoms.len());
+            for (i, g) in geoms.iter().enumerate() {
+                out.push(Entity {
+                    id: if i == 0 {
+                        self.new_entity_id
+                    } else {
+                        Uuid::new_v4()
+                    },
+                    layer_id: src.layer_id,
+                    geom: to_geom(g)?,
+                    style: src.style.clone(),
+                    tags: src.tags.clone(),
+                    meta: src.meta.clone(),
+                });
+            }
+            *cache = Some(out);
         }
-        let ent = cache
+        let ents = cache
             .as_ref()
             .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
-        if !doc.layers.iter().any(|l| l.id == ent.layer_id) {
+        if ents
+            .iter()
+            .any(|ent| !doc.layers.iter().any(|l| l.id == ent.layer_id))
+        {
             return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
         }
-        doc.entities.push(ent.clone());
+        doc.entities.extend(ents.iter().cloned());
         Ok(())
     }
 
     fn revert(&self, doc: &mut Document) -> Result<()> {
+        let cache = self
+            .cached
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let ids: Vec<Uuid> = cache
+            .as_ref()
+            .map(|ents| ents.iter().map(|e| e.id).collect())
+            .unwrap_or_else(|| vec![self.new_entity_id]);
         let before = doc.entities.len();
-        doc.entities.retain(|e| e.id != self.new_entity_id);
+        doc.entities.retain(|e| !ids.contains(&e.id));
         if before == doc.entities.len() {
             return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
         }
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::OffsetEntity(*self)
+    }
 }
@@ -0,0 +1,115 @@
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
+use craftcad_serialize::{Document, Reason, ReasonCode, Result, SelectionSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Saves `entity_ids` under `name`, replacing a set of the same name. An
+/// empty id list deletes the set.
+#[derive(Debug, Clone)]
+pub struct SaveSelectionSetInput {
+    pub name: String,
+    pub entity_ids: Vec<Uuid>,
+}
+
+pub struct SaveSelectionSetCommand {
+    preview: Option<SaveSelectionSetInput>,
+}
+
+impl SaveSelectionSetCommand {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+}
+impl Default for SaveSelectionSetCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for SaveSelectionSetCommand {
+    type Input = SaveSelectionSetInput;
+    fn begin(&mut self, _ctx: &CommandContext) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+    fn update(&mut self, input: Self::Input) -> Result<()> {
+        if input.name.trim().is_empty() {
+            return Err(Reason::from_code(ReasonCode::EditSelectionSetNameInvalid));
+        }
+        self.preview = Some(input);
+        Ok(())
+    }
+    fn commit(&mut self) -> Result<Box<dyn Delta>> {
+        let input = self
+            .preview
+            .clone()
+            .ok_or_else(|| Reason::from_code(ReasonCode::EditSelectionSetNameInvalid))?;
+        let mut entity_ids = input.entity_ids;
+        entity_ids.sort();
+        entity_ids.dedup();
+        Ok(Box::new(SelectionSetDelta {
+            name: input.name.clone(),
+            after: (!entity_ids.is_empty()).then_some(SelectionSet {
+                name: input.name,
+                entity_ids,
+            }),
+            before: Mutex::new(None),
+        }))
+    }
+    fn cancel(&mut self) -> Result<()> {
+        self.preview = None;
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SelectionSetDelta {
+    name: String,
+    after: Option<SelectionSet>,
+    /// Index and contents of the set replaced by the last `apply`.
+    before: Mutex<Option<(usize, Option<SelectionSet>)>>,
+}
+
+impl Delta for SelectionSetDelta {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        let sets = &mut doc.selection_sets;
+        let at = sets.iter().position(|s| s.name == self.name);
+        let before = match at {
+            Some(i) => (i, Some(sets.remove(i))),
+            None => (sets.len(), None),
+        };
+        if let Some(set) = &self.after {
+            sets.insert(before.0, set.clone());
+        }
+        *self
+            .before
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))? = Some(before);
+        Ok(())
+    }
+
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        let (i, before) = self
+            .before
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .take()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let sets = &mut doc.selection_sets;
+        if self.after.is_some() {
+            match sets.iter().position(|s| s.name == self.name) {
+                Some(j) => {
+                    sets.remove(j);
+                }
+                None => return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound)),
+            }
+        }
+        if let Some(set) = before {
+            sets.insert(i.min(sets.len()), set);
+        }
+        Ok(())
+    }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::SelectionSet(*self)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_edit_ops::{rotate, scale, translate};
 use craftcad_serialize::{Document, Geom2D, Reason, ReasonCode, Result, Vec2};
 use std::sync::Mutex;
@@ -18,6 +18,27 @@ pub struct TransformSelectionInput {
     pub transform: Transform,
 }
 
+impl TransformSelectionInput {
+    /// Input for the entities of the named selection set saved in `doc`.
+    /// Entities deleted since the set was saved are left out.
+    pub fn from_set(doc: &Document, name: &str, transform: Transform) -> Result<Self> {
+        let set = doc
+            .selection_sets
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+        Ok(Self {
+            selection_ids: set
+                .entity_ids
+                .iter()
+                .copied()
+                .filter(|id| doc.entities.iter().any(|e| e.id == *id))
+                .collect(),
+            transform,
+        })
+    }
+}
+
 pub struct TransformSelectionCommand {
     preview: Option<TransformSelectionInput>,
 }
@@ -62,7 +83,7 @@ impl Command for TransformSelectionCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct Entry {
     id: Uuid,
     layer_id: Uuid,
@@ -70,7 +91,7 @@ struct Entry {
     after: Geom2D,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TransformSelectionDelta {
     selection_ids: Vec<Uuid>,
     transform: Transform,
@@ -174,4 +195,7 @@ impl Delta for TransformSelectionDelta {
             .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
         Self::apply_entries(doc, entries, false)
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::TransformSelection(*self)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{command::Command, command::CommandContext, delta::Delta};
+use crate::{command::Command, command::CommandContext, delta::Delta, delta::DeltaRecord};
 use craftcad_serialize::{Document, Geom2D, Reason, ReasonCode, Result, Vec2};
 use diycad_geom::{
     trim_line_to_intersection, trim_polyline_to_intersection, EpsilonPolicy, Geom2D as GeomOp,
@@ -63,7 +63,7 @@ impl Command for TrimEntityCommand {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TrimEntityDelta {
     entity_id: Uuid,
     cutter_id: Uuid,
@@ -172,4 +172,7 @@ impl Delta for TrimEntityDelta {
         e.geom = before.clone();
         Ok(())
     }
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::TrimEntity(*self)
+    }
 }
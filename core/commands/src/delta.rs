@@ -1,6 +1,112 @@
+use crate::commands::{
+    advanced_edit::EntitiesDelta,
+    blocks::BlocksDelta,
+    clipboard::{CutDelta, PasteDelta},
+    create_line::CreateLineDelta,
+    create_part::{CreatePartDelta, DeletePartDelta, UpdatePartDelta},
+    create_shapes::CreateEntityDelta,
+    extend_entity::ExtendEntityDelta,
+    layers::LayersDelta,
+    nesting::{EditPlacementDelta, RunNestingDelta},
+    offset_entity::OffsetEntityDelta,
+    selection_set::SelectionSetDelta,
+    transform_selection::TransformSelectionDelta,
+    trim_entity::TrimEntityDelta,
+};
+use crate::history::DeltaGroup;
 use craftcad_serialize::{Document, Result};
 
 pub trait Delta: Send {
     fn apply(&self, doc: &mut Document) -> Result<()>;
     fn revert(&self, doc: &mut Document) -> Result<()>;
+    /// Serializable form of this delta, including any state cached by
+    /// `apply` that `revert` depends on.
+    fn into_record(self: Box<Self>) -> DeltaRecord;
+}
+
+/// Every delta kind, tagged by `kind` in the history journal.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum DeltaRecord {
+    CreateLine(CreateLineDelta),
+    CreateEntity(CreateEntityDelta),
+    CreatePart(CreatePartDelta),
+    UpdatePart(UpdatePartDelta),
+    DeletePart(DeletePartDelta),
+    Entities(EntitiesDelta),
+    TransformSelection(TransformSelectionDelta),
+    OffsetEntity(OffsetEntityDelta),
+    TrimEntity(TrimEntityDelta),
+    ExtendEntity(ExtendEntityDelta),
+    RunNesting(RunNestingDelta),
+    EditPlacement(EditPlacementDelta),
+    SelectionSet(SelectionSetDelta),
+    Cut(CutDelta),
+    Paste(PasteDelta),
+    Blocks(BlocksDelta),
+    Layers(LayersDelta),
+    Group(DeltaGroup),
+}
+
+impl DeltaRecord {
+    /// The `kind` tag as written to the journal.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeltaRecord::CreateLine(_) => "CreateLine",
+            DeltaRecord::CreateEntity(_) => "CreateEntity",
+            DeltaRecord::CreatePart(_) => "CreatePart",
+            DeltaRecord::UpdatePart(_) => "UpdatePart",
+            DeltaRecord::DeletePart(_) => "DeletePart",
+            DeltaRecord::Entities(_) => "Entities",
+            DeltaRecord::TransformSelection(_) => "TransformSelection",
+            DeltaRecord::OffsetEntity(_) => "OffsetEntity",
+            DeltaRecord::TrimEntity(_) => "TrimEntity",
+            DeltaRecord::ExtendEntity(_) => "ExtendEntity",
+            DeltaRecord::RunNesting(_) => "RunNesting",
+            DeltaRecord::EditPlacement(_) => "EditPlacement",
+            DeltaRecord::SelectionSet(_) => "SelectionSet",
+            DeltaRecord::Cut(_) => "Cut",
+            DeltaRecord::Paste(_) => "Paste",
+            DeltaRecord::Blocks(_) => "Blocks",
+            DeltaRecord::Layers(_) => "Layers",
+            DeltaRecord::Group(_) => "Group",
+        }
+    }
+
+    fn inner(&self) -> &dyn Delta {
+        match self {
+            DeltaRecord::CreateLine(d) => d,
+            DeltaRecord::CreateEntity(d) => d,
+            DeltaRecord::CreatePart(d) => d,
+            DeltaRecord::UpdatePart(d) => d,
+            DeltaRecord::DeletePart(d) => d,
+            DeltaRecord::Entities(d) => d,
+            DeltaRecord::TransformSelection(d) => d,
+            DeltaRecord::OffsetEntity(d) => d,
+            DeltaRecord::TrimEntity(d) => d,
+            DeltaRecord::ExtendEntity(d) => d,
+            DeltaRecord::RunNesting(d) => d,
+            DeltaRecord::EditPlacement(d) => d,
+            DeltaRecord::SelectionSet(d) => d,
+            DeltaRecord::Cut(d) => d,
+            DeltaRecord::Paste(d) => d,
+            DeltaRecord::Blocks(d) => d,
+            DeltaRecord::Layers(d) => d,
+            DeltaRecord::Group(d) => d,
+        }
+    }
+}
+
+impl Delta for DeltaRecord {
+    fn apply(&self, doc: &mut Document) -> Result<()> {
+        self.inner().apply(doc)
+    }
+
+    fn revert(&self, doc: &mut Document) -> Result<()> {
+        self.inner().revert(doc)
+    }
+
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        *self
+    }
 }
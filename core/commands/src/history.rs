@@ -1,12 +1,112 @@
-use crate::delta::Delta;
-use craftcad_serialize::{Document, Result};
+use crate::delta::{Delta, DeltaRecord};
+use craftcad_serialize::{Document, Reason, ReasonCode, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
 
+/// Version of the journal written by [`History::journal`]. Version 1
+/// journals (linear undo/redo stacks) are still read.
+pub const HISTORY_JOURNAL_VERSION: u32 = 2;
+
+/// Id of the node holding the state before any recorded change.
+pub const ROOT_NODE: usize = 0;
+
+/// Undo history as a tree: pushing after an undo starts a new branch and
+/// keeps the old one reachable through [`History::jump_to`].
 pub struct History {
-    undo_stack: Vec<Box<dyn Delta>>,
-    redo_stack: Vec<Box<dyn Delta>>,
+    nodes: Vec<Node>,
+    current: usize,
     active_group: Option<DeltaGroup>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Node {
+    parent: Option<usize>,
+    /// The change from the parent state; `None` only for the root.
+    delta: Option<DeltaRecord>,
+    /// Child that `redo` follows: the one last created or visited.
+    redo_child: Option<usize>,
+    #[serde(skip)]
+    children: Vec<usize>,
+}
+
+/// One undo or redo step as shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct HistoryEntry {
+    /// Node reached by redoing this step; pass to `jump_to`.
+    pub node: usize,
+    /// The `kind` tag of the step's delta.
+    pub kind: String,
+    /// The group name for grouped steps, otherwise the kind.
+    pub name: String,
+}
+
+/// Undo and redo entries, each listed next step first.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct HistoryEntries {
+    pub undo: Vec<HistoryEntry>,
+    pub redo: Vec<HistoryEntry>,
+}
+
+/// A node of the history tree, in creation order.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct HistoryNode {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    /// `ROOT_NODE` for the first step of a branch from the initial state.
+    pub parent: usize,
+    pub children: Vec<usize>,
+}
+
+/// Differences between two history states, from the first to the second.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct StateDiff {
+    pub entities_added: Vec<Uuid>,
+    pub entities_removed: Vec<Uuid>,
+    pub entities_changed: Vec<Uuid>,
+    pub parts_added: Vec<Uuid>,
+    pub parts_removed: Vec<Uuid>,
+    pub parts_changed: Vec<Uuid>,
+    /// Other top-level document fields that differ, such as `jobs`.
+    pub other_fields: Vec<String>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JournalOut<'a> {
+    version: u32,
+    current: usize,
+    nodes: &'a [Node],
+}
+
+#[derive(serde::Deserialize)]
+struct JournalV1 {
+    undo: Vec<DeltaRecord>,
+    redo: Vec<DeltaRecord>,
+}
+
+#[derive(serde::Deserialize)]
+struct JournalV2 {
+    current: usize,
+    nodes: Vec<Node>,
+}
+
+fn journal_error(key: &str, detail: String) -> Reason {
+    let mut r = Reason::from_code(ReasonCode::SerializePackageCorrupted);
+    r.debug.insert(key.into(), serde_json::json!(detail));
+    r
+}
+
+fn node_not_found(node: usize) -> Reason {
+    let mut r = Reason::from_code(ReasonCode::ModelReferenceNotFound);
+    r.params.insert("node".into(), serde_json::json!(node));
+    r
+}
+
 impl Default for History {
     fn default() -> Self {
         Self::new()
@@ -16,33 +116,62 @@ impl Default for History {
 impl History {
     pub fn new() -> Self {
         Self {
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+            nodes: vec![Node {
+                parent: None,
+                delta: None,
+                redo_child: None,
+                children: Vec::new(),
+            }],
+            current: ROOT_NODE,
             active_group: None,
         }
     }
 
+    /// Records `delta`, already applied to the document, as a child of the
+    /// current state. Existing redo branches are kept.
     pub fn push(&mut self, delta: Box<dyn Delta>) {
+        let record = delta.into_record();
         if let Some(group) = &mut self.active_group {
-            group.push(delta);
+            group.push(record);
         } else {
-            self.undo_stack.push(delta);
+            self.add_node(record);
         }
-        self.redo_stack.clear();
+    }
+
+    fn add_node(&mut self, delta: DeltaRecord) {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            parent: Some(self.current),
+            delta: Some(delta),
+            redo_child: None,
+            children: Vec::new(),
+        });
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(id);
+        parent.redo_child = Some(id);
+        self.current = id;
+    }
+
+    fn delta(&self, node: usize) -> Result<&DeltaRecord> {
+        self.nodes[node]
+            .delta
+            .as_ref()
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))
     }
 
     pub fn undo(&mut self, doc: &mut Document) -> Result<()> {
-        if let Some(delta) = self.undo_stack.pop() {
-            delta.revert(doc)?;
-            self.redo_stack.push(delta);
+        if let Some(parent) = self.nodes[self.current].parent {
+            self.delta(self.current)?.revert(doc)?;
+            self.nodes[parent].redo_child = Some(self.current);
+            self.current = parent;
         }
         Ok(())
     }
 
     pub fn redo(&mut self, doc: &mut Document) -> Result<()> {
-        if let Some(delta) = self.redo_stack.pop() {
-            delta.apply(doc)?;
-            self.undo_stack.push(delta);
+        if let Some(child) = self.nodes[self.current].redo_child {
+            self.delta(child)?.apply(doc)?;
+            self.current = child;
         }
         Ok(())
     }
@@ -56,27 +185,304 @@ impl History {
     pub fn end_group(&mut self) {
         if let Some(group) = self.active_group.take() {
             if !group.is_empty() {
-                self.undo_stack.push(Box::new(group));
-                self.redo_stack.clear();
+                self.add_node(DeltaRecord::Group(group));
+            }
+        }
+    }
+
+    /// Node of the document's present state.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    fn entry(&self, node: usize) -> HistoryEntry {
+        let (kind, name) = match &self.nodes[node].delta {
+            Some(DeltaRecord::Group(g)) => ("Group", g.name.clone()),
+            Some(d) => (d.kind(), d.kind().to_string()),
+            None => ("Root", String::new()),
+        };
+        HistoryEntry {
+            node,
+            kind: kind.to_string(),
+            name,
+        }
+    }
+
+    /// Steps available to undo and redo along the current branch. A group
+    /// still open is not listed until `end_group`.
+    pub fn entries(&self) -> HistoryEntries {
+        let mut undo = vec![];
+        let mut n = self.current;
+        while let Some(parent) = self.nodes[n].parent {
+            undo.push(self.entry(n));
+            n = parent;
+        }
+        let mut redo = vec![];
+        let mut n = self.current;
+        while let Some(child) = self.nodes[n].redo_child {
+            redo.push(self.entry(child));
+            n = child;
+        }
+        HistoryEntries { undo, redo }
+    }
+
+    /// Every recorded step across all branches, in creation order.
+    pub fn nodes(&self) -> Vec<HistoryNode> {
+        (1..self.nodes.len())
+            .map(|id| HistoryNode {
+                entry: self.entry(id),
+                parent: self.nodes[id].parent.unwrap_or(ROOT_NODE),
+                children: self.nodes[id].children.clone(),
+            })
+            .collect()
+    }
+
+    /// Nodes to revert (walking up from `from`) and then apply (walking
+    /// down to `to`) to move between the two states.
+    fn path(&self, from: usize, to: usize) -> Result<(Vec<usize>, Vec<usize>)> {
+        if to >= self.nodes.len() {
+            return Err(node_not_found(to));
+        }
+        let ancestors = |mut n: usize| {
+            let mut out = vec![n];
+            while let Some(p) = self.nodes[n].parent {
+                out.push(p);
+                n = p;
+            }
+            out
+        };
+        let up = ancestors(from);
+        let mut down = ancestors(to);
+        let common = *down
+            .iter()
+            .find(|n| up.contains(n))
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let up = up.into_iter().take_while(|n| *n != common).collect();
+        down.truncate(down.iter().position(|n| *n == common).unwrap_or(0));
+        down.reverse();
+        Ok((up, down))
+    }
+
+    /// Moves `doc` to the state of `node`, reverting up to the common
+    /// ancestor and applying down the target branch. An open group is
+    /// ended first so its changes stay reachable. Redo then continues along
+    /// the branch just visited.
+    pub fn jump_to(&mut self, node: usize, doc: &mut Document) -> Result<()> {
+        self.end_group();
+        let (up, down) = self.path(self.current, node)?;
+        for _ in up {
+            self.undo(doc)?;
+        }
+        for n in down {
+            self.delta(n)?.apply(doc)?;
+            self.current = n;
+            if let Some(p) = self.nodes[n].parent {
+                self.nodes[p].redo_child = Some(n);
+            }
+        }
+        Ok(())
+    }
+
+    /// The document at `node`, derived from `doc` at the current state
+    /// without moving the history.
+    pub fn state_at(&self, node: usize, doc: &Document) -> Result<Document> {
+        let (up, down) = self.path(self.current, node)?;
+        let mut out = doc.clone();
+        for n in up {
+            self.delta(n)?.revert(&mut out)?;
+        }
+        for n in down {
+            self.delta(n)?.apply(&mut out)?;
+        }
+        Ok(out)
+    }
+
+    /// Compares the states at nodes `a` and `b`; `doc` is the current state.
+    pub fn compare(&self, a: usize, b: usize, doc: &Document) -> Result<StateDiff> {
+        let a = self.state_at(a, doc)?;
+        let b = self.state_at(b, doc)?;
+        let mut diff = StateDiff::default();
+        diff_by_id(
+            a.entities.iter().map(|e| (e.id, e)),
+            b.entities.iter().map(|e| (e.id, e)),
+            &mut diff.entities_added,
+            &mut diff.entities_removed,
+            &mut diff.entities_changed,
+        )?;
+        diff_by_id(
+            a.parts.iter().map(|p| (p.id, p)),
+            b.parts.iter().map(|p| (p.id, p)),
+            &mut diff.parts_added,
+            &mut diff.parts_removed,
+            &mut diff.parts_changed,
+        )?;
+        let (av, bv) = (to_json(&a)?, to_json(&b)?);
+        if let (Some(am), Some(bm)) = (av.as_object(), bv.as_object()) {
+            let keys: BTreeSet<&String> = am.keys().chain(bm.keys()).collect();
+            diff.other_fields = keys
+                .into_iter()
+                .filter(|k| !matches!(k.as_str(), "entities" | "parts"))
+                .filter(|k| am.get(*k) != bm.get(*k))
+                .cloned()
+                .collect();
+        }
+        Ok(diff)
+    }
+
+    /// Serializes the whole tree for storage beside the document it was
+    /// recorded against. An open group is written as if it had ended.
+    pub fn journal(&self) -> Result<serde_json::Value> {
+        let mut out = to_json(&JournalOut {
+            version: HISTORY_JOURNAL_VERSION,
+            current: self.current,
+            nodes: &self.nodes,
+        })?;
+        if let Some(g) = self.active_group.as_ref().filter(|g| !g.is_empty()) {
+            // Same shape as `DeltaRecord::Group`, written from a borrow.
+            let mut delta = to_json(g)?;
+            delta["kind"] = serde_json::json!("Group");
+            let id = self.nodes.len();
+            let node = serde_json::json!({
+                "parent": self.current,
+                "delta": delta,
+                "redo_child": null,
+            });
+            out["nodes"][self.current]["redo_child"] = serde_json::json!(id);
+            if let Some(nodes) = out["nodes"].as_array_mut() {
+                nodes.push(node);
+            }
+            out["current"] = serde_json::json!(id);
+        }
+        Ok(out)
+    }
+
+    /// Restores the journal loaded with a package. Undo history never keeps
+    /// a document from opening: a journal that does not restore gives an
+    /// empty history and a `SERIALIZE_HISTORY_DROPPED` warning.
+    pub fn restore(journal: Option<serde_json::Value>) -> (Self, Option<Reason>) {
+        match journal.map(Self::from_journal) {
+            None => (Self::new(), None),
+            Some(Ok(h)) => (h, None),
+            Some(Err(cause)) => {
+                let mut r = Reason::from_code(ReasonCode::SerializeHistoryDropped);
+                r.debug
+                    .insert("cause".into(), serde_json::json!(cause.code));
+                r.debug.extend(cause.debug);
+                (Self::new(), Some(r))
+            }
+        }
+    }
+
+    /// Restores a history saved with [`History::journal`].
+    pub fn from_journal(journal: serde_json::Value) -> Result<Self> {
+        let version = journal.get("version").and_then(|v| v.as_u64());
+        match version {
+            Some(1) => {
+                let j: JournalV1 = serde_json::from_value(journal)
+                    .map_err(|e| journal_error("history_decode", e.to_string()))?;
+                let mut h = Self::new();
+                for d in j.undo {
+                    h.add_node(d);
+                }
+                let top = h.current;
+                for d in j.redo.into_iter().rev() {
+                    h.add_node(d);
+                }
+                h.current = top;
+                Ok(h)
+            }
+            Some(2) => {
+                let j: JournalV2 = serde_json::from_value(journal)
+                    .map_err(|e| journal_error("history_decode", e.to_string()))?;
+                let mut nodes = j.nodes;
+                let valid = !nodes.is_empty()
+                    && j.current < nodes.len()
+                    && nodes.iter().enumerate().all(|(i, n)| match n.parent {
+                        None => i == 0 && n.delta.is_none(),
+                        Some(p) => p < i && n.delta.is_some(),
+                    });
+                if !valid {
+                    return Err(journal_error("history_tree", "malformed tree".into()));
+                }
+                for i in 1..nodes.len() {
+                    let p = nodes[i].parent.unwrap_or(ROOT_NODE);
+                    nodes[p].children.push(i);
+                }
+                if nodes
+                    .iter()
+                    .any(|n| n.redo_child.is_some_and(|c| !n.children.contains(&c)))
+                {
+                    return Err(journal_error("history_tree", "bad redo child".into()));
+                }
+                Ok(Self {
+                    nodes,
+                    current: j.current,
+                    active_group: None,
+                })
+            }
+            _ => {
+                let mut r = Reason::from_code(ReasonCode::SerializeUnsupportedSchemaVersion);
+                r.params.insert(
+                    "schema_version".into(),
+                    journal.get("version").cloned().unwrap_or_default(),
+                );
+                Err(r)
             }
         }
     }
 }
 
-struct DeltaGroup {
-    _name: String,
-    deltas: Vec<Box<dyn Delta>>,
+fn to_json<T: serde::Serialize>(v: &T) -> Result<serde_json::Value> {
+    serde_json::to_value(v).map_err(|e| {
+        let mut r = Reason::from_code(ReasonCode::CoreInvariantViolation);
+        r.debug
+            .insert("history_serialize".into(), serde_json::json!(e.to_string()));
+        r
+    })
+}
+
+/// Splits ids into added (only in `b`), removed (only in `a`) and changed.
+fn diff_by_id<'a, T: serde::Serialize + 'a>(
+    a: impl Iterator<Item = (Uuid, &'a T)>,
+    b: impl Iterator<Item = (Uuid, &'a T)>,
+    added: &mut Vec<Uuid>,
+    removed: &mut Vec<Uuid>,
+    changed: &mut Vec<Uuid>,
+) -> Result<()> {
+    let a: BTreeMap<Uuid, &T> = a.collect();
+    let b: BTreeMap<Uuid, &T> = b.collect();
+    for (id, x) in &a {
+        match b.get(id) {
+            None => removed.push(*id),
+            Some(y) if to_json(x)? != to_json(y)? => changed.push(*id),
+            Some(_) => {}
+        }
+    }
+    added.extend(b.keys().filter(|id| !a.contains_key(id)));
+    Ok(())
+}
+
+/// Deltas pushed between `History::begin_group` and `end_group`, undone
+/// and redone as one step.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DeltaGroup {
+    name: String,
+    deltas: Vec<DeltaRecord>,
 }
 
 impl DeltaGroup {
     fn new(name: String) -> Self {
         Self {
-            _name: name,
+            name,
             deltas: Vec::new(),
         }
     }
 
-    fn push(&mut self, delta: Box<dyn Delta>) {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn push(&mut self, delta: DeltaRecord) {
         self.deltas.push(delta);
     }
 
@@ -99,4 +505,8 @@ impl Delta for DeltaGroup {
         }
         Ok(())
     }
+
+    fn into_record(self: Box<Self>) -> DeltaRecord {
+        DeltaRecord::Group(*self)
+    }
 }
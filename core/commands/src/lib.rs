@@ -6,5 +6,5 @@ pub mod delta;
 pub mod history;
 
 pub use command::{Command, CommandContext};
-pub use delta::Delta;
-pub use history::History;
+pub use delta::{Delta, DeltaRecord};
+pub use history::{History, HistoryEntries, HistoryEntry, HistoryNode, StateDiff, ROOT_NODE};
@@ -1,6 +1,7 @@
 use craftcad_commands::commands::advanced_edit::*;
 use craftcad_commands::{Command, CommandContext, History};
 use craftcad_serialize::{Document, Entity, Geom2D, Layer, ProjectSettings, Vec2};
+use diycad_geom::{BooleanOp, EpsilonPolicy};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
@@ -19,6 +20,9 @@ fn doc() -> (Document, Uuid, Uuid, Uuid) {
                 visible: true,
                 locked: false,
                 editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
             }],
             entities: vec![
                 Entity {
@@ -51,6 +55,9 @@ fn doc() -> (Document, Uuid, Uuid, Uuid) {
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
         },
         l,
         e1,
@@ -106,3 +113,115 @@ fn fillet_chamfer_mirror_pattern_roundtrip() {
     dp.apply(&mut d).unwrap();
     h.push(dp);
 }
+
+fn square(x0: f64, y0: f64, size: f64) -> Geom2D {
+    Geom2D::Polyline {
+        pts: vec![
+            Vec2 { x: x0, y: y0 },
+            Vec2 {
+                x: x0 + size,
+                y: y0,
+            },
+            Vec2 {
+                x: x0 + size,
+                y: y0 + size,
+            },
+            Vec2 {
+                x: x0,
+                y: y0 + size,
+            },
+        ],
+        closed: true,
+    }
+}
+
+#[test]
+fn boolean_difference_roundtrip_and_empty_intersection() {
+    let (mut d, l, e1, e2) = doc();
+    d.entities[0].geom = square(0.0, 0.0, 10.0);
+    d.entities[1].geom = square(3.0, 3.0, 4.0);
+    let before = serde_json::to_value(&d).unwrap();
+    let mut h = History::new();
+
+    let mut bc = BooleanCommand::new();
+    bc.begin(&CommandContext).unwrap();
+    bc.update(BooleanInput {
+        a_ids: vec![e1],
+        b_ids: vec![e2],
+        op: BooleanOp::Difference,
+        eps: EpsilonPolicy::default(),
+    })
+    .unwrap();
+    let db = bc.commit().unwrap();
+    db.apply(&mut d).unwrap();
+    h.push(db);
+
+    // Panel outline plus the cut-out as a hole ring, both on the source layer.
+    assert_eq!(d.entities.len(), 2);
+    assert!(d.entities.iter().all(|e| e.layer_id == l));
+    assert!(d.entities.iter().all(|e| e.id != e1 && e.id != e2));
+    let after = serde_json::to_value(&d).unwrap();
+    h.undo(&mut d).unwrap();
+    assert_eq!(serde_json::to_value(&d).unwrap(), before);
+    h.redo(&mut d).unwrap();
+    assert_eq!(serde_json::to_value(&d).unwrap(), after);
+
+    let (mut d, _l, e1, e2) = doc();
+    d.entities[0].geom = square(0.0, 0.0, 10.0);
+    d.entities[1].geom = square(20.0, 0.0, 5.0);
+    let mut bc = BooleanCommand::new();
+    bc.begin(&CommandContext).unwrap();
+    bc.update(BooleanInput {
+        a_ids: vec![e1],
+        b_ids: vec![e2],
+        op: BooleanOp::Intersection,
+        eps: EpsilonPolicy::default(),
+    })
+    .unwrap();
+    let err = bc.commit().unwrap().apply(&mut d).unwrap_err();
+    assert_eq!(err.code, "GEOM_BOOLEAN_EMPTY_RESULT");
+}
+
+#[test]
+fn fillet_all_polyline_corners_in_place_roundtrip() {
+    let (mut d, _l, e1, _e2) = doc();
+    d.entities[0].geom = square(0.0, 0.0, 10.0);
+    let before = serde_json::to_value(&d).unwrap();
+    let mut h = History::new();
+
+    let mut fc = FilletPolylineCommand::new();
+    fc.begin(&CommandContext).unwrap();
+    fc.update(FilletPolylineInput {
+        entity_id: e1,
+        vertex: None,
+        radius: 2.0,
+    })
+    .unwrap();
+    let df = fc.commit().unwrap();
+    df.apply(&mut d).unwrap();
+    h.push(df);
+
+    assert_eq!(d.entities.len(), 2);
+    let edited = d.entities.iter().find(|e| e.id == e1).unwrap();
+    match &edited.geom {
+        Geom2D::Polyline { pts, closed } => {
+            assert!(*closed);
+            assert!(pts.len() > 8);
+            assert!(!pts.iter().any(|p| p.x == 0.0 && p.y == 0.0));
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+    h.undo(&mut d).unwrap();
+    assert_eq!(serde_json::to_value(&d).unwrap(), before);
+
+    let mut cc = ChamferPolylineCommand::new();
+    cc.begin(&CommandContext).unwrap();
+    cc.update(ChamferPolylineInput {
+        entity_id: e1,
+        vertex: Some(1),
+        distance: 20.0,
+    })
+    .unwrap();
+    let err = cc.commit().unwrap().apply(&mut d).unwrap_err();
+    assert_eq!(err.code, "EDIT_CHAMFER_DISTANCE_TOO_LARGE");
+}
@@ -0,0 +1,360 @@
+use craftcad_commands::commands::blocks::{
+    instance_contents, instance_entities, DefineBlockCommand, DefineBlockInput, EditBlockCommand,
+    EditBlockInput, ExplodeBlockCommand, ExplodeBlockInput, InsertBlockCommand, InsertBlockInput,
+};
+use craftcad_commands::{Command, CommandContext, History};
+use craftcad_serialize::{
+    BlockDef, BlockInstance, BlockTransform, Document, Entity, Geom2D, Layer, ProjectSettings, Vec2,
+};
+use std::collections::BTreeMap;
+use std::f64::consts::FRAC_PI_2;
+use uuid::Uuid;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn entity(layer_id: Uuid, geom: Geom2D) -> Entity {
+    Entity {
+        id: Uuid::new_v4(),
+        layer_id,
+        geom,
+        style: serde_json::json!({}),
+        tags: vec![],
+        meta: BTreeMap::new(),
+    }
+}
+
+/// A hinge cutout: a 35 mm cup hole with a screw line beside it.
+fn doc() -> Document {
+    let layer_id = Uuid::new_v4();
+    Document {
+        schema_version: 3,
+        id: Uuid::new_v4(),
+        units: "mm".into(),
+        layers: vec![Layer {
+            id: layer_id,
+            name: "Cut".into(),
+            visible: true,
+            locked: false,
+            editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
+        }],
+        entities: vec![
+            entity(
+                layer_id,
+                Geom2D::Circle {
+                    c: v(10.0, 10.0),
+                    r: 17.5,
+                },
+            ),
+            entity(
+                layer_id,
+                Geom2D::Line {
+                    a: v(30.0, 0.0),
+                    b: v(30.0, 20.0),
+                },
+            ),
+        ],
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn json<T: serde::Serialize>(v: &T) -> serde_json::Value {
+    serde_json::to_value(v).unwrap()
+}
+
+fn run<C: Command>(cmd: &mut C, input: C::Input, doc: &mut Document, history: &mut History) {
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(input).unwrap();
+    let delta = cmd.commit().unwrap();
+    delta.apply(doc).unwrap();
+    history.push(delta);
+}
+
+fn define(doc: &mut Document, history: &mut History, name: &str, base: Vec2) {
+    let input = DefineBlockInput {
+        name: name.into(),
+        base_point: base,
+        entity_ids: doc.entities.iter().map(|e| e.id).collect(),
+        instance_ids: doc.block_instances.iter().map(|i| i.id).collect(),
+        layer_id: doc.layers[0].id,
+    };
+    run(&mut DefineBlockCommand::new(), input, doc, history);
+}
+
+fn insert(doc: &mut Document, history: &mut History, block: usize, transform: BlockTransform) {
+    let input = InsertBlockInput {
+        block_id: doc.blocks[block].id,
+        name: format!("copy {}", doc.block_instances.len()),
+        layer_id: doc.layers[0].id,
+        transform,
+    };
+    run(&mut InsertBlockCommand::new(), input, doc, history);
+}
+
+fn point(g: &Geom2D) -> (f64, f64) {
+    let p = match g {
+        Geom2D::Circle { c, .. } => c,
+        Geom2D::Line { a, .. } => a,
+        other => panic!("unexpected {other:?}"),
+    };
+    ((p.x * 1e6).round() / 1e6, (p.y * 1e6).round() / 1e6)
+}
+
+#[test]
+fn define_insert_and_undo_through_the_journal() {
+    let mut d = doc();
+    let before = json(&d);
+    let original: Vec<_> = d.entities.iter().map(|e| point(&e.geom)).collect();
+    let mut history = History::new();
+    define(&mut d, &mut history, "Hinge", v(10.0, 10.0));
+    assert!(d.entities.is_empty());
+    assert_eq!(d.blocks[0].entities.len(), 2);
+    let placed: Vec<_> = instance_entities(&d, d.block_instances[0].id)
+        .unwrap()
+        .iter()
+        .map(|e| point(&e.geom))
+        .collect();
+    assert_eq!(
+        placed, original,
+        "first instance sits where the geometry was"
+    );
+
+    // Mirrored, then turned a quarter turn about the cup.
+    insert(
+        &mut d,
+        &mut history,
+        0,
+        BlockTransform {
+            translate: v(100.0, 0.0),
+            rotate_rad: FRAC_PI_2,
+            mirror: true,
+            ..BlockTransform::default()
+        },
+    );
+    let copy = instance_entities(&d, d.block_instances[1].id).unwrap();
+    assert_eq!(point(&copy[0].geom), (100.0, 0.0));
+    assert_eq!(point(&copy[1].geom), (110.0, -20.0));
+
+    let after = json(&d);
+    let journal = history.journal().unwrap();
+    let inserted = journal["nodes"].as_array().unwrap().last().unwrap()["delta"].to_string();
+    assert!(
+        !inserted.contains(&d.blocks[0].entities[0].id.to_string()),
+        "an insert records only the new instance"
+    );
+    let mut history = History::from_journal(journal).unwrap();
+    history.undo(&mut d).unwrap();
+    history.undo(&mut d).unwrap();
+    assert_eq!(json(&d), before);
+    history.redo(&mut d).unwrap();
+    history.redo(&mut d).unwrap();
+    assert_eq!(json(&d), after, "redo keeps the drawn ids");
+
+    let mut cmd = DefineBlockCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    let err = cmd
+        .update(DefineBlockInput {
+            name: " ".into(),
+            base_point: v(0.0, 0.0),
+            entity_ids: vec![Uuid::new_v4()],
+            instance_ids: vec![],
+            layer_id: d.layers[0].id,
+        })
+        .unwrap_err();
+    assert_eq!(err.code, "EDIT_BLOCK_NAME_INVALID");
+}
+
+#[test]
+fn nested_blocks_edit_in_place_and_explode() {
+    let mut d = doc();
+    let mut history = History::new();
+    define(&mut d, &mut history, "Hinge", v(0.0, 0.0));
+    // A door blank holding two hinges.
+    insert(
+        &mut d,
+        &mut history,
+        0,
+        BlockTransform {
+            translate: v(0.0, 500.0),
+            ..BlockTransform::default()
+        },
+    );
+    define(&mut d, &mut history, "Door", v(0.0, 0.0));
+    assert_eq!(d.blocks[1].instances.len(), 2);
+    insert(
+        &mut d,
+        &mut history,
+        1,
+        BlockTransform {
+            translate: v(1000.0, 0.0),
+            rotate_rad: FRAC_PI_2,
+            scale: v(2.0, 2.0),
+            ..BlockTransform::default()
+        },
+    );
+    let door = d.block_instances[1].id;
+    let placed = instance_entities(&d, door).unwrap();
+    assert_eq!(placed.len(), 4);
+    assert_eq!(point(&placed[2].geom), (-20.0, 20.0));
+
+    // Move the hinge's screw line and every hinge in the door follows.
+    let mut entities = d.blocks[0].entities.clone();
+    entities[1].geom = Geom2D::Line {
+        a: v(40.0, 0.0),
+        b: v(40.0, 20.0),
+    };
+    let input = EditBlockInput {
+        block_id: d.blocks[0].id,
+        entities,
+        instances: vec![],
+        via_instance: None,
+    };
+    run(&mut EditBlockCommand::new(), input, &mut d, &mut history);
+    let placed = instance_entities(&d, door).unwrap();
+    assert_eq!(point(&placed[1].geom), (1000.0, 80.0));
+    assert_eq!(point(&placed[3].geom), (0.0, 80.0));
+
+    // Putting the door inside the hinge would place it inside itself.
+    let mut cmd = EditBlockCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(EditBlockInput {
+        block_id: d.blocks[0].id,
+        entities: vec![],
+        instances: vec![d.block_instances[1].clone()],
+        via_instance: None,
+    })
+    .unwrap();
+    let err = cmd.commit().unwrap().apply(&mut d).unwrap_err();
+    assert_eq!(err.code, "EDIT_BLOCK_CYCLE");
+
+    // Exploding the door leaves its hinges as instances with composed
+    // transforms, drawing the same geometry.
+    let world: Vec<_> = placed.iter().map(|e| point(&e.geom)).collect();
+    run(
+        &mut ExplodeBlockCommand::new(),
+        ExplodeBlockInput { instance_id: door },
+        &mut d,
+        &mut history,
+    );
+    assert_eq!(d.block_instances.len(), 3);
+    let mut exploded = vec![];
+    for i in &d.block_instances[1..] {
+        assert_eq!(i.transform.scale.x, 2.0);
+        exploded.extend(instance_entities(&d, i.id).unwrap());
+    }
+    let exploded: Vec<_> = exploded.iter().map(|e| point(&e.geom)).collect();
+    assert_eq!(exploded, world);
+    assert_eq!(d.blocks.len(), 2, "explode keeps the definition");
+}
+
+#[test]
+fn edit_in_place_through_a_mirrored_instance() {
+    let mut d = doc();
+    let mut history = History::new();
+    define(&mut d, &mut history, "Hinge", v(10.0, 10.0));
+    insert(
+        &mut d,
+        &mut history,
+        0,
+        BlockTransform {
+            translate: v(200.0, 0.0),
+            mirror: true,
+            ..BlockTransform::default()
+        },
+    );
+    let mirrored = d.block_instances[1].id;
+    let (mut entities, instances) = instance_contents(&d, mirrored).unwrap();
+    assert_eq!(point(&entities[1].geom), (180.0, -10.0));
+    // Drag the screw line 5 mm further from the cup in world space.
+    entities[1].geom = Geom2D::Line {
+        a: v(175.0, -10.0),
+        b: v(175.0, 10.0),
+    };
+    let input = EditBlockInput {
+        block_id: d.blocks[0].id,
+        entities,
+        instances,
+        via_instance: Some(mirrored),
+    };
+    run(&mut EditBlockCommand::new(), input, &mut d, &mut history);
+    assert_eq!(point(&d.blocks[0].entities[1].geom), (35.0, 0.0));
+    let first = instance_entities(&d, d.block_instances[0].id).unwrap();
+    assert_eq!(point(&first[1].geom), (35.0, 0.0));
+}
+
+#[test]
+fn cycle_check_visits_shared_blocks_once() {
+    // Forty levels, each placing the next level twice: 2^40 paths, 40 blocks.
+    let mut d = doc();
+    let layer_id = d.layers[0].id;
+    let ids: Vec<Uuid> = (0..40).map(|_| Uuid::new_v4()).collect();
+    d.blocks = ids
+        .iter()
+        .enumerate()
+        .map(|(k, id)| BlockDef {
+            id: *id,
+            name: format!("level {k}"),
+            base_point: v(0.0, 0.0),
+            entities: vec![],
+            instances: ids
+                .get(k + 1)
+                .map(|next| {
+                    (0..2)
+                        .map(|n| BlockInstance {
+                            id: Uuid::new_v4(),
+                            block_id: *next,
+                            name: String::new(),
+                            layer_id,
+                            transform: BlockTransform {
+                                translate: v(n as f64 * 10.0, 0.0),
+                                ..BlockTransform::default()
+                            },
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect();
+    let top = BlockInstance {
+        id: Uuid::new_v4(),
+        block_id: ids[0],
+        name: String::new(),
+        layer_id,
+        transform: BlockTransform::default(),
+    };
+
+    let mut cmd = EditBlockCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(EditBlockInput {
+        block_id: ids[39],
+        entities: vec![],
+        instances: vec![top],
+        via_instance: None,
+    })
+    .unwrap();
+    let err = cmd.commit().unwrap().apply(&mut d).unwrap_err();
+    assert_eq!(err.code, "EDIT_BLOCK_CYCLE");
+
+    let mut history = History::new();
+    let input = EditBlockInput {
+        block_id: ids[0],
+        entities: d.entities.clone(),
+        instances: d.blocks[0].instances.clone(),
+        via_instance: None,
+    };
+    run(&mut EditBlockCommand::new(), input, &mut d, &mut history);
+    assert_eq!(d.blocks[0].entities.len(), 2);
+}
@@ -0,0 +1,216 @@
+use craftcad_commands::commands::clipboard::{
+    copy_selection, ClipboardPayload, CutCommand, CutInput, PasteCommand, PasteInput,
+};
+use craftcad_commands::{Command, CommandContext, Delta, History};
+use craftcad_serialize::{
+    Document, Entity, Geom2D, Layer, Material, MaterialCategory, Part, Polygon2D, ProjectSettings,
+    Vec2,
+};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn layer(name: &str) -> Layer {
+    Layer {
+        id: Uuid::new_v4(),
+        name: name.into(),
+        visible: true,
+        locked: false,
+        editable: true,
+        color: None,
+        linetype: "CONTINUOUS".into(),
+        print: true,
+    }
+}
+
+fn doc(layers: Vec<Layer>) -> Document {
+    Document {
+        schema_version: 3,
+        id: Uuid::new_v4(),
+        units: "mm".into(),
+        layers,
+        entities: vec![],
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn line(layer_id: Uuid, x: f64) -> Entity {
+    Entity {
+        id: Uuid::new_v4(),
+        layer_id,
+        geom: Geom2D::Line {
+            a: Vec2 { x, y: 0.0 },
+            b: Vec2 { x, y: 10.0 },
+        },
+        style: serde_json::json!({}),
+        tags: vec!["edge".into()],
+        meta: BTreeMap::new(),
+    }
+}
+
+/// A document with a "Cut" layer, two lines and a birch part.
+fn source() -> Document {
+    let mut d = doc(vec![layer("Cut")]);
+    let cut = d.layers[0].id;
+    d.entities = vec![line(cut, 0.0), line(cut, 5.0)];
+    let birch = Uuid::new_v4();
+    d.materials.push(Material {
+        id: birch,
+        name: "Birch".into(),
+        category: MaterialCategory::Wood,
+        thickness_mm: Some(12.0),
+        sheet_default: None,
+        notes: "".into(),
+    });
+    d.parts.push(Part {
+        id: Uuid::new_v4(),
+        name: "Shelf".into(),
+        outline: Polygon2D {
+            outer: vec![
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 40.0, y: 0.0 },
+                Vec2 { x: 40.0, y: 20.0 },
+            ],
+            holes: vec![],
+        },
+        thickness: 12.0,
+        quantity: 1,
+        material_id: birch,
+        grain_dir: None,
+        allow_rotate: true,
+        margin: 0.0,
+        kerf: 0.0,
+    });
+    d
+}
+
+fn json(d: &Document) -> serde_json::Value {
+    serde_json::to_value(d).unwrap()
+}
+
+fn paste(payload: ClipboardPayload, dx: f64) -> Box<dyn Delta> {
+    let mut cmd = PasteCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(PasteInput {
+        payload,
+        offset: Vec2 { x: dx, y: 1.0 },
+    })
+    .unwrap();
+    cmd.commit().unwrap()
+}
+
+#[test]
+fn paste_into_another_document_remaps_ids_and_references() {
+    let src = source();
+    let payload = copy_selection(&src, &[src.entities[1].id], &[src.parts[0].id]).unwrap();
+    // The payload travels as JSON, e.g. through the system clipboard.
+    let payload: ClipboardPayload =
+        serde_json::from_value(serde_json::to_value(&payload).unwrap()).unwrap();
+    assert_eq!(payload.layers.len(), 1);
+    assert_eq!(payload.materials.len(), 1);
+
+    // The target has its own "Cut" layer but no birch.
+    let mut dst = doc(vec![layer("Draft"), layer("Cut")]);
+    let before = json(&dst);
+    let mut history = History::new();
+    let delta = paste(payload, 100.0);
+    delta.apply(&mut dst).unwrap();
+    history.push(delta);
+
+    assert_eq!(dst.layers.len(), 2, "layer matched by name");
+    let e = &dst.entities[0];
+    assert_ne!(e.id, src.entities[1].id);
+    assert_eq!(e.layer_id, dst.layers[1].id);
+    assert_eq!(e.tags, ["edge"]);
+    match &e.geom {
+        Geom2D::Line { a, .. } => assert_eq!((a.x, a.y), (105.0, 1.0)),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(dst.materials.len(), 1, "material created");
+    assert_ne!(dst.materials[0].id, src.materials[0].id);
+    let part = &dst.parts[0];
+    assert_ne!(part.id, src.parts[0].id);
+    assert_eq!(part.material_id, dst.materials[0].id);
+    assert_eq!(part.outline.outer[1].x, 140.0);
+
+    let after = json(&dst);
+    history.undo(&mut dst).unwrap();
+    assert_eq!(json(&dst), before);
+    history.redo(&mut dst).unwrap();
+    assert_eq!(json(&dst), after, "redo reuses the pasted ids");
+
+    // With a pasted part gone, undo fails and leaves the rest in place.
+    dst.parts.clear();
+    let damaged = json(&dst);
+    let err = history.undo(&mut dst).unwrap_err();
+    assert_eq!(err.code, "MODEL_REFERENCE_NOT_FOUND");
+    assert_eq!(json(&dst), damaged);
+}
+
+#[test]
+fn pasting_twice_and_into_missing_layers() {
+    let src = source();
+    let mut payload = copy_selection(&src, &[src.entities[0].id], &[]).unwrap();
+    assert!(payload.materials.is_empty());
+    payload.layers[0].locked = true;
+    payload.layers[0].visible = false;
+    payload.layers[0].color = Some("#FF8000".into());
+
+    let mut dst = doc(vec![]);
+    paste(payload.clone(), 0.0).apply(&mut dst).unwrap();
+    paste(payload.clone(), 0.0).apply(&mut dst).unwrap();
+    assert_eq!(dst.layers.len(), 1, "first paste creates the layer");
+    assert_eq!(dst.layers[0].name, "Cut");
+    assert!(dst.layers[0].visible && !dst.layers[0].locked && dst.layers[0].editable);
+    assert_eq!(dst.layers[0].color.as_deref(), Some("#FF8000"));
+    assert_eq!(dst.entities.len(), 2);
+    assert_ne!(dst.entities[0].id, dst.entities[1].id);
+    assert_eq!(dst.entities[0].layer_id, dst.entities[1].layer_id);
+
+    dst.layers[0].locked = true;
+    let err = paste(payload, 0.0).apply(&mut dst).unwrap_err();
+    assert_eq!(err.code, "EDIT_TARGET_LOCKED_OR_HIDDEN");
+
+    assert!(copy_selection(&src, &[], &[]).is_err());
+    assert!(copy_selection(&src, &[Uuid::new_v4()], &[]).is_err());
+}
+
+#[test]
+fn cut_removes_and_undo_restores_order() {
+    let mut d = source();
+    let before = json(&d);
+    let ids = [d.entities[0].id];
+    let payload = copy_selection(&d, &ids, &[d.parts[0].id]).unwrap();
+    let mut history = History::new();
+    let mut cmd = CutCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(CutInput {
+        entity_ids: ids.to_vec(),
+        part_ids: vec![d.parts[0].id],
+    })
+    .unwrap();
+    let delta = cmd.commit().unwrap();
+    delta.apply(&mut d).unwrap();
+    history.push(delta);
+    assert_eq!(d.entities.len(), 1);
+    assert!(d.parts.is_empty());
+
+    history.undo(&mut d).unwrap();
+    assert_eq!(json(&d), before);
+    history.redo(&mut d).unwrap();
+
+    // Paste back in place: same layer and material, new ids.
+    let delta = paste(payload, 0.0);
+    delta.apply(&mut d).unwrap();
+    assert_eq!(d.entities.len(), 2);
+    assert_eq!(d.materials.len(), 1);
+    assert_eq!(d.parts[0].material_id, d.materials[0].id);
+}
@@ -15,6 +15,9 @@ fn sample_doc() -> Document {
             visible: true,
             locked: false,
             editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
         }],
         entities: vec![],
         parts: vec![],
@@ -24,6 +27,9 @@ fn sample_doc() -> Document {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     }
 }
 
@@ -16,6 +16,9 @@ fn doc() -> (Document, Uuid) {
                 visible: true,
                 locked: false,
                 editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
             }],
             entities: vec![],
             parts: vec![],
@@ -25,6 +28,9 @@ fn doc() -> (Document, Uuid) {
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
         },
         l,
     )
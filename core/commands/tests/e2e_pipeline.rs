@@ -65,6 +65,9 @@ fn e2e_create_part_nest_export() {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     };
 
     let job = NestJob {
@@ -92,11 +95,13 @@ fn e2e_create_part_nest_export() {
             allow_rotate_default: true,
             no_go_zones: vec![],
             grain_policy: GrainPolicy::Ignore,
+            guillotine: false,
         },
         objective: NestObjective {
             w_utilization: 1.0,
             w_sheet_count: 1.0,
             w_cut_count: 1.0,
+            w_grain_misaligned: 1.0,
         },
         seed: 42,
         result: None,
@@ -0,0 +1,281 @@
+use craftcad_commands::commands::create_line::{CreateLineCommand, CreateLineInput};
+use craftcad_commands::commands::trim_entity::{TrimEntityCommand, TrimEntityInput};
+use craftcad_commands::{Command, CommandContext, History, HistoryEntry};
+use craftcad_serialize::{
+    create_manifest, load_diycad_with_history, save_diycad_with_history, Document, Entity, Geom2D,
+    Layer, Vec2,
+};
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn line(layer_id: Uuid, a: (f64, f64), b: (f64, f64)) -> Entity {
+    Entity {
+        id: Uuid::new_v4(),
+        layer_id,
+        geom: Geom2D::Line {
+            a: Vec2 { x: a.0, y: a.1 },
+            b: Vec2 { x: b.0, y: b.1 },
+        },
+        style: serde_json::json!({}),
+        tags: vec![],
+        meta: BTreeMap::new(),
+    }
+}
+
+fn base_doc() -> Document {
+    let layer_id = Uuid::new_v4();
+    Document {
+        schema_version: 3,
+        id: Uuid::new_v4(),
+        units: "mm".to_string(),
+        layers: vec![Layer {
+            id: layer_id,
+            name: "Default".into(),
+            visible: true,
+            locked: false,
+            editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
+        }],
+        entities: vec![
+            line(layer_id, (0.0, 0.0), (10.0, 0.0)),
+            line(layer_id, (5.0, -10.0), (5.0, 10.0)),
+        ],
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: craftcad_serialize::ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn doc_json(doc: &Document) -> serde_json::Value {
+    serde_json::to_value(doc).expect("serialize")
+}
+
+fn entry(node: usize, kind: &str, name: &str) -> HistoryEntry {
+    HistoryEntry {
+        node,
+        kind: kind.into(),
+        name: name.into(),
+    }
+}
+
+fn add_line(doc: &mut Document, history: &mut History, x: f64) {
+    let mut cmd = CreateLineCommand::new(doc.layers[0].id);
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(CreateLineInput {
+        a: Vec2 { x, y: 20.0 },
+        b: Vec2 { x, y: 30.0 },
+    })
+    .unwrap();
+    let delta = cmd.commit().unwrap();
+    delta.apply(doc).unwrap();
+    history.push(delta);
+}
+
+#[test]
+fn history_survives_save_and_reopen() {
+    let mut doc = base_doc();
+    let original = doc_json(&doc);
+    let mut history = History::new();
+
+    // The trim caches its before/after geometry on apply; the journal must
+    // carry that cache for undo after reopening.
+    history.begin_group("Trim and mark");
+    let mut trim = TrimEntityCommand::new();
+    trim.begin(&CommandContext).unwrap();
+    trim.update(TrimEntityInput {
+        entity_id: doc.entities[0].id,
+        cutter_id: doc.entities[1].id,
+        pick_point: Vec2 { x: 8.0, y: 0.0 },
+        eps: EpsilonPolicy::default(),
+        candidate_index: None,
+    })
+    .unwrap();
+    let delta = trim.commit().unwrap();
+    delta.apply(&mut doc).unwrap();
+    history.push(delta);
+    add_line(&mut doc, &mut history, 1.0);
+    history.end_group();
+    let trimmed = doc_json(&doc);
+
+    add_line(&mut doc, &mut history, 2.0);
+    add_line(&mut doc, &mut history, 3.0);
+    history.undo(&mut doc).unwrap();
+    assert_eq!(
+        history.entries().undo,
+        [
+            entry(2, "CreateLine", "CreateLine"),
+            entry(1, "Group", "Trim and mark")
+        ]
+    );
+    assert_eq!(
+        history.entries().redo,
+        [entry(3, "CreateLine", "CreateLine")]
+    );
+    let entries = history.entries();
+
+    let path = std::env::temp_dir().join(format!("history-{}.diycad", Uuid::new_v4()));
+    let manifest = create_manifest("craftcad", "test");
+    save_diycad_with_history(&path, &manifest, &doc, Some(&history.journal().unwrap())).unwrap();
+    let package = load_diycad_with_history(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(
+        package.manifest.history_path.as_deref(),
+        Some(craftcad_serialize::HISTORY_PATH)
+    );
+    assert!(package.warnings.is_empty());
+    let mut reopened = package.document;
+    assert_eq!(doc_json(&reopened), doc_json(&doc));
+
+    let mut history = History::from_journal(package.history.expect("journal saved")).unwrap();
+    assert_eq!(history.entries(), entries);
+    history.undo(&mut reopened).unwrap();
+    assert_eq!(doc_json(&reopened), trimmed);
+    history.undo(&mut reopened).unwrap();
+    assert_eq!(doc_json(&reopened), original);
+    assert!(history.entries().undo.is_empty());
+    assert_eq!(history.entries().redo.len(), 3);
+
+    history.redo(&mut reopened).unwrap();
+    assert_eq!(doc_json(&reopened), trimmed);
+}
+
+#[test]
+fn journal_versions_and_plain_packages() {
+    let doc = base_doc();
+    let path = std::env::temp_dir().join(format!("history-{}.diycad", Uuid::new_v4()));
+    save_diycad_with_history(&path, &create_manifest("craftcad", "test"), &doc, None).unwrap();
+    let package = load_diycad_with_history(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(package.manifest.history_path.is_none());
+    assert!(package.history.is_none() && package.warnings.is_empty());
+
+    let mut history = History::new();
+    history.begin_group("open group");
+    let mut d = doc.clone();
+    add_line(&mut d, &mut history, 0.0);
+    // An open group is journaled as a finished step.
+    let mut journal = history.journal().unwrap();
+    let reloaded = History::from_journal(journal.clone()).unwrap();
+    assert_eq!(reloaded.entries().undo, [entry(1, "Group", "open group")]);
+
+    // Version 1 journals held linear stacks: undo oldest first, redo with
+    // the next step last.
+    let line = &journal["nodes"][1]["delta"]["deltas"][0];
+    let v1 = serde_json::json!({"version": 1, "undo": [line], "redo": [line, line]});
+    let linear = History::from_journal(v1).unwrap();
+    assert_eq!(linear.current(), 1);
+    assert_eq!(linear.entries().redo.len(), 2);
+    assert_eq!(linear.nodes()[2].parent, 2);
+
+    journal["version"] = serde_json::json!(99);
+    let err = History::from_journal(journal)
+        .err()
+        .expect("version rejected");
+    assert_eq!(err.code, "SERIALIZE_UNSUPPORTED_SCHEMA_VERSION");
+    let err = History::from_journal(
+        serde_json::json!({"version": 1, "undo": [{"kind": "Nope"}], "redo": []}),
+    )
+    .err()
+    .expect("unknown kind rejected");
+    assert_eq!(err.code, "SERIALIZE_PACKAGE_CORRUPTED");
+}
+
+/// Rewrites one entry of a package, or drops it when `bytes` is `None`.
+fn rewrite_entry(path: &std::path::Path, name: &str, bytes: Option<&[u8]>) {
+    use std::io::{Read, Write};
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+    let mut entries = vec![];
+    for i in 0..zip.len() {
+        let mut f = zip.by_index(i).unwrap();
+        let mut buf = vec![];
+        f.read_to_end(&mut buf).unwrap();
+        entries.push((f.name().to_string(), buf));
+    }
+    let mut out = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (n, buf) in entries {
+        let buf = if n == name {
+            bytes.map(<[u8]>::to_vec)
+        } else {
+            Some(buf)
+        };
+        if let Some(buf) = buf {
+            out.start_file(n, zip::write::FileOptions::default())
+                .unwrap();
+            out.write_all(&buf).unwrap();
+        }
+    }
+    out.finish().unwrap();
+}
+
+#[test]
+fn damaged_or_stale_journals_open_with_empty_history() {
+    let mut doc = base_doc();
+    let mut history = History::new();
+    add_line(&mut doc, &mut history, 0.0);
+    let journal = history.journal().unwrap();
+    let manifest = create_manifest("craftcad", "test");
+    let save = |journal: &serde_json::Value| {
+        let path = std::env::temp_dir().join(format!("history-{}.diycad", Uuid::new_v4()));
+        save_diycad_with_history(&path, &manifest, &doc, Some(journal)).unwrap();
+        path
+    };
+    let open = |path: &std::path::Path| {
+        let package = load_diycad_with_history(path).unwrap();
+        std::fs::remove_file(path).ok();
+        package
+    };
+
+    let corrupt = save(&journal);
+    rewrite_entry(
+        &corrupt,
+        craftcad_serialize::HISTORY_PATH,
+        Some(b"{not json"),
+    );
+    let missing = save(&journal);
+    rewrite_entry(&missing, craftcad_serialize::HISTORY_PATH, None);
+    for path in [corrupt, missing] {
+        let package = open(&path);
+        assert_eq!(doc_json(&package.document), doc_json(&doc));
+        assert!(package.history.is_none());
+        assert_eq!(package.warnings[0].code, "SERIALIZE_HISTORY_DROPPED");
+    }
+
+    // document.json edited outside the app: undo would replay on the wrong state.
+    let stale = save(&journal);
+    let mut edited = doc.clone();
+    edited.entities.pop();
+    let edited_json = serde_json::to_vec_pretty(&edited).unwrap();
+    rewrite_entry(&stale, &manifest.document_path, Some(&edited_json));
+    let package = open(&stale);
+    assert_eq!(package.document.entities.len(), edited.entities.len());
+    assert!(package.history.is_none());
+    assert_eq!(package.warnings[0].code, "SERIALIZE_HISTORY_DROPPED");
+    assert!(package.warnings[0].debug.contains_key("document_digest"));
+
+    let mut future = journal.clone();
+    future["version"] = serde_json::json!(99);
+    let package = open(&save(&future));
+    assert!(package.warnings.is_empty());
+    let (history, warning) = History::restore(package.history);
+    assert!(history.entries().undo.is_empty());
+    let warning = warning.expect("journal dropped");
+    assert_eq!(warning.code, "SERIALIZE_HISTORY_DROPPED");
+    assert_eq!(
+        warning.debug["cause"],
+        serde_json::json!("SERIALIZE_UNSUPPORTED_SCHEMA_VERSION")
+    );
+
+    let (history, warning) = History::restore(Some(journal));
+    assert!(warning.is_none());
+    assert_eq!(history.entries().undo.len(), 1);
+}
@@ -1,5 +1,5 @@
 use craftcad_commands::commands::create_line::{CreateLineCommand, CreateLineInput};
-use craftcad_commands::{Command, CommandContext, History};
+use craftcad_commands::{Command, CommandContext, History, ROOT_NODE};
 use craftcad_serialize::{Document, Layer, Vec2};
 use uuid::Uuid;
 
@@ -15,6 +15,9 @@ fn sample_doc() -> Document {
             visible: true,
             locked: false,
             editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
         }],
         entities: vec![],
         parts: vec![],
@@ -24,6 +27,9 @@ fn sample_doc() -> Document {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     }
 }
 
@@ -106,3 +112,57 @@ fn grouped_changes_are_undone_in_one_step() {
     history.undo(&mut doc).expect("undo");
     assert_eq!(doc_json(&doc), before);
 }
+
+fn push_line(doc: &mut Document, history: &mut History, x: f64) -> uuid::Uuid {
+    let mut cmd = CreateLineCommand::new(doc.layers[0].id);
+    cmd.begin(&CommandContext).expect("begin");
+    cmd.update(CreateLineInput {
+        a: Vec2 { x, y: 0.0 },
+        b: Vec2 { x, y: 5.0 },
+    })
+    .expect("update");
+    let delta = cmd.commit().expect("commit");
+    delta.apply(doc).expect("apply");
+    history.push(delta);
+    doc.entities.last().expect("created").id
+}
+
+#[test]
+fn editing_after_undo_keeps_the_old_branch() {
+    let mut doc = sample_doc();
+    let before = doc_json(&doc);
+    let mut history = History::new();
+
+    let shared = push_line(&mut doc, &mut history, 0.0);
+    let variant_a = push_line(&mut doc, &mut history, 1.0);
+    let node_a = history.current();
+    history.undo(&mut doc).expect("undo");
+    let variant_b = push_line(&mut doc, &mut history, 2.0);
+    let node_b = history.current();
+
+    // Both variants hang off the shared step; redo follows the newest.
+    let nodes = history.nodes();
+    assert_eq!(nodes[0].children, [node_a, node_b]);
+    assert!(history.entries().redo.is_empty());
+    history.undo(&mut doc).expect("undo");
+    assert_eq!(history.entries().redo[0].node, node_b);
+
+    let diff = history.compare(node_a, node_b, &doc).expect("compare");
+    assert_eq!(diff.entities_removed, [variant_a]);
+    assert_eq!(diff.entities_added, [variant_b]);
+    assert!(diff.entities_changed.is_empty() && diff.other_fields.is_empty());
+    let at_a = history.state_at(node_a, &doc).expect("state");
+    assert_eq!(at_a.entities.len(), 2);
+    assert_eq!(doc.entities.len(), 1, "state_at leaves the document alone");
+
+    history.jump_to(node_b, &mut doc).expect("jump to b");
+    history.jump_to(node_a, &mut doc).expect("jump to a");
+    let ids: Vec<_> = doc.entities.iter().map(|e| e.id).collect();
+    assert_eq!(ids, [shared, variant_a]);
+    history.undo(&mut doc).expect("undo");
+    assert_eq!(history.entries().redo[0].node, node_a);
+
+    history.jump_to(ROOT_NODE, &mut doc).expect("jump to root");
+    assert_eq!(doc_json(&doc), before);
+    assert!(history.jump_to(99, &mut doc).is_err());
+}
@@ -0,0 +1,283 @@
+use craftcad_commands::commands::blocks::{DefineBlockCommand, DefineBlockInput};
+use craftcad_commands::commands::layers::{LayerCommand, LayerEdit};
+use craftcad_commands::{Command, CommandContext, History};
+use craftcad_serialize::{Document, Entity, Geom2D, Layer, ProjectSettings, Vec2};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn layer(name: &str) -> Layer {
+    Layer {
+        id: Uuid::new_v4(),
+        name: name.into(),
+        visible: true,
+        locked: false,
+        editable: true,
+        color: None,
+        linetype: "CONTINUOUS".into(),
+        print: true,
+    }
+}
+
+fn line(layer_id: Uuid, x: f64) -> Entity {
+    Entity {
+        id: Uuid::new_v4(),
+        layer_id,
+        geom: Geom2D::Line {
+            a: Vec2 { x, y: 0.0 },
+            b: Vec2 { x, y: 10.0 },
+        },
+        style: serde_json::json!({}),
+        tags: vec![],
+        meta: BTreeMap::new(),
+    }
+}
+
+/// "Cut" holds two lines, "Engrave" one.
+fn doc() -> Document {
+    let layers = vec![layer("Cut"), layer("Engrave")];
+    let entities = vec![
+        line(layers[0].id, 0.0),
+        line(layers[0].id, 5.0),
+        line(layers[1].id, 9.0),
+    ];
+    Document {
+        schema_version: 3,
+        id: Uuid::new_v4(),
+        units: "mm".into(),
+        layers,
+        entities,
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn json(d: &Document) -> serde_json::Value {
+    serde_json::to_value(d).unwrap()
+}
+
+#[allow(clippy::result_large_err)]
+fn edit(
+    doc: &mut Document,
+    history: &mut History,
+    edit: LayerEdit,
+) -> craftcad_serialize::Result<()> {
+    let mut cmd = LayerCommand::new();
+    cmd.begin(&CommandContext)?;
+    cmd.update(edit)?;
+    let delta = cmd.commit()?;
+    delta.apply(doc)?;
+    history.push(delta);
+    Ok(())
+}
+
+#[test]
+fn create_rename_restyle_and_reorder_undo_through_the_journal() {
+    let mut d = doc();
+    let before = json(&d);
+    let mut history = History::new();
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Create {
+            name: " Score ".into(),
+        },
+    )
+    .unwrap();
+    let score = d.layers[2].id;
+    assert_eq!(d.layers[2].name, "Score");
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Rename {
+            layer_id: score,
+            name: "Guide".into(),
+        },
+    )
+    .unwrap();
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Recolor {
+            layer_id: score,
+            color: Some("#ff8000".into()),
+        },
+    )
+    .unwrap();
+    // The edit arrives as JSON from the UI.
+    let props = serde_json::json!({
+        "op": "SetProps", "layer_id": score, "linetype": "dashed", "print": false,
+    });
+    edit(&mut d, &mut history, serde_json::from_value(props).unwrap()).unwrap();
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Reorder {
+            layer_id: score,
+            index: 0,
+        },
+    )
+    .unwrap();
+    let guide = &d.layers[0];
+    assert_eq!(guide.name, "Guide");
+    assert_eq!(guide.color.as_deref(), Some("#FF8000"));
+    assert_eq!(guide.linetype, "DASHED");
+    assert!(!guide.print && guide.visible);
+
+    let after = json(&d);
+    let mut history = History::from_journal(history.journal().unwrap()).unwrap();
+    for _ in 0..5 {
+        history.undo(&mut d).unwrap();
+    }
+    assert_eq!(json(&d), before);
+    for _ in 0..5 {
+        history.redo(&mut d).unwrap();
+    }
+    assert_eq!(json(&d), after, "redo keeps the new layer id");
+
+    let err = edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Rename {
+            layer_id: score,
+            name: "Cut".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "EDIT_LAYER_NAME_INVALID");
+    let err = edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Recolor {
+            layer_id: score,
+            color: Some("orange".into()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "EDIT_LAYER_STYLE_INVALID");
+}
+
+#[test]
+fn delete_merge_and_move_reassign_geometry() {
+    let mut d = doc();
+    let (cut, engrave) = (d.layers[0].id, d.layers[1].id);
+    let mut history = History::new();
+
+    // A block on "Engrave" keeps a reference inside its definition.
+    let mut define = DefineBlockCommand::new();
+    define.begin(&CommandContext).unwrap();
+    define
+        .update(DefineBlockInput {
+            name: "Mark".into(),
+            base_point: Vec2 { x: 0.0, y: 0.0 },
+            entity_ids: vec![d.entities[2].id],
+            instance_ids: vec![],
+            layer_id: engrave,
+        })
+        .unwrap();
+    let delta = define.commit().unwrap();
+    delta.apply(&mut d).unwrap();
+    history.push(delta);
+
+    let err = edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Delete {
+            layer_id: engrave,
+            reassign_to: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "EDIT_LAYER_IN_USE");
+
+    let before = json(&d);
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Merge {
+            from: engrave,
+            into: cut,
+        },
+    )
+    .unwrap();
+    assert_eq!(d.layers.len(), 1);
+    assert_eq!(d.block_instances[0].layer_id, cut);
+    assert_eq!(d.blocks[0].entities[0].layer_id, cut);
+    let err = edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Delete {
+            layer_id: cut,
+            reassign_to: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "EDIT_LAYER_IN_USE", "the last layer stays");
+    let journal = history.journal().unwrap();
+    let current = journal["current"].as_u64().unwrap() as usize;
+    let merge = &journal["nodes"][current]["delta"]["change"];
+    assert_eq!(
+        merge["reassigned"].as_array().unwrap().len(),
+        2,
+        "only the instance and the entity inside the block moved"
+    );
+    assert!(!merge.to_string().contains(&d.entities[0].id.to_string()));
+    history.undo(&mut d).unwrap();
+    assert_eq!(json(&d), before);
+
+    let ids = vec![d.entities[0].id, d.block_instances[0].id];
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::MoveEntities {
+            ids: ids.clone(),
+            layer_id: engrave,
+        },
+    )
+    .unwrap();
+    assert_eq!(d.entities[0].layer_id, engrave);
+    assert_eq!(d.entities[1].layer_id, cut);
+    assert_eq!(d.block_instances[0].layer_id, engrave);
+
+    d.layers[1].locked = true;
+    let err = edit(
+        &mut d,
+        &mut history,
+        LayerEdit::MoveEntities { ids, layer_id: cut },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "EDIT_TARGET_LOCKED_OR_HIDDEN");
+    d.layers[1].locked = false;
+
+    let rest = vec![d.entities[1].id];
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::MoveEntities {
+            ids: rest,
+            layer_id: engrave,
+        },
+    )
+    .unwrap();
+    edit(
+        &mut d,
+        &mut history,
+        LayerEdit::Delete {
+            layer_id: cut,
+            reassign_to: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        d.layers.len(),
+        1,
+        "an emptied layer deletes without a target"
+    );
+}
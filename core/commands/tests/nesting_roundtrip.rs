@@ -58,11 +58,13 @@ fn mk_doc() -> (Document, Uuid) {
                 allow_rotate_default: true,
                 no_go_zones: vec![],
                 grain_policy: GrainPolicy::Ignore,
+                guillotine: false,
             },
             objective: NestObjective {
                 w_utilization: 1.0,
                 w_sheet_count: 1.0,
                 w_cut_count: 0.01,
+                w_grain_misaligned: 1.0,
             },
             seed: 42,
             result: None,
@@ -80,6 +82,9 @@ fn mk_doc() -> (Document, Uuid) {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     };
     (doc, job_id)
 }
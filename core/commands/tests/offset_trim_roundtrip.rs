@@ -1,8 +1,9 @@
+use craftcad_commands::commands::extend_entity::{ExtendEntityCommand, ExtendEntityInput};
 use craftcad_commands::commands::offset_entity::{OffsetEntityCommand, OffsetEntityInput};
 use craftcad_commands::commands::trim_entity::{TrimEntityCommand, TrimEntityInput};
 use craftcad_commands::{Command, CommandContext, History};
 use craftcad_serialize::{Document, Entity, Geom2D, Layer, Vec2};
-use diycad_geom::EpsilonPolicy;
+use diycad_geom::{EpsilonPolicy, JoinStyle};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
@@ -21,6 +22,9 @@ fn base_doc() -> (Document, Uuid, Uuid) {
                 visible: true,
                 locked: false,
                 editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
             }],
             entities: vec![
                 Entity {
@@ -53,6 +57,9 @@ fn base_doc() -> (Document, Uuid, Uuid) {
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
         },
         target_id,
         cutter_id,
@@ -74,6 +81,7 @@ fn offset_line_and_history_roundtrip() {
     cmd.update(OffsetEntityInput {
         entity_id: target_id,
         dist: 2.0,
+        join: JoinStyle::default(),
         eps: EpsilonPolicy::default(),
     })
     .unwrap();
@@ -88,6 +96,43 @@ fn offset_line_and_history_roundtrip() {
     assert_eq!(doc.entities.len(), 3);
 }
 
+#[test]
+fn offset_closed_polyline_round_join_roundtrip() {
+    let (mut doc, target_id, _) = base_doc();
+    doc.entities[0].geom = Geom2D::Polyline {
+        pts: vec![
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 10.0, y: 0.0 },
+            Vec2 { x: 10.0, y: 10.0 },
+            Vec2 { x: 0.0, y: 10.0 },
+        ],
+        closed: true,
+    };
+    let before = doc_json(&doc);
+    let mut cmd = OffsetEntityCommand::new();
+    let mut history = History::new();
+
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(OffsetEntityInput {
+        entity_id: target_id,
+        dist: -1.0,
+        join: JoinStyle::Round,
+        eps: EpsilonPolicy::default(),
+    })
+    .unwrap();
+    let delta = cmd.commit().unwrap();
+    delta.apply(&mut doc).unwrap();
+    history.push(delta);
+
+    // 4 offset edges + 4 corner arcs on top of the two source entities.
+    assert_eq!(doc.entities.len(), 10);
+    let after = doc_json(&doc);
+    history.undo(&mut doc).unwrap();
+    assert_eq!(doc_json(&doc), before);
+    history.redo(&mut doc).unwrap();
+    assert_eq!(doc_json(&doc), after);
+}
+
 #[test]
 fn trim_line_and_history_roundtrip() {
     let (mut doc, target_id, cutter_id) = base_doc();
@@ -151,3 +196,67 @@ fn trim_ambiguous_returns_candidates() {
     assert_eq!(err.code, "EDIT_TRIM_AMBIGUOUS_CANDIDATE");
     assert!(err.debug.get("candidates").is_some());
 }
+
+#[test]
+fn extend_line_to_boundary_and_history_roundtrip() {
+    let (mut doc, target_id, cutter_id) = base_doc();
+    doc.entities[0].geom = Geom2D::Line {
+        a: Vec2 { x: 0.0, y: 0.0 },
+        b: Vec2 { x: 3.0, y: 0.0 },
+    };
+    let before = doc_json(&doc);
+    let mut history = History::new();
+
+    let mut cmd = ExtendEntityCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    // The target itself is ignored as a boundary.
+    let err = cmd
+        .update(ExtendEntityInput {
+            entity_id: target_id,
+            boundary_ids: vec![target_id],
+            pick_point: Vec2 { x: 2.5, y: 0.0 },
+            eps: EpsilonPolicy::default(),
+            candidate_index: None,
+        })
+        .unwrap_err();
+    assert_eq!(err.code, "EDIT_NO_SELECTION");
+
+    cmd.update(ExtendEntityInput {
+        entity_id: target_id,
+        boundary_ids: vec![target_id, cutter_id],
+        pick_point: Vec2 { x: 2.5, y: 0.0 },
+        eps: EpsilonPolicy::default(),
+        candidate_index: None,
+    })
+    .unwrap();
+    let delta = cmd.commit().unwrap();
+    delta.apply(&mut doc).unwrap();
+    history.push(delta);
+
+    match &doc.entities[0].geom {
+        Geom2D::Line { a, b } => {
+            assert_eq!((a.x, a.y), (0.0, 0.0));
+            assert!((b.x - 5.0).abs() < 1e-9 && b.y.abs() < 1e-9);
+        }
+        other => panic!("expected line, got {other:?}"),
+    }
+    let after = doc_json(&doc);
+    history.undo(&mut doc).unwrap();
+    assert_eq!(doc_json(&doc), before);
+    history.redo(&mut doc).unwrap();
+    assert_eq!(doc_json(&doc), after);
+
+    // Growing the other end finds nothing behind the line.
+    let mut cmd = ExtendEntityCommand::new();
+    cmd.begin(&CommandContext).unwrap();
+    cmd.update(ExtendEntityInput {
+        entity_id: target_id,
+        boundary_ids: vec![cutter_id],
+        pick_point: Vec2 { x: 0.5, y: 0.0 },
+        eps: EpsilonPolicy::default(),
+        candidate_index: None,
+    })
+    .unwrap();
+    let err = cmd.commit().unwrap().apply(&mut doc).unwrap_err();
+    assert_eq!(err.code, "GEOM_TRIM_NO_INTERSECTION");
+}
@@ -0,0 +1,133 @@
+use craftcad_commands::commands::selection_set::{SaveSelectionSetCommand, SaveSelectionSetInput};
+use craftcad_commands::commands::transform_selection::{
+    Transform, TransformSelectionCommand, TransformSelectionInput,
+};
+use craftcad_commands::{Command, CommandContext, Delta, History};
+use craftcad_serialize::{Document, Entity, Geom2D, Layer, ReasonCode, Vec2};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn sample_doc() -> (Document, Uuid) {
+    let layer_id = Uuid::new_v4();
+    let entity_id = Uuid::new_v4();
+    (
+        Document {
+            schema_version: 3,
+            id: Uuid::new_v4(),
+            units: "mm".to_string(),
+            layers: vec![Layer {
+                id: layer_id,
+                name: "Default".to_string(),
+                visible: true,
+                locked: false,
+                editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
+            }],
+            entities: vec![Entity {
+                id: entity_id,
+                layer_id,
+                geom: Geom2D::Line {
+                    a: Vec2 { x: 0.0, y: 0.0 },
+                    b: Vec2 { x: 10.0, y: 0.0 },
+                },
+                style: serde_json::json!({}),
+                tags: vec![],
+                meta: BTreeMap::new(),
+            }],
+            parts: vec![],
+            jobs: vec![],
+            materials: vec![],
+            settings: craftcad_serialize::ProjectSettings::default(),
+            used_presets: vec![],
+            used_templates: vec![],
+            wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
+        },
+        entity_id,
+    )
+}
+
+fn save(name: &str, entity_ids: Vec<Uuid>) -> Box<dyn Delta> {
+    let mut cmd = SaveSelectionSetCommand::new();
+    cmd.begin(&CommandContext).expect("begin");
+    cmd.update(SaveSelectionSetInput {
+        name: name.into(),
+        entity_ids,
+    })
+    .expect("update");
+    cmd.commit().expect("commit")
+}
+
+#[test]
+fn selection_set_save_replace_delete_undo() {
+    let (mut doc, id) = sample_doc();
+    let mut history = History::new();
+    let other = Uuid::new_v4();
+
+    for delta in [
+        save("legs", vec![id, id]),
+        save("legs", vec![other]),
+        save("legs", vec![]),
+    ] {
+        delta.apply(&mut doc).expect("apply");
+        history.push(delta);
+    }
+    assert!(doc.selection_sets.is_empty());
+
+    history.undo(&mut doc).expect("undo delete");
+    assert_eq!(doc.selection_sets[0].entity_ids, [other]);
+    history.undo(&mut doc).expect("undo replace");
+    assert_eq!(doc.selection_sets[0].entity_ids, [id]);
+    history.undo(&mut doc).expect("undo save");
+    assert!(doc.selection_sets.is_empty());
+    history.redo(&mut doc).expect("redo save");
+    assert_eq!(doc.selection_sets.len(), 1);
+
+    let json = serde_json::to_string(&doc).expect("doc json");
+    craftcad_serialize::validate_document_json_str(&json).expect("schema");
+
+    let mut cmd = SaveSelectionSetCommand::new();
+    cmd.begin(&CommandContext).expect("begin");
+    let err = cmd
+        .update(SaveSelectionSetInput {
+            name: "  ".into(),
+            entity_ids: vec![id],
+        })
+        .unwrap_err();
+    assert_eq!(err.code, ReasonCode::EditSelectionSetNameInvalid.as_str());
+}
+
+#[test]
+fn transform_consumes_named_set() {
+    let (mut doc, id) = sample_doc();
+    let deleted = Uuid::new_v4();
+    save("legs", vec![id, deleted])
+        .apply(&mut doc)
+        .expect("apply");
+
+    let input =
+        TransformSelectionInput::from_set(&doc, "legs", Transform::Translate { dx: 0.0, dy: 5.0 })
+            .expect("set");
+    assert_eq!(input.selection_ids, [id], "deleted entities are left out");
+    let mut cmd = TransformSelectionCommand::new();
+    cmd.begin(&CommandContext).expect("begin");
+    cmd.update(input).expect("update");
+    cmd.commit()
+        .expect("commit")
+        .apply(&mut doc)
+        .expect("apply");
+    assert!(matches!(
+        &doc.entities[0].geom,
+        Geom2D::Line { a, .. } if a.y == 5.0
+    ));
+    assert!(TransformSelectionInput::from_set(
+        &doc,
+        "arms",
+        Transform::Translate { dx: 0.0, dy: 1.0 }
+    )
+    .is_err());
+}
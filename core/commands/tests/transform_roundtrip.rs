@@ -20,6 +20,9 @@ fn sample_doc() -> (Document, Uuid) {
                 visible: true,
                 locked: false,
                 editable: true,
+                color: None,
+                linetype: "CONTINUOUS".into(),
+                print: true,
             }],
             entities: vec![Entity {
                 id: entity_id,
@@ -39,6 +42,9 @@ fn sample_doc() -> (Document, Uuid) {
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
         },
         entity_id,
     )
@@ -77,3 +83,67 @@ fn transform_roundtrip_undo_redo_json_equality() {
     history.undo(&mut doc).expect("undo 2");
     assert_eq!(doc_json(&doc), before);
 }
+
+fn ellipse_point(g: &Geom2D, t: f64) -> (f64, f64) {
+    match g {
+        Geom2D::EllipticalArc { c, rx, ry, rot, .. } => {
+            let (x, y) = (rx * t.cos(), ry * t.sin());
+            (
+                c.x + x * rot.cos() - y * rot.sin(),
+                c.y + x * rot.sin() + y * rot.cos(),
+            )
+        }
+        other => panic!("expected elliptical arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn non_uniform_scale_keeps_elliptical_arc_end_points() {
+    let (mut doc, entity_id) = sample_doc();
+    let arc = Geom2D::EllipticalArc {
+        c: Vec2 { x: 1.0, y: 2.0 },
+        rx: 4.0,
+        ry: 1.5,
+        rot: 0.5,
+        start_angle: 0.2,
+        end_angle: 2.0,
+        ccw: true,
+    };
+    doc.entities[0].geom = arc.clone();
+    for (sx, sy) in [(2.0, 0.5), (-1.5, 3.0)] {
+        let mut d = doc.clone();
+        let mut cmd = TransformSelectionCommand::new();
+        cmd.begin(&CommandContext).unwrap();
+        cmd.update(TransformSelectionInput {
+            selection_ids: vec![entity_id],
+            transform: Transform::Scale {
+                cx: 0.0,
+                cy: 0.0,
+                sx,
+                sy,
+            },
+        })
+        .unwrap();
+        cmd.commit().unwrap().apply(&mut d).unwrap();
+        let scaled = &d.entities[0].geom;
+        let (start_angle, end_angle, ccw) = match scaled {
+            Geom2D::EllipticalArc {
+                start_angle,
+                end_angle,
+                ccw,
+                ..
+            } => (*start_angle, *end_angle, *ccw),
+            other => panic!("expected elliptical arc, got {other:?}"),
+        };
+        assert_eq!(ccw, sx * sy > 0.0);
+        for (t_old, t_new) in [
+            (0.2, start_angle),
+            (1.1, (start_angle + end_angle) / 2.0),
+            (2.0, end_angle),
+        ] {
+            let (x, y) = ellipse_point(&arc, t_old);
+            let (nx, ny) = ellipse_point(scaled, t_new);
+            assert!((x * sx - nx).abs() < 1e-9 && (y * sy - ny).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::model::{Constraint, ConstraintIssue, DofReport, EntityDof, IssueKind, SolvePolicy};
+use crate::system::{build, check_policy, jacobian, referenced, Vars};
+use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_sketch::model::{EntityId, SketchDoc};
+
+/// Relative size below which a row counts as dependent on earlier rows. The
+/// Jacobian comes from central differences, so this sits well above their
+/// round-off.
+const RANK_EPS: f64 = 1e-6;
+
+/// Coefficient below which an earlier row does not take part in a dependency.
+const COMBO_EPS: f64 = 1e-6;
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(p, q)| p * q).sum()
+}
+
+fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Orthonormal basis of a row space, built by modified Gram-Schmidt. Every
+/// basis vector remembers how it is made from the original rows.
+struct RowSpace {
+    basis: Vec<Vec<f64>>,
+    /// `(original row, coefficient)` pairs per basis vector.
+    combos: Vec<Vec<(usize, f64)>>,
+    scale: f64,
+}
+
+impl RowSpace {
+    fn new(scale: f64) -> Self {
+        Self {
+            basis: vec![],
+            combos: vec![],
+            scale,
+        }
+    }
+
+    /// Adds `row` if it is independent. Otherwise returns the earlier rows it
+    /// is a combination of.
+    fn insert(&mut self, index: usize, row: &[f64]) -> Result<(), Vec<usize>> {
+        let mut w = row.to_vec();
+        let mut combo: Vec<(usize, f64)> = vec![(index, 1.0)];
+        for (q, qc) in self.basis.iter().zip(&self.combos) {
+            let c = dot(q, &w);
+            for (wi, qi) in w.iter_mut().zip(q) {
+                *wi -= c * qi;
+            }
+            for (j, k) in qc {
+                match combo.iter_mut().find(|(i, _)| i == j) {
+                    Some(e) => e.1 -= c * k,
+                    None => combo.push((*j, -c * k)),
+                }
+            }
+        }
+        let len = norm(&w);
+        if len <= RANK_EPS * self.scale.max(norm(row)) {
+            let mut deps: Vec<usize> = combo
+                .iter()
+                .filter(|(j, c)| *j != index && c.abs() > COMBO_EPS)
+                .map(|(j, _)| *j)
+                .collect();
+            deps.sort_unstable();
+            return Err(deps);
+        }
+        self.basis.push(w.iter().map(|v| v / len).collect());
+        self.combos
+            .push(combo.into_iter().map(|(j, c)| (j, c / len)).collect());
+        Ok(())
+    }
+}
+
+/// Rank of a set of vectors.
+fn rank(vectors: &[Vec<f64>], scale: f64) -> usize {
+    let mut space = RowSpace::new(scale);
+    vectors
+        .iter()
+        .enumerate()
+        .filter(|(i, v)| space.insert(*i, v).is_ok())
+        .count()
+}
+
+fn entity_slots(v: Vars) -> Vec<usize> {
+    match v {
+        Vars::Segment { a, b } => vec![a, a + 1, b, b + 1],
+        Vars::Circle { c, r } => vec![c, c + 1, r],
+        Vars::Arc { c, r, a0, a1 } => vec![c, c + 1, r, a0, a1],
+    }
+}
+
+fn issue_error(kind: IssueKind, constraints: &[usize], entities: &[String]) -> AppError {
+    let err = match kind {
+        IssueKind::Conflicting => AppError::new(
+            ReasonCode::new("DRAW_CONSTRAINT_CONFLICT"),
+            Severity::Error,
+            "constraints conflict; no solution satisfies all of them",
+        )
+        .with_hint("remove or relax one of the listed constraints"),
+        IssueKind::Redundant => AppError::new(
+            ReasonCode::new("DRAW_CONSTRAINT_REDUNDANT"),
+            Severity::Warn,
+            "constraint is implied by the other listed constraints",
+        )
+        .with_hint("remove one of the listed constraints"),
+    };
+    let list = |v: Vec<String>| v.join(",");
+    err.with_context(
+        "constraints",
+        list(constraints.iter().map(|i| i.to_string()).collect()),
+    )
+    .with_context("entity_ids", list(entities.to_vec()))
+}
+
+/// Degrees-of-freedom analysis of `constraints` on the current geometry of
+/// `doc`, which is left untouched.
+///
+/// The analysis linearises the constraints at the current geometry. Working
+/// through constraints in order, a constraint whose rows depend on earlier
+/// rows forms an issue together with the earlier constraints it depends on.
+/// The set is then solved on its own: if it solves it is redundant,
+/// otherwise conflicting. Remaining freedom is the null space of the
+/// constraint Jacobian, counted globally and per entity.
+pub fn analyze(
+    doc: &SketchDoc,
+    constraints: &[Constraint],
+    policy: &SolvePolicy,
+) -> AppResult<DofReport> {
+    check_policy(policy)?;
+    let system = build(doc, constraints, policy.tolerance, true)?;
+    let jac = jacobian(&system.rows, &system.x);
+    let scale = jac.iter().map(|r| norm(r)).fold(1.0, f64::max);
+
+    let mut space = RowSpace::new(scale);
+    let mut issues = vec![];
+    for (i, row) in jac.iter().enumerate() {
+        let Err(deps) = space.insert(i, row) else {
+            continue;
+        };
+        let own = system.owner[i];
+        let mut set: Vec<usize> = deps.iter().map(|j| system.owner[*j]).collect();
+        set.push(own);
+        set.sort_unstable();
+        set.dedup();
+        // A constraint with several dependent rows is reported once.
+        if issues
+            .iter()
+            .any(|x: &ConstraintIssue| x.constraints.last() == Some(&own))
+        {
+            continue;
+        }
+        let subset: Vec<Constraint> = set.iter().map(|k| constraints[*k].clone()).collect();
+        let kind = match crate::solver::solve(&mut doc.clone(), &subset, policy) {
+            Ok(_) => IssueKind::Redundant,
+            Err(_) => IssueKind::Conflicting,
+        };
+        let entities: Vec<EntityId> = doc
+            .entities
+            .iter()
+            .map(|e| e.id())
+            .filter(|id| {
+                set.iter()
+                    .any(|k| referenced(&constraints[*k]).contains(id))
+            })
+            .cloned()
+            .collect();
+        let names: Vec<String> = entities.iter().map(|e| e.0.clone()).collect();
+        issues.push(ConstraintIssue {
+            kind,
+            error: issue_error(kind, &set, &names),
+            constraints: set,
+            entities,
+        });
+    }
+
+    // Projector onto the null space, `I - Q^T Q`; an entity's freedom is the
+    // rank of the projector rows for its variables.
+    let n = system.x.len();
+    let null_row = |slot: usize| -> Vec<f64> {
+        (0..n)
+            .map(|j| {
+                let id = if j == slot { 1.0 } else { 0.0 };
+                id - space.basis.iter().map(|q| q[slot] * q[j]).sum::<f64>()
+            })
+            .collect()
+    };
+    let mut entities = vec![];
+    let mut under_constrained = vec![];
+    for (e, v) in doc.entities.iter().zip(&system.vars) {
+        let Some(v) = v else { continue };
+        let rows: Vec<Vec<f64>> = entity_slots(*v).into_iter().map(null_row).collect();
+        let dof = rank(&rows, 1.0) as u32;
+        if dof > 0 {
+            under_constrained.push(e.id().clone());
+        }
+        entities.push(EntityDof {
+            entity: e.id().clone(),
+            dof,
+        });
+    }
+    Ok(DofReport {
+        total_dof: (n - space.basis.len()) as u32,
+        entities,
+        under_constrained,
+        issues,
+    })
+}
+
+impl DofReport {
+    /// True when nothing can move and no constraint is redundant or in conflict.
+    pub fn is_well_constrained(&self) -> bool {
+        self.total_dof == 0 && self.issues.is_empty()
+    }
+
+    /// One error per issue, then one `DRAW_CONSTRAINT_UNDER_CONSTRAINED`
+    /// info per entity that can still move, for the UI to list and colour.
+    pub fn diagnostics(&self) -> Vec<AppError> {
+        let mut out: Vec<AppError> = self.issues.iter().map(|i| i.error.clone()).collect();
+        for e in &self.entities {
+            if e.dof > 0 {
+                out.push(
+                    AppError::new(
+                        ReasonCode::new("DRAW_CONSTRAINT_UNDER_CONSTRAINED"),
+                        Severity::Info,
+                        "entity can still move",
+                    )
+                    .with_context("entity_id", e.entity.0.clone())
+                    .with_context("dof", e.dof.to_string()),
+                );
+            }
+        }
+        out
+    }
+}
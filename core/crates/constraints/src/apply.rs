@@ -1,8 +1,14 @@
-use crate::model::SolvePolicy;
+use crate::model::{Constraint, SolvePolicy, SolveReport};
 
+/// Solves the constraints persisted in `doc`, in stored order.
 pub fn apply_constraints(
     doc: &mut craftcad_sketch::model::SketchDoc,
     policy: &SolvePolicy,
-) -> craftcad_errors::AppResult<()> {
-    crate::solver::solve(doc, policy)
+) -> craftcad_errors::AppResult<SolveReport> {
+    let constraints: Vec<Constraint> = doc
+        .constraints
+        .iter()
+        .map(|c| c.constraint.clone())
+        .collect();
+    crate::solver::solve(doc, &constraints, policy)
 }
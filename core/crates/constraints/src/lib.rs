@@ -1,3 +1,5 @@
+pub mod analysis;
 pub mod apply;
 pub mod model;
 pub mod solver;
+mod system;
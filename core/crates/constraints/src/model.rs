@@ -1,14 +1,7 @@
+use craftcad_errors::AppError;
 use craftcad_sketch::model::EntityId;
 
-#[derive(Clone, Debug)]
-pub enum Constraint {
-    Horizontal { seg: EntityId },
-    Vertical { seg: EntityId },
-    Perpendicular { a: EntityId, b: EntityId },
-    Parallel { a: EntityId, b: EntityId },
-    LengthFixed { seg: EntityId, len: f64 },
-    AngleFixed { a: EntityId, b: EntityId, deg: f64 },
-}
+pub use craftcad_sketch::model::{Constraint, PointKind, PointRef};
 
 #[derive(Clone, Debug)]
 pub struct SolvePolicy {
@@ -24,3 +17,44 @@ impl Default for SolvePolicy {
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveReport {
+    pub iterations: u32,
+    pub max_residual: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityDof {
+    pub entity: EntityId,
+    pub dof: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    /// Implied by other constraints; removing it changes nothing.
+    Redundant,
+    /// Cannot hold together with the other constraints of the set.
+    Conflicting,
+}
+
+/// A minimal set of constraints that depend on each other. `constraints`
+/// are indices into the analysed list, ascending; `entities` are the
+/// entities they touch, in document order.
+#[derive(Clone, Debug)]
+pub struct ConstraintIssue {
+    pub kind: IssueKind,
+    pub constraints: Vec<usize>,
+    pub entities: Vec<EntityId>,
+    pub error: AppError,
+}
+
+#[derive(Clone, Debug)]
+pub struct DofReport {
+    /// Remaining degrees of freedom of the whole sketch.
+    pub total_dof: u32,
+    /// Remaining degrees of freedom per segment, circle and arc.
+    pub entities: Vec<EntityDof>,
+    pub under_constrained: Vec<EntityId>,
+    pub issues: Vec<ConstraintIssue>,
+}
@@ -1,18 +1,180 @@
-use crate::model::SolvePolicy;
+use crate::model::{Constraint, SolvePolicy, SolveReport};
+use crate::system::{build, check_policy, delta, eval, jacobian, max_abs, sum_sq, Vars};
 use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_sketch::model::{Entity, SketchDoc};
 
-pub fn solve(doc: &mut craftcad_sketch::model::SketchDoc, policy: &SolvePolicy) -> AppResult<()> {
-    if !policy.tolerance.is_finite() || policy.tolerance <= 0.0 {
-        return Err(AppError::new(
-            ReasonCode::new("CAD_CONSTRAINT_POLICY_INVALID"),
-            Severity::Error,
-            "invalid solve policy",
-        ));
+/// Damping above which a rejected step means the solver cannot make progress.
+const MAX_LAMBDA: f64 = 1e12;
+
+fn conflict(message: &str) -> AppError {
+    AppError::new(
+        ReasonCode::new("DRAW_CONSTRAINT_CONFLICT"),
+        Severity::Error,
+        message,
+    )
+    .with_hint("remove or relax one of the listed constraints")
+}
+
+/// Solves `(a + lambda I) step = -g` by Cholesky; `None` if the damped
+/// matrix is not positive definite.
+fn damped_step(a: &[Vec<f64>], lambda: f64, g: &[f64]) -> Option<Vec<f64>> {
+    let n = g.len();
+    let mut l = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let dot: f64 = l[i][..j].iter().zip(&l[j][..j]).map(|(p, q)| p * q).sum();
+            let s = a[i][j] + if i == j { lambda } else { 0.0 } - dot;
+            if i == j {
+                if s <= 0.0 || !s.is_finite() {
+                    return None;
+                }
+                l[i][i] = s.sqrt();
+            } else {
+                l[i][j] = s / l[j][j];
+            }
+        }
+    }
+    let mut y = vec![0.0; n];
+    for i in 0..n {
+        let s: f64 = (0..i).map(|k| l[i][k] * y[k]).sum();
+        y[i] = (-g[i] - s) / l[i][i];
     }
-    for _ in 0..policy.iterations {
-        if doc.entities.is_empty() {
+    let mut step = vec![0.0; n];
+    for i in (0..n).rev() {
+        let s: f64 = (i + 1..n).map(|k| l[k][i] * step[k]).sum();
+        step[i] = (y[i] - s) / l[i][i];
+    }
+    Some(step)
+}
+
+/// Moves sketch geometry so that `constraints` hold within
+/// `policy.tolerance`, using damped least squares (Levenberg-Marquardt).
+/// Damping keeps under-constrained sketches close to where they were drawn.
+/// The document is only written when the solve succeeds.
+pub fn solve(
+    doc: &mut SketchDoc,
+    constraints: &[Constraint],
+    policy: &SolvePolicy,
+) -> AppResult<SolveReport> {
+    check_policy(policy)?;
+    let tol = policy.tolerance;
+
+    let system = build(doc, constraints, tol, false)?;
+    let (rows, owner, vars) = (&system.rows, &system.owner, &system.vars);
+    let mut x = system.x.clone();
+    let mut r = eval(rows, &x);
+    let mut lambda = 1e-3;
+    let mut iterations = 0;
+    let mut stalled = false;
+    while max_abs(&r) > tol && iterations < policy.iterations {
+        iterations += 1;
+        let jac = jacobian(rows, &x);
+        let n = x.len();
+        let mut a = vec![vec![0.0; n]; n];
+        let mut g = vec![0.0; n];
+        for (row, ri) in jac.iter().zip(&r) {
+            for i in 0..n {
+                if row[i] == 0.0 {
+                    continue;
+                }
+                g[i] += row[i] * ri;
+                for j in 0..n {
+                    a[i][j] += row[i] * row[j];
+                }
+            }
+        }
+        // A vanishing gradient with residual left means a least-squares
+        // minimum that does not satisfy every constraint.
+        if max_abs(&g) <= tol * 1e-6 {
+            stalled = true;
             break;
         }
+        let cost = sum_sq(&r);
+        let mut accepted = false;
+        while lambda <= MAX_LAMBDA {
+            if let Some(step) = damped_step(&a, lambda, &g) {
+                let trial: Vec<f64> = x.iter().zip(&step).map(|(v, d)| v + d).collect();
+                let rt = eval(rows, &trial);
+                let trial_cost = sum_sq(&rt);
+                if trial_cost < cost {
+                    stalled = cost - trial_cost <= cost * 1e-14;
+                    x = trial;
+                    r = rt;
+                    lambda = (lambda * 0.1).max(1e-12);
+                    accepted = true;
+                    break;
+                }
+            }
+            lambda *= 10.0;
+        }
+        if !accepted || stalled {
+            stalled = true;
+            break;
+        }
+    }
+
+    let max_residual = max_abs(&r);
+    if max_residual > tol {
+        let worst = r
+            .iter()
+            .enumerate()
+            .fold(0, |w, (i, v)| if v.abs() > r[w].abs() { i } else { w });
+        let err = if stalled {
+            conflict("constraints conflict; no solution satisfies all of them")
+        } else {
+            AppError::new(
+                ReasonCode::new("DRAW_CONSTRAINT_NOT_CONVERGED"),
+                Severity::Error,
+                "constraint solve did not converge within the iteration limit",
+            )
+            .with_hint("increase the iteration limit or start from a closer sketch")
+        };
+        return Err(err
+            .with_context("constraint", owner[worst].to_string())
+            .with_context("max_residual", format!("{max_residual:e}")));
+    }
+    for (pos, v) in vars.iter().enumerate() {
+        let size = match *v {
+            Some(Vars::Segment { a, b }) => {
+                let (dx, dy) = delta(&x, (a, b));
+                dx.hypot(dy)
+            }
+            Some(Vars::Circle { r, .. } | Vars::Arc { r, .. }) => x[r],
+            None => continue,
+        };
+        if size <= tol {
+            return Err(conflict("constraints collapse an entity to a point")
+                .with_context("entity_id", doc.entities[pos].id().0.clone()));
+        }
+    }
+
+    // Unconstrained entities sharing a welded point follow it.
+    let solved = |p| system.moved(&x, p, tol);
+    for (e, v) in doc.entities.iter_mut().zip(vars) {
+        match (e, *v) {
+            (Entity::Segment(s), _) => {
+                s.a = solved(s.a);
+                s.b = solved(s.b);
+            }
+            (Entity::Circle(c), v) => {
+                c.c = solved(c.c);
+                if let Some(Vars::Circle { r, .. }) = v {
+                    c.r = x[r];
+                }
+            }
+            (Entity::Arc(arc), v) => {
+                arc.c = solved(arc.c);
+                if let Some(Vars::Arc { r, a0, a1, .. }) = v {
+                    arc.r = x[r];
+                    arc.start_deg = x[a0].to_degrees();
+                    arc.end_deg = x[a1].to_degrees();
+                }
+            }
+            _ => {}
+        }
     }
-    Ok(())
+    Ok(SolveReport {
+        iterations,
+        max_residual,
+    })
 }
@@ -0,0 +1,504 @@
+use crate::model::{Constraint, PointKind, PointRef, SolvePolicy};
+use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_geom2d::{dist, Pt};
+use craftcad_sketch::model::{Entity, EntityId, SketchDoc};
+use std::f64::consts::FRAC_PI_2;
+
+/// Unknowns of one entity, as offsets into the solution vector. Points take
+/// two slots (x, y); arc angles are in radians.
+#[derive(Clone, Copy)]
+pub(crate) enum Vars {
+    Segment {
+        a: usize,
+        b: usize,
+    },
+    Circle {
+        c: usize,
+        r: usize,
+    },
+    Arc {
+        c: usize,
+        r: usize,
+        a0: usize,
+        a1: usize,
+    },
+}
+
+/// A point a constraint acts on: a point variable, or an arc end derived
+/// from the arc's center, radius and angle.
+#[derive(Clone, Copy)]
+pub(crate) enum P {
+    Var(usize),
+    ArcEnd { c: usize, r: usize, ang: usize },
+}
+
+/// Curve a point can lie on or a circle can touch. Arcs act as their circle.
+#[derive(Clone, Copy)]
+pub(crate) enum Curve {
+    Line(usize, usize),
+    Circle { c: usize, r: usize },
+}
+
+/// One residual row; every residual is measured in document length units
+/// except arc angles, which only appear through derived points.
+pub(crate) enum Row {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+    Length(usize, usize, f64),
+    /// Line angle from the first segment to the second, modulo 180 degrees.
+    Angle((usize, usize), (usize, usize), f64),
+    /// `p[axis] - value`.
+    Coord(P, usize, f64),
+    /// `q[axis] - p[axis] - value`.
+    Diff(P, P, usize, f64),
+    OnCurve(P, Curve),
+    /// Distance from the circle center to the line minus the radius.
+    LineTangent((usize, usize), (usize, usize)),
+    /// Center distance minus `r1 + r2`, or minus `sign * (r1 - r2)` when the
+    /// circles touch from inside.
+    CircleTangent((usize, usize), (usize, usize), Option<f64>),
+    EqualLength((usize, usize), (usize, usize)),
+    /// `scale * x[var] - value`.
+    Scalar(usize, f64, f64),
+    /// `x[a] - x[b]`.
+    Same(usize, usize),
+    /// Midpoint of `p`, `q` on the axis line, and `pq` normal to it.
+    SymmetricMid(P, P, (usize, usize)),
+    SymmetricNormal(P, P, (usize, usize)),
+}
+
+fn invalid_numeric(idx: usize) -> AppError {
+    AppError::new(
+        ReasonCode::new("DRAW_INVALID_NUMERIC"),
+        Severity::Error,
+        "constraint value must be finite",
+    )
+    .with_context("constraint", idx.to_string())
+}
+
+fn invalid_target(idx: usize, id: &EntityId, message: &str) -> AppError {
+    AppError::new(
+        ReasonCode::new("DRAW_CONSTRAINT_TARGET_INVALID"),
+        Severity::Error,
+        message,
+    )
+    .with_context("constraint", idx.to_string())
+    .with_context("entity_id", id.0.clone())
+}
+
+/// Ids of the entities a constraint references, in field order.
+pub(crate) fn referenced(c: &Constraint) -> Vec<&EntityId> {
+    match c {
+        Constraint::Horizontal { seg }
+        | Constraint::Vertical { seg }
+        | Constraint::LengthFixed { seg, .. } => vec![seg],
+        Constraint::Perpendicular { a, b }
+        | Constraint::Parallel { a, b }
+        | Constraint::AngleFixed { a, b, .. }
+        | Constraint::Tangent { a, b }
+        | Constraint::Concentric { a, b }
+        | Constraint::EqualLength { a, b }
+        | Constraint::EqualRadius { a, b } => vec![a, b],
+        Constraint::Coincident { p, q }
+        | Constraint::HorizontalDistance { p, q, .. }
+        | Constraint::VerticalDistance { p, q, .. } => vec![&p.entity, &q.entity],
+        Constraint::PointOnCurve { p, curve } => vec![&p.entity, curve],
+        Constraint::Symmetric { p, q, axis } => vec![&p.entity, &q.entity, axis],
+        Constraint::Radius { curve, .. } | Constraint::Diameter { curve, .. } => vec![curve],
+        Constraint::Fixed { p, .. } => vec![&p.entity],
+    }
+}
+
+/// Solution vector under construction. Segment ends and centers closer than
+/// `tol` share one point variable so connected geometry stays connected.
+struct Builder {
+    x: Vec<f64>,
+    points: Vec<(Pt, usize)>,
+    tol: f64,
+}
+
+impl Builder {
+    fn point(&mut self, p: Pt) -> usize {
+        if let Some((_, at)) = self.points.iter().find(|(q, _)| dist(*q, p) <= self.tol) {
+            return *at;
+        }
+        let at = self.scalar(p.x);
+        self.x.push(p.y);
+        self.points.push((p, at));
+        at
+    }
+
+    fn scalar(&mut self, v: f64) -> usize {
+        self.x.push(v);
+        self.x.len() - 1
+    }
+}
+
+fn finite(vals: &[f64]) -> bool {
+    vals.iter().all(|v| v.is_finite())
+}
+
+fn point_at(x: &[f64], p: P) -> (f64, f64) {
+    match p {
+        P::Var(at) => (x[at], x[at + 1]),
+        P::ArcEnd { c, r, ang } => (x[c] + x[r] * x[ang].cos(), x[c + 1] + x[r] * x[ang].sin()),
+    }
+}
+
+pub(crate) fn delta(x: &[f64], (a, b): (usize, usize)) -> (f64, f64) {
+    (x[b] - x[a], x[b + 1] - x[a + 1])
+}
+
+/// Signed distance from `p` to the line through segment `(a, b)`.
+fn line_dist(x: &[f64], (a, b): (usize, usize), (px, py): (f64, f64)) -> f64 {
+    let (dx, dy) = delta(x, (a, b));
+    let len = dx.hypot(dy).max(f64::EPSILON);
+    (dx * (py - x[a + 1]) - dy * (px - x[a])) / len
+}
+
+pub(crate) fn eval(rows: &[Row], x: &[f64]) -> Vec<f64> {
+    rows.iter()
+        .map(|row| match *row {
+            Row::Horizontal(a, b) => delta(x, (a, b)).1,
+            Row::Vertical(a, b) => delta(x, (a, b)).0,
+            Row::Length(a, b, len) => {
+                let (dx, dy) = delta(x, (a, b));
+                dx.hypot(dy) - len
+            }
+            Row::Angle(sa, sb, theta) => {
+                let (ux, uy) = delta(x, sa);
+                let (vx, vy) = delta(x, sb);
+                let (s, c) = theta.sin_cos();
+                let (wx, wy) = (ux * c - uy * s, ux * s + uy * c);
+                let scale = (ux.hypot(uy) * vx.hypot(vy)).sqrt().max(f64::EPSILON);
+                (wx * vy - wy * vx) / scale
+            }
+            Row::Coord(p, axis, v) => {
+                let (px, py) = point_at(x, p);
+                [px, py][axis] - v
+            }
+            Row::Diff(p, q, axis, v) => {
+                let (px, py) = point_at(x, p);
+                let (qx, qy) = point_at(x, q);
+                [qx - px, qy - py][axis] - v
+            }
+            Row::OnCurve(p, Curve::Line(a, b)) => line_dist(x, (a, b), point_at(x, p)),
+            Row::OnCurve(p, Curve::Circle { c, r }) => {
+                let (px, py) = point_at(x, p);
+                (px - x[c]).hypot(py - x[c + 1]) - x[r]
+            }
+            Row::LineTangent(seg, (c, r)) => line_dist(x, seg, (x[c], x[c + 1])).abs() - x[r],
+            Row::CircleTangent((c1, r1), (c2, r2), inside) => {
+                let d = (x[c2] - x[c1]).hypot(x[c2 + 1] - x[c1 + 1]);
+                match inside {
+                    Some(sign) => d - sign * (x[r1] - x[r2]),
+                    None => d - (x[r1] + x[r2]),
+                }
+            }
+            Row::EqualLength(sa, sb) => {
+                let (ux, uy) = delta(x, sa);
+                let (vx, vy) = delta(x, sb);
+                ux.hypot(uy) - vx.hypot(vy)
+            }
+            Row::Scalar(at, scale, v) => scale * x[at] - v,
+            Row::Same(a, b) => x[a] - x[b],
+            Row::SymmetricMid(p, q, axis) => {
+                let (px, py) = point_at(x, p);
+                let (qx, qy) = point_at(x, q);
+                line_dist(x, axis, ((px + qx) / 2.0, (py + qy) / 2.0))
+            }
+            Row::SymmetricNormal(p, q, axis) => {
+                let (px, py) = point_at(x, p);
+                let (qx, qy) = point_at(x, q);
+                let (dx, dy) = delta(x, axis);
+                (dx * (qx - px) + dy * (qy - py)) / dx.hypot(dy).max(f64::EPSILON)
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn max_abs(v: &[f64]) -> f64 {
+    v.iter().fold(0.0, |m, r| m.max(r.abs()))
+}
+
+pub(crate) fn sum_sq(v: &[f64]) -> f64 {
+    v.iter().map(|r| r * r).sum()
+}
+
+/// Central-difference Jacobian, row major (`rows.len()` x `x.len()`).
+pub(crate) fn jacobian(rows: &[Row], x: &[f64]) -> Vec<Vec<f64>> {
+    let mut xs = x.to_vec();
+    let mut jac = vec![vec![0.0; x.len()]; rows.len()];
+    for j in 0..x.len() {
+        let h = 1e-7 * (1.0 + x[j].abs());
+        xs[j] = x[j] + h;
+        let plus = eval(rows, &xs);
+        xs[j] = x[j] - h;
+        let minus = eval(rows, &xs);
+        xs[j] = x[j];
+        for (i, row) in jac.iter_mut().enumerate() {
+            row[j] = (plus[i] - minus[i]) / (2.0 * h);
+        }
+    }
+    jac
+}
+
+/// Turns constraints into residual rows over the entity variables.
+/// Returns the rows and, for each row, the index of its constraint.
+fn build_rows(
+    doc: &SketchDoc,
+    constraints: &[Constraint],
+    vars: &[Option<Vars>],
+    x: &[f64],
+) -> AppResult<(Vec<Row>, Vec<usize>)> {
+    let pos = |id: &EntityId| {
+        doc.entities
+            .iter()
+            .position(|e| e.id() == id)
+            .expect("referenced entities are resolved before building rows")
+    };
+    let mut rows = Vec::with_capacity(constraints.len());
+    let mut owner = Vec::with_capacity(constraints.len());
+    for (idx, c) in constraints.iter().enumerate() {
+        let entity = |id: &EntityId| vars[pos(id)].expect("referenced entities have variables");
+        let segment = |id: &EntityId| match entity(id) {
+            Vars::Segment { a, b } => Ok((a, b)),
+            _ => Err(invalid_target(idx, id, "constraint needs a segment")),
+        };
+        let circle = |id: &EntityId| match entity(id) {
+            Vars::Circle { c, r } | Vars::Arc { c, r, .. } => Ok((c, r)),
+            _ => Err(invalid_target(idx, id, "constraint needs a circle or arc")),
+        };
+        let curve = |id: &EntityId| match entity(id) {
+            Vars::Segment { a, b } => Curve::Line(a, b),
+            Vars::Circle { c, r } | Vars::Arc { c, r, .. } => Curve::Circle { c, r },
+        };
+        let point = |p: &PointRef| match (entity(&p.entity), p.kind) {
+            (Vars::Segment { a, .. }, PointKind::Start) => Ok(P::Var(a)),
+            (Vars::Segment { b, .. }, PointKind::End) => Ok(P::Var(b)),
+            (Vars::Circle { c, .. } | Vars::Arc { c, .. }, PointKind::Center) => Ok(P::Var(c)),
+            (Vars::Arc { c, r, a0, .. }, PointKind::Start) => Ok(P::ArcEnd { c, r, ang: a0 }),
+            (Vars::Arc { c, r, a1, .. }, PointKind::End) => Ok(P::ArcEnd { c, r, ang: a1 }),
+            _ => Err(invalid_target(idx, &p.entity, "entity has no such point")),
+        };
+        let value = |v: f64, positive: bool| {
+            if !v.is_finite() || (positive && v <= 0.0) {
+                Err(invalid_numeric(idx))
+            } else {
+                Ok(v)
+            }
+        };
+
+        let mut add = |r: Row| {
+            rows.push(r);
+            owner.push(idx);
+        };
+        match c {
+            Constraint::Horizontal { seg } => {
+                let (a, b) = segment(seg)?;
+                add(Row::Horizontal(a, b));
+            }
+            Constraint::Vertical { seg } => {
+                let (a, b) = segment(seg)?;
+                add(Row::Vertical(a, b));
+            }
+            Constraint::LengthFixed { seg, len } => {
+                let (a, b) = segment(seg)?;
+                add(Row::Length(a, b, value(*len, true)?));
+            }
+            Constraint::Parallel { a, b } => add(Row::Angle(segment(a)?, segment(b)?, 0.0)),
+            Constraint::Perpendicular { a, b } => {
+                add(Row::Angle(segment(a)?, segment(b)?, FRAC_PI_2))
+            }
+            Constraint::AngleFixed { a, b, deg } => add(Row::Angle(
+                segment(a)?,
+                segment(b)?,
+                value(*deg, false)?.to_radians(),
+            )),
+            Constraint::Coincident { p, q } => {
+                let (p, q) = (point(p)?, point(q)?);
+                add(Row::Diff(p, q, 0, 0.0));
+                add(Row::Diff(p, q, 1, 0.0));
+            }
+            Constraint::PointOnCurve { p, curve: id } => {
+                add(Row::OnCurve(point(p)?, curve(id)));
+            }
+            Constraint::Tangent { a, b } => match (curve(a), curve(b)) {
+                (Curve::Line(s0, s1), Curve::Circle { c, r })
+                | (Curve::Circle { c, r }, Curve::Line(s0, s1)) => {
+                    add(Row::LineTangent((s0, s1), (c, r)))
+                }
+                (Curve::Circle { c: c1, r: r1 }, Curve::Circle { c: c2, r: r2 }) => {
+                    // Keep the kind of contact the sketch is closest to.
+                    let d = (x[c2] - x[c1]).hypot(x[c2 + 1] - x[c1 + 1]);
+                    let outside = (d - (x[r1] + x[r2])).abs();
+                    let inside = (d - (x[r1] - x[r2]).abs()).abs();
+                    let sign = if x[r1] >= x[r2] { 1.0 } else { -1.0 };
+                    add(Row::CircleTangent(
+                        (c1, r1),
+                        (c2, r2),
+                        (inside < outside).then_some(sign),
+                    ));
+                }
+                (Curve::Line(..), Curve::Line(..)) => {
+                    return Err(invalid_target(idx, b, "tangent needs a circle or arc"));
+                }
+            },
+            Constraint::Concentric { a, b } => {
+                let (ca, cb) = (circle(a)?.0, circle(b)?.0);
+                add(Row::Diff(P::Var(ca), P::Var(cb), 0, 0.0));
+                add(Row::Diff(P::Var(ca), P::Var(cb), 1, 0.0));
+            }
+            Constraint::EqualLength { a, b } => add(Row::EqualLength(segment(a)?, segment(b)?)),
+            Constraint::EqualRadius { a, b } => add(Row::Same(circle(a)?.1, circle(b)?.1)),
+            Constraint::Symmetric { p, q, axis } => {
+                let (p, q, axis) = (point(p)?, point(q)?, segment(axis)?);
+                add(Row::SymmetricMid(p, q, axis));
+                add(Row::SymmetricNormal(p, q, axis));
+            }
+            Constraint::HorizontalDistance { p, q, dist } => {
+                add(Row::Diff(point(p)?, point(q)?, 0, value(*dist, false)?))
+            }
+            Constraint::VerticalDistance { p, q, dist } => {
+                add(Row::Diff(point(p)?, point(q)?, 1, value(*dist, false)?))
+            }
+            Constraint::Radius { curve: id, r } => {
+                add(Row::Scalar(circle(id)?.1, 1.0, value(*r, true)?))
+            }
+            Constraint::Diameter { curve: id, d } => {
+                add(Row::Scalar(circle(id)?.1, 2.0, value(*d, true)?))
+            }
+            Constraint::Fixed { p, at } => {
+                if !finite(&[at.x, at.y]) {
+                    return Err(invalid_numeric(idx));
+                }
+                let p = point(p)?;
+                add(Row::Coord(p, 0, at.x));
+                add(Row::Coord(p, 1, at.y));
+            }
+        }
+    }
+    Ok((rows, owner))
+}
+
+pub(crate) fn check_policy(policy: &SolvePolicy) -> AppResult<()> {
+    if !policy.tolerance.is_finite() || policy.tolerance <= 0.0 {
+        return Err(AppError::new(
+            ReasonCode::new("CAD_CONSTRAINT_POLICY_INVALID"),
+            Severity::Error,
+            "invalid solve policy",
+        ));
+    }
+    Ok(())
+}
+
+/// Constraint system over the current sketch geometry.
+pub(crate) struct System {
+    /// Initial values of the unknowns.
+    pub x: Vec<f64>,
+    /// Welded points: original position and offset of the x slot.
+    pub points: Vec<(Pt, usize)>,
+    /// Variables per document entity; `None` for entities outside the system.
+    pub vars: Vec<Option<Vars>>,
+    pub rows: Vec<Row>,
+    /// Constraint index of every row.
+    pub owner: Vec<usize>,
+}
+
+impl System {
+    /// Position of a welded point after solving to `x`, or `p` itself when
+    /// it is not part of the system.
+    pub fn moved(&self, x: &[f64], p: Pt, tol: f64) -> Pt {
+        self.points
+            .iter()
+            .find(|(q, _)| dist(*q, p) <= tol)
+            .map_or(p, |(_, at)| Pt {
+                x: x[*at],
+                y: x[*at + 1],
+            })
+    }
+}
+
+/// Builds the system for `constraints`. Only referenced entities get
+/// variables unless `all_entities` is set, in which case every segment,
+/// circle and arc does. Entities are numbered in document order, so the
+/// layout does not depend on the order constraints were added.
+pub(crate) fn build(
+    doc: &SketchDoc,
+    constraints: &[Constraint],
+    tol: f64,
+    all_entities: bool,
+) -> AppResult<System> {
+    let supported =
+        |e: &Entity| matches!(e, Entity::Segment(_) | Entity::Circle(_) | Entity::Arc(_));
+    let mut used: Vec<bool> = doc
+        .entities
+        .iter()
+        .map(|e| all_entities && supported(e))
+        .collect();
+    for (idx, c) in constraints.iter().enumerate() {
+        for id in referenced(c) {
+            match doc.entities.iter().position(|e| e.id() == id) {
+                Some(pos) if supported(&doc.entities[pos]) => used[pos] = true,
+                _ => {
+                    return Err(invalid_target(
+                        idx,
+                        id,
+                        "constraint must reference an existing segment, circle or arc",
+                    ))
+                }
+            }
+        }
+    }
+    let mut builder = Builder {
+        x: Vec::new(),
+        points: Vec::new(),
+        tol,
+    };
+    let mut vars = vec![None; doc.entities.len()];
+    for (pos, e) in doc.entities.iter().enumerate() {
+        if !used[pos] {
+            continue;
+        }
+        let ok = match e {
+            Entity::Segment(s) => finite(&[s.a.x, s.a.y, s.b.x, s.b.y]),
+            Entity::Circle(c) => finite(&[c.c.x, c.c.y, c.r]),
+            Entity::Arc(a) => finite(&[a.c.x, a.c.y, a.r, a.start_deg, a.end_deg]),
+            _ => true,
+        };
+        if !ok {
+            return Err(AppError::new(
+                ReasonCode::new("DRAW_INVALID_NUMERIC"),
+                Severity::Error,
+                "entity coordinates must be finite",
+            )
+            .with_context("entity_id", e.id().0.clone()));
+        }
+        vars[pos] = match e {
+            Entity::Segment(s) => Some(Vars::Segment {
+                a: builder.point(s.a),
+                b: builder.point(s.b),
+            }),
+            Entity::Circle(c) => Some(Vars::Circle {
+                c: builder.point(c.c),
+                r: builder.scalar(c.r),
+            }),
+            Entity::Arc(a) => Some(Vars::Arc {
+                c: builder.point(a.c),
+                r: builder.scalar(a.r),
+                a0: builder.scalar(a.start_deg.to_radians()),
+                a1: builder.scalar(a.end_deg.to_radians()),
+            }),
+            _ => None,
+        };
+    }
+
+    let (rows, owner) = build_rows(doc, constraints, &vars, &builder.x)?;
+    Ok(System {
+        x: builder.x,
+        points: builder.points,
+        vars,
+        rows,
+        owner,
+    })
+}
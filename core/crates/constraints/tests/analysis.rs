@@ -0,0 +1,116 @@
+use craftcad_constraints::analysis::analyze;
+use craftcad_constraints::model::{Constraint, IssueKind, PointKind, PointRef, SolvePolicy};
+use craftcad_sketch::model::{CircleEntity, Entity};
+
+mod common;
+use common::{at, doc, id, p, segment};
+
+fn start(entity: &str) -> PointRef {
+    at(entity, PointKind::Start)
+}
+
+fn dof_of(report: &craftcad_constraints::model::DofReport, name: &str) -> u32 {
+    report
+        .entities
+        .iter()
+        .find(|e| e.entity == id(name))
+        .unwrap()
+        .dof
+}
+
+#[test]
+fn free_and_fully_constrained_entities() {
+    let d = doc(vec![
+        segment("s0", p(0.0, 0.0), p(10.0, 0.0)),
+        segment("s1", p(10.0, 0.0), p(10.0, 5.0)),
+        Entity::Circle(CircleEntity {
+            id: id("c0"),
+            layer_id: "L0".into(),
+            c: p(20.0, 0.0),
+            r: 2.0,
+        }),
+    ]);
+    let policy = SolvePolicy::default();
+
+    // Nothing constrained: 6 point coordinates + 3 circle unknowns.
+    let report = analyze(&d, &[], &policy).unwrap();
+    assert_eq!(report.total_dof, 9);
+    assert_eq!(dof_of(&report, "s0"), 4);
+    assert_eq!(dof_of(&report, "c0"), 3);
+
+    let constraints = [
+        Constraint::Fixed {
+            p: start("s0"),
+            at: p(0.0, 0.0),
+        },
+        Constraint::Horizontal { seg: id("s0") },
+        Constraint::LengthFixed {
+            seg: id("s0"),
+            len: 10.0,
+        },
+        Constraint::Perpendicular {
+            a: id("s0"),
+            b: id("s1"),
+        },
+    ];
+    let report = analyze(&d, &constraints, &policy).unwrap();
+    assert!(report.issues.is_empty());
+    assert_eq!(dof_of(&report, "s0"), 0);
+    // s1 can still change its length.
+    assert_eq!(dof_of(&report, "s1"), 1);
+    assert_eq!(report.under_constrained, vec![id("s1"), id("c0")]);
+    assert_eq!(report.total_dof, 4);
+    assert!(!report.is_well_constrained());
+
+    let codes: Vec<String> = report.diagnostics().into_iter().map(|e| e.code).collect();
+    assert_eq!(
+        codes,
+        vec![
+            "DRAW_CONSTRAINT_UNDER_CONSTRAINED",
+            "DRAW_CONSTRAINT_UNDER_CONSTRAINED"
+        ]
+    );
+}
+
+#[test]
+fn redundant_and_conflicting_sets_are_minimal() {
+    let d = doc(vec![
+        segment("s0", p(0.0, 0.0), p(10.0, 0.0)),
+        segment("s1", p(0.0, 5.0), p(10.0, 5.0)),
+        segment("s2", p(0.0, 9.0), p(10.0, 9.0)),
+    ]);
+    let constraints = [
+        Constraint::Horizontal { seg: id("s0") },
+        Constraint::LengthFixed {
+            seg: id("s2"),
+            len: 10.0,
+        },
+        Constraint::Parallel {
+            a: id("s0"),
+            b: id("s1"),
+        },
+        Constraint::Horizontal { seg: id("s1") },
+        Constraint::LengthFixed {
+            seg: id("s2"),
+            len: 12.0,
+        },
+    ];
+    let report = analyze(&d, &constraints, &SolvePolicy::default()).unwrap();
+    assert_eq!(report.issues.len(), 2);
+
+    let redundant = &report.issues[0];
+    assert_eq!(redundant.kind, IssueKind::Redundant);
+    assert_eq!(redundant.constraints, vec![0, 2, 3]);
+    assert_eq!(redundant.entities, vec![id("s0"), id("s1")]);
+    assert_eq!(redundant.error.code, "DRAW_CONSTRAINT_REDUNDANT");
+
+    let conflict = &report.issues[1];
+    assert_eq!(conflict.kind, IssueKind::Conflicting);
+    assert_eq!(conflict.constraints, vec![1, 4]);
+    assert_eq!(conflict.entities, vec![id("s2")]);
+    assert_eq!(conflict.error.code, "DRAW_CONSTRAINT_CONFLICT");
+    assert!(conflict
+        .error
+        .context
+        .contains(&("constraints".to_string(), "1,4".to_string())));
+}
@@ -0,0 +1,75 @@
+//! Sketch builders shared by the constraint tests.
+#![allow(dead_code)]
+
+use craftcad_constraints::model::{PointKind, PointRef};
+use craftcad_geom2d::Pt;
+use craftcad_sketch::model::{
+    ArcEntity, CircleEntity, Entity, EntityId, Meta, SegmentEntity, SketchDoc, Units,
+};
+
+pub fn p(x: f64, y: f64) -> Pt {
+    Pt { x, y }
+}
+
+pub fn id(s: &str) -> EntityId {
+    EntityId(s.to_string())
+}
+
+pub fn at(entity: &str, kind: PointKind) -> PointRef {
+    PointRef {
+        entity: id(entity),
+        kind,
+    }
+}
+
+pub fn segment(name: &str, a: Pt, b: Pt) -> Entity {
+    Entity::Segment(SegmentEntity {
+        id: id(name),
+        layer_id: "L0".into(),
+        a,
+        b,
+    })
+}
+
+pub fn circle(name: &str, c: Pt, r: f64) -> Entity {
+    Entity::Circle(CircleEntity {
+        id: id(name),
+        layer_id: "L0".into(),
+        c,
+        r,
+    })
+}
+
+pub fn arc(name: &str, c: Pt, r: f64, start_deg: f64, end_deg: f64) -> Entity {
+    Entity::Arc(ArcEntity {
+        id: id(name),
+        layer_id: "L0".into(),
+        c,
+        r,
+        start_deg,
+        end_deg,
+    })
+}
+
+pub fn doc(entities: Vec<Entity>) -> SketchDoc {
+    SketchDoc {
+        id: "sketch".into(),
+        units: Units::Mm,
+        layers: vec![],
+        entities,
+        constraints: vec![],
+        meta: Meta {
+            schema_version: 1,
+            created_by: "test".into(),
+            updated_at: "0".into(),
+        },
+    }
+}
+
+/// End points of the `i`-th entity, which must be a segment.
+pub fn seg_of(d: &SketchDoc, i: usize) -> (Pt, Pt) {
+    match &d.entities[i] {
+        Entity::Segment(s) => (s.a, s.b),
+        other => panic!("expected segment, got {other:?}"),
+    }
+}
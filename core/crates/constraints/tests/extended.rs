@@ -0,0 +1,239 @@
+use craftcad_constraints::apply::apply_constraints;
+use craftcad_constraints::model::{Constraint, PointKind, SolvePolicy};
+use craftcad_constraints::solver::solve;
+use craftcad_geom2d::{dist, Pt};
+use craftcad_sketch::model::{ConstraintRef, Entity, SketchDoc};
+
+mod common;
+use common::{arc, at, circle, doc, id, p, seg_of, segment};
+
+fn circle_of(d: &SketchDoc, i: usize) -> (Pt, f64) {
+    match &d.entities[i] {
+        Entity::Circle(c) => (c.c, c.r),
+        Entity::Arc(a) => (a.c, a.r),
+        other => panic!("expected circle or arc, got {other:?}"),
+    }
+}
+
+fn near(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-4
+}
+
+#[test]
+fn circle_sits_tangent_on_fixed_base_line() {
+    let mut d = doc(vec![
+        segment("base", p(0.0, 0.0), p(10.0, 0.2)),
+        circle("hole", p(5.0, 3.4), 2.8),
+        circle("ring", p(5.3, 3.1), 4.0),
+    ]);
+    let constraints = [
+        Constraint::Fixed {
+            p: at("base", PointKind::Start),
+            at: p(0.0, 0.0),
+        },
+        Constraint::Horizontal { seg: id("base") },
+        Constraint::Radius {
+            curve: id("hole"),
+            r: 3.0,
+        },
+        Constraint::Tangent {
+            a: id("base"),
+            b: id("hole"),
+        },
+        Constraint::Concentric {
+            a: id("hole"),
+            b: id("ring"),
+        },
+        Constraint::Diameter {
+            curve: id("ring"),
+            d: 9.0,
+        },
+    ];
+    solve(&mut d, &constraints, &SolvePolicy::default()).unwrap();
+
+    let (a, b) = seg_of(&d, 0);
+    assert!(dist(a, p(0.0, 0.0)) <= 1e-4);
+    assert!(near(b.y, 0.0));
+    let (c, r) = circle_of(&d, 1);
+    assert!(near(r, 3.0) && near(c.y, 3.0));
+    let (rc, rr) = circle_of(&d, 2);
+    assert!(near(rr, 4.5));
+    assert!(dist(rc, c) <= 1e-4);
+}
+
+#[test]
+fn arc_joins_segments_and_circles_touch() {
+    let mut d = doc(vec![
+        segment("left", p(0.0, 0.0), p(0.0, 10.0)),
+        arc("top", p(5.2, 10.1), 5.0, 180.0, 0.0),
+        segment("right", p(10.0, 10.0), p(10.0, 0.0)),
+        circle("a", p(0.0, -5.0), 2.0),
+        circle("b", p(4.5, -5.0), 2.0),
+    ]);
+    let constraints = [
+        Constraint::Coincident {
+            p: at("left", PointKind::End),
+            q: at("top", PointKind::Start),
+        },
+        Constraint::Coincident {
+            p: at("top", PointKind::End),
+            q: at("right", PointKind::Start),
+        },
+        Constraint::Vertical { seg: id("left") },
+        Constraint::Vertical { seg: id("right") },
+        Constraint::EqualLength {
+            a: id("left"),
+            b: id("right"),
+        },
+        Constraint::PointOnCurve {
+            p: at("a", PointKind::Center),
+            curve: id("left"),
+        },
+        Constraint::EqualRadius {
+            a: id("a"),
+            b: id("b"),
+        },
+        Constraint::Tangent {
+            a: id("a"),
+            b: id("b"),
+        },
+    ];
+    solve(&mut d, &constraints, &SolvePolicy::default()).unwrap();
+
+    let (_, left_end) = seg_of(&d, 0);
+    let (right_start, _) = seg_of(&d, 2);
+    let (c, r) = circle_of(&d, 1);
+    let Entity::Arc(top) = &d.entities[1] else {
+        unreachable!()
+    };
+    let start = p(
+        c.x + r * top.start_deg.to_radians().cos(),
+        c.y + r * top.start_deg.to_radians().sin(),
+    );
+    let end = p(
+        c.x + r * top.end_deg.to_radians().cos(),
+        c.y + r * top.end_deg.to_radians().sin(),
+    );
+    assert!(dist(start, left_end) <= 1e-4 && dist(end, right_start) <= 1e-4);
+    let (l0, l1) = seg_of(&d, 0);
+    let (r0, r1) = seg_of(&d, 2);
+    assert!(near(dist(l0, l1), dist(r0, r1)));
+
+    let (ca, ra) = circle_of(&d, 3);
+    let (cb, rb) = circle_of(&d, 4);
+    assert!(near(ca.x, l0.x));
+    assert!(near(ra, rb));
+    assert!(near(dist(ca, cb), ra + rb));
+}
+
+#[test]
+fn symmetric_points_and_offsets() {
+    let mut d = doc(vec![
+        segment("axis", p(0.0, -10.0), p(0.0, 10.0)),
+        segment("s", p(-3.0, 1.0), p(2.0, 2.0)),
+        segment("t", p(6.0, 0.0), p(8.0, 3.0)),
+    ]);
+    let constraints = [
+        Constraint::Vertical { seg: id("axis") },
+        Constraint::Symmetric {
+            p: at("s", PointKind::Start),
+            q: at("s", PointKind::End),
+            axis: id("axis"),
+        },
+        Constraint::HorizontalDistance {
+            p: at("s", PointKind::End),
+            q: at("t", PointKind::Start),
+            dist: 5.0,
+        },
+        Constraint::VerticalDistance {
+            p: at("t", PointKind::Start),
+            q: at("t", PointKind::End),
+            dist: -2.0,
+        },
+    ];
+    solve(&mut d, &constraints, &SolvePolicy::default()).unwrap();
+    let (axis, _) = seg_of(&d, 0);
+    let (s0, s1) = seg_of(&d, 1);
+    let (t0, t1) = seg_of(&d, 2);
+    assert!(near((s0.x + s1.x) / 2.0, axis.x));
+    assert!(near(s0.y, s1.y));
+    assert!(near(t0.x - s1.x, 5.0));
+    assert!(near(t1.y - t0.y, -2.0));
+}
+
+#[test]
+fn persisted_constraints_roundtrip_and_apply() {
+    let mut d = doc(vec![
+        segment("s0", p(0.0, 0.0), p(8.0, 1.0)),
+        arc("a0", p(8.0, 4.0), 3.0, -90.0, 90.0),
+    ]);
+    d.constraints = vec![
+        ConstraintRef {
+            id: "c0".into(),
+            constraint: Constraint::Horizontal { seg: id("s0") },
+        },
+        ConstraintRef {
+            id: "c1".into(),
+            constraint: Constraint::Coincident {
+                p: at("s0", PointKind::End),
+                q: at("a0", PointKind::Start),
+            },
+        },
+        ConstraintRef {
+            id: "c2".into(),
+            constraint: Constraint::Tangent {
+                a: id("s0"),
+                b: id("a0"),
+            },
+        },
+    ];
+    let json = serde_json::to_string(&d).unwrap();
+    assert!(json.contains("\"Coincident\""));
+    let mut loaded: SketchDoc = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        loaded.constraints[1].constraint,
+        d.constraints[1].constraint
+    );
+
+    apply_constraints(&mut loaded, &SolvePolicy::default()).unwrap();
+    let (a, b) = seg_of(&loaded, 0);
+    let (c, r) = circle_of(&loaded, 1);
+    assert!(near(a.y, b.y));
+    assert!(near((c.y - b.y).abs(), r));
+}
+
+#[test]
+fn unsupported_targets_are_rejected() {
+    let mut d = doc(vec![
+        segment("s0", p(0.0, 0.0), p(5.0, 0.0)),
+        segment("s1", p(0.0, 1.0), p(5.0, 2.0)),
+    ]);
+    for c in [
+        Constraint::Tangent {
+            a: id("s0"),
+            b: id("s1"),
+        },
+        Constraint::Radius {
+            curve: id("s0"),
+            r: 2.0,
+        },
+        Constraint::Fixed {
+            p: at("s0", PointKind::Center),
+            at: p(0.0, 0.0),
+        },
+    ] {
+        let err = solve(&mut d, &[c], &SolvePolicy::default()).unwrap_err();
+        assert_eq!(err.code, "DRAW_CONSTRAINT_TARGET_INVALID");
+    }
+    let err = solve(
+        &mut d,
+        &[Constraint::HorizontalDistance {
+            p: at("s0", PointKind::Start),
+            q: at("s1", PointKind::Start),
+            dist: f64::INFINITY,
+        }],
+        &SolvePolicy::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "DRAW_INVALID_NUMERIC");
+}
@@ -0,0 +1,144 @@
+use craftcad_constraints::model::{Constraint, SolvePolicy};
+use craftcad_constraints::solver::solve;
+use craftcad_geom2d::dist;
+
+mod common;
+use common::{doc, id, p, seg_of, segment};
+
+#[test]
+fn rectangle_sketch_solves_and_stays_connected() {
+    // A rough quadrilateral drawn by hand.
+    let mut d = doc(vec![
+        segment("s0", p(0.0, 0.0), p(10.2, 0.3)),
+        segment("s1", p(10.2, 0.3), p(9.8, 5.1)),
+        segment("s2", p(9.8, 5.1), p(0.2, 4.9)),
+        segment("s3", p(0.2, 4.9), p(0.0, 0.0)),
+    ]);
+    let constraints = [
+        Constraint::Horizontal { seg: id("s0") },
+        Constraint::Perpendicular {
+            a: id("s0"),
+            b: id("s1"),
+        },
+        Constraint::Parallel {
+            a: id("s0"),
+            b: id("s2"),
+        },
+        Constraint::Vertical { seg: id("s3") },
+        Constraint::LengthFixed {
+            seg: id("s0"),
+            len: 10.0,
+        },
+        Constraint::LengthFixed {
+            seg: id("s1"),
+            len: 5.0,
+        },
+    ];
+    let policy = SolvePolicy::default();
+    let report = solve(&mut d, &constraints, &policy).unwrap();
+    assert!(report.max_residual <= policy.tolerance);
+
+    for i in 0..4 {
+        let (_, end) = seg_of(&d, i);
+        let (next, _) = seg_of(&d, (i + 1) % 4);
+        assert_eq!(end, next);
+    }
+    let (a, b) = seg_of(&d, 0);
+    assert!((a.y - b.y).abs() <= 1e-4);
+    assert!((dist(a, b) - 10.0).abs() <= 1e-4);
+    let (a, b) = seg_of(&d, 1);
+    assert!((a.x - b.x).abs() <= 1e-4);
+    assert!((dist(a, b) - 5.0).abs() <= 1e-4);
+
+    // Same input, same output.
+    let mut again = doc(vec![
+        segment("s0", p(0.0, 0.0), p(10.2, 0.3)),
+        segment("s1", p(10.2, 0.3), p(9.8, 5.1)),
+        segment("s2", p(9.8, 5.1), p(0.2, 4.9)),
+        segment("s3", p(0.2, 4.9), p(0.0, 0.0)),
+    ]);
+    solve(&mut again, &constraints, &policy).unwrap();
+    for i in 0..4 {
+        assert_eq!(seg_of(&d, i), seg_of(&again, i));
+    }
+}
+
+#[test]
+fn angle_fixed_turns_second_segment() {
+    let mut d = doc(vec![
+        segment("base", p(0.0, 0.0), p(10.0, 0.0)),
+        segment("arm", p(0.0, 0.0), p(5.0, 1.0)),
+    ]);
+    let constraints = [
+        Constraint::Horizontal { seg: id("base") },
+        Constraint::AngleFixed {
+            a: id("base"),
+            b: id("arm"),
+            deg: 30.0,
+        },
+    ];
+    solve(&mut d, &constraints, &SolvePolicy::default()).unwrap();
+    let (a, b) = seg_of(&d, 1);
+    let deg = (b.y - a.y).atan2(b.x - a.x).to_degrees();
+    assert!((deg - 30.0).abs() < 1e-3, "{deg}");
+}
+
+#[test]
+fn conflicting_lengths_report_conflict_and_leave_doc_untouched() {
+    let mut d = doc(vec![segment("s0", p(0.0, 0.0), p(7.0, 0.0))]);
+    let constraints = [
+        Constraint::LengthFixed {
+            seg: id("s0"),
+            len: 5.0,
+        },
+        Constraint::LengthFixed {
+            seg: id("s0"),
+            len: 10.0,
+        },
+    ];
+    let err = solve(&mut d, &constraints, &SolvePolicy::default()).unwrap_err();
+    assert_eq!(err.code, "DRAW_CONSTRAINT_CONFLICT");
+    assert_eq!(seg_of(&d, 0), (p(0.0, 0.0), p(7.0, 0.0)));
+
+    // Horizontal and vertical together can only be met by a point.
+    let constraints = [
+        Constraint::Horizontal { seg: id("s0") },
+        Constraint::Vertical { seg: id("s0") },
+    ];
+    let err = solve(&mut d, &constraints, &SolvePolicy::default()).unwrap_err();
+    assert_eq!(err.code, "DRAW_CONSTRAINT_CONFLICT");
+}
+
+#[test]
+fn bad_targets_and_policy_are_rejected() {
+    let mut d = doc(vec![segment("s0", p(0.0, 0.0), p(7.0, 1.0))]);
+    let err = solve(
+        &mut d,
+        &[Constraint::Horizontal { seg: id("nope") }],
+        &SolvePolicy::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "DRAW_CONSTRAINT_TARGET_INVALID");
+
+    let err = solve(
+        &mut d,
+        &[Constraint::Horizontal { seg: id("s0") }],
+        &SolvePolicy {
+            iterations: 0,
+            tolerance: 1e-4,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "DRAW_CONSTRAINT_NOT_CONVERGED");
+
+    let err = solve(
+        &mut d,
+        &[],
+        &SolvePolicy {
+            iterations: 10,
+            tolerance: f64::NAN,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "CAD_CONSTRAINT_POLICY_INVALID");
+}
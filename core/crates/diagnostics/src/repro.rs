@@ -66,7 +66,7 @@ pub fn generate_repro_markdown(
     let _ = writeln!(&mut s, "\n## Steps");
     if let Some(op) = oplog {
         let mut actions = op.actions.clone();
-        actions.sort_by(|a, b| a.seq.cmp(&b.seq));
+        actions.sort_by_key(|a| a.seq);
         for a in &actions {
             let _ = writeln!(&mut s, "{}. {}", a.seq, action_to_text(a));
         }
@@ -3,9 +3,15 @@
 pub mod ops;
 mod util;
 
-pub use ops::edit::{chamfer_lines, fillet_lines, mirror_geom};
+pub use ops::boolean::polygon_boolean;
+pub use ops::curve::{bbox, flatten};
+pub use ops::edit::{
+    chamfer_curves, chamfer_lines, chamfer_polyline, fillet_curves, fillet_lines, fillet_polyline,
+    mirror_geom,
+};
+pub use ops::extend::extend_to_boundary;
 pub use ops::intersect::intersect;
-pub use ops::offset::offset;
+pub use ops::offset::{offset, offset_chain, offset_with_join};
 pub use ops::project::project_point;
 pub use ops::split::split_at;
 pub use ops::trim::{trim_line_to_intersection, trim_polyline_to_intersection};
@@ -42,6 +48,34 @@ impl Default for EpsilonPolicy {
     }
 }
 
+/// How offset chains close the gap that opens at a convex corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum JoinStyle {
+    /// Extend both sides until they meet. Corners whose miter point lies
+    /// further than `limit * |dist|` from the source vertex are beveled.
+    Miter {
+        limit: f64,
+    },
+    Round,
+    Bevel,
+}
+
+impl Default for JoinStyle {
+    fn default() -> Self {
+        Self::Miter { limit: 4.0 }
+    }
+}
+
+/// Region operation for [`polygon_boolean`]. `Difference` is `a - b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOp {
+    Union,
+    Difference,
+    Intersection,
+    Xor,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Geom2D {
@@ -64,6 +98,161 @@ pub enum Geom2D {
         pts: Vec<Vec2>,
         closed: bool,
     },
+    Ellipse {
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+    },
+    /// `start_angle`/`end_angle` are parametric angles in the ellipse frame
+    /// (`c + R(rot) * (rx cos t, ry sin t)`), not polar angles.
+    EllipticalArc {
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+        start_angle: f64,
+        end_angle: f64,
+        ccw: bool,
+    },
+    CubicBezier {
+        a: Vec2,
+        c1: Vec2,
+        c2: Vec2,
+        b: Vec2,
+    },
+}
+
+// The kernel mirrors the document's geometry types field for field, so
+// converting between them cannot fail.
+
+impl From<&craftcad_serialize::Vec2> for Vec2 {
+    fn from(v: &craftcad_serialize::Vec2) -> Self {
+        Self { x: v.x, y: v.y }
+    }
+}
+
+impl From<Vec2> for craftcad_serialize::Vec2 {
+    fn from(v: Vec2) -> Self {
+        Self { x: v.x, y: v.y }
+    }
+}
+
+impl From<&craftcad_serialize::Geom2D> for Geom2D {
+    fn from(g: &craftcad_serialize::Geom2D) -> Self {
+        use craftcad_serialize::Geom2D as D;
+        match g {
+            D::Line { a, b } => Self::Line {
+                a: a.into(),
+                b: b.into(),
+            },
+            D::Circle { c, r } => Self::Circle { c: c.into(), r: *r },
+            D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            } => Self::Arc {
+                c: c.into(),
+                r: *r,
+                start_angle: *start_angle,
+                end_angle: *end_angle,
+                ccw: *ccw,
+            },
+            D::Polyline { pts, closed } => Self::Polyline {
+                pts: pts.iter().map(Vec2::from).collect(),
+                closed: *closed,
+            },
+            D::Ellipse { c, rx, ry, rot } => Self::Ellipse {
+                c: c.into(),
+                rx: *rx,
+                ry: *ry,
+                rot: *rot,
+            },
+            D::EllipticalArc {
+                c,
+                rx,
+                ry,
+                rot,
+                start_angle,
+                end_angle,
+                ccw,
+            } => Self::EllipticalArc {
+                c: c.into(),
+                rx: *rx,
+                ry: *ry,
+                rot: *rot,
+                start_angle: *start_angle,
+                end_angle: *end_angle,
+                ccw: *ccw,
+            },
+            D::CubicBezier { a, c1, c2, b } => Self::CubicBezier {
+                a: a.into(),
+                c1: c1.into(),
+                c2: c2.into(),
+                b: b.into(),
+            },
+        }
+    }
+}
+
+impl From<&Geom2D> for craftcad_serialize::Geom2D {
+    fn from(g: &Geom2D) -> Self {
+        match *g {
+            Geom2D::Line { a, b } => Self::Line {
+                a: a.into(),
+                b: b.into(),
+            },
+            Geom2D::Circle { c, r } => Self::Circle { c: c.into(), r },
+            Geom2D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            } => Self::Arc {
+                c: c.into(),
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            },
+            Geom2D::Polyline { ref pts, closed } => Self::Polyline {
+                pts: pts.iter().map(|&v| v.into()).collect(),
+                closed,
+            },
+            Geom2D::Ellipse { c, rx, ry, rot } => Self::Ellipse {
+                c: c.into(),
+                rx,
+                ry,
+                rot,
+            },
+            Geom2D::EllipticalArc {
+                c,
+                rx,
+                ry,
+                rot,
+                start_angle,
+                end_angle,
+                ccw,
+            } => Self::EllipticalArc {
+                c: c.into(),
+                rx,
+                ry,
+                rot,
+                start_angle,
+                end_angle,
+                ccw,
+            },
+            Geom2D::CubicBezier { a, c1, c2, b } => Self::CubicBezier {
+                a: a.into(),
+                c1: c1.into(),
+                c2: c2.into(),
+                b: b.into(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
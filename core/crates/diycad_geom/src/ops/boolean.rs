@@ -0,0 +1,466 @@
+use crate::{
+    util::{dist2, dot, lerp, sub},
+    BooleanOp, EpsilonPolicy, Vec2,
+};
+use craftcad_serialize::{Polygon2D, Reason, ReasonCode, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::f64::consts::TAU;
+
+// Booleans run on the edge graph of both operands:
+// 1. every ring is normalised (outer CCW, holes CW, so the filled side is always
+//    on the left of an edge),
+// 2. edges are split wherever they meet an edge of the other operand and all
+//    vertices are interned within `eq_dist`,
+// 3. each piece is kept, reversed or dropped depending on the operation and on
+//    whether it lies inside the other operand or on a shared boundary,
+// 4. the kept pieces are traced into rings, always taking the tightest turn so
+//    that rings touching at a vertex come out as separate rings,
+// 5. rings are classified by signed area and holes are attached to the smallest
+//    outer that contains them.
+// Output rings start at their lowest-left vertex and polygons are sorted the
+// same way, so the result does not depend on input ring start points.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    fn as_str(self) -> &'static str {
+        match self {
+            Side::A => "a",
+            Side::B => "b",
+        }
+    }
+}
+
+fn cross(a: Vec2, b: Vec2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn signed_area(pts: &[Vec2]) -> f64 {
+    let n = pts.len();
+    0.5 * (0..n).map(|i| cross(pts[i], pts[(i + 1) % n])).sum::<f64>()
+}
+
+fn invalid_ring(side: Side, polygon: usize, ring: usize, code: ReasonCode) -> Reason {
+    let mut r = Reason::from_code(code);
+    r.debug
+        .insert("operand".into(), serde_json::json!(side.as_str()));
+    r.debug.insert("polygon".into(), serde_json::json!(polygon));
+    r.debug.insert("ring".into(), serde_json::json!(ring));
+    r
+}
+
+fn normalize_ring(
+    pts: &[craftcad_serialize::Vec2],
+    outer: bool,
+    eps: &EpsilonPolicy,
+) -> std::result::Result<Vec<Vec2>, ReasonCode> {
+    let mut out: Vec<Vec2> = Vec::with_capacity(pts.len());
+    for p in pts {
+        if !p.x.is_finite() || !p.y.is_finite() {
+            return Err(ReasonCode::GeomInvalidNumeric);
+        }
+        let p = Vec2 { x: p.x, y: p.y };
+        if out
+            .last()
+            .map(|q| dist2(*q, p) > eps.eq_dist * eps.eq_dist)
+            .unwrap_or(true)
+        {
+            out.push(p);
+        }
+    }
+    if out.len() > 1 && dist2(out[0], out[out.len() - 1]) <= eps.eq_dist * eps.eq_dist {
+        out.pop();
+    }
+    if out.len() < 3 {
+        return Err(ReasonCode::GeomDegenerate);
+    }
+    let area = signed_area(&out);
+    if area.abs() <= eps.area_tol {
+        return Err(ReasonCode::GeomDegenerate);
+    }
+    if (area > 0.0) != outer {
+        out.reverse();
+    }
+    Ok(out)
+}
+
+fn operand_rings(polys: &[Polygon2D], side: Side, eps: &EpsilonPolicy) -> Result<Vec<Vec<Vec2>>> {
+    let mut rings = Vec::new();
+    for (pi, poly) in polys.iter().enumerate() {
+        rings.push(
+            normalize_ring(&poly.outer, true, eps).map_err(|c| invalid_ring(side, pi, 0, c))?,
+        );
+        for (hi, hole) in poly.holes.iter().enumerate() {
+            rings.push(
+                normalize_ring(hole, false, eps).map_err(|c| invalid_ring(side, pi, hi + 1, c))?,
+            );
+        }
+    }
+    Ok(rings)
+}
+
+// Even-odd test over every ring of an operand.
+fn point_in_rings(p: Vec2, rings: &[Vec<Vec2>]) -> bool {
+    let mut inside = false;
+    for ring in rings {
+        let n = ring.len();
+        for i in 0..n {
+            let a = ring[i];
+            let b = ring[(i + 1) % n];
+            if (a.y > p.y) != (b.y > p.y) {
+                let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                if p.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    inside
+}
+
+// Parameter pairs (and the shared point) where two edges touch, including the
+// end points of collinear overlaps.
+fn edge_hits(p0: Vec2, p1: Vec2, q0: Vec2, q1: Vec2, eps: &EpsilonPolicy) -> Vec<(f64, f64, Vec2)> {
+    let r = sub(p1, p0);
+    let s = sub(q1, q0);
+    let len_r = dot(r, r).sqrt();
+    let len_s = dot(s, s).sqrt();
+    let tol_r = eps.eq_dist / len_r;
+    let tol_s = eps.eq_dist / len_s;
+    let denom = cross(r, s);
+    let mut out = Vec::new();
+    if denom.abs() <= eps.intersect_tol * len_r * len_s {
+        if cross(r, sub(q0, p0)).abs() / len_r > eps.eq_dist {
+            return out;
+        }
+        for (u, q) in [(0.0, q0), (1.0, q1)] {
+            let t = dot(sub(q, p0), r) / (len_r * len_r);
+            if t > -tol_r && t < 1.0 + tol_r {
+                out.push((t.clamp(0.0, 1.0), u, q));
+            }
+        }
+        for (t, p) in [(0.0, p0), (1.0, p1)] {
+            let u = dot(sub(p, q0), s) / (len_s * len_s);
+            if u > -tol_s && u < 1.0 + tol_s {
+                out.push((t, u.clamp(0.0, 1.0), p));
+            }
+        }
+        return out;
+    }
+    let w = sub(q0, p0);
+    let t = cross(w, s) / denom;
+    let u = cross(w, r) / denom;
+    if t >= -tol_r && t <= 1.0 + tol_r && u >= -tol_s && u <= 1.0 + tol_s {
+        let t = t.clamp(0.0, 1.0);
+        let u = u.clamp(0.0, 1.0);
+        let pt = if t == 0.0 {
+            p0
+        } else if t == 1.0 {
+            p1
+        } else if u == 0.0 {
+            q0
+        } else if u == 1.0 {
+            q1
+        } else {
+            lerp(p0, p1, t)
+        };
+        out.push((t, u, pt));
+    }
+    out
+}
+
+struct Vertices {
+    pts: Vec<Vec2>,
+    tol2: f64,
+}
+
+impl Vertices {
+    fn intern(&mut self, p: Vec2) -> usize {
+        if let Some(i) = self.pts.iter().position(|q| dist2(*q, p) <= self.tol2) {
+            return i;
+        }
+        self.pts.push(p);
+        self.pts.len() - 1
+    }
+}
+
+struct Edge {
+    a: Vec2,
+    b: Vec2,
+    cuts: Vec<(f64, Vec2)>,
+}
+
+fn ring_edges(rings: &[Vec<Vec2>]) -> Vec<Edge> {
+    rings
+        .iter()
+        .flat_map(|ring| {
+            (0..ring.len()).map(move |i| Edge {
+                a: ring[i],
+                b: ring[(i + 1) % ring.len()],
+                cuts: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn bbox_apart(e: &Edge, f: &Edge, tol: f64) -> bool {
+    e.a.x.max(e.b.x) + tol < f.a.x.min(f.b.x)
+        || f.a.x.max(f.b.x) + tol < e.a.x.min(e.b.x)
+        || e.a.y.max(e.b.y) + tol < f.a.y.min(f.b.y)
+        || f.a.y.max(f.b.y) + tol < e.a.y.min(e.b.y)
+}
+
+fn pieces(edges: &[Edge], verts: &mut Vertices) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    for e in edges {
+        let mut cuts = e.cuts.clone();
+        cuts.push((0.0, e.a));
+        cuts.push((1.0, e.b));
+        cuts.sort_by(|l, r| l.0.total_cmp(&r.0));
+        let ids: Vec<usize> = cuts.iter().map(|(_, p)| verts.intern(*p)).collect();
+        for w in ids.windows(2) {
+            if w[0] != w[1] {
+                out.push((w[0], w[1]));
+            }
+        }
+    }
+    out
+}
+
+// Clockwise angle from `from` to `to`, in (0, 2π].
+fn cw_angle(from: Vec2, to: Vec2) -> f64 {
+    let a = (-cross(from, to)).atan2(dot(from, to));
+    if a <= 1e-12 {
+        a + TAU
+    } else {
+        a
+    }
+}
+
+fn trace_rings(edges: &[(usize, usize)], verts: &[Vec2]) -> Result<Vec<Vec<Vec2>>> {
+    let mut outgoing: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, (u, _)) in edges.iter().enumerate() {
+        outgoing.entry(*u).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = edges[first].0;
+        let mut ring = vec![verts[start]];
+        let mut cur = first;
+        loop {
+            let (u, v) = edges[cur];
+            let back = sub(verts[u], verts[v]);
+            let next = outgoing
+                .get(&v)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&k| !used[k] || (k == first && v == start))
+                .min_by(|&l, &r| {
+                    let al = cw_angle(back, sub(verts[edges[l].1], verts[v]));
+                    let ar = cw_angle(back, sub(verts[edges[r].1], verts[v]));
+                    al.total_cmp(&ar).then(l.cmp(&r))
+                });
+            match next {
+                Some(k) if k == first => break,
+                Some(k) => {
+                    used[k] = true;
+                    ring.push(verts[v]);
+                    cur = k;
+                }
+                None => {
+                    let mut r = Reason::from_code(ReasonCode::GeomIntersectionAmbiguous);
+                    r.debug
+                        .insert("case".into(), serde_json::json!("boolean_open_chain"));
+                    return Err(r);
+                }
+            }
+        }
+        rings.push(ring);
+    }
+    Ok(rings)
+}
+
+fn simplify_ring(ring: Vec<Vec2>, eps: &EpsilonPolicy) -> Vec<Vec2> {
+    let mut pts = ring;
+    let mut changed = true;
+    while changed && pts.len() >= 3 {
+        changed = false;
+        let n = pts.len();
+        for i in 0..n {
+            let prev = pts[(i + n - 1) % n];
+            let next = pts[(i + 1) % n];
+            let chord = sub(next, prev);
+            let len = dot(chord, chord).sqrt();
+            let off = if len <= eps.eq_dist {
+                0.0
+            } else {
+                cross(chord, sub(pts[i], prev)).abs() / len
+            };
+            if off <= eps.eq_dist {
+                pts.remove(i);
+                changed = true;
+                break;
+            }
+        }
+    }
+    pts
+}
+
+fn lowest_left(pts: &[Vec2]) -> usize {
+    (0..pts.len())
+        .min_by(|&i, &j| {
+            pts[i]
+                .x
+                .total_cmp(&pts[j].x)
+                .then(pts[i].y.total_cmp(&pts[j].y))
+        })
+        .unwrap_or(0)
+}
+
+fn canonical(mut ring: Vec<Vec2>) -> Vec<Vec2> {
+    let k = lowest_left(&ring);
+    ring.rotate_left(k);
+    ring
+}
+
+fn to_serialize(pts: &[Vec2]) -> Vec<craftcad_serialize::Vec2> {
+    pts.iter()
+        .map(|p| craftcad_serialize::Vec2 { x: p.x, y: p.y })
+        .collect()
+}
+
+fn first_cmp(a: &[Vec2], b: &[Vec2]) -> std::cmp::Ordering {
+    a[0].x.total_cmp(&b[0].x).then(a[0].y.total_cmp(&b[0].y))
+}
+
+/// Union, difference (`a - b`), intersection or XOR of two sets of polygons.
+///
+/// Polygons inside one operand must not overlap each other. Rings may be given
+/// in either orientation; results have CCW outers and CW holes, start at their
+/// lowest-left vertex and are sorted by it. An empty result is not an error.
+pub fn polygon_boolean(
+    a: &[Polygon2D],
+    b: &[Polygon2D],
+    op: BooleanOp,
+    eps: &EpsilonPolicy,
+) -> Result<Vec<Polygon2D>> {
+    let rings_a = operand_rings(a, Side::A, eps)?;
+    let rings_b = operand_rings(b, Side::B, eps)?;
+    let mut edges_a = ring_edges(&rings_a);
+    let mut edges_b = ring_edges(&rings_b);
+    for ea in edges_a.iter_mut() {
+        for eb in edges_b.iter_mut() {
+            if bbox_apart(ea, eb, eps.eq_dist) {
+                continue;
+            }
+            for (t, u, p) in edge_hits(ea.a, ea.b, eb.a, eb.b, eps) {
+                ea.cuts.push((t, p));
+                eb.cuts.push((u, p));
+            }
+        }
+    }
+
+    let mut verts = Vertices {
+        pts: Vec::new(),
+        tol2: eps.eq_dist * eps.eq_dist,
+    };
+    let pieces_a = pieces(&edges_a, &mut verts);
+    let pieces_b = pieces(&edges_b, &mut verts);
+    let set_a: BTreeSet<(usize, usize)> = pieces_a.iter().copied().collect();
+    let set_b: BTreeSet<(usize, usize)> = pieces_b.iter().copied().collect();
+    let mid = |(u, v): (usize, usize)| lerp(verts.pts[u], verts.pts[v], 0.5);
+
+    let mut kept: Vec<(usize, usize)> = Vec::new();
+    for &(u, v) in &pieces_a {
+        if set_b.contains(&(u, v)) {
+            if matches!(op, BooleanOp::Union | BooleanOp::Intersection) {
+                kept.push((u, v));
+            }
+            continue;
+        }
+        if set_b.contains(&(v, u)) {
+            if op == BooleanOp::Difference {
+                kept.push((u, v));
+            }
+            continue;
+        }
+        let inside = point_in_rings(mid((u, v)), &rings_b);
+        match (op, inside) {
+            (BooleanOp::Union | BooleanOp::Difference, false) => kept.push((u, v)),
+            (BooleanOp::Intersection, true) => kept.push((u, v)),
+            (BooleanOp::Xor, false) => kept.push((u, v)),
+            (BooleanOp::Xor, true) => kept.push((v, u)),
+            _ => {}
+        }
+    }
+    for &(u, v) in &pieces_b {
+        if set_a.contains(&(u, v)) || set_a.contains(&(v, u)) {
+            continue;
+        }
+        let inside = point_in_rings(mid((u, v)), &rings_a);
+        match (op, inside) {
+            (BooleanOp::Union, false) | (BooleanOp::Intersection, true) => kept.push((u, v)),
+            (BooleanOp::Difference, true) => kept.push((v, u)),
+            (BooleanOp::Xor, false) => kept.push((u, v)),
+            (BooleanOp::Xor, true) => kept.push((v, u)),
+            _ => {}
+        }
+    }
+
+    let mut outers: Vec<(Vec<Vec2>, f64)> = Vec::new();
+    let mut holes: Vec<Vec<Vec2>> = Vec::new();
+    for ring in trace_rings(&kept, &verts.pts)? {
+        let ring = simplify_ring(ring, eps);
+        if ring.len() < 3 {
+            continue;
+        }
+        let area = signed_area(&ring);
+        if area > eps.area_tol {
+            outers.push((canonical(ring), area));
+        } else if area < -eps.area_tol {
+            holes.push(canonical(ring));
+        }
+    }
+
+    let mut result: Vec<Polygon2D> = Vec::with_capacity(outers.len());
+    let mut owned: Vec<Vec<Vec<Vec2>>> = vec![Vec::new(); outers.len()];
+    for hole in holes {
+        let probe = lerp(hole[0], hole[1], 0.5);
+        let owner = outers
+            .iter()
+            .enumerate()
+            .filter(|(_, (ring, _))| point_in_rings(probe, std::slice::from_ref(ring)))
+            .min_by(|l, r| l.1 .1.total_cmp(&r.1 .1))
+            .map(|(i, _)| i);
+        match owner {
+            Some(i) => owned[i].push(hole),
+            None => {
+                let mut r = Reason::from_code(ReasonCode::GeomIntersectionAmbiguous);
+                r.debug
+                    .insert("case".into(), serde_json::json!("boolean_orphan_hole"));
+                return Err(r);
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..outers.len()).collect();
+    order.sort_by(|&i, &j| first_cmp(&outers[i].0, &outers[j].0));
+    for i in order {
+        let mut hs = std::mem::take(&mut owned[i]);
+        hs.sort_by(|l, r| first_cmp(l, r));
+        result.push(Polygon2D {
+            outer: to_serialize(&outers[i].0),
+            holes: hs.iter().map(|h| to_serialize(h)).collect(),
+        });
+    }
+    Ok(result)
+}
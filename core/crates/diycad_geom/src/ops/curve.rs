@@ -0,0 +1,657 @@
+//! Parametric view of the curved variants. Ellipses, elliptical arcs and
+//! cubic Beziers have no closed-form intersect/project/offset, so those ops
+//! work on an adaptive flattening and polish the result on the exact curve.
+
+use crate::{
+    util::{add, dist2, dot, lerp, mul, sub},
+    EpsilonPolicy, Geom2D, ProjectHit, SplitBy, SplitResult, Vec2, AABB,
+};
+use craftcad_serialize::{Reason, ReasonCode, Result};
+use std::f64::consts::PI;
+
+const TAU: f64 = 2.0 * PI;
+const MAX_DEPTH: u32 = 12;
+
+fn cross(a: Vec2, b: Vec2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn finite(p: &Vec2) -> Result<()> {
+    if p.x.is_finite() && p.y.is_finite() {
+        Ok(())
+    } else {
+        Err(Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }
+}
+
+fn arc_sweep(start: f64, end: f64, ccw: bool) -> f64 {
+    let d = if ccw {
+        (end - start).rem_euclid(TAU)
+    } else {
+        (start - end).rem_euclid(TAU)
+    };
+    if d <= 1e-12 {
+        TAU
+    } else {
+        d
+    }
+}
+
+/// One smooth piece with parameter `u` in `[0, 1]`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Curve {
+    Line {
+        a: Vec2,
+        b: Vec2,
+    },
+    /// Ellipse (or circle) from parametric angle `t0` over signed `sweep`.
+    Conic {
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+        t0: f64,
+        sweep: f64,
+    },
+    Bezier([Vec2; 4]),
+}
+
+impl Curve {
+    fn conic(
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+        range: Option<(f64, f64, bool)>,
+        eps: &EpsilonPolicy,
+    ) -> Result<Self> {
+        finite(&c)?;
+        if !rx.is_finite() || !ry.is_finite() || rx <= eps.eq_dist || ry <= eps.eq_dist {
+            return Err(Reason::from_code(ReasonCode::GeomCircleRadiusInvalid));
+        }
+        if !rot.is_finite() {
+            return Err(Reason::from_code(ReasonCode::GeomInvalidNumeric));
+        }
+        let (t0, sweep) = match range {
+            None => (0.0, TAU),
+            Some((s, e, ccw)) => {
+                if !s.is_finite() || !e.is_finite() {
+                    return Err(Reason::from_code(ReasonCode::GeomArcRangeInvalid));
+                }
+                let sweep = arc_sweep(s, e, ccw);
+                (s, if ccw { sweep } else { -sweep })
+            }
+        };
+        Ok(Self::Conic {
+            c,
+            rx,
+            ry,
+            rot,
+            t0,
+            sweep,
+        })
+    }
+
+    /// Smooth pieces of `g`; polylines give one line per segment.
+    pub(crate) fn pieces(g: &Geom2D, eps: &EpsilonPolicy) -> Result<Vec<Self>> {
+        Ok(match g {
+            Geom2D::Line { a, b } => {
+                finite(a)?;
+                finite(b)?;
+                vec![Self::Line { a: *a, b: *b }]
+            }
+            Geom2D::Polyline { pts, closed } => {
+                pts.iter().try_for_each(finite)?;
+                let n = pts.len();
+                let seg_count = if *closed { n } else { n.saturating_sub(1) };
+                if seg_count == 0 {
+                    return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+                }
+                (0..seg_count)
+                    .map(|i| Self::Line {
+                        a: pts[i],
+                        b: pts[(i + 1) % n],
+                    })
+                    .collect()
+            }
+            Geom2D::Circle { c, r } => vec![Self::conic(*c, *r, *r, 0.0, None, eps)?],
+            Geom2D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            } => vec![Self::conic(
+                *c,
+                *r,
+                *r,
+                0.0,
+                Some((*start_angle, *end_angle, *ccw)),
+                eps,
+            )?],
+            Geom2D::Ellipse { c, rx, ry, rot } => vec![Self::conic(*c, *rx, *ry, *rot, None, eps)?],
+            Geom2D::EllipticalArc {
+                c,
+                rx,
+                ry,
+                rot,
+                start_angle,
+                end_angle,
+                ccw,
+            } => vec![Self::conic(
+                *c,
+                *rx,
+                *ry,
+                *rot,
+                Some((*start_angle, *end_angle, *ccw)),
+                eps,
+            )?],
+            Geom2D::CubicBezier { a, c1, c2, b } => {
+                let p = [*a, *c1, *c2, *b];
+                p.iter().try_for_each(finite)?;
+                if p.iter().all(|q| dist2(*q, *a) <= eps.eq_dist * eps.eq_dist) {
+                    return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+                }
+                vec![Self::Bezier(p)]
+            }
+        })
+    }
+
+    fn frame(rot: f64, v: Vec2) -> Vec2 {
+        let (s, c) = rot.sin_cos();
+        Vec2 {
+            x: v.x * c - v.y * s,
+            y: v.x * s + v.y * c,
+        }
+    }
+
+    pub(crate) fn point(&self, u: f64) -> Vec2 {
+        match *self {
+            Self::Line { a, b } => lerp(a, b, u),
+            Self::Conic {
+                c,
+                rx,
+                ry,
+                rot,
+                t0,
+                sweep,
+            } => {
+                let t = t0 + sweep * u;
+                add(
+                    c,
+                    Self::frame(
+                        rot,
+                        Vec2 {
+                            x: rx * t.cos(),
+                            y: ry * t.sin(),
+                        },
+                    ),
+                )
+            }
+            Self::Bezier([p0, p1, p2, p3]) => {
+                let v = 1.0 - u;
+                let w = [v * v * v, 3.0 * v * v * u, 3.0 * v * u * u, u * u * u];
+                Vec2 {
+                    x: w[0] * p0.x + w[1] * p1.x + w[2] * p2.x + w[3] * p3.x,
+                    y: w[0] * p0.y + w[1] * p1.y + w[2] * p2.y + w[3] * p3.y,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn deriv(&self, u: f64) -> Vec2 {
+        match *self {
+            Self::Line { a, b } => sub(b, a),
+            Self::Conic {
+                rx,
+                ry,
+                rot,
+                t0,
+                sweep,
+                ..
+            } => {
+                let t = t0 + sweep * u;
+                mul(
+                    Self::frame(
+                        rot,
+                        Vec2 {
+                            x: -rx * t.sin(),
+                            y: ry * t.cos(),
+                        },
+                    ),
+                    sweep,
+                )
+            }
+            Self::Bezier([p0, p1, p2, p3]) => {
+                let v = 1.0 - u;
+                add(
+                    add(mul(sub(p1, p0), 3.0 * v * v), mul(sub(p2, p1), 6.0 * v * u)),
+                    mul(sub(p3, p2), 3.0 * u * u),
+                )
+            }
+        }
+    }
+
+    fn deriv2(&self, u: f64) -> Vec2 {
+        match *self {
+            Self::Line { .. } => Vec2 { x: 0.0, y: 0.0 },
+            Self::Conic { c, sweep, .. } => mul(sub(self.point(u), c), -sweep * sweep),
+            Self::Bezier([p0, p1, p2, p3]) => {
+                let a = add(sub(p2, mul(p1, 2.0)), p0);
+                let b = add(sub(p3, mul(p2, 2.0)), p1);
+                add(mul(a, 6.0 * (1.0 - u)), mul(b, 6.0 * u))
+            }
+        }
+    }
+
+    /// Parameters of a flattening whose chords stay within `tol` of the curve.
+    pub(crate) fn flatten_params(&self, tol: f64) -> Vec<f64> {
+        let initial = match *self {
+            Self::Line { .. } => return vec![0.0, 1.0],
+            Self::Conic { sweep, .. } => ((sweep.abs() / (PI / 4.0)).ceil() as usize).max(2),
+            Self::Bezier(_) => 4,
+        };
+        let tol2 = tol.max(1e-9).powi(2);
+        let mut out = vec![0.0];
+        for i in 0..initial {
+            let u0 = i as f64 / initial as f64;
+            let u1 = (i + 1) as f64 / initial as f64;
+            self.refine(u0, u1, tol2, 0, &mut out);
+        }
+        out
+    }
+
+    fn refine(&self, u0: f64, u1: f64, tol2: f64, depth: u32, out: &mut Vec<f64>) {
+        let um = 0.5 * (u0 + u1);
+        let (p0, p1) = (self.point(u0), self.point(u1));
+        let pm = self.point(um);
+        let chord = sub(p1, p0);
+        let len2 = dot(chord, chord);
+        let dev2 = if len2 <= f64::EPSILON {
+            dist2(pm, p0)
+        } else {
+            cross(chord, sub(pm, p0)).powi(2) / len2
+        };
+        if depth < MAX_DEPTH && dev2 > tol2 {
+            self.refine(u0, um, tol2, depth + 1, out);
+            self.refine(um, u1, tol2, depth + 1, out);
+        } else {
+            out.push(u1);
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self, Self::Conic { sweep, .. } if (sweep.abs() - TAU).abs() <= 1e-12)
+    }
+}
+
+fn chord_tol(eps: &EpsilonPolicy) -> f64 {
+    eps.snap_dist.max(eps.eq_dist)
+}
+
+/// Flattens any variant into one polyline whose chords stay within `tol` of
+/// the source. Circles and ellipses come back closed without a repeated
+/// start point.
+pub fn flatten(g: &Geom2D, tol: f64, eps: &EpsilonPolicy) -> Result<Geom2D> {
+    if !tol.is_finite() || tol <= 0.0 {
+        return Err(Reason::from_code(ReasonCode::GeomInvalidNumeric));
+    }
+    if let Geom2D::Polyline { .. } = g {
+        Curve::pieces(g, eps)?;
+        return Ok(g.clone());
+    }
+    let curve = Curve::pieces(g, eps)?[0];
+    let mut pts: Vec<Vec2> = curve
+        .flatten_params(tol)
+        .into_iter()
+        .map(|u| curve.point(u))
+        .collect();
+    let closed = curve.is_closed();
+    if closed {
+        pts.pop();
+    }
+    Ok(Geom2D::Polyline { pts, closed })
+}
+
+fn in_sweep(t: f64, t0: f64, sweep: f64) -> bool {
+    let u = if sweep >= 0.0 {
+        (t - t0).rem_euclid(TAU)
+    } else {
+        (t0 - t).rem_euclid(TAU)
+    };
+    u <= sweep.abs()
+}
+
+/// Axis-aligned bounds of the exact curve (not of its control points).
+pub fn bbox(g: &Geom2D, eps: &EpsilonPolicy) -> Result<AABB> {
+    let mut pts: Vec<Vec2> = vec![];
+    for piece in Curve::pieces(g, eps)? {
+        pts.push(piece.point(0.0));
+        pts.push(piece.point(1.0));
+        match piece {
+            Curve::Line { .. } => {}
+            Curve::Conic {
+                c,
+                rx,
+                ry,
+                rot,
+                t0,
+                sweep,
+            } => {
+                let (s, co) = rot.sin_cos();
+                let tx = (-ry * s).atan2(rx * co);
+                let ty = (ry * co).atan2(rx * s);
+                for t in [tx, tx + PI, ty, ty + PI] {
+                    if in_sweep(t, t0, sweep) {
+                        let local = Vec2 {
+                            x: rx * t.cos(),
+                            y: ry * t.sin(),
+                        };
+                        pts.push(add(c, Curve::frame(rot, local)));
+                    }
+                }
+            }
+            Curve::Bezier(p) => {
+                let axis = |f: fn(&Vec2) -> f64| {
+                    // Derivative / 3 = a u^2 + b u + c per axis.
+                    let (q0, q1, q2, q3) = (f(&p[0]), f(&p[1]), f(&p[2]), f(&p[3]));
+                    let a = -q0 + 3.0 * q1 - 3.0 * q2 + q3;
+                    let b = 2.0 * (q0 - 2.0 * q1 + q2);
+                    let c = q1 - q0;
+                    let mut roots = vec![];
+                    if a.abs() <= 1e-12 {
+                        if b.abs() > 1e-12 {
+                            roots.push(-c / b);
+                        }
+                    } else {
+                        let disc = b * b - 4.0 * a * c;
+                        if disc >= 0.0 {
+                            let sq = disc.sqrt();
+                            roots.push((-b + sq) / (2.0 * a));
+                            roots.push((-b - sq) / (2.0 * a));
+                        }
+                    }
+                    roots
+                };
+                for u in axis(|v| v.x).into_iter().chain(axis(|v| v.y)) {
+                    if (0.0..=1.0).contains(&u) {
+                        pts.push(piece.point(u));
+                    }
+                }
+            }
+        }
+    }
+    let mut min = pts[0];
+    let mut max = pts[0];
+    for p in &pts {
+        min.x = min.x.min(p.x);
+        min.y = min.y.min(p.y);
+        max.x = max.x.max(p.x);
+        max.y = max.y.max(p.y);
+    }
+    Ok(AABB { min, max })
+}
+
+pub(crate) fn is_curved(g: &Geom2D) -> bool {
+    matches!(
+        g,
+        Geom2D::Ellipse { .. } | Geom2D::EllipticalArc { .. } | Geom2D::CubicBezier { .. }
+    )
+}
+
+/// Newton on `P(u) - Q(v) = 0` from a flattened hit.
+fn polish(p: &Curve, q: &Curve, mut u: f64, mut v: f64) -> (Vec2, Vec2) {
+    for _ in 0..32 {
+        let f = sub(p.point(u), q.point(v));
+        let du = p.deriv(u);
+        let dv = mul(q.deriv(v), -1.0);
+        let det = cross(du, dv);
+        if det.abs() <= 1e-18 {
+            break;
+        }
+        let su = (f.x * dv.y - f.y * dv.x) / det;
+        let sv = (du.x * f.y - du.y * f.x) / det;
+        u = (u - su).clamp(0.0, 1.0);
+        v = (v - sv).clamp(0.0, 1.0);
+        if su.abs() < 1e-15 && sv.abs() < 1e-15 {
+            break;
+        }
+    }
+    (p.point(u), q.point(v))
+}
+
+fn seg_hit(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> Option<(f64, f64)> {
+    let r = sub(a1, a0);
+    let s = sub(b1, b0);
+    let denom = cross(r, s);
+    if denom.abs() <= f64::EPSILON * dot(r, r).max(dot(s, s)) {
+        return None;
+    }
+    let qp = sub(b0, a0);
+    let t = cross(qp, s) / denom;
+    let w = cross(qp, r) / denom;
+    let slack = 1e-9;
+    if (-slack..=1.0 + slack).contains(&t) && (-slack..=1.0 + slack).contains(&w) {
+        Some((t.clamp(0.0, 1.0), w.clamp(0.0, 1.0)))
+    } else {
+        None
+    }
+}
+
+/// Intersections when at least one side is a curved variant. Candidates come
+/// from the two flattenings and are polished with Newton on the exact curves;
+/// polished points further than `snap_dist` apart on the two curves (e.g.
+/// near-misses inside the chord error) are dropped.
+pub(crate) fn intersect_curves(a: &Geom2D, b: &Geom2D, eps: &EpsilonPolicy) -> Result<Vec<Vec2>> {
+    let tol = chord_tol(eps);
+    let pa = Curve::pieces(a, eps)?;
+    let pb = Curve::pieces(b, eps)?;
+    let mut out = vec![];
+    for p in &pa {
+        let up = p.flatten_params(tol);
+        let xp: Vec<Vec2> = up.iter().map(|u| p.point(*u)).collect();
+        for q in &pb {
+            let uq = q.flatten_params(tol);
+            let xq: Vec<Vec2> = uq.iter().map(|u| q.point(*u)).collect();
+            for i in 0..xp.len() - 1 {
+                for j in 0..xq.len() - 1 {
+                    let Some((s, t)) = seg_hit(xp[i], xp[i + 1], xq[j], xq[j + 1]) else {
+                        continue;
+                    };
+                    let u = up[i] + (up[i + 1] - up[i]) * s;
+                    let v = uq[j] + (uq[j + 1] - uq[j]) * t;
+                    let (hp, hq) = polish(p, q, u, v);
+                    if dist2(hp, hq) <= eps.snap_dist * eps.snap_dist {
+                        out.push(lerp(hp, hq, 0.5));
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn golden_min(f: &dyn Fn(f64) -> f64, mut lo: f64, mut hi: f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let mut x1 = hi - ratio * (hi - lo);
+    let mut x2 = lo + ratio * (hi - lo);
+    let (mut f1, mut f2) = (f(x1), f(x2));
+    for _ in 0..80 {
+        if f1 < f2 {
+            hi = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = hi - ratio * (hi - lo);
+            f1 = f(x1);
+        } else {
+            lo = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = lo + ratio * (hi - lo);
+            f2 = f(x2);
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Closest point on a curved variant; `t_global` is the curve parameter.
+pub(crate) fn project_curve(g: &Geom2D, p: Vec2, eps: &EpsilonPolicy) -> Result<ProjectHit> {
+    let curve = Curve::pieces(g, eps)?[0];
+    let us = curve.flatten_params(chord_tol(eps));
+    let (mut best_i, mut best_d) = (0usize, f64::INFINITY);
+    for (i, u) in us.iter().enumerate() {
+        let d = dist2(curve.point(*u), p);
+        if d < best_d {
+            best_i = i;
+            best_d = d;
+        }
+    }
+    // Golden-section search on the spans either side of the best sample;
+    // closed curves wrap around u = 0.
+    let last = us.len() - 1;
+    let f = |u: f64| dist2(curve.point(u), p);
+    let windows = if curve.is_closed() && (best_i == 0 || best_i == last) {
+        vec![(us[last - 1], 1.0), (0.0, us[1])]
+    } else {
+        vec![(us[best_i.saturating_sub(1)], us[(best_i + 1).min(last)])]
+    };
+    let mut u = us[best_i];
+    for (lo, hi) in windows {
+        let cand = golden_min(&f, lo, hi);
+        if f(cand) < f(u) {
+            u = cand;
+        }
+    }
+    if curve.is_closed() && u >= 1.0 {
+        u = 0.0;
+    }
+    let q = curve.point(u);
+    Ok(ProjectHit {
+        point: q,
+        t_global: u,
+        dist: dist2(p, q).sqrt(),
+    })
+}
+
+/// Splits an elliptical arc or a Bezier at a curve parameter. Ellipses are
+/// closed and, like circles, cannot be split into two pieces.
+pub(crate) fn split_curve(g: &Geom2D, by: SplitBy, eps: &EpsilonPolicy) -> Result<SplitResult> {
+    if let Geom2D::Ellipse { .. } = g {
+        return Err(Reason::from_code(ReasonCode::GeomSplitPointNotOnGeom));
+    }
+    let curve = Curve::pieces(g, eps)?[0];
+    let t = match by {
+        SplitBy::T(t) => t,
+        SplitBy::Point(p) => {
+            let hit = project_curve(g, p, eps)?;
+            if hit.dist > eps.snap_dist {
+                return Err(Reason::from_code(ReasonCode::GeomSplitPointNotOnGeom));
+            }
+            hit.t_global
+        }
+    };
+    if !t.is_finite() || t <= eps.eq_dist || t >= 1.0 - eps.eq_dist {
+        return Err(Reason::from_code(ReasonCode::GeomSplitPointNotOnGeom));
+    }
+    let split_point = curve.point(t);
+    let (left, right) = match (g, curve) {
+        (
+            Geom2D::EllipticalArc {
+                c,
+                rx,
+                ry,
+                rot,
+                start_angle,
+                end_angle,
+                ccw,
+            },
+            Curve::Conic { t0, sweep, .. },
+        ) => {
+            let mid = t0 + sweep * t;
+            let part = |s: f64, e: f64| Geom2D::EllipticalArc {
+                c: *c,
+                rx: *rx,
+                ry: *ry,
+                rot: *rot,
+                start_angle: s,
+                end_angle: e,
+                ccw: *ccw,
+            };
+            (part(*start_angle, mid), part(mid, *end_angle))
+        }
+        (_, Curve::Bezier([p0, p1, p2, p3])) => {
+            // de Casteljau
+            let q0 = lerp(p0, p1, t);
+            let q1 = lerp(p1, p2, t);
+            let q2 = lerp(p2, p3, t);
+            let r0 = lerp(q0, q1, t);
+            let r1 = lerp(q1, q2, t);
+            (
+                Geom2D::CubicBezier {
+                    a: p0,
+                    c1: q0,
+                    c2: r0,
+                    b: split_point,
+                },
+                Geom2D::CubicBezier {
+                    a: split_point,
+                    c1: r1,
+                    c2: q2,
+                    b: p3,
+                },
+            )
+        }
+        _ => return Err(Reason::from_code(ReasonCode::GeomSplitPointNotOnGeom)),
+    };
+    Ok(SplitResult {
+        left,
+        right,
+        split_point,
+    })
+}
+
+/// Approximate offset of a curved variant as a polyline through offset
+/// samples of the source flattening. Positive `dist` is to the left of the
+/// direction of travel, so `+dist` shrinks an ellipse.
+pub(crate) fn offset_curve(g: &Geom2D, dist: f64, eps: &EpsilonPolicy) -> Result<Geom2D> {
+    let curve = Curve::pieces(g, eps)?[0];
+    let mut us = curve.flatten_params(chord_tol(eps));
+    let closed = curve.is_closed();
+    if closed {
+        us.pop();
+    }
+    let mut pts = Vec::with_capacity(us.len());
+    for u in us {
+        let d1 = curve.deriv(u);
+        let speed = dot(d1, d1).sqrt();
+        if speed <= f64::EPSILON {
+            // Bezier cusp: no normal to follow.
+            let mut r = Reason::from_code(ReasonCode::GeomOffsetNotSupported);
+            r.debug.insert("case".into(), serde_json::json!("cusp"));
+            return Err(r);
+        }
+        // Past the centre of curvature the offset folds over itself.
+        let k = cross(d1, curve.deriv2(u)) / speed.powi(3);
+        if dist * k >= 1.0 - 1e-12 {
+            let mut r = Reason::from_code(ReasonCode::GeomOffsetSelfIntersection);
+            r.debug
+                .insert("case".into(), serde_json::json!("curvature"));
+            return Err(r);
+        }
+        let n = Vec2 {
+            x: -d1.y / speed,
+            y: d1.x / speed,
+        };
+        pts.push(add(curve.point(u), mul(n, dist)));
+    }
+    Ok(Geom2D::Polyline { pts, closed })
+}
+
+/// Flattened points of an open curved variant, for chains that only carry
+/// lines and arcs.
+pub(crate) fn flatten_open(g: &Geom2D, eps: &EpsilonPolicy) -> Result<Vec<Vec2>> {
+    match flatten(g, chord_tol(eps), eps)? {
+        Geom2D::Polyline { pts, closed: false } => Ok(pts),
+        _ => Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported)),
+    }
+}
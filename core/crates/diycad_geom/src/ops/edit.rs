@@ -1,5 +1,6 @@
-use crate::{Geom2D, Vec2};
+use crate::{EpsilonPolicy, Geom2D, Vec2};
 use craftcad_serialize::{Reason, ReasonCode, Result};
+use std::f64::consts::{PI, TAU};
 
 fn sub(a: Vec2, b: Vec2) -> Vec2 {
     Vec2 {
@@ -25,6 +26,9 @@ fn dot(a: Vec2, b: Vec2) -> f64 {
 fn len(a: Vec2) -> f64 {
     (a.x * a.x + a.y * a.y).sqrt()
 }
+fn cross(a: Vec2, b: Vec2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
 fn norm(a: Vec2) -> Option<Vec2> {
     let l = len(a);
     if l <= 1e-12 {
@@ -72,6 +76,37 @@ pub fn mirror_geom(geom: &Geom2D, axis_a: Vec2, axis_b: Vec2) -> Result<Geom2D>
             pts: pts.iter().copied().map(mirror_pt).collect(),
             closed: *closed,
         },
+        // Reflecting the frame across an axis at angle `phi` turns `rot` into
+        // `2 phi - rot` and parameter `t` into `-t`.
+        Geom2D::Ellipse { c, rx, ry, rot } => Geom2D::Ellipse {
+            c: mirror_pt(*c),
+            rx: *rx,
+            ry: *ry,
+            rot: 2.0 * u.y.atan2(u.x) - rot,
+        },
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => Geom2D::EllipticalArc {
+            c: mirror_pt(*c),
+            rx: *rx,
+            ry: *ry,
+            rot: 2.0 * u.y.atan2(u.x) - rot,
+            start_angle: -*start_angle,
+            end_angle: -*end_angle,
+            ccw: !*ccw,
+        },
+        Geom2D::CubicBezier { a, c1, c2, b } => Geom2D::CubicBezier {
+            a: mirror_pt(*a),
+            c1: mirror_pt(*c1),
+            c2: mirror_pt(*c2),
+            b: mirror_pt(*b),
+        },
     })
 }
 
@@ -130,21 +165,26 @@ pub fn fillet_lines(
         norm(add(ua, ub)).ok_or_else(|| Reason::from_code(ReasonCode::EditFilletRadiusTooLarge))?;
     let h = radius / (theta / 2.0).sin();
     let center = add(p, mul(bis, h));
-    let a0 = (pa.y - center.y).atan2(pa.x - center.x);
-    let a1 = (pb.y - center.y).atan2(pb.x - center.x);
     Ok((
         Geom2D::Line { a, b: pa },
         Geom2D::Line { a: c, b: pb },
-        Geom2D::Arc {
-            c: center,
-            r: radius,
-            start_angle: a0,
-            end_angle: a1,
-            ccw: true,
-        },
+        fillet_arc(center, radius, pa, pb),
     ))
 }
 
+// The short arc from `from` to `to` around `center`.
+fn fillet_arc(center: Vec2, radius: f64, from: Vec2, to: Vec2) -> Geom2D {
+    let u = sub(from, center);
+    let v = sub(to, center);
+    Geom2D::Arc {
+        c: center,
+        r: radius,
+        start_angle: u.y.atan2(u.x),
+        end_angle: v.y.atan2(v.x),
+        ccw: cross(u, v) > 0.0,
+    }
+}
+
 fn line_intersection(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Option<Vec2> {
     let r = sub(b, a);
     let s = sub(d, c);
@@ -155,3 +195,531 @@ fn line_intersection(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Option<Vec2> {
     let t = ((c.x - a.x) * s.y - (c.y - a.y) * s.x) / den;
     Some(add(a, mul(r, t)))
 }
+
+fn unsupported(index: usize) -> Reason {
+    let mut r = Reason::from_code(ReasonCode::EditFilletUnsupportedGeometry);
+    r.debug.insert("index".into(), serde_json::json!(index));
+    r
+}
+
+fn polar(c: Vec2, r: f64, theta: f64) -> Vec2 {
+    Vec2 {
+        x: c.x + r * theta.cos(),
+        y: c.y + r * theta.sin(),
+    }
+}
+
+fn angle_of(c: Vec2, p: Vec2) -> f64 {
+    (p.y - c.y).atan2(p.x - c.x)
+}
+
+// A line or arc taking part in a corner, together with which end survives.
+#[derive(Debug, Clone, Copy)]
+enum Curve {
+    Line {
+        a: Vec2,
+        b: Vec2,
+    },
+    Arc {
+        c: Vec2,
+        r: f64,
+        start: f64,
+        end: f64,
+        ccw: bool,
+    },
+}
+
+impl Curve {
+    fn from_geom(g: &Geom2D, index: usize) -> Result<Self> {
+        match *g {
+            Geom2D::Line { a, b } => {
+                if len(sub(b, a)) <= 1e-12 {
+                    return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+                }
+                Ok(Curve::Line { a, b })
+            }
+            Geom2D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            } => {
+                if !r.is_finite() || r <= 0.0 {
+                    return Err(Reason::from_code(ReasonCode::GeomCircleRadiusInvalid));
+                }
+                Ok(Curve::Arc {
+                    c,
+                    r,
+                    start: start_angle,
+                    end: end_angle,
+                    ccw,
+                })
+            }
+            _ => Err(unsupported(index)),
+        }
+    }
+
+    fn ends(&self) -> [Vec2; 2] {
+        match *self {
+            Curve::Line { a, b } => [a, b],
+            Curve::Arc {
+                c, r, start, end, ..
+            } => [polar(c, r, start), polar(c, r, end)],
+        }
+    }
+
+    // Unit tangent at `p` (on the carrier) pointing towards the kept end.
+    fn dir_at(&self, p: Vec2, keep_end: bool) -> Vec2 {
+        let sign = if keep_end { 1.0 } else { -1.0 };
+        match *self {
+            Curve::Line { a, b } => mul(sub(b, a), sign / len(sub(b, a))),
+            Curve::Arc { c, ccw, .. } => {
+                let th = angle_of(c, p);
+                let s = if ccw { sign } else { -sign };
+                Vec2 {
+                    x: -th.sin() * s,
+                    y: th.cos() * s,
+                }
+            }
+        }
+    }
+
+    // Distance travelled from `from` to `to` along the carrier in direction `d`
+    // (the direction from `dir_at`). Arcs measure angles in [0, 2π).
+    fn advance(&self, from: Vec2, to: Vec2, d: Vec2) -> f64 {
+        match *self {
+            Curve::Line { .. } => dot(sub(to, from), d),
+            Curve::Arc { c, r, .. } => {
+                let s = cross(sub(from, c), d).signum();
+                r * ((angle_of(c, to) - angle_of(c, from)) * s).rem_euclid(TAU)
+            }
+        }
+    }
+
+    fn point_along(&self, from: Vec2, d: Vec2, dist: f64) -> Vec2 {
+        match *self {
+            Curve::Line { .. } => add(from, mul(d, dist)),
+            Curve::Arc { c, r, .. } => {
+                let s = cross(sub(from, c), d).signum();
+                polar(c, r, angle_of(c, from) + s * dist / r)
+            }
+        }
+    }
+
+    fn foot(&self, x: Vec2) -> Vec2 {
+        match *self {
+            Curve::Line { a, b } => {
+                let d = sub(b, a);
+                add(a, mul(d, dot(sub(x, a), d) / dot(d, d)))
+            }
+            Curve::Arc { c, r, .. } => {
+                let u = norm(sub(x, c)).unwrap_or(Vec2 { x: 1.0, y: 0.0 });
+                add(c, mul(u, r))
+            }
+        }
+    }
+
+    // The curve with its dropped end moved to `p`.
+    fn trimmed(&self, p: Vec2, keep_end: bool) -> Geom2D {
+        match *self {
+            Curve::Line { a, b } => {
+                if keep_end {
+                    Geom2D::Line { a: p, b }
+                } else {
+                    Geom2D::Line { a, b: p }
+                }
+            }
+            Curve::Arc {
+                c,
+                r,
+                start,
+                end,
+                ccw,
+            } => {
+                let th = angle_of(c, p);
+                let (start_angle, end_angle) = if keep_end { (th, end) } else { (start, th) };
+                Geom2D::Arc {
+                    c,
+                    r,
+                    start_angle,
+                    end_angle,
+                    ccw,
+                }
+            }
+        }
+    }
+}
+
+// Candidate points where the carriers (infinite lines, full circles) meet.
+fn carrier_hits(p: &Curve, q: &Curve, eps: &EpsilonPolicy) -> Vec<Vec2> {
+    match (*p, *q) {
+        (Curve::Line { a, b }, Curve::Line { a: c, b: d }) => {
+            line_intersection(a, b, c, d).into_iter().collect()
+        }
+        (Curve::Line { a, b }, Curve::Arc { c, r, .. })
+        | (Curve::Arc { c, r, .. }, Curve::Line { a, b }) => line_circle(a, sub(b, a), c, r, eps),
+        (Curve::Arc { c: c0, r: r0, .. }, Curve::Arc { c: c1, r: r1, .. }) => {
+            circle_circle(c0, r0, c1, r1, eps)
+        }
+    }
+}
+
+fn line_circle(p: Vec2, d: Vec2, c: Vec2, r: f64, eps: &EpsilonPolicy) -> Vec<Vec2> {
+    let len2 = dot(d, d);
+    let foot = add(p, mul(d, dot(sub(c, p), d) / len2));
+    let h = len(sub(foot, c));
+    if h > r + eps.intersect_tol {
+        return vec![];
+    }
+    if (h - r).abs() <= eps.intersect_tol {
+        return vec![foot];
+    }
+    let half = mul(d, (r * r - h * h).sqrt() / len2.sqrt());
+    vec![sub(foot, half), add(foot, half)]
+}
+
+fn circle_circle(c0: Vec2, r0: f64, c1: Vec2, r1: f64, eps: &EpsilonPolicy) -> Vec<Vec2> {
+    let d = len(sub(c1, c0));
+    if d <= eps.eq_dist
+        || d > r0 + r1 + eps.intersect_tol
+        || d < (r0 - r1).abs() - eps.intersect_tol
+    {
+        return vec![];
+    }
+    let a = (r0 * r0 - r1 * r1 + d * d) / (2.0 * d);
+    let h2 = (r0 * r0 - a * a).max(0.0);
+    let u = mul(sub(c1, c0), 1.0 / d);
+    let m = add(c0, mul(u, a));
+    if h2.sqrt() <= eps.intersect_tol {
+        return vec![m];
+    }
+    let perp = mul(Vec2 { x: -u.y, y: u.x }, h2.sqrt());
+    vec![add(m, perp), sub(m, perp)]
+}
+
+fn nearest(cands: Vec<Vec2>, to: Vec2) -> Option<Vec2> {
+    cands
+        .into_iter()
+        .min_by(|l, r| len(sub(*l, to)).total_cmp(&len(sub(*r, to))))
+}
+
+struct Corner {
+    curves: [Curve; 2],
+    // Which end of each curve is kept (`true` = end, `false` = start).
+    keep_end: [bool; 2],
+    apex: Vec2,
+    dirs: [Vec2; 2],
+}
+
+// The corner two curves form: the carrier crossing nearest to the pair of
+// endpoints that lie closest together, with each curve keeping its other end.
+fn corner(g1: &Geom2D, g2: &Geom2D, eps: &EpsilonPolicy) -> Result<Corner> {
+    let curves = [Curve::from_geom(g1, 0)?, Curve::from_geom(g2, 1)?];
+    let (e0, e1) = (curves[0].ends(), curves[1].ends());
+    let (mut bi, mut bj, mut best) = (0, 0, f64::INFINITY);
+    for (i, p) in e0.iter().enumerate() {
+        for (j, q) in e1.iter().enumerate() {
+            let d = len(sub(*p, *q));
+            if d < best {
+                (bi, bj, best) = (i, j, d);
+            }
+        }
+    }
+    let joint = mul(add(e0[bi], e1[bj]), 0.5);
+    let apex = nearest(carrier_hits(&curves[0], &curves[1], eps), joint)
+        .ok_or_else(|| Reason::from_code(ReasonCode::GeomNoIntersection))?;
+    let keep_end = [bi == 0, bj == 0];
+    let dirs = [
+        curves[0].dir_at(apex, keep_end[0]),
+        curves[1].dir_at(apex, keep_end[1]),
+    ];
+    Ok(Corner {
+        curves,
+        keep_end,
+        apex,
+        dirs,
+    })
+}
+
+impl Corner {
+    // `p` must lie strictly between the apex and the kept end of curve `k`.
+    fn check_on_kept(
+        &self,
+        k: usize,
+        p: Vec2,
+        code: ReasonCode,
+        eps: &EpsilonPolicy,
+    ) -> Result<()> {
+        let c = &self.curves[k];
+        let kept = c.ends()[usize::from(self.keep_end[k])];
+        let to_p = c.advance(self.apex, p, self.dirs[k]);
+        let to_kept = c.advance(self.apex, kept, self.dirs[k]);
+        if to_p < -eps.eq_dist || to_p > to_kept - eps.eq_dist {
+            let mut r = Reason::from_code(code);
+            r.debug.insert("index".into(), serde_json::json!(k));
+            return Err(r);
+        }
+        Ok(())
+    }
+}
+
+/// Rounds the corner between two lines or arcs. Each curve keeps the end away
+/// from the corner; returns the two trimmed curves (direction preserved) and the
+/// fillet arc.
+pub fn fillet_curves(
+    g1: &Geom2D,
+    g2: &Geom2D,
+    radius: f64,
+    eps: &EpsilonPolicy,
+) -> Result<(Geom2D, Geom2D, Geom2D)> {
+    if !radius.is_finite() || radius <= 0.0 {
+        return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+    }
+    let k = corner(g1, g2, eps)?;
+    let turn = cross(k.dirs[0], k.dirs[1]);
+    if turn.abs() <= 1e-9 {
+        return Err(Reason::from_code(ReasonCode::EditFilletRadiusTooLarge));
+    }
+    // The fillet centre sits on the side of each curve the other one heads to.
+    let sides = [turn.signum(), -turn.signum()];
+    let mut offsets = [k.curves[0]; 2];
+    for i in 0..2 {
+        let d = k.dirs[i];
+        let n = mul(Vec2 { x: -d.y, y: d.x }, sides[i]);
+        offsets[i] = match k.curves[i] {
+            Curve::Line { .. } => Curve::Line {
+                a: add(k.apex, mul(n, radius)),
+                b: add(add(k.apex, d), mul(n, radius)),
+            },
+            Curve::Arc {
+                c,
+                r,
+                start,
+                end,
+                ccw,
+            } => {
+                let r2 = if dot(n, sub(c, k.apex)) > 0.0 {
+                    r - radius
+                } else {
+                    r + radius
+                };
+                if r2 <= eps.eq_dist {
+                    return Err(Reason::from_code(ReasonCode::EditFilletRadiusTooLarge));
+                }
+                Curve::Arc {
+                    c,
+                    r: r2,
+                    start,
+                    end,
+                    ccw,
+                }
+            }
+        };
+    }
+    let center = nearest(carrier_hits(&offsets[0], &offsets[1], eps), k.apex)
+        .ok_or_else(|| Reason::from_code(ReasonCode::EditFilletRadiusTooLarge))?;
+    let t0 = k.curves[0].foot(center);
+    let t1 = k.curves[1].foot(center);
+    k.check_on_kept(0, t0, ReasonCode::EditFilletRadiusTooLarge, eps)?;
+    k.check_on_kept(1, t1, ReasonCode::EditFilletRadiusTooLarge, eps)?;
+    Ok((
+        k.curves[0].trimmed(t0, k.keep_end[0]),
+        k.curves[1].trimmed(t1, k.keep_end[1]),
+        fillet_arc(center, radius, t0, t1),
+    ))
+}
+
+/// Cuts the corner between two lines or arcs with a straight segment `dist`
+/// away from the corner along each curve (arc length for arcs).
+pub fn chamfer_curves(
+    g1: &Geom2D,
+    g2: &Geom2D,
+    dist: f64,
+    eps: &EpsilonPolicy,
+) -> Result<(Geom2D, Geom2D, Geom2D)> {
+    if !dist.is_finite() || dist <= 0.0 {
+        return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+    }
+    let k = corner(g1, g2, eps)?;
+    let p0 = k.curves[0].point_along(k.apex, k.dirs[0], dist);
+    let p1 = k.curves[1].point_along(k.apex, k.dirs[1], dist);
+    k.check_on_kept(0, p0, ReasonCode::EditChamferDistanceTooLarge, eps)?;
+    k.check_on_kept(1, p1, ReasonCode::EditChamferDistanceTooLarge, eps)?;
+    Ok((
+        k.curves[0].trimmed(p0, k.keep_end[0]),
+        k.curves[1].trimmed(p1, k.keep_end[1]),
+        Geom2D::Line { a: p0, b: p1 },
+    ))
+}
+
+#[derive(Clone, Copy)]
+enum CornerCut {
+    Fillet(f64),
+    Chamfer(f64),
+}
+
+impl CornerCut {
+    fn too_large(self) -> ReasonCode {
+        match self {
+            CornerCut::Fillet(_) => ReasonCode::EditFilletRadiusTooLarge,
+            CornerCut::Chamfer(_) => ReasonCode::EditChamferDistanceTooLarge,
+        }
+    }
+}
+
+fn corner_error(code: ReasonCode, vertex: usize) -> Reason {
+    let mut r = Reason::from_code(code);
+    r.debug.insert("vertex".into(), serde_json::json!(vertex));
+    r
+}
+
+fn cut_polyline(
+    g: &Geom2D,
+    vertex: Option<usize>,
+    cut: CornerCut,
+    eps: &EpsilonPolicy,
+) -> Result<Geom2D> {
+    let size = match cut {
+        CornerCut::Fillet(v) | CornerCut::Chamfer(v) => v,
+    };
+    if !size.is_finite() || size <= 0.0 {
+        return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+    }
+    let (mut pts, closed) = match g {
+        Geom2D::Polyline { pts, closed } => (pts.clone(), *closed),
+        _ => return Err(unsupported(0)),
+    };
+    if closed && pts.len() > 1 && len(sub(pts[0], pts[pts.len() - 1])) <= eps.eq_dist {
+        pts.pop();
+    }
+    let n = pts.len();
+    if n < if closed { 3 } else { 2 } {
+        return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+    }
+    let is_corner = |i: usize| closed || (i > 0 && i + 1 < n);
+    let targets: Vec<usize> = match vertex {
+        Some(i) => {
+            let i = if closed && i == n { 0 } else { i };
+            if i >= n || !is_corner(i) {
+                return Err(corner_error(ReasonCode::EditCandidateNotFound, i));
+            }
+            vec![i]
+        }
+        None => (0..n).filter(|&i| is_corner(i)).collect(),
+    };
+
+    // Per vertex: unit directions towards both neighbours, the interior angle and
+    // how far the cut reaches along each edge.
+    let mut reach = vec![0.0; n];
+    let mut geo: Vec<Option<(Vec2, Vec2, f64)>> = vec![None; n];
+    for &i in &targets {
+        let v = pts[i];
+        let prev = pts[(i + n - 1) % n];
+        let next = pts[(i + 1) % n];
+        let (u1, u2) = match (norm(sub(prev, v)), norm(sub(next, v))) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Err(corner_error(ReasonCode::GeomDegenerate, i)),
+        };
+        let theta = dot(u1, u2).clamp(-1.0, 1.0).acos();
+        if PI - theta <= 1e-6 {
+            if vertex.is_some() {
+                return Err(corner_error(cut.too_large(), i));
+            }
+            continue;
+        }
+        if theta <= 1e-6 {
+            return Err(corner_error(cut.too_large(), i));
+        }
+        reach[i] = match cut {
+            CornerCut::Fillet(r) => r / (theta / 2.0).tan(),
+            CornerCut::Chamfer(d) => d,
+        };
+        geo[i] = Some((u1, u2, theta));
+    }
+    let seg_count = if closed { n } else { n - 1 };
+    for s in 0..seg_count {
+        let (a, b) = (s, (s + 1) % n);
+        if reach[a] + reach[b] > len(sub(pts[b], pts[a])) + eps.eq_dist {
+            let at = if reach[a] >= reach[b] { a } else { b };
+            return Err(corner_error(cut.too_large(), at));
+        }
+    }
+
+    let mut out: Vec<Vec2> = Vec::with_capacity(n * 2);
+    let mut push = |p: Vec2| {
+        if out
+            .last()
+            .map(|q| len(sub(*q, p)) > eps.eq_dist)
+            .unwrap_or(true)
+        {
+            out.push(p);
+        }
+    };
+    for i in 0..n {
+        let v = pts[i];
+        let Some((u1, u2, theta)) = geo[i] else {
+            push(v);
+            continue;
+        };
+        let t1 = add(v, mul(u1, reach[i]));
+        let t2 = add(v, mul(u2, reach[i]));
+        match cut {
+            CornerCut::Chamfer(_) => {
+                push(t1);
+                push(t2);
+            }
+            CornerCut::Fillet(r) => {
+                // Polylines carry no arc segments, so the fillet is flattened with
+                // a chord error of at most `snap_dist`.
+                let bis = norm(add(u1, u2)).ok_or_else(|| corner_error(cut.too_large(), i))?;
+                let center = add(v, mul(bis, r / (theta / 2.0).sin()));
+                let a0 = angle_of(center, t1);
+                let sweep = {
+                    let u = sub(t1, center);
+                    let w = sub(t2, center);
+                    cross(u, w).atan2(dot(u, w))
+                };
+                let step = if eps.snap_dist < r {
+                    2.0 * (1.0 - eps.snap_dist / r).acos()
+                } else {
+                    PI / 2.0
+                };
+                let count = ((sweep.abs() / step).ceil() as usize).clamp(1, 256);
+                push(t1);
+                for k in 1..count {
+                    push(polar(center, r, a0 + sweep * k as f64 / count as f64));
+                }
+                push(t2);
+            }
+        }
+    }
+    if closed && out.len() > 1 && len(sub(out[0], out[out.len() - 1])) <= eps.eq_dist {
+        out.pop();
+    }
+    Ok(Geom2D::Polyline { pts: out, closed })
+}
+
+/// Rounds one vertex of a polyline (`Some(index)`) or every corner of it
+/// (`None`; open polylines keep their end points) and returns the polyline with
+/// the fillets flattened in place. Straight vertices are skipped when rounding
+/// all corners.
+pub fn fillet_polyline(
+    g: &Geom2D,
+    vertex: Option<usize>,
+    radius: f64,
+    eps: &EpsilonPolicy,
+) -> Result<Geom2D> {
+    cut_polyline(g, vertex, CornerCut::Fillet(radius), eps)
+}
+
+/// Chamfers one vertex of a polyline (`Some(index)`) or every corner of it
+/// (`None`), `dist` back along both adjacent edges.
+pub fn chamfer_polyline(
+    g: &Geom2D,
+    vertex: Option<usize>,
+    dist: f64,
+    eps: &EpsilonPolicy,
+) -> Result<Geom2D> {
+    cut_polyline(g, vertex, CornerCut::Chamfer(dist), eps)
+}
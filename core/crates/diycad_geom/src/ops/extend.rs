@@ -0,0 +1,284 @@
+use crate::{
+    ops::{curve::bbox, intersect::intersect},
+    util::{add, dist2, mul, sub},
+    EpsilonPolicy, Geom2D, Vec2,
+};
+use craftcad_serialize::{Reason, ReasonCode, Result};
+use std::f64::consts::PI;
+
+const TAU: f64 = 2.0 * PI;
+
+fn normalize_angle(mut a: f64) -> f64 {
+    while a <= -PI {
+        a += TAU;
+    }
+    while a > PI {
+        a -= TAU;
+    }
+    a
+}
+
+fn arc_sweep(start: f64, end: f64, ccw: bool) -> f64 {
+    if ccw {
+        (end - start).rem_euclid(TAU)
+    } else {
+        (start - end).rem_euclid(TAU)
+    }
+}
+
+fn in_arc_range(theta: f64, start: f64, end: f64, ccw: bool, tol: f64) -> bool {
+    let u = if ccw {
+        (theta - start).rem_euclid(TAU)
+    } else {
+        (start - theta).rem_euclid(TAU)
+    };
+    u <= arc_sweep(start, end, ccw) + tol || u >= TAU - tol
+}
+
+/// Hits of `probe` with one boundary. Pairs that never meet or only overlap
+/// (colinear lines, coincident circles) give no stop point and are skipped.
+fn boundary_hits(probe: &Geom2D, boundary: &Geom2D, eps: &EpsilonPolicy) -> Result<Vec<Vec2>> {
+    let skip = |r: Reason| -> Result<Vec<Vec2>> {
+        if r.code == ReasonCode::GeomNoIntersection.as_str()
+            || r.code == ReasonCode::GeomIntersectionAmbiguous.as_str()
+        {
+            Ok(vec![])
+        } else {
+            Err(r)
+        }
+    };
+    match (probe, boundary) {
+        (
+            Geom2D::Circle { .. },
+            Geom2D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            },
+        ) => {
+            let full = Geom2D::Circle { c: *c, r: *r };
+            let pts = match intersect(probe, &full, eps) {
+                Ok(set) => set.points,
+                Err(e) => return skip(e),
+            };
+            let tol = eps.intersect_tol.max(eps.eq_dist / r);
+            Ok(pts
+                .into_iter()
+                .filter(|p| {
+                    let theta = (p.y - c.y).atan2(p.x - c.x);
+                    in_arc_range(theta, *start_angle, *end_angle, *ccw, tol)
+                })
+                .collect())
+        }
+        (Geom2D::Circle { .. }, Geom2D::Polyline { pts, closed }) => {
+            let seg_count = if *closed {
+                pts.len()
+            } else {
+                pts.len().saturating_sub(1)
+            };
+            let mut out = vec![];
+            for i in 0..seg_count {
+                let seg = Geom2D::Line {
+                    a: pts[i],
+                    b: pts[(i + 1) % pts.len()],
+                };
+                match intersect(probe, &seg, eps) {
+                    Ok(set) => out.extend(set.points),
+                    Err(e) => out.extend(skip(e)?),
+                }
+            }
+            Ok(out)
+        }
+        _ => match intersect(probe, boundary, eps) {
+            Ok(set) => Ok(set.points),
+            Err(e) => skip(e),
+        },
+    }
+}
+
+/// Largest distance from `from` to any corner of the boundary bounds, used to
+/// size the probe segment so that it reaches past every boundary.
+fn reach(from: Vec2, boundaries: &[Geom2D], eps: &EpsilonPolicy) -> Result<f64> {
+    let mut out: f64 = 0.0;
+    for g in boundaries {
+        let b = bbox(g, eps)?;
+        for x in [b.min.x, b.max.x] {
+            for y in [b.min.y, b.max.y] {
+                out = out.max(dist2(from, Vec2 { x, y }).sqrt());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Candidate stop points ranked by how far the end has to travel, nearest
+/// first. `candidate_index` picks from this order; without it, two hits
+/// within `tol` of the nearest travel are ambiguous.
+fn choose_stop(
+    mut ranked: Vec<(Vec2, f64)>,
+    candidate_index: Option<usize>,
+    tol: f64,
+) -> Result<(Vec2, f64)> {
+    if ranked.is_empty() {
+        return Err(Reason::from_code(ReasonCode::GeomTrimNoIntersection));
+    }
+    ranked.sort_by(|l, r| l.1.total_cmp(&r.1));
+    let candidates = || {
+        serde_json::json!(ranked
+            .iter()
+            .enumerate()
+            .map(|(i, (p, t))| serde_json::json!({"index":i,"x":p.x,"y":p.y,"t":t}))
+            .collect::<Vec<_>>())
+    };
+
+    if let Some(idx) = candidate_index {
+        if idx >= ranked.len() {
+            let mut err = Reason::from_code(ReasonCode::EditTrimAmbiguousCandidate);
+            err.debug.insert("candidates".into(), candidates());
+            err.debug
+                .insert("invalid_candidate_index".into(), serde_json::json!(idx));
+            return Err(err);
+        }
+        return Ok(ranked[idx]);
+    }
+
+    if ranked.len() >= 2 && (ranked[0].1 - ranked[1].1).abs() <= tol {
+        let mut err = Reason::from_code(ReasonCode::EditTrimAmbiguousCandidate);
+        err.debug.insert("candidates".into(), candidates());
+        return Err(err);
+    }
+    Ok(ranked[0])
+}
+
+fn extend_line_end(
+    fixed: Vec2,
+    end: Vec2,
+    boundaries: &[Geom2D],
+    eps: &EpsilonPolicy,
+    candidate_index: Option<usize>,
+) -> Result<Vec2> {
+    let dir = sub(end, fixed);
+    let len = dist2(fixed, end).sqrt();
+    if len <= eps.eq_dist {
+        return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+    }
+    let u = mul(dir, 1.0 / len);
+    let probe = Geom2D::Line {
+        a: end,
+        b: add(end, mul(u, reach(end, boundaries, eps)? + 1.0)),
+    };
+    let mut ranked = vec![];
+    for b in boundaries {
+        for p in boundary_hits(&probe, b, eps)? {
+            let t = (p.x - end.x) * u.x + (p.y - end.y) * u.y;
+            if t > eps.eq_dist {
+                ranked.push((p, t));
+            }
+        }
+    }
+    Ok(choose_stop(ranked, candidate_index, eps.eq_dist)?.0)
+}
+
+/// Extends the end of `target` nearest `pick_point` until it meets one of
+/// `boundaries`. Lines and open polylines grow along their end direction,
+/// arcs along their circle. The nearest hit wins unless `candidate_index`
+/// selects another; equally near hits need one.
+pub fn extend_to_boundary(
+    target: &Geom2D,
+    boundaries: &[Geom2D],
+    pick_point: Vec2,
+    eps: &EpsilonPolicy,
+    candidate_index: Option<usize>,
+) -> Result<Geom2D> {
+    match target {
+        Geom2D::Line { a, b } => {
+            if dist2(*a, pick_point) < dist2(*b, pick_point) {
+                let na = extend_line_end(*b, *a, boundaries, eps, candidate_index)?;
+                Ok(Geom2D::Line { a: na, b: *b })
+            } else {
+                let nb = extend_line_end(*a, *b, boundaries, eps, candidate_index)?;
+                Ok(Geom2D::Line { a: *a, b: nb })
+            }
+        }
+        Geom2D::Polyline { pts, closed } => {
+            if *closed || pts.len() < 2 {
+                return Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported));
+            }
+            let n = pts.len();
+            let mut out = pts.clone();
+            if dist2(pts[0], pick_point) < dist2(pts[n - 1], pick_point) {
+                out[0] = extend_line_end(pts[1], pts[0], boundaries, eps, candidate_index)?;
+            } else {
+                out[n - 1] =
+                    extend_line_end(pts[n - 2], pts[n - 1], boundaries, eps, candidate_index)?;
+            }
+            Ok(Geom2D::Polyline {
+                pts: out,
+                closed: false,
+            })
+        }
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            if !r.is_finite() || *r <= eps.eq_dist {
+                return Err(Reason::from_code(ReasonCode::GeomCircleRadiusInvalid));
+            }
+            if !start_angle.is_finite() || !end_angle.is_finite() {
+                return Err(Reason::from_code(ReasonCode::GeomArcRangeInvalid));
+            }
+            let at = |ang: f64| Vec2 {
+                x: c.x + r * ang.cos(),
+                y: c.y + r * ang.sin(),
+            };
+            let from_start =
+                dist2(at(*start_angle), pick_point) < dist2(at(*end_angle), pick_point);
+            // Growing the end keeps the arc direction; growing the start runs against it.
+            let (anchor, forward) = if from_start {
+                (*start_angle, !*ccw)
+            } else {
+                (*end_angle, *ccw)
+            };
+            let free = TAU - arc_sweep(*start_angle, *end_angle, *ccw);
+            let tol = eps.eq_dist / r;
+            let probe = Geom2D::Circle { c: *c, r: *r };
+            let mut ranked = vec![];
+            for b in boundaries {
+                for p in boundary_hits(&probe, b, eps)? {
+                    let theta = (p.y - c.y).atan2(p.x - c.x);
+                    let s = if forward {
+                        (theta - anchor).rem_euclid(TAU)
+                    } else {
+                        (anchor - theta).rem_euclid(TAU)
+                    };
+                    if s > tol && s < free - tol {
+                        ranked.push((p, s));
+                    }
+                }
+            }
+            let (_, s) = choose_stop(ranked, candidate_index, tol)?;
+            let moved = normalize_angle(if forward { anchor + s } else { anchor - s });
+            let (start_angle, end_angle) = if from_start {
+                (moved, *end_angle)
+            } else {
+                (*start_angle, moved)
+            };
+            Ok(Geom2D::Arc {
+                c: *c,
+                r: *r,
+                start_angle,
+                end_angle,
+                ccw: *ccw,
+            })
+        }
+        Geom2D::Circle { .. }
+        | Geom2D::Ellipse { .. }
+        | Geom2D::EllipticalArc { .. }
+        | Geom2D::CubicBezier { .. } => Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported)),
+    }
+}
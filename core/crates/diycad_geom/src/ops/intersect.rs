@@ -1,4 +1,4 @@
-use crate::{util::dist2, EpsilonPolicy, Geom2D, IntersectionSet, Vec2};
+use crate::{ops::curve, util::dist2, EpsilonPolicy, Geom2D, IntersectionSet, Vec2};
 use craftcad_serialize::{Reason, ReasonCode, Result};
 
 fn normalize_angle(mut a: f64) -> f64 {
@@ -216,6 +216,9 @@ fn filter_points_for_arc(
 }
 
 fn compute_with_eps(a: &Geom2D, b: &Geom2D, eps: &EpsilonPolicy) -> Result<Vec<Vec2>> {
+    if curve::is_curved(a) || curve::is_curved(b) {
+        return curve::intersect_curves(a, b, eps);
+    }
     match (a, b) {
         (Geom2D::Line { a: a0, b: a1 }, Geom2D::Line { a: b0, b: b1 }) => {
             line_line(*a0, *a1, *b0, *b1, eps)
@@ -1,8 +1,12 @@
+pub mod curve;
 pub mod intersect;
 pub mod project;
 pub mod split;
 
+pub mod extend;
 pub mod offset;
 pub mod trim;
 
 pub mod edit;
+
+pub mod boolean;
@@ -1,91 +1,915 @@
 use crate::{
-    util::{add, dist2, mul, sub},
-    EpsilonPolicy, Geom2D, Vec2,
+    ops::curve,
+    util::{add, dist2, dot, lerp, mul, sub},
+    EpsilonPolicy, Geom2D, JoinStyle, Vec2,
 };
 use craftcad_serialize::{Reason, ReasonCode, Result};
+use std::f64::consts::{PI, TAU};
 
-fn line_normal(a: Vec2, b: Vec2, eps: &EpsilonPolicy) -> Result<Vec2> {
+// Offsets follow one sign convention everywhere: a positive distance moves the
+// curve to the left of its direction of travel. Circles travel counter-clockwise,
+// so a positive distance shrinks them, and a counter-clockwise closed outline
+// shrinks as well.
+//
+// Chains are offset in three passes:
+// 1. every segment is offset on its own; concave corners are trimmed back to the
+//    point where the neighbours cross and convex corners get the requested join,
+// 2. the raw curve is split at all of its self-intersections,
+// 3. pieces that come closer than |dist| to the source are dropped and the rest
+//    are stitched back together in their original order.
+
+fn normalize_angle(mut a: f64) -> f64 {
+    while a <= -PI {
+        a += 2.0 * PI;
+    }
+    while a > PI {
+        a -= 2.0 * PI;
+    }
+    a
+}
+
+fn arc_sweep(start: f64, end: f64, ccw: bool) -> f64 {
+    let s = normalize_angle(start);
+    let e = normalize_angle(end);
+    let mut d = if ccw { e - s } else { s - e };
+    if d < 0.0 {
+        d += 2.0 * PI;
+    }
+    d
+}
+
+fn cross(a: Vec2, b: Vec2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn polar(c: Vec2, r: f64, theta: f64) -> Vec2 {
+    Vec2 {
+        x: c.x + r * theta.cos(),
+        y: c.y + r * theta.sin(),
+    }
+}
+
+// Position of angle `theta` along an arc as a fraction of its sweep. Angles just
+// before the start come back slightly negative instead of wrapping to ~1.
+fn arc_param(a0: f64, sweep: f64, theta: f64) -> f64 {
+    let span = sweep.abs();
+    let u = ((theta - a0) * sweep.signum()).rem_euclid(TAU);
+    if u > span && TAU - u < u - span {
+        -(TAU - u) / span
+    } else {
+        u / span
+    }
+}
+
+fn collapsed() -> Reason {
+    let mut r = Reason::from_code(ReasonCode::GeomOffsetSelfIntersection);
+    r.debug
+        .insert("case".into(), serde_json::json!("offset_collapsed"));
+    r
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Seg {
+    Line {
+        a: Vec2,
+        b: Vec2,
+    },
+    // `sweep` is signed: positive runs counter-clockwise from `a0`.
+    Arc {
+        c: Vec2,
+        r: f64,
+        a0: f64,
+        sweep: f64,
+    },
+}
+
+impl Seg {
+    fn start(&self) -> Vec2 {
+        self.point_at(0.0)
+    }
+
+    fn end(&self) -> Vec2 {
+        self.point_at(1.0)
+    }
+
+    fn point_at(&self, t: f64) -> Vec2 {
+        match *self {
+            Seg::Line { a, .. } if t == 0.0 => a,
+            Seg::Line { b, .. } if t == 1.0 => b,
+            Seg::Line { a, b } => lerp(a, b, t),
+            Seg::Arc { c, r, a0, sweep } => polar(c, r, a0 + sweep * t),
+        }
+    }
+
+    fn length(&self) -> f64 {
+        match *self {
+            Seg::Line { a, b } => dist2(a, b).sqrt(),
+            Seg::Arc { r, sweep, .. } => r * sweep.abs(),
+        }
+    }
+
+    fn param_tol(&self, eps: &EpsilonPolicy) -> f64 {
+        eps.eq_dist / self.length().max(eps.eq_dist)
+    }
+
+    // Unit direction of travel at `t`.
+    fn tangent_at(&self, t: f64) -> Vec2 {
+        match *self {
+            Seg::Line { a, b } => {
+                let d = sub(b, a);
+                mul(d, 1.0 / dot(d, d).sqrt())
+            }
+            Seg::Arc { a0, sweep, .. } => {
+                let th = a0 + sweep * t;
+                let s = sweep.signum();
+                Vec2 {
+                    x: -th.sin() * s,
+                    y: th.cos() * s,
+                }
+            }
+        }
+    }
+
+    fn param_of(&self, p: Vec2) -> f64 {
+        match *self {
+            Seg::Line { a, b } => {
+                let d = sub(b, a);
+                dot(sub(p, a), d) / dot(d, d)
+            }
+            Seg::Arc { c, a0, sweep, .. } => arc_param(a0, sweep, (p.y - c.y).atan2(p.x - c.x)),
+        }
+    }
+
+    fn distance_to(&self, p: Vec2) -> f64 {
+        let t = self.param_of(p);
+        if (0.0..=1.0).contains(&t) {
+            match *self {
+                Seg::Line { .. } => dist2(p, self.point_at(t)).sqrt(),
+                Seg::Arc { c, r, .. } => (dist2(p, c).sqrt() - r).abs(),
+            }
+        } else {
+            dist2(p, self.start())
+                .sqrt()
+                .min(dist2(p, self.end()).sqrt())
+        }
+    }
+
+    fn sub_seg(&self, t0: f64, t1: f64) -> Seg {
+        match *self {
+            Seg::Line { .. } => Seg::Line {
+                a: self.point_at(t0),
+                b: self.point_at(t1),
+            },
+            Seg::Arc { c, r, a0, sweep } => Seg::Arc {
+                c,
+                r,
+                a0: a0 + sweep * t0,
+                sweep: sweep * (t1 - t0),
+            },
+        }
+    }
+
+    // `None` when an arc shrinks to nothing.
+    fn offset(&self, dist: f64, eps: &EpsilonPolicy) -> Option<Seg> {
+        match *self {
+            Seg::Line { a, b } => {
+                let t = self.tangent_at(0.0);
+                let d = Vec2 {
+                    x: -t.y * dist,
+                    y: t.x * dist,
+                };
+                Some(Seg::Line {
+                    a: add(a, d),
+                    b: add(b, d),
+                })
+            }
+            Seg::Arc { c, r, a0, sweep } => {
+                let r2 = r - sweep.signum() * dist;
+                if r2 <= eps.eq_dist {
+                    None
+                } else {
+                    Some(Seg::Arc {
+                        c,
+                        r: r2,
+                        a0,
+                        sweep,
+                    })
+                }
+            }
+        }
+    }
+
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Seg::Line { a, b } => (
+                Vec2 {
+                    x: a.x.min(b.x),
+                    y: a.y.min(b.y),
+                },
+                Vec2 {
+                    x: a.x.max(b.x),
+                    y: a.y.max(b.y),
+                },
+            ),
+            Seg::Arc { c, r, .. } => (
+                Vec2 {
+                    x: c.x - r,
+                    y: c.y - r,
+                },
+                Vec2 {
+                    x: c.x + r,
+                    y: c.y + r,
+                },
+            ),
+        }
+    }
+
+    fn to_geom(self) -> Geom2D {
+        match self {
+            Seg::Line { a, b } => Geom2D::Line { a, b },
+            Seg::Arc { c, r, a0, sweep } => Geom2D::Arc {
+                c,
+                r,
+                start_angle: normalize_angle(a0),
+                end_angle: normalize_angle(a0 + sweep),
+                ccw: sweep > 0.0,
+            },
+        }
+    }
+}
+
+fn line_circle(a: Vec2, b: Vec2, c: Vec2, r: f64, eps: &EpsilonPolicy) -> Vec<Vec2> {
     let d = sub(b, a);
-    let len2 = d.x * d.x + d.y * d.y;
-    if len2 <= eps.eq_dist * eps.eq_dist {
-        return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+    let len2 = dot(d, d);
+    let foot = add(a, mul(d, dot(sub(c, a), d) / len2));
+    let h = dist2(foot, c).sqrt();
+    if h > r + eps.intersect_tol {
+        return vec![];
     }
-    let inv = 1.0 / len2.sqrt();
-    Ok(Vec2 {
-        x: -d.y * inv,
-        y: d.x * inv,
-    })
+    if (h - r).abs() <= eps.intersect_tol {
+        return vec![foot];
+    }
+    let half = mul(d, (r * r - h * h).sqrt() / len2.sqrt());
+    vec![sub(foot, half), add(foot, half)]
+}
+
+fn circle_circle(c0: Vec2, r0: f64, c1: Vec2, r1: f64, eps: &EpsilonPolicy) -> Vec<Vec2> {
+    let d = dist2(c0, c1).sqrt();
+    if d <= eps.eq_dist
+        || d > r0 + r1 + eps.intersect_tol
+        || d < (r0 - r1).abs() - eps.intersect_tol
+    {
+        return vec![];
+    }
+    let a = (r0 * r0 - r1 * r1 + d * d) / (2.0 * d);
+    let h2 = r0 * r0 - a * a;
+    let u = mul(sub(c1, c0), 1.0 / d);
+    let m = add(c0, mul(u, a));
+    if h2 <= eps.intersect_tol * eps.intersect_tol {
+        return vec![m];
+    }
+    let h = h2.sqrt();
+    let perp = Vec2 {
+        x: -u.y * h,
+        y: u.x * h,
+    };
+    vec![add(m, perp), sub(m, perp)]
+}
+
+// Parameter pairs where `p` and `q` cross, clamped to [0, 1].
+fn seg_intersections(p: &Seg, q: &Seg, eps: &EpsilonPolicy) -> Vec<(f64, f64)> {
+    let pts = match (*p, *q) {
+        (Seg::Line { a: a0, b: a1 }, Seg::Line { a: b0, b: b1 }) => {
+            let r = sub(a1, a0);
+            let s = sub(b1, b0);
+            let denom = cross(r, s);
+            if denom.abs() <= eps.intersect_tol * p.length() * q.length() {
+                vec![]
+            } else {
+                vec![add(a0, mul(r, cross(sub(b0, a0), s) / denom))]
+            }
+        }
+        (Seg::Line { a, b }, Seg::Arc { c, r, .. })
+        | (Seg::Arc { c, r, .. }, Seg::Line { a, b }) => line_circle(a, b, c, r, eps),
+        (Seg::Arc { c: c0, r: r0, .. }, Seg::Arc { c: c1, r: r1, .. }) => {
+            circle_circle(c0, r0, c1, r1, eps)
+        }
+    };
+    let (tp, tq) = (p.param_tol(eps), q.param_tol(eps));
+    pts.into_iter()
+        .filter_map(|x| {
+            let s = p.param_of(x);
+            let u = q.param_of(x);
+            if s >= -tp && s <= 1.0 + tp && u >= -tq && u <= 1.0 + tq {
+                Some((s.clamp(0.0, 1.0), u.clamp(0.0, 1.0)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Origin {
+    // Offset copy of source segment `i`.
+    Offset(usize),
+    // Join closing the convex corner between two source segments.
+    Join(usize, usize),
+    // Straight bridge over a concave corner whose neighbours never cross.
+    Bridge,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    seg: Seg,
+    origin: Origin,
+}
+
+enum Corner {
+    Smooth,
+    Concave,
+    // Signed turn of the offset side, in radians.
+    Convex(f64),
+}
+
+fn corner(prev: &Seg, next: &Seg, e: Vec2, s: Vec2, dist: f64, eps: &EpsilonPolicy) -> Corner {
+    if dist2(e, s) <= eps.eq_dist * eps.eq_dist {
+        return Corner::Smooth;
+    }
+    let t0 = prev.tangent_at(1.0);
+    let t1 = next.tangent_at(0.0);
+    let cr = cross(t0, t1);
+    let dt = dot(t0, t1);
+    if cr * dist < 0.0 || (cr.abs() <= 1e-12 && dt < 0.0) {
+        Corner::Convex(-dist.signum() * cr.abs().atan2(dt))
+    } else {
+        Corner::Concave
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn push_join(
+    raw: &mut Vec<Piece>,
+    v: Vec2,
+    e: Vec2,
+    s: Vec2,
+    t0: Vec2,
+    t1: Vec2,
+    turn: f64,
+    dist: f64,
+    join: JoinStyle,
+    origin: Origin,
+) {
+    let mut line = |a: Vec2, b: Vec2| {
+        raw.push(Piece {
+            seg: Seg::Line { a, b },
+            origin,
+        })
+    };
+    match join {
+        JoinStyle::Bevel => line(e, s),
+        JoinStyle::Round => raw.push(Piece {
+            seg: Seg::Arc {
+                c: v,
+                r: dist.abs(),
+                a0: (e.y - v.y).atan2(e.x - v.x),
+                sweep: turn,
+            },
+            origin,
+        }),
+        JoinStyle::Miter { limit } => {
+            let denom = cross(t0, t1);
+            let miter = if denom.abs() <= 1e-12 {
+                None
+            } else {
+                Some(add(e, mul(t0, cross(sub(s, e), t1) / denom)))
+            };
+            match miter {
+                Some(m) if dist2(m, v).sqrt() <= limit * dist.abs() => {
+                    line(e, m);
+                    line(m, s);
+                }
+                _ => line(e, s),
+            }
+        }
+    }
+}
+
+fn try_merge(a: &Seg, b: &Seg, eps: &EpsilonPolicy) -> Option<Seg> {
+    match (*a, *b) {
+        (Seg::Line { a: p0, b: p1 }, Seg::Line { b: p2, .. }) => {
+            let chord = sub(p2, p0);
+            let len = dot(chord, chord).sqrt();
+            if len <= eps.eq_dist || dot(sub(p1, p0), sub(p2, p1)) <= 0.0 {
+                return None;
+            }
+            if cross(chord, sub(p1, p0)).abs() / len <= eps.eq_dist {
+                Some(Seg::Line { a: p0, b: p2 })
+            } else {
+                None
+            }
+        }
+        (
+            Seg::Arc {
+                c: c0,
+                r: r0,
+                a0,
+                sweep: s0,
+            },
+            Seg::Arc {
+                c: c1,
+                r: r1,
+                sweep: s1,
+                ..
+            },
+        ) => {
+            let same_circle =
+                dist2(c0, c1) <= eps.eq_dist * eps.eq_dist && (r0 - r1).abs() <= eps.eq_dist;
+            if same_circle && s0 * s1 > 0.0 && (s0 + s1).abs() <= TAU + 1e-9 {
+                Some(Seg::Arc {
+                    c: c0,
+                    r: r0,
+                    a0,
+                    sweep: s0 + s1,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn merge_runs(segs: Vec<Seg>, closed: bool, eps: &EpsilonPolicy) -> Vec<Seg> {
+    let mut out: Vec<Seg> = Vec::with_capacity(segs.len());
+    for s in segs {
+        if let Some(m) = out.last().and_then(|last| try_merge(last, &s, eps)) {
+            *out.last_mut().expect("non-empty") = m;
+        } else {
+            out.push(s);
+        }
+    }
+    if closed && out.len() > 1 {
+        if let Some(m) = try_merge(&out[out.len() - 1], &out[0], eps) {
+            out[0] = m;
+            out.pop();
+        }
+    }
+    out
 }
 
-fn line_intersection_inf(
-    a0: Vec2,
-    a1: Vec2,
-    b0: Vec2,
-    b1: Vec2,
+fn offset_segs(
+    src: &[Seg],
+    closed: bool,
+    dist: f64,
+    join: JoinStyle,
     eps: &EpsilonPolicy,
-) -> Result<Vec2> {
-    let r = sub(a1, a0);
-    let s = sub(b1, b0);
-    let denom = r.x * s.y - r.y * s.x;
-    if denom.abs() <= eps.intersect_tol {
-        return Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported));
+) -> Result<Vec<Seg>> {
+    let offs: Vec<(usize, Seg)> = src
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| s.offset(dist, eps).map(|o| (i, o)))
+        .collect();
+    if offs.is_empty() {
+        return Err(collapsed());
+    }
+    let n = offs.len();
+    let join_count = if closed { n } else { n - 1 };
+    let adjacent = |i: usize, j: usize| j == (i + 1) % src.len();
+
+    // Pass 1a: trim concave corners back to the crossing of their neighbours.
+    let mut span = vec![(0.0_f64, 1.0_f64); n];
+    let mut trimmed = vec![false; join_count];
+    for k in 0..join_count {
+        let (i, a) = offs[k];
+        let (j, b) = offs[(k + 1) % n];
+        if !adjacent(i, j)
+            || !matches!(
+                corner(&src[i], &src[j], a.end(), b.start(), dist, eps),
+                Corner::Concave
+            )
+        {
+            continue;
+        }
+        let best = seg_intersections(&a, &b, eps)
+            .into_iter()
+            .max_by(|l, r| l.0.total_cmp(&r.0).then_with(|| r.1.total_cmp(&l.1)));
+        if let Some((ta, tb)) = best {
+            span[k].1 = ta;
+            span[(k + 1) % n].0 = tb;
+            trimmed[k] = true;
+        }
+    }
+    // A segment trimmed away from both ends is swallowed by its neighbours; bridge
+    // those corners instead and leave the loop to pass 3.
+    for m in 0..n {
+        if span[m].1 - span[m].0 > offs[m].1.param_tol(eps) {
+            continue;
+        }
+        let before = if m > 0 {
+            Some(m - 1)
+        } else if closed {
+            Some(n - 1)
+        } else {
+            None
+        };
+        let after = (m < join_count).then_some(m);
+        for k in [before, after].into_iter().flatten() {
+            if trimmed[k] {
+                trimmed[k] = false;
+                span[k].1 = 1.0;
+                span[(k + 1) % n].0 = 0.0;
+            }
+        }
+    }
+
+    // Pass 1b: assemble the raw offset curve with joins and bridges.
+    let mut raw: Vec<Piece> = Vec::with_capacity(n * 2);
+    for k in 0..n {
+        let (i, a) = offs[k];
+        raw.push(Piece {
+            seg: a.sub_seg(span[k].0, span[k].1),
+            origin: Origin::Offset(i),
+        });
+        if k >= join_count || trimmed[k] {
+            continue;
+        }
+        let (j, b) = offs[(k + 1) % n];
+        let e = a.point_at(span[k].1);
+        let s = b.point_at(span[(k + 1) % n].0);
+        let bridge = Piece {
+            seg: Seg::Line { a: e, b: s },
+            origin: Origin::Bridge,
+        };
+        if !adjacent(i, j) {
+            raw.push(bridge);
+            continue;
+        }
+        match corner(&src[i], &src[j], e, s, dist, eps) {
+            Corner::Smooth => {}
+            Corner::Concave => raw.push(bridge),
+            Corner::Convex(turn) => push_join(
+                &mut raw,
+                src[i].end(),
+                e,
+                s,
+                src[i].tangent_at(1.0),
+                src[j].tangent_at(0.0),
+                turn,
+                dist,
+                join,
+                Origin::Join(i, j),
+            ),
+        }
+    }
+    raw.retain(|p| p.seg.length() > eps.eq_dist);
+    if raw.is_empty() {
+        return Err(collapsed());
     }
-    let qp = sub(b0, a0);
-    let t = (qp.x * s.y - qp.y * s.x) / denom;
-    Ok(add(a0, mul(r, t)))
+
+    // Pass 2: split the raw curve at its self-intersections.
+    let m = raw.len();
+    let bounds: Vec<(Vec2, Vec2)> = raw.iter().map(|p| p.seg.bounds()).collect();
+    let mut cuts: Vec<Vec<f64>> = vec![Vec::new(); m];
+    for p in 0..m {
+        for q in p + 1..m {
+            let (pmin, pmax) = bounds[p];
+            let (qmin, qmax) = bounds[q];
+            if pmax.x + eps.eq_dist < qmin.x
+                || qmax.x + eps.eq_dist < pmin.x
+                || pmax.y + eps.eq_dist < qmin.y
+                || qmax.y + eps.eq_dist < pmin.y
+            {
+                continue;
+            }
+            let tol_p = raw[p].seg.param_tol(eps);
+            let tol_q = raw[q].seg.param_tol(eps);
+            let next = q == p + 1;
+            let wrap = closed && p == 0 && q == m - 1;
+            for (tp, tq) in seg_intersections(&raw[p].seg, &raw[q].seg, eps) {
+                if next && tp >= 1.0 - tol_p && tq <= tol_q {
+                    continue;
+                }
+                if wrap && tp <= tol_p && tq >= 1.0 - tol_q {
+                    continue;
+                }
+                cuts[p].push(tp);
+                cuts[q].push(tq);
+            }
+        }
+    }
+    let mut pieces: Vec<Piece> = Vec::with_capacity(m);
+    for (k, p) in raw.iter().enumerate() {
+        let tol = p.seg.param_tol(eps);
+        let mut ts: Vec<f64> = cuts[k]
+            .iter()
+            .copied()
+            .filter(|t| *t > tol && *t < 1.0 - tol)
+            .collect();
+        ts.sort_by(f64::total_cmp);
+        ts.dedup_by(|a, b| (*a - *b).abs() <= tol);
+        let mut t0 = 0.0;
+        for t in ts.into_iter().chain(std::iter::once(1.0)) {
+            pieces.push(Piece {
+                seg: p.seg.sub_seg(t0, t),
+                origin: p.origin,
+            });
+            t0 = t;
+        }
+    }
+
+    // Pass 3: drop pieces that intrude on the source and stitch the rest.
+    let min_dist = dist.abs() - eps.eq_dist;
+    let keep: Vec<bool> = pieces
+        .iter()
+        .map(|p| {
+            let mid = p.seg.point_at(0.5);
+            src.iter()
+                .enumerate()
+                .filter(|(i, _)| match p.origin {
+                    Origin::Join(a, b) => *i != a && *i != b,
+                    _ => true,
+                })
+                .all(|(_, s)| s.distance_to(mid) >= min_dist)
+        })
+        .collect();
+
+    let count = pieces.len();
+    let is_offset = |k: usize| matches!(pieces[k].origin, Origin::Offset(_));
+    let first = (0..count)
+        .find(|&k| keep[k] && is_offset(k))
+        .ok_or_else(collapsed)?;
+    let near = |a: Vec2, b: Vec2| dist2(a, b) <= eps.snap_dist * eps.snap_dist;
+    let mut used = vec![false; count];
+    used[first] = true;
+    let mut chain = vec![first];
+    let mut cur = first;
+    let mut loop_closed = false;
+    loop {
+        let end = pieces[cur].seg.end();
+        let steps = if closed { count } else { count - cur - 1 };
+        let next = (1..=steps)
+            .map(|s| (cur + s) % count)
+            .find(|&k| keep[k] && (k == first || !used[k]) && near(pieces[k].seg.start(), end));
+        match next {
+            Some(k) if k == first => {
+                loop_closed = true;
+                break;
+            }
+            Some(k) => {
+                used[k] = true;
+                chain.push(k);
+                cur = k;
+            }
+            None => break,
+        }
+    }
+    if closed && !loop_closed {
+        let mut r = Reason::from_code(ReasonCode::GeomOffsetSelfIntersection);
+        r.debug
+            .insert("case".into(), serde_json::json!("loop_not_closed"));
+        return Err(r);
+    }
+    let leftovers = (0..count)
+        .filter(|&k| keep[k] && !used[k] && is_offset(k) && pieces[k].seg.length() > eps.snap_dist)
+        .count();
+    if leftovers > 0 {
+        let mut r = Reason::from_code(ReasonCode::GeomOffsetSelfIntersection);
+        r.debug.insert(
+            "case".into(),
+            serde_json::json!("split_into_multiple_loops"),
+        );
+        r.debug
+            .insert("leftover_pieces".into(), serde_json::json!(leftovers));
+        return Err(r);
+    }
+
+    let out: Vec<Seg> = chain.into_iter().map(|k| pieces[k].seg).collect();
+    Ok(merge_runs(out, closed, eps))
 }
 
-pub fn offset(g: &Geom2D, dist: f64, eps: &EpsilonPolicy) -> Result<Geom2D> {
+fn check_vec(p: &Vec2) -> Result<()> {
+    if p.x.is_finite() && p.y.is_finite() {
+        Ok(())
+    } else {
+        Err(Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }
+}
+
+fn arc_seg(c: Vec2, r: f64, start: f64, end: f64, ccw: bool, eps: &EpsilonPolicy) -> Result<Seg> {
+    check_vec(&c)?;
+    if !r.is_finite() || r <= eps.eq_dist {
+        return Err(Reason::from_code(ReasonCode::GeomCircleRadiusInvalid));
+    }
+    if !start.is_finite() || !end.is_finite() {
+        return Err(Reason::from_code(ReasonCode::GeomArcRangeInvalid));
+    }
+    let mut sweep = arc_sweep(start, end, ccw);
+    if sweep <= eps.intersect_tol {
+        sweep = TAU;
+    }
+    Ok(Seg::Arc {
+        c,
+        r,
+        a0: start,
+        sweep: if ccw { sweep } else { -sweep },
+    })
+}
+
+fn polyline_segs(pts: &[Vec2], closed: bool, eps: &EpsilonPolicy) -> Result<Vec<Seg>> {
+    let mut clean: Vec<Vec2> = Vec::with_capacity(pts.len());
+    for p in pts {
+        check_vec(p)?;
+        if clean
+            .last()
+            .map(|q| dist2(*q, *p) > eps.eq_dist * eps.eq_dist)
+            .unwrap_or(true)
+        {
+            clean.push(*p);
+        }
+    }
+    if closed && clean.len() > 1 {
+        let (first, last) = (clean[0], clean[clean.len() - 1]);
+        if dist2(first, last) <= eps.eq_dist * eps.eq_dist {
+            clean.pop();
+        }
+    }
+    if clean.len() < if closed { 3 } else { 2 } {
+        return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+    }
+    let seg_count = if closed { clean.len() } else { clean.len() - 1 };
+    Ok((0..seg_count)
+        .map(|i| Seg::Line {
+            a: clean[i],
+            b: clean[(i + 1) % clean.len()],
+        })
+        .collect())
+}
+
+// Chains made only of lines come back as a single polyline; anything with arcs
+// comes back as the ordered list of its segments.
+fn pack(segs: Vec<Seg>, closed: bool) -> Vec<Geom2D> {
+    if segs.iter().all(|s| matches!(s, Seg::Line { .. })) {
+        let mut pts: Vec<Vec2> = segs.iter().map(|s| s.start()).collect();
+        if !closed {
+            if let Some(last) = segs.last() {
+                pts.push(last.end());
+            }
+        }
+        vec![Geom2D::Polyline { pts, closed }]
+    } else {
+        segs.into_iter().map(Seg::to_geom).collect()
+    }
+}
+
+fn check_params(dist: f64, join: JoinStyle) -> Result<()> {
     if !dist.is_finite() {
         return Err(Reason::from_code(ReasonCode::GeomInvalidNumeric));
     }
+    if let JoinStyle::Miter { limit } = join {
+        if !limit.is_finite() || limit < 1.0 {
+            return Err(Reason::from_code(ReasonCode::GeomInvalidNumeric));
+        }
+    }
+    Ok(())
+}
+
+/// Offsets `g` by `dist` to the left of its direction of travel using the
+/// default miter join. Fails with `GEOM_OFFSET_NOT_SUPPORTED` only when the
+/// result cannot be expressed as a single entity.
+pub fn offset(g: &Geom2D, dist: f64, eps: &EpsilonPolicy) -> Result<Geom2D> {
+    let mut out = offset_with_join(g, dist, JoinStyle::default(), eps)?;
+    if out.len() == 1 {
+        return Ok(out.remove(0));
+    }
+    let mut r = Reason::from_code(ReasonCode::GeomOffsetNotSupported);
+    r.debug
+        .insert("result_segments".into(), serde_json::json!(out.len()));
+    Err(r)
+}
+
+/// Offsets a single entity. Lines, circles and arcs map to one entity of the
+/// same kind; polylines map to one polyline unless round joins introduce arcs,
+/// in which case the connected line/arc chain is returned in order. Ellipses,
+/// elliptical arcs and Beziers map to an approximating polyline.
+pub fn offset_with_join(
+    g: &Geom2D,
+    dist: f64,
+    join: JoinStyle,
+    eps: &EpsilonPolicy,
+) -> Result<Vec<Geom2D>> {
+    check_params(dist, join)?;
     match g {
         Geom2D::Line { a, b } => {
-            let n = line_normal(*a, *b, eps)?;
-            let d = mul(n, dist);
-            Ok(Geom2D::Line {
-                a: add(*a, d),
-                b: add(*b, d),
-            })
+            check_vec(a)?;
+            check_vec(b)?;
+            if dist2(*a, *b) <= eps.eq_dist * eps.eq_dist {
+                return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+            }
+            let seg = Seg::Line { a: *a, b: *b };
+            Ok(vec![seg.offset(dist, eps).ok_or_else(collapsed)?.to_geom()])
         }
-        Geom2D::Polyline { pts, closed } => {
-            if *closed {
-                return Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported));
+        Geom2D::Circle { c, r } => {
+            check_vec(c)?;
+            if !r.is_finite() || *r <= eps.eq_dist {
+                return Err(Reason::from_code(ReasonCode::GeomCircleRadiusInvalid));
             }
-            if pts.len() < 2 {
-                return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+            let r2 = r - dist;
+            if r2 <= eps.eq_dist {
+                return Err(collapsed());
             }
-            let seg_count = pts.len() - 1;
-            let mut segs = Vec::with_capacity(seg_count);
-            for i in 0..seg_count {
-                let a = pts[i];
-                let b = pts[i + 1];
-                let n = line_normal(a, b, eps)?;
-                let d = mul(n, dist);
-                segs.push((add(a, d), add(b, d)));
-            }
-
-            let mut out = Vec::with_capacity(pts.len());
-            out.push(segs[0].0);
-            for i in 1..seg_count {
-                let (a0, a1) = segs[i - 1];
-                let (b0, b1) = segs[i];
-                let j = line_intersection_inf(a0, a1, b0, b1, eps)?;
-                out.push(j);
-            }
-            out.push(segs[seg_count - 1].1);
-
-            for i in 0..out.len().saturating_sub(1) {
-                if dist2(out[i], out[i + 1]) <= eps.eq_dist * eps.eq_dist {
-                    return Err(Reason::from_code(ReasonCode::GeomOffsetSelfIntersection));
-                }
+            Ok(vec![Geom2D::Circle { c: *c, r: r2 }])
+        }
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            let seg = arc_seg(*c, *r, *start_angle, *end_angle, *ccw, eps)?;
+            match seg.offset(dist, eps).ok_or_else(collapsed)? {
+                Seg::Arc { c, r, .. } => Ok(vec![Geom2D::Arc {
+                    c,
+                    r,
+                    start_angle: *start_angle,
+                    end_angle: *end_angle,
+                    ccw: *ccw,
+                }]),
+                other => Ok(vec![other.to_geom()]),
+            }
+        }
+        Geom2D::Polyline { pts, closed } => {
+            let src = polyline_segs(pts, *closed, eps)?;
+            if dist == 0.0 {
+                return Ok(pack(src, *closed));
             }
+            Ok(pack(offset_segs(&src, *closed, dist, join, eps)?, *closed))
+        }
+        Geom2D::Ellipse { .. } | Geom2D::EllipticalArc { .. } | Geom2D::CubicBezier { .. } => {
+            Ok(vec![curve::offset_curve(g, dist, eps)?])
+        }
+    }
+}
 
-            Ok(Geom2D::Polyline {
-                pts: out,
-                closed: false,
-            })
+/// Offsets a connected chain of lines, arcs, open polylines and (flattened)
+/// elliptical arcs and Beziers, each starting
+/// where the previous one ends (within `snap_dist`). Closed chains must also
+/// end where they start. The result uses the same packing as
+/// [`offset_with_join`].
+pub fn offset_chain(
+    chain: &[Geom2D],
+    closed: bool,
+    dist: f64,
+    join: JoinStyle,
+    eps: &EpsilonPolicy,
+) -> Result<Vec<Geom2D>> {
+    check_params(dist, join)?;
+    let mut src: Vec<Seg> = Vec::with_capacity(chain.len());
+    for (index, g) in chain.iter().enumerate() {
+        let segs = match g {
+            Geom2D::Line { a, b } => polyline_segs(&[*a, *b], false, eps)?,
+            Geom2D::Arc {
+                c,
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+            } => vec![arc_seg(*c, *r, *start_angle, *end_angle, *ccw, eps)?],
+            Geom2D::Polyline { pts, closed: false } => polyline_segs(pts, false, eps)?,
+            Geom2D::EllipticalArc { .. } | Geom2D::CubicBezier { .. } => {
+                polyline_segs(&curve::flatten_open(g, eps)?, false, eps)?
+            }
+            _ => {
+                let mut r = Reason::from_code(ReasonCode::GeomOffsetNotSupported);
+                r.debug
+                    .insert("case".into(), serde_json::json!("closed_member_in_chain"));
+                r.debug.insert("index".into(), serde_json::json!(index));
+                return Err(r);
+            }
+        };
+        if let (Some(prev), Some(next)) = (src.last(), segs.first()) {
+            if dist2(prev.end(), next.start()) > eps.snap_dist * eps.snap_dist {
+                let mut r = Reason::from_code(ReasonCode::GeomOffsetNotSupported);
+                r.debug
+                    .insert("case".into(), serde_json::json!("chain_not_connected"));
+                r.debug.insert("index".into(), serde_json::json!(index));
+                return Err(r);
+            }
         }
-        _ => Err(Reason::from_code(ReasonCode::GeomOffsetNotSupported)),
+        src.extend(segs);
+    }
+    if src.is_empty() {
+        return Err(Reason::from_code(ReasonCode::GeomDegenerate));
+    }
+    if closed && dist2(src[src.len() - 1].end(), src[0].start()) > eps.snap_dist * eps.snap_dist {
+        let mut r = Reason::from_code(ReasonCode::GeomOffsetNotSupported);
+        r.debug
+            .insert("case".into(), serde_json::json!("chain_not_closed"));
+        return Err(r);
+    }
+    if dist == 0.0 {
+        return Ok(pack(src, closed));
     }
+    Ok(pack(offset_segs(&src, closed, dist, join, eps)?, closed))
 }
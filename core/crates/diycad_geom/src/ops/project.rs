@@ -1,4 +1,5 @@
 use crate::{
+    ops::curve,
     util::{dist2, dot, lerp, sub},
     EpsilonPolicy, Geom2D, ProjectHit, Vec2,
 };
@@ -82,7 +83,7 @@ fn project_line(a: Vec2, b: Vec2, p: Vec2) -> Result<(Vec2, f64, f64)> {
     Ok((q, t, dist2(p, q).sqrt()))
 }
 
-pub fn project_point(g: &Geom2D, p: Vec2, _eps: &EpsilonPolicy) -> Result<ProjectHit> {
+pub fn project_point(g: &Geom2D, p: Vec2, eps: &EpsilonPolicy) -> Result<ProjectHit> {
     match g {
         Geom2D::Line { a, b } => {
             let (q, t, d) = project_line(*a, *b, p)?;
@@ -203,5 +204,10 @@ pub fn project_point(g: &Geom2D, p: Vec2, _eps: &EpsilonPolicy) -> Result<Projec
             }
             best.ok_or_else(|| Reason::from_code(ReasonCode::GeomDegenerate))
         }
+        // Curved variants: `t_global` is the curve parameter (Bezier `u`, or
+        // the fraction of the parametric sweep), not arc length.
+        Geom2D::Ellipse { .. } | Geom2D::EllipticalArc { .. } | Geom2D::CubicBezier { .. } => {
+            curve::project_curve(g, p, eps)
+        }
     }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    ops::project::project_point, util::lerp, EpsilonPolicy, Geom2D, SplitBy, SplitResult, Vec2,
+    ops::curve, ops::project::project_point, util::lerp, EpsilonPolicy, Geom2D, SplitBy,
+    SplitResult, Vec2,
 };
 use craftcad_serialize::{Reason, ReasonCode, Result};
 
@@ -167,6 +168,9 @@ pub fn split_at(g: &Geom2D, by: SplitBy, eps: &EpsilonPolicy) -> Result<SplitRes
                 split_point: split,
             })
         }
+        Geom2D::Ellipse { .. } | Geom2D::EllipticalArc { .. } | Geom2D::CubicBezier { .. } => {
+            curve::split_curve(g, by, eps)
+        }
         _ => Err(Reason::from_code(ReasonCode::GeomSplitPointNotOnGeom)),
     }
 }
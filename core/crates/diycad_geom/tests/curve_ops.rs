@@ -0,0 +1,197 @@
+use diycad_geom::{
+    bbox, flatten, intersect, mirror_geom, offset, project_point, split_at, EpsilonPolicy, Geom2D,
+    SplitBy, Vec2,
+};
+use std::f64::consts::PI;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn near(a: f64, b: f64, tol: f64) -> bool {
+    (a - b).abs() <= tol
+}
+
+fn ellipse() -> Geom2D {
+    Geom2D::Ellipse {
+        c: v(0.0, 0.0),
+        rx: 4.0,
+        ry: 2.0,
+        rot: 0.0,
+    }
+}
+
+fn bezier() -> Geom2D {
+    Geom2D::CubicBezier {
+        a: v(0.0, 0.0),
+        c1: v(0.0, 4.0),
+        c2: v(4.0, 4.0),
+        b: v(4.0, 0.0),
+    }
+}
+
+#[test]
+fn ellipse_line_intersections_are_exact() {
+    let eps = EpsilonPolicy::default();
+    let line = Geom2D::Line {
+        a: v(-10.0, 1.0),
+        b: v(10.0, 1.0),
+    };
+    let set = intersect(&ellipse(), &line, &eps).unwrap();
+    assert_eq!(set.points.len(), 2);
+    // x^2/16 + 1/4 = 1 -> x = +-sqrt(12)
+    for p in &set.points {
+        assert!(near(p.x.abs(), 12f64.sqrt(), 1e-9), "{p:?}");
+        assert!(near(p.y, 1.0, 1e-9));
+    }
+
+    let miss = Geom2D::Line {
+        a: v(-10.0, 3.0),
+        b: v(10.0, 3.0),
+    };
+    let err = intersect(&ellipse(), &miss, &eps).unwrap_err();
+    assert_eq!(err.code, "GEOM_NO_INTERSECTION");
+}
+
+#[test]
+fn bezier_meets_circle_and_bezier() {
+    let eps = EpsilonPolicy::default();
+    let circle = Geom2D::Circle {
+        c: v(2.0, 0.0),
+        r: 2.5,
+    };
+    let set = intersect(&bezier(), &circle, &eps).unwrap();
+    assert_eq!(set.points.len(), 2);
+    for p in &set.points {
+        let d = ((p.x - 2.0).powi(2) + p.y.powi(2)).sqrt();
+        assert!(near(d, 2.5, 1e-9));
+    }
+
+    let flipped = Geom2D::CubicBezier {
+        a: v(0.0, 3.0),
+        c1: v(0.0, -1.0),
+        c2: v(4.0, -1.0),
+        b: v(4.0, 3.0),
+    };
+    let set = intersect(&bezier(), &flipped, &eps).unwrap();
+    assert_eq!(set.points.len(), 2);
+    for p in &set.points {
+        assert!(near(p.y, 1.5, 1e-9), "{p:?}");
+    }
+}
+
+#[test]
+fn project_point_on_ellipse_and_bezier() {
+    let eps = EpsilonPolicy::default();
+    let hit = project_point(&ellipse(), v(0.0, 5.0), &eps).unwrap();
+    assert!(near(hit.point.x, 0.0, 1e-7) && near(hit.point.y, 2.0, 1e-9));
+    assert!(near(hit.t_global, 0.25, 1e-7));
+    assert!(near(hit.dist, 3.0, 1e-9));
+
+    // Near u = 0 the search wraps instead of stopping at the seam.
+    let hit = project_point(&ellipse(), v(5.0, -0.01), &eps).unwrap();
+    assert!(hit.t_global > 0.99 || hit.t_global < 1e-9);
+    assert!(near(hit.point.x, 4.0, 1e-4));
+
+    let hit = project_point(&bezier(), v(2.0, 10.0), &eps).unwrap();
+    assert!(near(hit.t_global, 0.5, 1e-7));
+    assert!(near(hit.point.y, 3.0, 1e-9));
+}
+
+#[test]
+fn split_bezier_and_elliptical_arc() {
+    let eps = EpsilonPolicy::default();
+    let r = split_at(&bezier(), SplitBy::T(0.5), &eps).unwrap();
+    assert!(near(r.split_point.x, 2.0, 1e-12) && near(r.split_point.y, 3.0, 1e-12));
+    match (&r.left, &r.right) {
+        (Geom2D::CubicBezier { b: lb, .. }, Geom2D::CubicBezier { a: ra, b: rb, .. }) => {
+            assert_eq!(lb, ra);
+            assert_eq!(*rb, v(4.0, 0.0));
+        }
+        other => panic!("expected beziers, got {other:?}"),
+    }
+
+    let arc = Geom2D::EllipticalArc {
+        c: v(0.0, 0.0),
+        rx: 4.0,
+        ry: 2.0,
+        rot: 0.0,
+        start_angle: 0.0,
+        end_angle: PI,
+        ccw: true,
+    };
+    let r = split_at(&arc, SplitBy::Point(v(0.0, 2.0)), &eps).unwrap();
+    match r.left {
+        Geom2D::EllipticalArc { end_angle, .. } => assert!(near(end_angle, PI / 2.0, 1e-7)),
+        other => panic!("expected elliptical arc, got {other:?}"),
+    }
+
+    let err = split_at(&ellipse(), SplitBy::T(0.5), &eps).unwrap_err();
+    assert_eq!(err.code, "GEOM_SPLIT_POINT_NOT_ON_GEOM");
+}
+
+#[test]
+fn offset_ellipse_is_polyline_and_collapses_past_curvature() {
+    let eps = EpsilonPolicy::default();
+    match offset(&ellipse(), -1.0, &eps).unwrap() {
+        Geom2D::Polyline { pts, closed } => {
+            assert!(closed);
+            assert!(pts.len() > 16);
+            assert!(near(pts[0].x, 5.0, 1e-9) && near(pts[0].y, 0.0, 1e-9));
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+    // Smallest radius of curvature is ry^2 / rx = 1 at the ends of the major axis.
+    let err = offset(&ellipse(), 1.0, &eps).unwrap_err();
+    assert_eq!(err.code, "GEOM_OFFSET_SELF_INTERSECTION");
+    assert!(offset(&ellipse(), 0.9, &eps).is_ok());
+}
+
+#[test]
+fn bbox_covers_curve_not_control_points() {
+    let eps = EpsilonPolicy::default();
+    let b = bbox(&bezier(), &eps).unwrap();
+    assert!(near(b.max.y, 3.0, 1e-12));
+    assert!(near(b.min.x, 0.0, 1e-12) && near(b.max.x, 4.0, 1e-12));
+
+    let rotated = Geom2D::Ellipse {
+        c: v(1.0, 1.0),
+        rx: 4.0,
+        ry: 2.0,
+        rot: PI / 2.0,
+    };
+    let b = bbox(&rotated, &eps).unwrap();
+    assert!(near(b.min.x, -1.0, 1e-9) && near(b.max.x, 3.0, 1e-9));
+    assert!(near(b.min.y, -3.0, 1e-9) && near(b.max.y, 5.0, 1e-9));
+}
+
+#[test]
+fn flatten_stays_within_tolerance_and_mirror_keeps_shape() {
+    let eps = EpsilonPolicy::default();
+    match flatten(&ellipse(), 1e-3, &eps).unwrap() {
+        Geom2D::Polyline { pts, closed } => {
+            assert!(closed);
+            for w in pts.windows(2) {
+                let m = v((w[0].x + w[1].x) / 2.0, (w[0].y + w[1].y) / 2.0);
+                let hit = project_point(&ellipse(), m, &eps).unwrap();
+                assert!(hit.dist <= 1e-3 + 1e-9);
+            }
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+
+    let tilted = Geom2D::Ellipse {
+        c: v(3.0, 1.0),
+        rx: 4.0,
+        ry: 2.0,
+        rot: 0.4,
+    };
+    let m = mirror_geom(&tilted, v(0.0, 0.0), v(1.0, 1.0)).unwrap();
+    // Every mirrored sample lies on the mirrored ellipse.
+    if let Geom2D::Polyline { pts, .. } = flatten(&tilted, 1e-2, &eps).unwrap() {
+        for p in pts {
+            let hit = project_point(&m, v(p.y, p.x), &eps).unwrap();
+            assert!(hit.dist < 1e-7, "{hit:?}");
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use diycad_geom::{extend_to_boundary, EpsilonPolicy, Geom2D, Vec2};
+use std::f64::consts::PI;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn line(ax: f64, ay: f64, bx: f64, by: f64) -> Geom2D {
+    Geom2D::Line {
+        a: v(ax, ay),
+        b: v(bx, by),
+    }
+}
+
+fn assert_v(p: Vec2, x: f64, y: f64) {
+    assert!(
+        (p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9,
+        "{p:?} vs ({x}, {y})"
+    );
+}
+
+#[test]
+fn line_extends_picked_end_to_nearest_boundary() {
+    let eps = EpsilonPolicy::default();
+    let target = line(0.0, 0.0, 5.0, 0.0);
+    let walls = [line(8.0, -5.0, 8.0, 5.0), line(12.0, -5.0, 12.0, 5.0)];
+
+    match extend_to_boundary(&target, &walls, v(4.0, 0.0), &eps, None).unwrap() {
+        Geom2D::Line { a, b } => {
+            assert_v(a, 0.0, 0.0);
+            assert_v(b, 8.0, 0.0);
+        }
+        other => panic!("expected line, got {other:?}"),
+    }
+    match extend_to_boundary(&target, &walls, v(4.0, 0.0), &eps, Some(1)).unwrap() {
+        Geom2D::Line { b, .. } => assert_v(b, 12.0, 0.0),
+        other => panic!("expected line, got {other:?}"),
+    }
+
+    // Boundaries behind the picked end are ignored.
+    let err = extend_to_boundary(&target, &walls, v(1.0, 0.0), &eps, None).unwrap_err();
+    assert_eq!(err.code, "GEOM_TRIM_NO_INTERSECTION");
+}
+
+#[test]
+fn out_of_range_candidate_lists_choices() {
+    let eps = EpsilonPolicy::default();
+    let target = line(0.0, 0.0, 5.0, 0.0);
+    let ring = [Geom2D::Circle {
+        c: v(10.0, 0.0),
+        r: 2.0,
+    }];
+    let err = extend_to_boundary(&target, &ring, v(5.0, 0.0), &eps, Some(2)).unwrap_err();
+    assert_eq!(err.code, "EDIT_TRIM_AMBIGUOUS_CANDIDATE");
+    let candidates = err.debug["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[0]["x"], serde_json::json!(8.0));
+    assert_eq!(err.debug["invalid_candidate_index"], serde_json::json!(2));
+}
+
+#[test]
+fn equally_near_hits_need_a_candidate() {
+    let eps = EpsilonPolicy::default();
+    let target = line(0.0, 0.0, 5.0, 0.0);
+    // Both walls cross the path at (8, 0).
+    let walls = [line(8.0, -5.0, 8.0, 5.0), line(6.0, -2.0, 10.0, 2.0)];
+    let err = extend_to_boundary(&target, &walls, v(4.0, 0.0), &eps, None).unwrap_err();
+    assert_eq!(err.code, "EDIT_TRIM_AMBIGUOUS_CANDIDATE");
+    assert_eq!(err.debug["candidates"].as_array().unwrap().len(), 2);
+    assert!(err.debug.get("invalid_candidate_index").is_none());
+    match extend_to_boundary(&target, &walls, v(4.0, 0.0), &eps, Some(1)).unwrap() {
+        Geom2D::Line { b, .. } => assert_v(b, 8.0, 0.0),
+        other => panic!("expected line, got {other:?}"),
+    }
+}
+
+#[test]
+fn arc_extends_along_its_circle() {
+    let eps = EpsilonPolicy::default();
+    let arc = Geom2D::Arc {
+        c: v(0.0, 0.0),
+        r: 5.0,
+        start_angle: 0.0,
+        end_angle: PI / 4.0,
+        ccw: true,
+    };
+    // The y axis cuts the circle at 90 degrees ahead of the end.
+    let wall = [line(0.0, 0.0, 0.0, 10.0)];
+    match extend_to_boundary(&arc, &wall, v(3.0, 4.0), &eps, None).unwrap() {
+        Geom2D::Arc {
+            start_angle,
+            end_angle,
+            ccw,
+            ..
+        } => {
+            assert!(ccw);
+            assert!(start_angle.abs() < 1e-9);
+            assert!((end_angle - PI / 2.0).abs() < 1e-9);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+
+    // Growing the start runs clockwise for a ccw arc.
+    let floor = [line(-10.0, -3.0, 10.0, -3.0)];
+    match extend_to_boundary(&arc, &floor, v(5.0, 0.0), &eps, None).unwrap() {
+        Geom2D::Arc {
+            start_angle,
+            end_angle,
+            ..
+        } => {
+            assert!((start_angle - (-3.0f64 / 5.0).asin()).abs() < 1e-9);
+            assert!((end_angle - PI / 4.0).abs() < 1e-9);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn arc_stops_at_boundary_arc_within_its_range() {
+    let eps = EpsilonPolicy::default();
+    let arc = Geom2D::Arc {
+        c: v(0.0, 0.0),
+        r: 5.0,
+        start_angle: 0.0,
+        end_angle: PI / 4.0,
+        ccw: true,
+    };
+    // Circle of radius 5 around (-5,5) meets ours at (0,5) and (-5,0); the
+    // boundary arc stops short of (-5,0), so (0,5) is the only hit.
+    let boundary = [Geom2D::Arc {
+        c: v(-5.0, 5.0),
+        r: 5.0,
+        start_angle: -PI / 4.0,
+        end_angle: PI / 2.0,
+        ccw: true,
+    }];
+    match extend_to_boundary(&arc, &boundary, v(3.5, 3.5), &eps, None).unwrap() {
+        Geom2D::Arc { end_angle, .. } => assert!((end_angle - PI / 2.0).abs() < 1e-9),
+        other => panic!("expected arc, got {other:?}"),
+    }
+    let err = extend_to_boundary(&arc, &boundary, v(3.5, 3.5), &eps, Some(1)).unwrap_err();
+    assert_eq!(err.code, "EDIT_TRIM_AMBIGUOUS_CANDIDATE");
+}
+
+#[test]
+fn open_polyline_extends_first_or_last_segment() {
+    let eps = EpsilonPolicy::default();
+    let target = Geom2D::Polyline {
+        pts: vec![v(2.0, 2.0), v(5.0, 2.0), v(5.0, 5.0)],
+        closed: false,
+    };
+    let frame = [Geom2D::Polyline {
+        pts: vec![v(0.0, 0.0), v(10.0, 0.0), v(10.0, 10.0), v(0.0, 10.0)],
+        closed: true,
+    }];
+    match extend_to_boundary(&target, &frame, v(2.5, 2.0), &eps, None).unwrap() {
+        Geom2D::Polyline { pts, closed } => {
+            assert!(!closed);
+            assert_v(pts[0], 0.0, 2.0);
+            assert_v(pts[2], 5.0, 5.0);
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+    match extend_to_boundary(&target, &frame, v(5.0, 4.0), &eps, None).unwrap() {
+        Geom2D::Polyline { pts, .. } => {
+            assert_v(pts[0], 2.0, 2.0);
+            assert_v(pts[2], 5.0, 10.0);
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+
+    let closed = Geom2D::Polyline {
+        pts: vec![v(2.0, 2.0), v(5.0, 2.0), v(5.0, 5.0)],
+        closed: true,
+    };
+    let err = extend_to_boundary(&closed, &frame, v(2.0, 2.0), &eps, None).unwrap_err();
+    assert_eq!(err.code, "GEOM_OFFSET_NOT_SUPPORTED");
+}
+
+#[test]
+fn colinear_boundary_is_skipped() {
+    let eps = EpsilonPolicy::default();
+    let target = line(0.0, 0.0, 5.0, 0.0);
+    let walls = [line(6.0, 0.0, 20.0, 0.0), line(9.0, -1.0, 9.0, 1.0)];
+    match extend_to_boundary(&target, &walls, v(5.0, 0.0), &eps, None).unwrap() {
+        Geom2D::Line { b, .. } => assert_v(b, 9.0, 0.0),
+        other => panic!("expected line, got {other:?}"),
+    }
+}
@@ -0,0 +1,199 @@
+use diycad_geom::{
+    chamfer_curves, chamfer_polyline, fillet_curves, fillet_lines, fillet_polyline, EpsilonPolicy,
+    Geom2D, Vec2,
+};
+use std::f64::consts::PI;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn near(a: Vec2, b: Vec2) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+fn arc_sweep(g: &Geom2D) -> f64 {
+    match g {
+        Geom2D::Arc {
+            start_angle,
+            end_angle,
+            ccw,
+            ..
+        } => {
+            let d = if *ccw {
+                end_angle - start_angle
+            } else {
+                start_angle - end_angle
+            };
+            d.rem_euclid(2.0 * PI)
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn fillet_lines_takes_the_short_arc() {
+    let (_, _, arc) =
+        fillet_lines(v(10.0, 0.0), v(0.0, 0.0), v(0.0, 10.0), v(0.0, 0.0), 2.0).unwrap();
+    assert!((arc_sweep(&arc) - PI / 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn fillet_line_and_arc() {
+    let eps = EpsilonPolicy::default();
+    // Horizontal line ending where a quarter arc (centre (10,10), r=10) starts.
+    let line = Geom2D::Line {
+        a: v(0.0, 0.0),
+        b: v(10.0, 0.0),
+    };
+    let arc = Geom2D::Arc {
+        c: v(20.0, 0.0),
+        r: 10.0,
+        start_angle: PI,
+        end_angle: PI / 2.0,
+        ccw: false,
+    };
+    let (l, a, f) = fillet_curves(&line, &arc, 2.0, &eps).unwrap();
+    match (&l, &f) {
+        (Geom2D::Line { a: la, b: lb }, Geom2D::Arc { c, r, .. }) => {
+            assert!(near(*la, v(0.0, 0.0)));
+            // Tangent point on the line sits right under the fillet centre.
+            assert!((lb.x - c.x).abs() < 1e-9 && lb.y.abs() < 1e-9);
+            assert!((r - 2.0).abs() < 1e-12);
+            // The corner opens away from the arc centre: 10 + 2.
+            assert!(((c.x - 20.0).hypot(c.y) - 12.0).abs() < 1e-9);
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    match a {
+        Geom2D::Arc {
+            end_angle, ccw, r, ..
+        } => {
+            assert!(!ccw);
+            assert!((r - 10.0).abs() < 1e-12);
+            assert!((end_angle - PI / 2.0).abs() < 1e-12);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+    assert!(arc_sweep(&f) < PI);
+}
+
+#[test]
+fn fillet_and_chamfer_two_arcs() {
+    let eps = EpsilonPolicy::default();
+    // Two arcs meeting square at (0,0); the corner opens to the upper right,
+    // outside the first arc and inside the second.
+    let a1 = Geom2D::Arc {
+        c: v(-5.0, 0.0),
+        r: 5.0,
+        start_angle: PI / 2.0,
+        end_angle: 0.0,
+        ccw: false,
+    };
+    let a2 = Geom2D::Arc {
+        c: v(0.0, 5.0),
+        r: 5.0,
+        start_angle: -PI / 2.0,
+        end_angle: 0.0,
+        ccw: true,
+    };
+    let (g1, g2, f) = fillet_curves(&a1, &a2, 1.0, &eps).unwrap();
+    for g in [&g1, &g2] {
+        assert!(matches!(g, Geom2D::Arc { r, .. } if (r - 5.0).abs() < 1e-12));
+    }
+    match f {
+        Geom2D::Arc { c, r, .. } => {
+            assert!((r - 1.0).abs() < 1e-12);
+            assert!(((c.x + 5.0).hypot(c.y) - 6.0).abs() < 1e-9);
+            assert!((c.x.hypot(c.y - 5.0) - 4.0).abs() < 1e-9);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+    assert!(arc_sweep(&f) < PI);
+
+    let (_, _, ch) = chamfer_curves(&a1, &a2, 1.0, &eps).unwrap();
+    match ch {
+        Geom2D::Line { a, b } => {
+            assert!(((a.x + 5.0).hypot(a.y) - 5.0).abs() < 1e-9 && a.y > 0.0);
+            assert!((b.x.hypot(b.y - 5.0) - 5.0).abs() < 1e-9 && b.x > 0.0);
+        }
+        other => panic!("expected line, got {other:?}"),
+    }
+    let err = fillet_curves(&a1, &a2, 50.0, &eps).unwrap_err();
+    assert_eq!(err.code, "EDIT_FILLET_RADIUS_TOO_LARGE");
+}
+
+#[test]
+fn fillet_curves_rejects_circles() {
+    let eps = EpsilonPolicy::default();
+    let line = Geom2D::Line {
+        a: v(0.0, 0.0),
+        b: v(10.0, 0.0),
+    };
+    let circle = Geom2D::Circle {
+        c: v(0.0, 5.0),
+        r: 5.0,
+    };
+    let err = fillet_curves(&line, &circle, 1.0, &eps).unwrap_err();
+    assert_eq!(err.code, "EDIT_FILLET_UNSUPPORTED_GEOMETRY");
+}
+
+fn board() -> Geom2D {
+    Geom2D::Polyline {
+        pts: vec![v(0.0, 0.0), v(100.0, 0.0), v(100.0, 50.0), v(0.0, 50.0)],
+        closed: true,
+    }
+}
+
+fn pts(g: &Geom2D) -> Vec<Vec2> {
+    match g {
+        Geom2D::Polyline { pts, .. } => pts.clone(),
+        other => panic!("expected polyline, got {other:?}"),
+    }
+}
+
+#[test]
+fn fillet_all_corners_of_board() {
+    let eps = EpsilonPolicy::default();
+    let out = pts(&fillet_polyline(&board(), None, 5.0, &eps).unwrap());
+    assert!(out.len() > 8);
+    for p in &out {
+        assert!(p.x >= -1e-9 && p.x <= 100.0 + 1e-9 && p.y >= -1e-9 && p.y <= 50.0 + 1e-9);
+    }
+    assert!(out.iter().any(|p| near(*p, v(5.0, 0.0))));
+    assert!(out.iter().any(|p| near(*p, v(95.0, 0.0))));
+    assert!(out.iter().any(|p| near(*p, v(0.0, 45.0))));
+    assert!(!out.iter().any(|p| near(*p, v(0.0, 0.0))));
+    // Every flattened fillet point stays within snap_dist of its true arc.
+    for p in out.iter().filter(|p| p.x < 5.0 && p.y < 5.0) {
+        let d = (p.x - 5.0).hypot(p.y - 5.0);
+        assert!((d - 5.0).abs() <= eps.snap_dist);
+    }
+
+    let err = fillet_polyline(&board(), None, 30.0, &eps).unwrap_err();
+    assert_eq!(err.code, "EDIT_FILLET_RADIUS_TOO_LARGE");
+}
+
+#[test]
+fn fillet_and_chamfer_single_vertex() {
+    let eps = EpsilonPolicy::default();
+    let one = pts(&fillet_polyline(&board(), Some(2), 5.0, &eps).unwrap());
+    assert!(one.iter().any(|p| near(*p, v(0.0, 0.0))));
+    assert!(!one.iter().any(|p| near(*p, v(100.0, 50.0))));
+    assert!(one.iter().any(|p| near(*p, v(100.0, 45.0))));
+    assert!(one.iter().any(|p| near(*p, v(95.0, 50.0))));
+
+    let ch = pts(&chamfer_polyline(&board(), Some(0), 3.0, &eps).unwrap());
+    assert_eq!(ch.len(), 5);
+    assert!(near(ch[0], v(0.0, 3.0)));
+    assert!(near(ch[1], v(3.0, 0.0)));
+
+    let open = Geom2D::Polyline {
+        pts: vec![v(0.0, 0.0), v(10.0, 0.0), v(10.0, 10.0)],
+        closed: false,
+    };
+    let err = chamfer_polyline(&open, Some(0), 1.0, &eps).unwrap_err();
+    assert_eq!(err.code, "EDIT_CANDIDATE_NOT_FOUND");
+    let all = pts(&chamfer_polyline(&open, None, 1.0, &eps).unwrap());
+    assert_eq!(all.len(), 4);
+}
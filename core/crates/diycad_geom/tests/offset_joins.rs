@@ -0,0 +1,279 @@
+use diycad_geom::{offset, offset_chain, offset_with_join, EpsilonPolicy, Geom2D, JoinStyle, Vec2};
+use std::f64::consts::PI;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn square() -> Geom2D {
+    Geom2D::Polyline {
+        pts: vec![v(0.0, 0.0), v(10.0, 0.0), v(10.0, 10.0), v(0.0, 10.0)],
+        closed: true,
+    }
+}
+
+fn polyline_pts(out: &[Geom2D]) -> Vec<Vec2> {
+    assert_eq!(out.len(), 1, "expected single polyline: {out:?}");
+    match &out[0] {
+        Geom2D::Polyline { pts, closed } => {
+            assert!(*closed);
+            pts.clone()
+        }
+        other => panic!("expected polyline, got {other:?}"),
+    }
+}
+
+fn assert_pts(actual: &[Vec2], expected: &[(f64, f64)]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?}");
+    for (p, (x, y)) in actual.iter().zip(expected) {
+        assert!(
+            (p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9,
+            "{actual:?} vs {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn closed_square_outward_miter() {
+    let out = offset_with_join(
+        &square(),
+        -1.0,
+        JoinStyle::default(),
+        &EpsilonPolicy::default(),
+    )
+    .unwrap();
+    assert_pts(
+        &polyline_pts(&out),
+        &[(-1.0, -1.0), (11.0, -1.0), (11.0, 11.0), (-1.0, 11.0)],
+    );
+}
+
+#[test]
+fn closed_square_outward_round_and_bevel() {
+    let eps = EpsilonPolicy::default();
+    let round = offset_with_join(&square(), -1.0, JoinStyle::Round, &eps).unwrap();
+    assert_eq!(round.len(), 8);
+    let arcs: Vec<_> = round
+        .iter()
+        .filter_map(|g| match g {
+            Geom2D::Arc {
+                r,
+                start_angle,
+                end_angle,
+                ccw,
+                ..
+            } => Some((*r, *start_angle, *end_angle, *ccw)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(arcs.len(), 4);
+    for (r, s, e, ccw) in arcs {
+        assert!((r - 1.0).abs() < 1e-9);
+        assert!(ccw);
+        let sweep = (e - s).rem_euclid(2.0 * PI);
+        assert!((sweep - PI / 2.0).abs() < 1e-9);
+    }
+
+    let bevel = offset_with_join(&square(), -1.0, JoinStyle::Bevel, &eps).unwrap();
+    assert_eq!(polyline_pts(&bevel).len(), 8);
+}
+
+#[test]
+fn closed_square_inward() {
+    let out =
+        offset_with_join(&square(), 1.0, JoinStyle::Round, &EpsilonPolicy::default()).unwrap();
+    assert_pts(
+        &polyline_pts(&out),
+        &[(1.0, 1.0), (9.0, 1.0), (9.0, 9.0), (1.0, 9.0)],
+    );
+}
+
+#[test]
+fn miter_limit_falls_back_to_bevel() {
+    // A sharp spike: the miter point at the tip is far from the source vertex.
+    let spike = Geom2D::Polyline {
+        pts: vec![v(0.0, 0.0), v(10.0, 0.0), v(0.0, 1.0)],
+        closed: false,
+    };
+    let eps = EpsilonPolicy::default();
+    let loose = offset_with_join(&spike, -0.5, JoinStyle::Miter { limit: 100.0 }, &eps).unwrap();
+    let tight = offset_with_join(&spike, -0.5, JoinStyle::Miter { limit: 2.0 }, &eps).unwrap();
+    let count = |g: &[Geom2D]| match &g[0] {
+        Geom2D::Polyline { pts, .. } => pts.len(),
+        _ => panic!("expected polyline"),
+    };
+    assert_eq!(count(&loose), 3);
+    assert_eq!(count(&tight), 4);
+    assert!(offset_with_join(&spike, -0.5, JoinStyle::Miter { limit: 0.5 }, &eps).is_err());
+}
+
+#[test]
+fn concave_corners_are_trimmed() {
+    let step = Geom2D::Polyline {
+        pts: vec![
+            v(0.0, 0.0),
+            v(10.0, 0.0),
+            v(10.0, 10.0),
+            v(5.0, 10.0),
+            v(5.0, 9.5),
+            v(0.0, 9.5),
+        ],
+        closed: true,
+    };
+    let out =
+        offset_with_join(&step, 1.0, JoinStyle::default(), &EpsilonPolicy::default()).unwrap();
+    assert_pts(
+        &polyline_pts(&out),
+        &[
+            (1.0, 1.0),
+            (9.0, 1.0),
+            (9.0, 9.0),
+            (6.0, 9.0),
+            (6.0, 8.5),
+            (1.0, 8.5),
+        ],
+    );
+}
+
+#[test]
+fn circle_offset_and_collapse() {
+    let c = Geom2D::Circle {
+        c: v(0.0, 0.0),
+        r: 5.0,
+    };
+    let eps = EpsilonPolicy::default();
+    assert_eq!(
+        offset(&c, -2.0, &eps).unwrap(),
+        Geom2D::Circle {
+            c: v(0.0, 0.0),
+            r: 7.0
+        }
+    );
+    let err = offset(&c, 5.0, &eps).unwrap_err();
+    assert_eq!(err.code, "GEOM_OFFSET_SELF_INTERSECTION");
+}
+
+#[test]
+fn arc_offset_follows_direction() {
+    let eps = EpsilonPolicy::default();
+    let arc = |ccw| Geom2D::Arc {
+        c: v(0.0, 0.0),
+        r: 5.0,
+        start_angle: 0.0,
+        end_angle: PI / 2.0,
+        ccw,
+    };
+    match offset(&arc(true), 1.0, &eps).unwrap() {
+        Geom2D::Arc { r, .. } => assert!((r - 4.0).abs() < 1e-9),
+        other => panic!("expected arc, got {other:?}"),
+    }
+    match offset(&arc(false), 1.0, &eps).unwrap() {
+        Geom2D::Arc { r, .. } => assert!((r - 6.0).abs() < 1e-9),
+        other => panic!("expected arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn slot_chain_offsets_outward() {
+    // Slot: bottom line, right half circle, top line, left half circle (CCW).
+    let chain = vec![
+        Geom2D::Line {
+            a: v(0.0, 0.0),
+            b: v(10.0, 0.0),
+        },
+        Geom2D::Arc {
+            c: v(10.0, 2.0),
+            r: 2.0,
+            start_angle: -PI / 2.0,
+            end_angle: PI / 2.0,
+            ccw: true,
+        },
+        Geom2D::Line {
+            a: v(10.0, 4.0),
+            b: v(0.0, 4.0),
+        },
+        Geom2D::Arc {
+            c: v(0.0, 2.0),
+            r: 2.0,
+            start_angle: PI / 2.0,
+            end_angle: -PI / 2.0,
+            ccw: true,
+        },
+    ];
+    let out = offset_chain(
+        &chain,
+        true,
+        -1.0,
+        JoinStyle::default(),
+        &EpsilonPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(out.len(), 4);
+    match &out[0] {
+        Geom2D::Line { a, b } => {
+            assert!((a.y + 1.0).abs() < 1e-9 && (b.y + 1.0).abs() < 1e-9);
+        }
+        other => panic!("expected line, got {other:?}"),
+    }
+    match &out[1] {
+        Geom2D::Arc { r, .. } => assert!((r - 3.0).abs() < 1e-9),
+        other => panic!("expected arc, got {other:?}"),
+    }
+
+    let gap = vec![
+        chain[0].clone(),
+        Geom2D::Line {
+            a: v(11.0, 0.0),
+            b: v(11.0, 4.0),
+        },
+    ];
+    let err = offset_chain(
+        &gap,
+        false,
+        1.0,
+        JoinStyle::default(),
+        &EpsilonPolicy::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "GEOM_OFFSET_NOT_SUPPORTED");
+}
+
+#[test]
+fn inward_offset_past_thickness_collapses() {
+    let err = offset_with_join(
+        &square(),
+        6.0,
+        JoinStyle::default(),
+        &EpsilonPolicy::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "GEOM_OFFSET_SELF_INTERSECTION");
+}
+
+#[test]
+fn narrow_notch_closes_instead_of_failing() {
+    let notched = Geom2D::Polyline {
+        pts: vec![
+            v(0.0, 0.0),
+            v(10.0, 0.0),
+            v(10.0, 10.0),
+            v(5.5, 10.0),
+            v(5.5, 5.0),
+            v(4.5, 5.0),
+            v(4.5, 10.0),
+            v(0.0, 10.0),
+        ],
+        closed: true,
+    };
+    let out = offset_with_join(
+        &notched,
+        -1.0,
+        JoinStyle::default(),
+        &EpsilonPolicy::default(),
+    )
+    .unwrap();
+    assert_pts(
+        &polyline_pts(&out),
+        &[(-1.0, -1.0), (11.0, -1.0), (11.0, 11.0), (-1.0, 11.0)],
+    );
+}
@@ -0,0 +1,138 @@
+use craftcad_serialize::{Polygon2D, Vec2};
+use diycad_geom::{polygon_boolean, BooleanOp, EpsilonPolicy};
+
+fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Vec2> {
+    vec![
+        Vec2 { x: x0, y: y0 },
+        Vec2 { x: x1, y: y0 },
+        Vec2 { x: x1, y: y1 },
+        Vec2 { x: x0, y: y1 },
+    ]
+}
+
+fn poly(outer: Vec<Vec2>, holes: Vec<Vec<Vec2>>) -> Polygon2D {
+    Polygon2D { outer, holes }
+}
+
+fn area(pts: &[Vec2]) -> f64 {
+    let n = pts.len();
+    0.5 * (0..n)
+        .map(|i| pts[i].x * pts[(i + 1) % n].y - pts[(i + 1) % n].x * pts[i].y)
+        .sum::<f64>()
+}
+
+fn net_area(polys: &[Polygon2D]) -> f64 {
+    polys
+        .iter()
+        .map(|p| area(&p.outer) + p.holes.iter().map(|h| area(h)).sum::<f64>())
+        .sum()
+}
+
+fn coords(pts: &[Vec2]) -> Vec<(f64, f64)> {
+    pts.iter().map(|p| (p.x, p.y)).collect()
+}
+
+#[test]
+fn overlapping_squares_all_ops() {
+    let eps = EpsilonPolicy::default();
+    let a = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    let b = [poly(rect(5.0, 5.0, 15.0, 15.0), vec![])];
+
+    let u = polygon_boolean(&a, &b, BooleanOp::Union, &eps).unwrap();
+    assert_eq!(u.len(), 1);
+    assert_eq!(u[0].outer.len(), 8);
+    assert!((net_area(&u) - 175.0).abs() < 1e-9);
+
+    let i = polygon_boolean(&a, &b, BooleanOp::Intersection, &eps).unwrap();
+    assert_eq!(
+        coords(&i[0].outer),
+        vec![(5.0, 5.0), (10.0, 5.0), (10.0, 10.0), (5.0, 10.0)]
+    );
+
+    let d = polygon_boolean(&a, &b, BooleanOp::Difference, &eps).unwrap();
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].outer.len(), 6);
+    assert!((net_area(&d) - 75.0).abs() < 1e-9);
+
+    let x = polygon_boolean(&a, &b, BooleanOp::Xor, &eps).unwrap();
+    assert_eq!(x.len(), 2);
+    assert!((net_area(&x) - 150.0).abs() < 1e-9);
+}
+
+#[test]
+fn difference_inside_makes_hole_and_union_keeps_it() {
+    let eps = EpsilonPolicy::default();
+    let panel = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    let cut = [poly(rect(3.0, 3.0, 7.0, 7.0), vec![])];
+    let d = polygon_boolean(&panel, &cut, BooleanOp::Difference, &eps).unwrap();
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].holes.len(), 1);
+    assert!(area(&d[0].holes[0]) < 0.0);
+    assert!((net_area(&d) - 84.0).abs() < 1e-9);
+
+    // A patch over part of the hole shrinks it.
+    let patch = [poly(rect(2.0, 2.0, 5.0, 8.0), vec![])];
+    let u = polygon_boolean(&d, &patch, BooleanOp::Union, &eps).unwrap();
+    assert_eq!(u.len(), 1);
+    assert_eq!(u[0].holes.len(), 1);
+    assert!((net_area(&u) - 92.0).abs() < 1e-9);
+}
+
+#[test]
+fn notch_on_shared_edge_and_adjacent_union() {
+    let eps = EpsilonPolicy::default();
+    let panel = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    // Notch flush with the bottom edge.
+    let notch = [poly(rect(4.0, 0.0, 6.0, 3.0), vec![])];
+    let d = polygon_boolean(&panel, &notch, BooleanOp::Difference, &eps).unwrap();
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].outer.len(), 8);
+    assert!((net_area(&d) - 94.0).abs() < 1e-9);
+
+    // Edge-adjacent panels merge into one rectangle.
+    let right = [poly(rect(10.0, 0.0, 20.0, 10.0), vec![])];
+    let u = polygon_boolean(&panel, &right, BooleanOp::Union, &eps).unwrap();
+    assert_eq!(
+        coords(&u[0].outer),
+        vec![(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (0.0, 10.0)]
+    );
+    let i = polygon_boolean(&panel, &right, BooleanOp::Intersection, &eps).unwrap();
+    assert!(i.is_empty());
+}
+
+#[test]
+fn result_is_independent_of_ring_start_and_orientation() {
+    let eps = EpsilonPolicy::default();
+    let a = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    let mut shifted = rect(5.0, 5.0, 15.0, 15.0);
+    shifted.rotate_left(2);
+    shifted.reverse();
+    let b1 = [poly(rect(5.0, 5.0, 15.0, 15.0), vec![])];
+    let b2 = [poly(shifted, vec![])];
+    let r1 = polygon_boolean(&a, &b1, BooleanOp::Union, &eps).unwrap();
+    let r2 = polygon_boolean(&a, &b2, BooleanOp::Union, &eps).unwrap();
+    assert_eq!(
+        serde_json::to_value(&r1).unwrap(),
+        serde_json::to_value(&r2).unwrap()
+    );
+}
+
+#[test]
+fn near_coincident_edges_snap_within_eq_dist() {
+    let eps = EpsilonPolicy::default();
+    let a = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    let b = [poly(rect(10.0 + 1e-8, 0.0, 20.0, 10.0), vec![])];
+    let u = polygon_boolean(&a, &b, BooleanOp::Union, &eps).unwrap();
+    assert_eq!(u.len(), 1);
+    assert_eq!(u[0].outer.len(), 4);
+}
+
+#[test]
+fn degenerate_operand_is_rejected() {
+    let eps = EpsilonPolicy::default();
+    let a = [poly(rect(0.0, 0.0, 10.0, 10.0), vec![])];
+    let flat = [poly(rect(0.0, 0.0, 10.0, 0.0), vec![])];
+    let err = polygon_boolean(&a, &flat, BooleanOp::Union, &eps).unwrap_err();
+    assert_eq!(err.code, "GEOM_DEGENERATE");
+    assert_eq!(err.debug["operand"], serde_json::json!("b"));
+}
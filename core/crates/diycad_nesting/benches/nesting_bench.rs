@@ -54,6 +54,9 @@ fn sample_doc_and_job() -> (Document, NestJob) {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     };
     let job = NestJob {
         id: Uuid::new_v4(),
@@ -77,11 +80,13 @@ fn sample_doc_and_job() -> (Document, NestJob) {
             allow_rotate_default: true,
             no_go_zones: vec![],
             grain_policy: GrainPolicy::Ignore,
+            guillotine: false,
         },
         objective: NestObjective {
             w_utilization: 1.0,
             w_sheet_count: 1.0,
             w_cut_count: 1.0,
+            w_grain_misaligned: 1.0,
         },
         seed: 123,
         result: None,
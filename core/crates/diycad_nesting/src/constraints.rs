@@ -1,4 +1,12 @@
-use craftcad_serialize::{Document, NestJob, Reason, ReasonCode, Result};
+use crate::model::PartEval;
+use crate::nfp::Piece;
+use craftcad_serialize::{
+    Document, GrainPolicy, NestConstraints, NestJob, NoGoZone, Reason, ReasonCode, Result,
+};
+use diycad_geom::Vec2;
+
+/// Angular tolerance, in degrees, for grain alignment.
+const GRAIN_TOL_DEG: f64 = 1e-6;
 
 pub fn validate_job(job: &NestJob, doc: &Document) -> Result<()> {
     if job.sheet_defs.is_empty() {
@@ -9,6 +17,18 @@ pub fn validate_job(job: &NestJob, doc: &Document) -> Result<()> {
             return Err(Reason::from_code(ReasonCode::NestInternalInfeasible));
         }
     }
+    for z in &job.constraints.no_go_zones {
+        let NoGoZone::Rect {
+            x,
+            y,
+            width,
+            height,
+        } = *z;
+        let finite = [x, y, width, height].iter().all(|v| v.is_finite());
+        if !(finite && width > 0.0 && height > 0.0) {
+            return Err(Reason::from_code(ReasonCode::NestInternalInfeasible));
+        }
+    }
     for r in &job.parts_ref {
         if !doc.parts.iter().any(|p| p.id == r.part_id) {
             return Err(Reason::from_code(ReasonCode::ModelReferenceNotFound));
@@ -16,3 +36,55 @@ pub fn validate_job(job: &NestJob, doc: &Document) -> Result<()> {
     }
     Ok(())
 }
+
+/// Whether the part's grain, turned by `rotation_deg`, runs along the
+/// sheet grain (the sheet's x axis). Parts without a grain always do.
+pub fn grain_aligned(p: &PartEval, rotation_deg: f64) -> bool {
+    let Some(dir) = p.grain_dir else {
+        return true;
+    };
+    let off = (dir + rotation_deg).rem_euclid(180.0);
+    off.min(180.0 - off) <= GRAIN_TOL_DEG
+}
+
+/// Rotations the part may take, ignoring the grain policy.
+pub fn free_rotations(p: &PartEval) -> Vec<f64> {
+    if p.allow_rotate {
+        vec![0.0, 90.0, 180.0, 270.0]
+    } else {
+        vec![0.0]
+    }
+}
+
+/// Rotations left once a strict grain policy has been applied.
+pub fn rotations(p: &PartEval, c: &NestConstraints) -> Vec<f64> {
+    let mut rots = free_rotations(p);
+    if matches!(c.grain_policy, GrainPolicy::Strict) {
+        rots.retain(|&r| grain_aligned(p, r));
+    }
+    rots
+}
+
+/// No-go zones as obstacles, in the coordinates of every sheet.
+pub fn zone_pieces(c: &NestConstraints) -> Vec<Piece> {
+    c.no_go_zones
+        .iter()
+        .map(|z| {
+            let NoGoZone::Rect {
+                x,
+                y,
+                width,
+                height,
+            } = *z;
+            Piece::new(vec![
+                Vec2 { x, y },
+                Vec2 { x: x + width, y },
+                Vec2 {
+                    x: x + width,
+                    y: y + height,
+                },
+                Vec2 { x, y: y + height },
+            ])
+        })
+        .collect()
+}
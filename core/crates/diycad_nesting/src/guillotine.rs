@@ -0,0 +1,84 @@
+//! Guillotine placement for panel saws.
+//!
+//! Each sheet keeps the rectangles not yet cut into. A part's grown
+//! footprint goes in the corner of one of them, and at most two
+//! edge-to-edge cuts free it: the first across the whole rectangle, the
+//! second across the strip the part sits in. Cuts are recorded as they are
+//! made, so a cut always lies inside a panel earlier cuts have freed.
+
+use crate::nfp::Piece;
+use diycad_geom::Vec2;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+fn clear(x: f64, y: f64, w: f64, h: f64, zones: &[Piece], eps: f64) -> bool {
+    zones.iter().all(|z| {
+        x + w <= z.min.x + eps || z.max.x <= x + eps || y + h <= z.min.y + eps || z.max.y <= y + eps
+    })
+}
+
+/// Lowest then leftmost free rectangle with room for a `w` by `h`
+/// footprint in its corner, clear of `zones`.
+pub fn find(free: &[Rect], w: f64, h: f64, zones: &[Piece], eps: f64) -> Option<usize> {
+    free.iter()
+        .enumerate()
+        .filter(|(_, r)| w <= r.w + eps && h <= r.h + eps)
+        .filter(|(_, r)| clear(r.x, r.y, w, h, zones, eps))
+        .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+        .map(|(i, _)| i)
+}
+
+/// Cuts a `w` by `h` footprint out of the corner of `free[i]`, replaces
+/// that rectangle with the offcuts and returns the cuts as line segments.
+/// The first cut keeps the full side on the larger offcut.
+pub fn split(free: &mut Vec<Rect>, i: usize, w: f64, h: f64, eps: f64) -> Vec<(Vec2, Vec2)> {
+    let r = free.swap_remove(i);
+    let (right, top) = (r.w - w, r.h - h);
+    let rip_first = top * r.w >= right * r.h;
+    let (rip_w, cross_h) = if rip_first { (r.w, h) } else { (w, r.h) };
+    let (x, y) = (r.x + w, r.y + h);
+    let rip = (top > eps).then(|| {
+        let offcut = Rect {
+            x: r.x,
+            y,
+            w: rip_w,
+            h: top,
+        };
+        (offcut, (Vec2 { x: r.x, y }, Vec2 { x: r.x + rip_w, y }))
+    });
+    let cross = (right > eps).then(|| {
+        let offcut = Rect {
+            x,
+            y: r.y,
+            w: right,
+            h: cross_h,
+        };
+        (
+            offcut,
+            (
+                Vec2 { x, y: r.y },
+                Vec2 {
+                    x,
+                    y: r.y + cross_h,
+                },
+            ),
+        )
+    });
+    let ordered = if rip_first {
+        [rip, cross]
+    } else {
+        [cross, rip]
+    };
+    let mut cuts = vec![];
+    for (offcut, cut) in ordered.into_iter().flatten() {
+        free.push(offcut);
+        cuts.push(cut);
+    }
+    cuts
+}
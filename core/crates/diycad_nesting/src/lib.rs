@@ -1,9 +1,12 @@
 #![allow(clippy::result_large_err)]
 
 pub mod constraints;
+pub mod guillotine;
 pub mod model;
+pub mod nfp;
 pub mod pack;
 pub mod score;
+pub mod search;
 pub mod trace;
 
 use crate::model::PlacementRect;
@@ -34,7 +37,7 @@ pub fn validate_nest_job(job: &NestJob, doc: &Document) -> Result<()> {
 pub fn run_nesting(
     job: &NestJob,
     doc: &Document,
-    _eps: &EpsilonPolicy,
+    eps: &EpsilonPolicy,
     limits: RunLimits,
 ) -> Result<(NestResultV1, NestTraceV1)> {
     validate_nest_job(job, doc)?;
@@ -46,6 +49,11 @@ pub fn run_nesting(
     let mut best_updates = vec![];
     let mut failure_stats = std::collections::BTreeMap::new();
 
+    // Simulated annealing from the greedy order; see `search`.
+    let eval_parts = model::expand_parts(job, doc, eps)?;
+    let mut current = search::Candidate::greedy(&eval_parts);
+    let mut current_score = f64::NEG_INFINITY;
+
     let iterations = limits.iteration_limit.max(1);
     let mut actual_iters = 0;
     for iter in 0..iterations {
@@ -53,20 +61,28 @@ pub fn run_nesting(
             break;
         }
         actual_iters += 1;
-        let eval_parts = model::expand_parts(job, doc, &mut rng)?;
+        let candidate = if iter == 0 {
+            current.clone()
+        } else {
+            current.neighbour(&eval_parts, &job.constraints, &mut rng)
+        };
+        let parts = candidate.parts(&eval_parts);
         let mut part_status: Vec<PartPlacementStatus> = vec![];
-        let (placements, local_failures) = pack::pack_parts(job, &eval_parts, &mut part_status)?;
-        for c in local_failures {
+        let packed = pack::pack_parts(job, &parts, &mut part_status, eps)?;
+        for c in packed.failure_codes {
             *failure_stats.entry(c).or_insert(0) += 1;
         }
-        let metrics = score::compute_metrics(job, &placements, &part_status);
+        let metrics =
+            score::compute_metrics(job, &parts, &packed.placements, &packed.cuts, &part_status);
         let result = NestResultV1 {
-            placements: placements
+            placements: packed
+                .placements
                 .into_iter()
                 .map(PlacementRect::into_placement)
                 .collect(),
             metrics: metrics.clone(),
             per_part_status: part_status,
+            cut_sequence: packed.cuts,
         };
         if metrics.score > best_score {
             best_score = metrics.score;
@@ -78,6 +94,10 @@ pub fn run_nesting(
                 utilization: metrics.utilization_per_sheet.iter().copied().sum::<f64>(),
             });
         }
+        if search::accept(current_score, metrics.score, iter, iterations, &mut rng) {
+            current = candidate;
+            current_score = metrics.score;
+        }
     }
 
     let stop_reason = if actual_iters < iterations {
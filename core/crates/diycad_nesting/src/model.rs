@@ -1,6 +1,8 @@
+use crate::nfp::{convex_pieces, signed_area, Ring};
 use craftcad_serialize::{
     BBox, Document, NestJob, Part, PartRef, Placement, Reason, ReasonCode, Result,
 };
+use diycad_geom::{EpsilonPolicy, Vec2};
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -17,6 +19,9 @@ impl DeterministicRng {
     }
 }
 
+/// A part instance to place. `width`/`height` are the inflated bounds;
+/// `pieces` is the outline minus its holes as convex pieces, in part
+/// coordinates and not inflated.
 #[derive(Clone, Debug)]
 pub struct PartEval {
     pub part_id: Uuid,
@@ -24,8 +29,21 @@ pub struct PartEval {
     pub height: f64,
     pub area: f64,
     pub allow_rotate: bool,
+    pub pieces: Vec<Ring>,
+    pub inflate: f64,
+    pub net_area: f64,
+    /// Grain direction in degrees from the part's +x axis.
+    pub grain_dir: Option<f64>,
+    pub material_id: Uuid,
+    /// Indices into `job.sheet_defs` of the sheets of the part's material
+    /// and thickness.
+    pub sheet_defs: Vec<usize>,
+    /// Rotation the packer prefers on each sheet, when it fits there.
+    pub rotation_hint: Option<f64>,
 }
 
+/// A placed part: its outline turned by `rotation_deg` about the part
+/// origin and moved so that its inflated bounds start at (`x`, `y`).
 #[derive(Clone, Debug)]
 pub struct PlacementRect {
     pub part_id: Uuid,
@@ -35,6 +53,10 @@ pub struct PlacementRect {
     pub rotation_deg: f64,
     pub width: f64,
     pub height: f64,
+    /// Material area of the part, without margin and kerf.
+    pub area: f64,
+    /// The part has a grain direction that does not run along the sheet's.
+    pub grain_misaligned: bool,
 }
 impl PlacementRect {
     pub fn into_placement(self) -> Placement {
@@ -75,11 +97,46 @@ fn dims(part: &Part) -> Result<(f64, f64)> {
     Ok((max_x - min_x, max_y - min_y))
 }
 
-pub fn expand_parts(
-    job: &NestJob,
-    doc: &Document,
-    _rng: &mut DeterministicRng,
-) -> Result<Vec<PartEval>> {
+fn ring(pts: &[craftcad_serialize::Vec2]) -> Ring {
+    pts.iter().map(|p| Vec2 { x: p.x, y: p.y }).collect()
+}
+
+/// Convex pieces of the outline and its net area.
+fn shape(part: &Part) -> Result<(Vec<Ring>, f64)> {
+    let outer = ring(&part.outline.outer);
+    let holes: Vec<Ring> = part.outline.holes.iter().map(|h| ring(h)).collect();
+    if holes
+        .iter()
+        .flatten()
+        .any(|p| !p.x.is_finite() || !p.y.is_finite())
+    {
+        return Err(Reason::from_code(ReasonCode::PartInvalidOutline));
+    }
+    let area = signed_area(&outer).abs() - holes.iter().map(|h| signed_area(h).abs()).sum::<f64>();
+    if area <= 0.0 {
+        return Err(Reason::from_code(ReasonCode::PartInvalidOutline));
+    }
+    Ok((convex_pieces(&outer, &holes), area))
+}
+
+/// Sheets a part can be cut from: same material, and the material's
+/// thickness, when it has one, matches the part's.
+fn matching_sheets(job: &NestJob, doc: &Document, part: &Part, eps: &EpsilonPolicy) -> Vec<usize> {
+    let thickness = doc
+        .materials
+        .iter()
+        .find(|m| m.id == part.material_id)
+        .and_then(|m| m.thickness_mm);
+    job.sheet_defs
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.material_id == part.material_id)
+        .filter(|_| thickness.is_none_or(|t| (t - part.thickness).abs() <= eps.eq_dist))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+pub fn expand_parts(job: &NestJob, doc: &Document, eps: &EpsilonPolicy) -> Result<Vec<PartEval>> {
     let mut out = vec![];
     let mut refs: Vec<PartRef> = job.parts_ref.clone();
     refs.sort_by_key(|r| r.part_id);
@@ -97,6 +154,8 @@ pub fn expand_parts(
             })?;
         let qty = r.quantity_override.unwrap_or(p.quantity).max(1);
         let (w, h) = dims(p)?;
+        let (pieces, net_area) = shape(p)?;
+        let sheet_defs = matching_sheets(job, doc, p, eps);
         let inflate =
             job.constraints.global_margin + job.constraints.global_kerf + p.margin + p.kerf;
         let ew = (w + 2.0 * inflate).max(0.0);
@@ -108,6 +167,13 @@ pub fn expand_parts(
                 height: eh,
                 area: ew * eh,
                 allow_rotate: p.allow_rotate || job.constraints.allow_rotate_default,
+                pieces: pieces.clone(),
+                inflate,
+                net_area,
+                grain_dir: p.grain_dir,
+                material_id: p.material_id,
+                sheet_defs: sheet_defs.clone(),
+                rotation_hint: None,
             });
         }
     }
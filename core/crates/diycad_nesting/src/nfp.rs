@@ -0,0 +1,432 @@
+//! No-fit polygon placement.
+//!
+//! Outlines are split once into convex pieces that cover the material (the
+//! outer ring minus its holes). The no-fit polygon of a placed part and a
+//! moving part is then the union of the Minkowski sums of their pieces, so a
+//! hole in a placed part stays free for smaller parts.
+
+use diycad_geom::Vec2;
+
+pub type Ring = Vec<Vec2>;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn sub(a: Vec2, b: Vec2) -> Vec2 {
+    v(a.x - b.x, a.y - b.y)
+}
+
+fn cross(o: Vec2, a: Vec2, b: Vec2) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+pub fn signed_area(ring: &[Vec2]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Tolerance for cross products over points spread across `ring`.
+fn area_tol(rings: &[&[Vec2]]) -> f64 {
+    let mut ext: f64 = 0.0;
+    for p in rings.iter().flat_map(|r| r.iter()) {
+        ext = ext.max(p.x.abs()).max(p.y.abs());
+    }
+    1e-12 * (1.0 + ext * ext)
+}
+
+/// Drops repeated points, including a closing copy of the first.
+fn dedup(ring: &[Vec2]) -> Ring {
+    let mut out: Ring = Vec::with_capacity(ring.len());
+    for &p in ring {
+        if out.last() != Some(&p) {
+            out.push(p);
+        }
+    }
+    while out.len() > 1 && out.first() == out.last() {
+        out.pop();
+    }
+    out
+}
+
+fn oriented(ring: &[Vec2], ccw: bool) -> Ring {
+    let mut r = dedup(ring);
+    if (signed_area(&r) > 0.0) != ccw {
+        r.reverse();
+    }
+    r
+}
+
+/// Counter-clockwise hull without collinear points (monotone chain).
+pub fn convex_hull(points: &[Vec2]) -> Ring {
+    let mut pts = points.to_vec();
+    pts.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    pts.dedup();
+    if pts.len() < 3 {
+        return pts;
+    }
+    let mut lower: Ring = vec![];
+    for &p in &pts {
+        while lower.len() >= 2 && cross(lower[lower.len() - 2], lower[lower.len() - 1], p) <= 0.0 {
+            lower.pop();
+        }
+        lower.push(p);
+    }
+    let mut upper: Ring = vec![];
+    for &p in pts.iter().rev() {
+        while upper.len() >= 2 && cross(upper[upper.len() - 2], upper[upper.len() - 1], p) <= 0.0 {
+            upper.pop();
+        }
+        upper.push(p);
+    }
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+/// Whether segments `ab` and `cd` cross at a point interior to both.
+fn crosses(a: Vec2, b: Vec2, c: Vec2, d: Vec2, tol: f64) -> bool {
+    let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+    let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+    ((d1 > tol && d2 < -tol) || (d1 < -tol && d2 > tol))
+        && ((d3 > tol && d4 < -tol) || (d3 < -tol && d4 > tol))
+}
+
+fn ring_edges(ring: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+/// Splices each hole into the outer ring through a bridge to a visible
+/// outer vertex, giving one weakly simple counter-clockwise ring.
+fn bridge_holes(outer: Ring, mut holes: Vec<Ring>, tol: f64) -> Option<Ring> {
+    let rightmost = |h: &Ring| {
+        (0..h.len())
+            .max_by(|&i, &j| h[i].x.total_cmp(&h[j].x).then(h[j].y.total_cmp(&h[i].y)))
+            .unwrap_or(0)
+    };
+    holes.sort_by(|a, b| b[rightmost(b)].x.total_cmp(&a[rightmost(a)].x));
+    let mut ring = outer;
+    for k in 0..holes.len() {
+        let h = &holes[k];
+        let mi = rightmost(h);
+        let m = h[mi];
+        let mut order: Vec<usize> = (0..ring.len()).collect();
+        order.sort_by(|&i, &j| {
+            let (a, b) = (sub(ring[i], m), sub(ring[j], m));
+            (a.x * a.x + a.y * a.y)
+                .total_cmp(&(b.x * b.x + b.y * b.y))
+                .then(i.cmp(&j))
+        });
+        let visible = |p: Vec2| {
+            let blocked = |r: &Ring| ring_edges(r).any(|(c, d)| crosses(m, p, c, d, tol));
+            !blocked(&ring) && !holes[k..].iter().any(blocked)
+        };
+        let at = order.into_iter().find(|&i| visible(ring[i]))?;
+        let mut spliced = ring[..=at].to_vec();
+        spliced.extend(h[mi..].iter().chain(h[..=mi].iter()));
+        spliced.extend(ring[at..].iter());
+        ring = spliced;
+    }
+    Some(ring)
+}
+
+fn in_triangle(a: Vec2, b: Vec2, c: Vec2, p: Vec2, tol: f64) -> bool {
+    cross(a, b, p) >= -tol && cross(b, c, p) >= -tol && cross(c, a, p) >= -tol
+}
+
+fn ear_clip(ring: &[Vec2], tol: f64) -> Option<Vec<Ring>> {
+    let mut idx: Vec<usize> = (0..ring.len()).collect();
+    let mut tris = vec![];
+    while idx.len() > 3 {
+        let n = idx.len();
+        let ear = (0..n).find_map(|k| {
+            let (a, b, c) = (
+                ring[idx[(k + n - 1) % n]],
+                ring[idx[k]],
+                ring[idx[(k + 1) % n]],
+            );
+            let turn = cross(a, b, c);
+            if turn.abs() <= tol && a != c {
+                // Collinear: drop the middle vertex without a triangle.
+                return Some((k, None));
+            }
+            if turn <= tol {
+                return None;
+            }
+            let blocked = idx.iter().any(|&j| {
+                let p = ring[j];
+                p != a && p != b && p != c && in_triangle(a, b, c, p, tol)
+            });
+            (!blocked).then_some((k, Some(vec![a, b, c])))
+        })?;
+        idx.remove(ear.0);
+        tris.extend(ear.1);
+    }
+    let (a, b, c) = (ring[idx[0]], ring[idx[1]], ring[idx[2]]);
+    if cross(a, b, c) > tol {
+        tris.push(vec![a, b, c]);
+    }
+    Some(tris)
+}
+
+fn is_convex(ring: &[Vec2], tol: f64) -> bool {
+    let n = ring.len();
+    (0..n).all(|i| cross(ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]) >= -tol)
+}
+
+/// Joins two pieces along the edge `a[i]->a[i+1]`, which `b` walks the
+/// other way from `b[j]`.
+fn join(a: &Ring, i: usize, b: &Ring, j: usize) -> Ring {
+    let mut out: Ring = (1..=a.len()).map(|k| a[(i + k) % a.len()]).collect();
+    out.extend((2..b.len()).map(|k| b[(j + k) % b.len()]));
+    out
+}
+
+/// Merges neighbouring pieces while the union stays convex
+/// (Hertel-Mehlhorn).
+fn merge_convex(mut pieces: Vec<Ring>, tol: f64) -> Vec<Ring> {
+    'again: loop {
+        for p in 0..pieces.len() {
+            for q in p + 1..pieces.len() {
+                let (a, b) = (&pieces[p], &pieces[q]);
+                for i in 0..a.len() {
+                    let (s, e) = (a[i], a[(i + 1) % a.len()]);
+                    let Some(j) = (0..b.len()).find(|&j| b[j] == e && b[(j + 1) % b.len()] == s)
+                    else {
+                        continue;
+                    };
+                    let merged = join(a, i, b, j);
+                    if is_convex(&merged, tol) {
+                        pieces[p] = merged;
+                        pieces.swap_remove(q);
+                        continue 'again;
+                    }
+                }
+            }
+        }
+        return pieces;
+    }
+}
+
+/// Convex pieces covering `outer` minus `holes`. An outline that cannot be
+/// triangulated (self-intersecting, say) falls back to the hull of `outer`,
+/// which treats its holes as material.
+pub fn convex_pieces(outer: &[Vec2], holes: &[Ring]) -> Vec<Ring> {
+    let outer = oriented(outer, true);
+    let holes: Vec<Ring> = holes
+        .iter()
+        .map(|h| oriented(h, false))
+        .filter(|h| h.len() >= 3)
+        .collect();
+    let mut all: Vec<&[Vec2]> = vec![&outer];
+    all.extend(holes.iter().map(|h| h.as_slice()));
+    let tol = area_tol(&all);
+    let tris = bridge_holes(outer.clone(), holes, tol).and_then(|r| ear_clip(&r, tol));
+    match tris {
+        Some(t) if !t.is_empty() => merge_convex(t, tol),
+        _ => vec![convex_hull(&outer)],
+    }
+}
+
+/// Rotation about the origin; quarter turns are exact.
+pub fn rotate(p: Vec2, deg: f64) -> Vec2 {
+    match deg.rem_euclid(360.0) {
+        0.0 => p,
+        90.0 => v(-p.y, p.x),
+        180.0 => v(-p.x, -p.y),
+        270.0 => v(p.y, -p.x),
+        d => {
+            let (s, c) = d.to_radians().sin_cos();
+            v(p.x * c - p.y * s, p.x * s + p.y * c)
+        }
+    }
+}
+
+/// A convex piece with its bounding box.
+#[derive(Clone, Debug)]
+pub struct Piece {
+    pub ring: Ring,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Piece {
+    pub fn new(ring: Ring) -> Self {
+        let mut min = v(f64::INFINITY, f64::INFINITY);
+        let mut max = v(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for p in &ring {
+            min = v(min.x.min(p.x), min.y.min(p.y));
+            max = v(max.x.max(p.x), max.y.max(p.y));
+        }
+        Self { ring, min, max }
+    }
+
+    pub fn translated(&self, t: Vec2) -> Self {
+        Self {
+            ring: self.ring.iter().map(|p| v(p.x + t.x, p.y + t.y)).collect(),
+            min: v(self.min.x + t.x, self.min.y + t.y),
+            max: v(self.max.x + t.x, self.max.y + t.y),
+        }
+    }
+}
+
+/// Pieces rotated by `deg` and grown by `inflate` on every side (a square
+/// brush, matching margin and kerf on rectangular parts exactly).
+pub fn shape(pieces: &[Ring], deg: f64, inflate: f64) -> Vec<Piece> {
+    pieces
+        .iter()
+        .map(|ring| {
+            let pts: Vec<Vec2> = ring
+                .iter()
+                .map(|&p| rotate(p, deg))
+                .flat_map(|p| {
+                    [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                        .map(|(sx, sy)| v(p.x + sx * inflate, p.y + sy * inflate))
+                })
+                .collect();
+            Piece::new(convex_hull(&pts))
+        })
+        .collect()
+}
+
+pub fn bounds(pieces: &[Piece]) -> (Vec2, Vec2) {
+    pieces.iter().fold(
+        (
+            v(f64::INFINITY, f64::INFINITY),
+            v(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(lo, hi), p| {
+            (
+                v(lo.x.min(p.min.x), lo.y.min(p.min.y)),
+                v(hi.x.max(p.max.x), hi.y.max(p.max.y)),
+            )
+        },
+    )
+}
+
+/// `fixed ⊕ -moving`: the translations that make the two pieces overlap.
+fn no_fit(fixed: &Piece, moving: &Piece) -> Piece {
+    let pts: Vec<Vec2> = fixed
+        .ring
+        .iter()
+        .flat_map(|&a| moving.ring.iter().map(move |&b| sub(a, b)))
+        .collect();
+    Piece::new(convex_hull(&pts))
+}
+
+/// Whether `p` lies inside the convex piece by more than `eps`.
+fn strictly_inside(piece: &Piece, p: Vec2, eps: f64) -> bool {
+    if p.x <= piece.min.x + eps
+        || p.x >= piece.max.x - eps
+        || p.y <= piece.min.y + eps
+        || p.y >= piece.max.y - eps
+        || piece.ring.len() < 3
+    {
+        return false;
+    }
+    ring_edges(&piece.ring).all(|(a, b)| {
+        let len = (b.x - a.x).hypot(b.y - a.y);
+        cross(a, b, p) > eps * len
+    })
+}
+
+fn segment_hit(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Option<Vec2> {
+    let r = sub(b, a);
+    let s = sub(d, c);
+    let den = r.x * s.y - r.y * s.x;
+    if den == 0.0 {
+        return None;
+    }
+    let ca = sub(c, a);
+    let t = (ca.x * s.y - ca.y * s.x) / den;
+    let u = (ca.x * r.y - ca.y * r.x) / den;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| v(a.x + t * r.x, a.y + t * r.y))
+}
+
+fn overlaps(a: &Piece, b: &Piece, eps: f64) -> bool {
+    a.min.x <= b.max.x + eps
+        && b.min.x <= a.max.x + eps
+        && a.min.y <= b.max.y + eps
+        && b.min.y <= a.max.y + eps
+}
+
+/// Lowest, then leftmost translation that keeps `moving` inside the
+/// `width` x `height` sheet without overlapping `placed`. Candidates are the
+/// vertices of the feasible region: sheet corners, no-fit polygon vertices
+/// and crossings of no-fit polygon edges with each other and the sheet.
+pub fn bottom_left(
+    width: f64,
+    height: f64,
+    placed: &[Piece],
+    moving: &[Piece],
+    eps: f64,
+) -> Option<Vec2> {
+    let (lo, hi) = bounds(moving);
+    if hi.x - lo.x > width + eps || hi.y - lo.y > height + eps {
+        return None;
+    }
+    // Translations that keep the moving bounds on the sheet.
+    let fit = Piece::new(vec![
+        v(-lo.x, -lo.y),
+        v(width - hi.x, -lo.y),
+        v(width - hi.x, height - hi.y),
+        v(-lo.x, height - hi.y),
+    ]);
+    let mut nfps = vec![];
+    for f in placed {
+        for m in moving {
+            let reach = Piece {
+                ring: vec![],
+                min: sub(f.min, m.max),
+                max: sub(f.max, m.min),
+            };
+            if overlaps(&reach, &fit, eps) {
+                nfps.push(no_fit(f, m));
+            }
+        }
+    }
+
+    let mut candidates: Vec<Vec2> = fit.ring.clone();
+    for (i, a) in nfps.iter().enumerate() {
+        candidates.extend(a.ring.iter().copied());
+        for (p, q) in ring_edges(&a.ring) {
+            for (c, d) in ring_edges(&fit.ring) {
+                candidates.extend(segment_hit(p, q, c, d));
+            }
+        }
+        for b in nfps[i + 1..].iter().filter(|b| overlaps(a, b, eps)) {
+            for (p, q) in ring_edges(&a.ring) {
+                for (c, d) in ring_edges(&b.ring) {
+                    candidates.extend(segment_hit(p, q, c, d));
+                }
+            }
+        }
+    }
+    let mut candidates: Vec<Vec2> = candidates
+        .into_iter()
+        .filter(|c| {
+            c.x >= fit.min.x - eps
+                && c.x <= fit.max.x + eps
+                && c.y >= fit.min.y - eps
+                && c.y <= fit.max.y + eps
+        })
+        .map(|c| {
+            v(
+                c.x.clamp(fit.min.x, fit.max.x.max(fit.min.x)),
+                c.y.clamp(fit.min.y, fit.max.y.max(fit.min.y)),
+            )
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .find(|&c| !nfps.iter().any(|n| strictly_inside(n, c, eps)))
+}
@@ -1,72 +1,233 @@
+use crate::constraints::{free_rotations, grain_aligned, rotations, zone_pieces};
+use crate::guillotine::{self, Rect};
 use crate::model::{PartEval, PlacementRect};
+use crate::nfp::{bottom_left, bounds, shape, Piece};
 use craftcad_serialize::{
-    NestJob, PartPlacementStatus, PartPlacementStatusKind, Reason, ReasonCode,
+    Cut, CutKind, GrainPolicy, NestJob, PartPlacementStatus, PartPlacementStatusKind, Reason,
+    ReasonCode, SheetDef,
 };
+use diycad_geom::{EpsilonPolicy, Vec2};
 
+struct Sheet {
+    def: usize,
+    width: f64,
+    height: f64,
+    placed: Vec<Piece>,
+    /// Rectangles not cut into yet, in guillotine mode.
+    free: Vec<Rect>,
+}
+
+impl Sheet {
+    fn new(def: usize, s: &SheetDef, zones: &[Piece]) -> Self {
+        Self {
+            def,
+            width: s.width,
+            height: s.height,
+            placed: zones.to_vec(),
+            free: vec![Rect {
+                x: 0.0,
+                y: 0.0,
+                w: s.width,
+                h: s.height,
+            }],
+        }
+    }
+}
+
+/// Job-wide settings the fit search needs.
+#[derive(Clone, Copy)]
+struct Ctx<'a> {
+    zones: &'a [Piece],
+    prefer_grain: bool,
+    guillotine: bool,
+    eps: f64,
+}
+
+/// (unhinted, misaligned, bounds min y, bounds min x); lower is better.
+type Rank = (bool, bool, f64, f64);
+
+struct Fit {
+    rank: Rank,
+    rot: f64,
+    placed: Vec<Piece>,
+    /// The free rectangle the part goes in, in guillotine mode.
+    free: Option<usize>,
+}
+
+/// Lowest then leftmost position of `p` on `sheet` over `rots`. The
+/// part's rotation hint beats the other rotations, then, under a preferred
+/// grain policy, an aligned rotation beats any misaligned one.
+fn best_fit(p: &PartEval, rots: &[f64], sheet: &Sheet, ctx: &Ctx) -> Option<Fit> {
+    let mut best: Option<Fit> = None;
+    for &rot in rots {
+        let moving = shape(&p.pieces, rot, p.inflate);
+        let (t, free) = if ctx.guillotine {
+            let (lo, hi) = bounds(&moving);
+            let (w, h) = (hi.x - lo.x, hi.y - lo.y);
+            let Some(i) = guillotine::find(&sheet.free, w, h, ctx.zones, ctx.eps) else {
+                continue;
+            };
+            let r = sheet.free[i];
+            let t = Vec2 {
+                x: r.x - lo.x,
+                y: r.y - lo.y,
+            };
+            (t, Some(i))
+        } else {
+            let at = bottom_left(sheet.width, sheet.height, &sheet.placed, &moving, ctx.eps);
+            let Some(t) = at else {
+                continue;
+            };
+            (t, None)
+        };
+        let placed: Vec<Piece> = moving.iter().map(|m| m.translated(t)).collect();
+        let (lo, _) = bounds(&placed);
+        let unhinted = p.rotation_hint.is_some_and(|h| h != rot);
+        let misaligned = ctx.prefer_grain && !grain_aligned(p, rot);
+        let rank = (unhinted, misaligned, lo.y, lo.x);
+        if best.as_ref().is_none_or(|b| rank < b.rank) {
+            best = Some(Fit {
+                rank,
+                rot,
+                placed,
+                free,
+            });
+        }
+    }
+    best
+}
+
+/// The part's own sheet definitions.
+fn own_sheets<'a>(job: &'a NestJob, p: &'a PartEval) -> impl Iterator<Item = &'a SheetDef> {
+    p.sheet_defs.iter().map(|&i| &job.sheet_defs[i])
+}
+
+/// Whether the inflated bounds fit one of the part's sheets in one of `rots`.
+fn fits_any(job: &NestJob, p: &PartEval, rots: &[f64]) -> bool {
+    own_sheets(job, p).any(|s| {
+        rots.iter().any(|&r| {
+            let (w, h) = if r % 180.0 == 0.0 {
+                (p.width, p.height)
+            } else {
+                (p.height, p.width)
+            };
+            w <= s.width && h <= s.height
+        })
+    })
+}
+
+/// The first constraint, in policy order, that keeps `p` off every sheet.
+fn classify(job: &NestJob, p: &PartEval, ctx: &Ctx) -> ReasonCode {
+    if p.sheet_defs.is_empty() {
+        return ReasonCode::NestNoSheetForMaterial;
+    }
+    let rots = rotations(p, &job.constraints);
+    if !fits_any(job, p, &free_rotations(p)) {
+        return ReasonCode::NestPartTooLargeForAnySheet;
+    }
+    if !fits_any(job, p, &rots) {
+        return ReasonCode::NestGrainConstraintBlocksFit;
+    }
+    let ctx = Ctx {
+        prefer_grain: false,
+        ..*ctx
+    };
+    let clear = p.sheet_defs.iter().any(|&def| {
+        let empty = Sheet::new(def, &job.sheet_defs[def], ctx.zones);
+        best_fit(p, &rots, &empty, &ctx).is_some()
+    });
+    if !clear {
+        return ReasonCode::NestNoGoZoneBlocksFit;
+    }
+    ReasonCode::NestNoFeasiblePositionWithMarginAndKerf
+}
+
+pub struct Packed {
+    pub placements: Vec<PlacementRect>,
+    pub failure_codes: Vec<String>,
+    /// Saw cuts in order, in guillotine mode.
+    pub cuts: Vec<Cut>,
+}
+
+/// Each part goes to the first sheet of its material where some rotation
+/// fits, at the lowest then leftmost position: against the no-fit polygons
+/// of the parts already there, or in guillotine mode in the corner of an
+/// uncut rectangle. No-go zones sit on every sheet as obstacles.
 pub fn pack_parts(
     job: &NestJob,
     parts: &[PartEval],
     statuses: &mut Vec<PartPlacementStatus>,
-) -> craftcad_serialize::Result<(Vec<PlacementRect>, Vec<String>)> {
+    eps: &EpsilonPolicy,
+) -> craftcad_serialize::Result<Packed> {
     let mut placements = vec![];
     let mut failure_codes = vec![];
+    let mut cuts = vec![];
+    let zones = zone_pieces(&job.constraints);
+    let ctx = Ctx {
+        zones: &zones,
+        prefer_grain: matches!(job.constraints.grain_policy, GrainPolicy::Prefer),
+        guillotine: job.constraints.guillotine,
+        eps: eps.eq_dist,
+    };
 
     let mut sheets = vec![];
-    for s in &job.sheet_defs {
+    for (def, s) in job.sheet_defs.iter().enumerate() {
         for _ in 0..s.quantity {
-            sheets.push((s.width, s.height, 0.0f64, 0.0f64, 0.0f64)); // w,h,cursor_x,cursor_y,row_h
+            sheets.push(Sheet::new(def, s, &zones));
         }
     }
 
     'part: for p in parts {
+        let rots = rotations(p, &job.constraints);
         for (sheet_index, sheet) in sheets.iter_mut().enumerate() {
-            let (sw, sh, cx, cy, row_h) = sheet;
-            let mut try_dims = vec![(p.width, p.height, 0.0)];
-            if p.allow_rotate {
-                try_dims.push((p.height, p.width, 90.0));
+            if !p.sheet_defs.contains(&sheet.def) {
+                continue;
             }
-            for (w, h, rot) in try_dims {
-                if w > *sw || h > *sh {
-                    continue;
-                }
-                if *cx + w > *sw {
-                    *cx = 0.0;
-                    *cy += *row_h;
-                    *row_h = 0.0;
+            let Some(fit) = best_fit(p, &rots, sheet, &ctx) else {
+                continue;
+            };
+            let (lo, hi) = bounds(&fit.placed);
+            let (w, h) = (hi.x - lo.x, hi.y - lo.y);
+            if let Some(i) = fit.free {
+                for (from, to) in guillotine::split(&mut sheet.free, i, w, h, ctx.eps) {
+                    let kind = if from.y == to.y {
+                        CutKind::Rip
+                    } else {
+                        CutKind::Crosscut
+                    };
+                    cuts.push(Cut {
+                        sheet_instance_index: sheet_index as u32,
+                        kind,
+                        from: craftcad_serialize::Vec2 {
+                            x: from.x,
+                            y: from.y,
+                        },
+                        to: craftcad_serialize::Vec2 { x: to.x, y: to.y },
+                        kerf: job.constraints.global_kerf,
+                    });
                 }
-                if *cy + h > *sh {
-                    continue;
-                }
-                placements.push(PlacementRect {
-                    part_id: p.part_id,
-                    sheet_instance_index: sheet_index as u32,
-                    x: *cx,
-                    y: *cy,
-                    rotation_deg: rot,
-                    width: w,
-                    height: h,
-                });
-                *cx += w;
-                *row_h = row_h.max(h);
-                statuses.push(PartPlacementStatus {
-                    part_id: p.part_id,
-                    status: PartPlacementStatusKind::Placed,
-                    reason: None,
-                });
-                continue 'part;
             }
+            placements.push(PlacementRect {
+                part_id: p.part_id,
+                sheet_instance_index: sheet_index as u32,
+                x: lo.x,
+                y: lo.y,
+                rotation_deg: fit.rot,
+                width: w,
+                height: h,
+                area: p.net_area,
+                grain_misaligned: !grain_aligned(p, fit.rot),
+            });
+            sheet.placed.extend(fit.placed);
+            statuses.push(PartPlacementStatus {
+                part_id: p.part_id,
+                status: PartPlacementStatusKind::Placed,
+                reason: None,
+            });
+            continue 'part;
         }
 
-        // reason classification
-        let too_large = !job.sheet_defs.iter().any(|s| {
-            (p.width <= s.width && p.height <= s.height)
-                || (p.allow_rotate && p.height <= s.width && p.width <= s.height)
-        });
-        let code = if too_large {
-            ReasonCode::NestPartTooLargeForAnySheet
-        } else {
-            ReasonCode::NestNoFeasiblePositionWithMarginAndKerf
-        };
+        let code = classify(job, p, &ctx);
         failure_codes.push(code.as_str().to_string());
         statuses.push(PartPlacementStatus {
             part_id: p.part_id,
@@ -75,5 +236,9 @@ pub fn pack_parts(
         });
     }
 
-    Ok((placements, failure_codes))
+    Ok(Packed {
+        placements,
+        failure_codes,
+        cuts,
+    })
 }
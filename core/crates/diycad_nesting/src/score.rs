@@ -1,22 +1,31 @@
-use crate::model::PlacementRect;
-use craftcad_serialize::{NestJob, NestMetrics, PartPlacementStatus, PartPlacementStatusKind};
+use crate::model::{PartEval, PlacementRect};
+use craftcad_serialize::{
+    Cut, GrainPolicy, MaterialMetrics, NestJob, NestMetrics, PartPlacementStatus,
+    PartPlacementStatusKind,
+};
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 pub fn compute_metrics(
     job: &NestJob,
+    parts: &[PartEval],
     placements: &[PlacementRect],
+    cuts: &[Cut],
     statuses: &[PartPlacementStatus],
 ) -> NestMetrics {
     let mut sheet_area = vec![];
+    let mut sheet_material = vec![];
     for s in &job.sheet_defs {
         for _ in 0..s.quantity {
             sheet_area.push(s.width * s.height);
+            sheet_material.push(s.material_id);
         }
     }
     let mut used = vec![0.0f64; sheet_area.len()];
     for p in placements {
         let idx = p.sheet_instance_index as usize;
         if idx < used.len() {
-            used[idx] += p.width * p.height;
+            used[idx] += p.area;
         }
     }
     let utilization_per_sheet = used
@@ -25,20 +34,92 @@ pub fn compute_metrics(
         .map(|(u, a)| if *a > 0.0 { u / a } else { 0.0 })
         .collect::<Vec<_>>();
     let sheet_count_used = used.iter().filter(|v| **v > 0.0).count() as u32;
-    let cut_count_estimate = placements.len() as u32 * 4;
+    let (cut_count_estimate, guillotine_cut_length) = if job.constraints.guillotine {
+        let length = cuts
+            .iter()
+            .map(|c| (c.to.x - c.from.x).hypot(c.to.y - c.from.y))
+            .sum();
+        (cuts.len() as u32, Some(length))
+    } else {
+        (placements.len() as u32 * 4, None)
+    };
     let unplaced = statuses
         .iter()
         .filter(|s| matches!(s.status, PartPlacementStatusKind::Unplaced))
         .count() as f64;
+    let misaligned = if matches!(job.constraints.grain_policy, GrainPolicy::Prefer) {
+        placements.iter().filter(|p| p.grain_misaligned).count() as f64
+    } else {
+        0.0
+    };
     let score = job.objective.w_utilization * utilization_per_sheet.iter().sum::<f64>()
         - job.objective.w_sheet_count * sheet_count_used as f64
         - job.objective.w_cut_count * cut_count_estimate as f64
-        - unplaced * 1_000.0;
+        - unplaced * 1_000.0
+        - job.objective.w_grain_misaligned * misaligned;
 
     NestMetrics {
         utilization_per_sheet,
         sheet_count_used,
         cut_count_estimate,
+        guillotine_cut_length,
         score,
+        per_material: per_material(parts, statuses, &sheet_material, &sheet_area, &used),
     }
 }
+
+fn slot(out: &mut BTreeMap<Uuid, (MaterialMetrics, f64)>, id: Uuid) -> &mut (MaterialMetrics, f64) {
+    out.entry(id).or_insert_with(|| {
+        let m = MaterialMetrics {
+            material_id: id,
+            sheet_count_used: 0,
+            utilization: 0.0,
+            placed_count: 0,
+            unplaced_count: 0,
+        };
+        (m, 0.0)
+    })
+}
+
+/// Groups sheet usage and part outcomes by material. Materials without
+/// sheets still appear when they have unplaced parts.
+fn per_material(
+    parts: &[PartEval],
+    statuses: &[PartPlacementStatus],
+    sheet_material: &[Uuid],
+    sheet_area: &[f64],
+    used: &[f64],
+) -> Vec<MaterialMetrics> {
+    // Per material: metrics with utilization holding the used part area,
+    // and the area of its used sheets.
+    let mut out = BTreeMap::new();
+    for ((&id, &area), &u) in sheet_material.iter().zip(sheet_area).zip(used) {
+        let (m, used_area) = slot(&mut out, id);
+        if u > 0.0 {
+            m.sheet_count_used += 1;
+            m.utilization += u;
+            *used_area += area;
+        }
+    }
+    let material: BTreeMap<Uuid, Uuid> = parts.iter().map(|p| (p.part_id, p.material_id)).collect();
+    for s in statuses {
+        let Some(&id) = material.get(&s.part_id) else {
+            continue;
+        };
+        let (m, _) = slot(&mut out, id);
+        match s.status {
+            PartPlacementStatusKind::Placed => m.placed_count += 1,
+            PartPlacementStatusKind::Unplaced => m.unplaced_count += 1,
+        }
+    }
+    out.into_values()
+        .map(|(mut m, used_area)| {
+            m.utilization = if used_area > 0.0 {
+                m.utilization / used_area
+            } else {
+                0.0
+            };
+            m
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+//! Simulated annealing over placement sequences.
+//!
+//! A candidate is an order of the part instances plus, per instance, the
+//! rotation the packer tries first. The first candidate is the greedy
+//! order from `expand_parts` with no rotation hints, so more iterations can
+//! only improve on it.
+
+use crate::constraints::rotations;
+use crate::model::{DeterministicRng, PartEval};
+use craftcad_serialize::NestConstraints;
+
+/// Temperature at the first iteration, in score units. One sheet at unit
+/// weight is worth 1.0.
+const START_TEMPERATURE: f64 = 0.5;
+
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub order: Vec<usize>,
+    pub hints: Vec<Option<f64>>,
+}
+
+impl Candidate {
+    pub fn greedy(parts: &[PartEval]) -> Self {
+        Self {
+            order: (0..parts.len()).collect(),
+            hints: vec![None; parts.len()],
+        }
+    }
+
+    /// The parts in candidate order with their rotation hints.
+    pub fn parts(&self, parts: &[PartEval]) -> Vec<PartEval> {
+        self.order
+            .iter()
+            .map(|&i| PartEval {
+                rotation_hint: self.hints[i],
+                ..parts[i].clone()
+            })
+            .collect()
+    }
+
+    /// Swaps two parts, moves one part elsewhere in the order, or changes
+    /// one part's rotation hint.
+    pub fn neighbour(
+        &self,
+        parts: &[PartEval],
+        c: &NestConstraints,
+        rng: &mut DeterministicRng,
+    ) -> Self {
+        let mut next = self.clone();
+        let n = parts.len();
+        if n == 0 {
+            return next;
+        }
+        let a = pick(rng, n);
+        match pick(rng, 3) {
+            0 if n > 1 => {
+                let b = pick(rng, n);
+                next.order.swap(a, b);
+            }
+            1 if n > 1 => {
+                let part = next.order.remove(a);
+                next.order.insert(pick(rng, n), part);
+            }
+            _ => {
+                let rots = rotations(&parts[a], c);
+                let k = pick(rng, rots.len() + 1);
+                next.hints[a] = rots.get(k).copied();
+            }
+        }
+        next
+    }
+}
+
+fn pick(rng: &mut DeterministicRng, n: usize) -> usize {
+    rng.next_u32() as usize % n
+}
+
+/// Whether to move from a candidate scoring `current` to one scoring
+/// `next`, with the temperature falling linearly to zero over the run.
+pub fn accept(
+    current: f64,
+    next: f64,
+    iter: u32,
+    iterations: u32,
+    rng: &mut DeterministicRng,
+) -> bool {
+    if next >= current {
+        return true;
+    }
+    let t = START_TEMPERATURE * (1.0 - iter as f64 / iterations as f64);
+    if t <= 0.0 {
+        return false;
+    }
+    let u = rng.next_u32() as f64 / (u32::MAX as f64 + 1.0);
+    u < ((next - current) / t).exp()
+}
@@ -57,11 +57,13 @@ fn base_doc(part_w: f64, part_h: f64, margin: f64, kerf: f64) -> (Document, Uuid
                     allow_rotate_default: true,
                     no_go_zones: vec![],
                     grain_policy: GrainPolicy::Ignore,
+                    guillotine: false,
                 },
                 objective: NestObjective {
                     w_utilization: 1.0,
                     w_sheet_count: 1.0,
                     w_cut_count: 0.0,
+                    w_grain_misaligned: 1.0,
                 },
                 seed: 7,
                 result: None,
@@ -79,6 +81,9 @@ fn base_doc(part_w: f64, part_h: f64, margin: f64, kerf: f64) -> (Document, Uuid
             used_presets: vec![],
             used_templates: vec![],
             wizard_runs: vec![],
+            selection_sets: vec![],
+            blocks: vec![],
+            block_instances: vec![],
         },
         job,
     )
@@ -159,3 +164,315 @@ fn margin_kerf_infeasible_reason() {
         "NEST_NO_FEASIBLE_POSITION_WITH_MARGIN_AND_KERF"
     );
 }
+
+fn poly(points: &[(f64, f64)]) -> Vec<Vec2> {
+    points.iter().map(|&(x, y)| Vec2 { x, y }).collect()
+}
+
+fn run(doc: &Document) -> NestResultV1 {
+    run_nesting(
+        &doc.jobs[0],
+        doc,
+        &EpsilonPolicy::default(),
+        RunLimits {
+            time_limit_ms: 100,
+            iteration_limit: 1,
+        },
+    )
+    .unwrap()
+    .0
+}
+
+#[test]
+fn l_shapes_interlock_on_a_tight_sheet() {
+    let (mut doc, _) = base_doc(10.0, 10.0, 0.0, 0.0);
+    doc.parts[0].outline = Polygon2D {
+        outer: poly(&[
+            (0.0, 0.0),
+            (20.0, 0.0),
+            (20.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 20.0),
+            (0.0, 20.0),
+        ]),
+        holes: vec![],
+    };
+    doc.parts[0].quantity = 2;
+    doc.jobs[0].sheet_defs[0].width = 30.0;
+    let out = run(&doc);
+    assert_eq!(
+        out.placements.len(),
+        2,
+        "bounding boxes alone would not fit"
+    );
+    let second = &out.placements[1];
+    assert_eq!(second.rotation_deg, 180.0);
+    assert_eq!((second.bbox.min_x, second.bbox.min_y), (10.0, 0.0));
+}
+
+#[test]
+fn small_part_nests_inside_a_hole() {
+    let (mut doc, _) = base_doc(40.0, 40.0, 0.0, 0.0);
+    doc.parts[0].outline.holes = vec![poly(&[
+        (10.0, 10.0),
+        (30.0, 10.0),
+        (30.0, 30.0),
+        (10.0, 30.0),
+    ])];
+    let mut small = doc.parts[0].clone();
+    small.id = Uuid::new_v4();
+    small.outline = rect(10.0, 10.0);
+    doc.jobs[0].parts_ref.push(PartRef {
+        part_id: small.id,
+        quantity_override: None,
+    });
+    doc.parts.push(small);
+    doc.jobs[0].sheet_defs[0].width = 40.0;
+    doc.jobs[0].sheet_defs[0].height = 40.0;
+    let out = run(&doc);
+    assert_eq!(out.placements.len(), 2);
+    let inner = &out.placements[1];
+    assert_eq!((inner.bbox.min_x, inner.bbox.min_y), (10.0, 10.0));
+    assert_eq!(out.metrics.sheet_count_used, 1);
+}
+
+#[test]
+fn strict_grain_restricts_rotations_and_prefer_penalises() {
+    let (mut doc, _) = base_doc(30.0, 10.0, 0.0, 0.0);
+    doc.parts[0].grain_dir = Some(90.0);
+    doc.jobs[0].sheet_defs[0].width = 40.0;
+    doc.jobs[0].constraints.grain_policy = GrainPolicy::Strict;
+    let out = run(&doc);
+    let reason = out.per_part_status[0].reason.as_ref().unwrap();
+    assert_eq!(reason.code, "NEST_GRAIN_CONSTRAINT_BLOCKS_FIT");
+
+    // Across the grain is the only way onto the sheet.
+    doc.jobs[0].constraints.grain_policy = GrainPolicy::Ignore;
+    let ignored = run(&doc);
+    doc.jobs[0].constraints.grain_policy = GrainPolicy::Prefer;
+    let preferred = run(&doc);
+    assert_eq!(preferred.placements[0].rotation_deg, 0.0);
+    assert_eq!(preferred.metrics.score, ignored.metrics.score - 1.0);
+    doc.jobs[0].objective.w_grain_misaligned = 2.5;
+    let weighted = run(&doc);
+    assert_eq!(weighted.metrics.score, ignored.metrics.score - 2.5);
+    doc.jobs[0].objective.w_grain_misaligned = 1.0;
+
+    // With room to turn, the aligned rotation wins.
+    doc.jobs[0].sheet_defs[0].height = 40.0;
+    let turned = run(&doc);
+    assert_eq!(turned.placements[0].rotation_deg, 90.0);
+}
+
+#[test]
+fn no_go_zones_are_kept_clear() {
+    let (mut doc, _) = base_doc(10.0, 10.0, 0.0, 0.0);
+    doc.jobs[0].constraints.no_go_zones = vec![NoGoZone::Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 10.0,
+        height: 10.0,
+    }];
+    let out = run(&doc);
+    assert_eq!(
+        (out.placements[0].bbox.min_x, out.placements[0].bbox.min_y),
+        (10.0, 0.0)
+    );
+
+    doc.jobs[0].constraints.no_go_zones = vec![NoGoZone::Rect {
+        x: 5.0,
+        y: 5.0,
+        width: 10.0,
+        height: 10.0,
+    }];
+    let out = run(&doc);
+    let reason = out.per_part_status[0].reason.as_ref().unwrap();
+    assert_eq!(reason.code, "NEST_NO_GO_ZONE_BLOCKS_FIT");
+}
+
+#[test]
+fn parts_only_go_on_sheets_of_their_material_and_thickness() {
+    let (mut doc, _) = base_doc(10.0, 10.0, 0.0, 0.0);
+    let ply = doc.materials[0].id;
+    doc.materials[0].thickness_mm = Some(18.0);
+    doc.parts[0].thickness = 18.0;
+    let mut material = |name: &str, thickness: f64| {
+        let mut m = doc.materials[0].clone();
+        m.id = Uuid::new_v4();
+        m.name = name.into();
+        m.thickness_mm = Some(thickness);
+        doc.materials.push(m.clone());
+        m.id
+    };
+    let (back, mdf) = (material("back", 6.0), material("mdf", 12.0));
+    let mut add_part = |material_id: Uuid, thickness: f64| {
+        let mut p = doc.parts[0].clone();
+        p.id = Uuid::new_v4();
+        p.material_id = material_id;
+        p.thickness = thickness;
+        doc.jobs[0].parts_ref.push(PartRef {
+            part_id: p.id,
+            quantity_override: None,
+        });
+        doc.parts.push(p);
+        doc.parts.last().unwrap().id
+    };
+    let back_part = add_part(back, 6.0);
+    let thin_ply = add_part(ply, 6.0);
+    let mdf_part = add_part(mdf, 12.0);
+    let mut back_sheet = doc.jobs[0].sheet_defs[0].clone();
+    back_sheet.id = Uuid::new_v4();
+    back_sheet.material_id = back;
+    doc.jobs[0].sheet_defs.push(back_sheet);
+
+    let out = run(&doc);
+    let placed: Vec<(Uuid, u32)> = out
+        .placements
+        .iter()
+        .map(|p| (p.part_id, p.sheet_instance_index))
+        .collect();
+    assert!(placed.contains(&(doc.parts[0].id, 0)));
+    assert!(placed.contains(&(back_part, 1)));
+    for id in [thin_ply, mdf_part] {
+        let status = out.per_part_status.iter().find(|s| s.part_id == id);
+        let reason = status.unwrap().reason.as_ref().unwrap();
+        assert_eq!(reason.code, "NEST_NO_SHEET_FOR_MATERIAL");
+    }
+
+    let per: Vec<(Uuid, u32, u32, u32)> = out
+        .metrics
+        .per_material
+        .iter()
+        .map(|m| {
+            (
+                m.material_id,
+                m.sheet_count_used,
+                m.placed_count,
+                m.unplaced_count,
+            )
+        })
+        .collect();
+    let mut expected = vec![(ply, 1, 1, 1), (back, 1, 1, 0), (mdf, 0, 0, 1)];
+    expected.sort();
+    assert_eq!(per, expected);
+    for m in &out.metrics.per_material {
+        let expected = if m.material_id == mdf { 0.0 } else { 0.25 };
+        assert_eq!(m.utilization, expected);
+    }
+}
+
+#[test]
+fn search_improves_on_the_greedy_order_with_more_iterations() {
+    let (mut doc, _) = base_doc(6.0, 16.0, 0.0, 0.0);
+    for (w, h) in [(10.0, 20.0), (8.0, 20.0), (16.0, 4.0)] {
+        let mut p = doc.parts[0].clone();
+        p.id = Uuid::new_v4();
+        p.outline = rect(w, h);
+        doc.jobs[0].parts_ref.push(PartRef {
+            part_id: p.id,
+            quantity_override: None,
+        });
+        doc.parts.push(p);
+    }
+    let run_for = |iteration_limit| {
+        run_nesting(
+            &doc.jobs[0],
+            &doc,
+            &EpsilonPolicy::default(),
+            RunLimits {
+                time_limit_ms: 60_000,
+                iteration_limit,
+            },
+        )
+        .unwrap()
+    };
+    let (greedy, _) = run_for(1);
+    assert_eq!(
+        greedy.placements.len(),
+        2,
+        "the two tall parts leave a sliver"
+    );
+    let (searched, trace) = run_for(100);
+    assert_eq!(searched.placements.len(), 3);
+    assert!(trace.best_updates.len() > 1);
+    assert!(trace
+        .best_updates
+        .windows(2)
+        .all(|w| w[0].iter < w[1].iter && w[0].score < w[1].score));
+    let (again, _) = run_for(100);
+    assert_eq!(
+        serde_json::to_value(searched).unwrap(),
+        serde_json::to_value(again).unwrap()
+    );
+}
+
+#[test]
+fn guillotine_mode_emits_an_ordered_cut_sequence() {
+    // With 1 mm kerf each part grows to 20x10 or 10x10.
+    let (mut doc, _) = base_doc(18.0, 8.0, 0.0, 0.0);
+    doc.parts[0].quantity = 2;
+    doc.parts[0].allow_rotate = false;
+    let mut small = doc.parts[0].clone();
+    small.id = Uuid::new_v4();
+    small.outline = rect(8.0, 8.0);
+    doc.jobs[0].parts_ref.push(PartRef {
+        part_id: small.id,
+        quantity_override: None,
+    });
+    doc.parts.push(small);
+    let job = &mut doc.jobs[0];
+    job.sheet_defs[0].width = 30.0;
+    job.constraints.allow_rotate_default = false;
+    job.constraints.global_kerf = 1.0;
+
+    let free = run(&doc);
+    assert_eq!(free.placements.len(), 4);
+    assert!(free.cut_sequence.is_empty());
+    assert_eq!(free.metrics.cut_count_estimate, 16);
+    assert_eq!(free.metrics.guillotine_cut_length, None);
+
+    doc.jobs[0].constraints.guillotine = true;
+    let out = run(&doc);
+    assert_eq!(out.placements.len(), 4);
+    let cuts = out
+        .cut_sequence
+        .iter()
+        .map(|c| (c.kind, (c.from.x, c.from.y), (c.to.x, c.to.y)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cuts,
+        vec![
+            (CutKind::Rip, (0.0, 10.0), (30.0, 10.0)),
+            (CutKind::Crosscut, (20.0, 0.0), (20.0, 10.0)),
+            (CutKind::Crosscut, (20.0, 10.0), (20.0, 20.0)),
+        ]
+    );
+    assert!(out.cut_sequence.iter().all(|c| c.kerf == 1.0));
+    assert_eq!(out.metrics.cut_count_estimate, 3);
+    assert_eq!(out.metrics.guillotine_cut_length, Some(50.0));
+}
+
+#[test]
+fn guillotine_mode_does_not_nest_into_holes() {
+    let (mut doc, _) = base_doc(40.0, 40.0, 0.0, 0.0);
+    doc.parts[0].outline.holes = vec![poly(&[
+        (10.0, 10.0),
+        (30.0, 10.0),
+        (30.0, 30.0),
+        (10.0, 30.0),
+    ])];
+    let mut small = doc.parts[0].clone();
+    small.id = Uuid::new_v4();
+    small.outline = rect(10.0, 10.0);
+    doc.jobs[0].parts_ref.push(PartRef {
+        part_id: small.id,
+        quantity_override: None,
+    });
+    doc.parts.push(small);
+    doc.jobs[0].sheet_defs[0].width = 40.0;
+    doc.jobs[0].sheet_defs[0].height = 40.0;
+    doc.jobs[0].constraints.guillotine = true;
+    let out = run(&doc);
+    assert_eq!(out.placements.len(), 1);
+    assert!(out.cut_sequence.is_empty(), "the frame fills the sheet");
+}
@@ -0,0 +1,366 @@
+//! Typed HUD input: lengths with units, arithmetic with variables, and
+//! absolute, relative (`@dx,dy`) or relative polar (`@dist<deg`) points.
+//! Unitless numbers are in document units.
+
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{Reason, ReasonCode, Result};
+use std::collections::BTreeMap;
+
+const MAX_INPUT_CHARS: usize = 256;
+const MAX_TOKENS: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HudInput {
+    /// A bare length or angle, in document units or degrees.
+    Value(f64),
+    Point(Pt),
+    /// Offset from the last picked point.
+    Relative(Pt),
+}
+
+impl HudInput {
+    /// Absolute point of a point input; relative input needs `last`.
+    pub fn point(&self, last: Option<Pt>) -> Result<Pt> {
+        match *self {
+            HudInput::Value(_) => Err(Reason::from_code(ReasonCode::DrawInsufficientInput)),
+            HudInput::Point(p) => Ok(p),
+            HudInput::Relative(d) => {
+                let base =
+                    last.ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+                Ok(Pt {
+                    x: base.x + d.x,
+                    y: base.y + d.y,
+                })
+            }
+        }
+    }
+}
+
+/// Millimetres per unit for lengths, degrees per unit for angles.
+fn unit_factor(unit: &str, angle: bool) -> Option<f64> {
+    if angle {
+        return match unit {
+            "deg" | "°" => Some(1.0),
+            "rad" => Some(180.0 / std::f64::consts::PI),
+            _ => None,
+        };
+    }
+    match unit {
+        "mm" => Some(1.0),
+        "cm" => Some(10.0),
+        "m" => Some(1000.0),
+        "in" | "inch" | "\"" => Some(25.4),
+        "ft" | "'" => Some(304.8),
+        _ => None,
+    }
+}
+
+fn input_error(code: ReasonCode, input: &str) -> Reason {
+    let mut r = Reason::from_code(code);
+    r.debug.insert("input".into(), serde_json::json!(input));
+    r
+}
+
+fn syntax(input: &str) -> Reason {
+    input_error(ReasonCode::DrawInputSyntax, input)
+}
+
+fn named(code: ReasonCode, key: &str, value: &str) -> Reason {
+    let mut r = Reason::from_code(code);
+    r.params.insert(key.into(), serde_json::json!(value));
+    r
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Neg,
+    Mul,
+    Div,
+    LParen,
+    RParen,
+}
+
+/// Reads an unsigned integer or decimal starting at `i`.
+fn read_number(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while *i < chars.len() && (chars[*i].is_ascii_digit() || chars[*i] == '.') {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect()
+}
+
+/// Reads `num/den` at `i` when both are integers, leaving `i` untouched
+/// otherwise.
+fn read_fraction(chars: &[char], i: &mut usize) -> Option<(f64, f64)> {
+    let mut j = *i;
+    let num = read_number(chars, &mut j);
+    if num.is_empty() || num.contains('.') || chars.get(j) != Some(&'/') {
+        return None;
+    }
+    j += 1;
+    let den = read_number(chars, &mut j);
+    if den.is_empty() || den.contains('.') {
+        return None;
+    }
+    *i = j;
+    Some((num.parse().ok()?, den.parse().ok()?))
+}
+
+/// Reads a unit suffix after a number: a quote mark, or a letter run
+/// (optionally after spaces) that names a known unit.
+fn read_unit(chars: &[char], i: &mut usize, angle: bool) -> Option<String> {
+    if let Some(c @ ('"' | '\'' | '°')) = chars.get(*i) {
+        *i += 1;
+        return Some(c.to_string());
+    }
+    let mut j = *i;
+    while j < chars.len() && chars[j] == ' ' {
+        j += 1;
+    }
+    let start = j;
+    while j < chars.len() && chars[j].is_ascii_alphabetic() {
+        j += 1;
+    }
+    let word: String = chars[start..j].iter().collect();
+    if word.is_empty() {
+        return None;
+    }
+    // Directly attached words are always units; spaced ones only when known.
+    if start == *i || unit_factor(&word, angle).is_some() {
+        *i = j;
+        return Some(word);
+    }
+    None
+}
+
+/// Tokenizes `expr`, converting each literal with a unit to `doc_factor`
+/// units. A whole number followed by a fraction (`4 3/4`) is one literal,
+/// and a feet literal followed by a number adds them (`5'6"`).
+fn tokenize(expr: &str, doc_factor: f64, angle: bool) -> Result<Vec<Tok>> {
+    let chars: Vec<char> = expr.chars().collect();
+    if chars.len() > MAX_INPUT_CHARS {
+        return Err(syntax(expr));
+    }
+    let mut toks = vec![];
+    let mut after_feet = false;
+    let mut i = 0usize;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let operand_expected = matches!(
+            toks.last(),
+            None | Some(Tok::Plus | Tok::Minus | Tok::Neg | Tok::Mul | Tok::Div | Tok::LParen)
+        );
+        let was_feet = std::mem::take(&mut after_feet);
+        match c {
+            '+' => toks.push(Tok::Plus),
+            '-' if operand_expected => toks.push(Tok::Neg),
+            '-' => toks.push(Tok::Minus),
+            '*' => toks.push(Tok::Mul),
+            '/' => toks.push(Tok::Div),
+            '(' => toks.push(Tok::LParen),
+            ')' => toks.push(Tok::RParen),
+            _ if c.is_ascii_digit() || c == '.' => {
+                let (mut value, whole) = match read_fraction(&chars, &mut i) {
+                    Some((n, d)) => (n / d, false),
+                    None => {
+                        let s = read_number(&chars, &mut i);
+                        (
+                            s.parse::<f64>().map_err(|_| syntax(expr))?,
+                            !s.contains('.'),
+                        )
+                    }
+                };
+                // Mixed number: a whole number, spaces, then a fraction.
+                let mut j = i;
+                while j < chars.len() && chars[j] == ' ' {
+                    j += 1;
+                }
+                if whole && j > i {
+                    if let Some((n, d)) = read_fraction(&chars, &mut j) {
+                        value += n / d;
+                        i = j;
+                    }
+                }
+                if !value.is_finite() {
+                    return Err(input_error(ReasonCode::DrawInvalidNumeric, expr));
+                }
+                if let Some(unit) = read_unit(&chars, &mut i, angle) {
+                    let f = unit_factor(&unit, angle)
+                        .ok_or_else(|| named(ReasonCode::DrawInputUnknownUnit, "unit", &unit))?;
+                    value *= f / doc_factor;
+                    after_feet = !angle && f == 304.8;
+                }
+                if was_feet && !operand_expected {
+                    toks.push(Tok::Plus);
+                }
+                toks.push(Tok::Num(value));
+                if toks.len() > MAX_TOKENS {
+                    return Err(syntax(expr));
+                }
+                continue;
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                toks.push(Tok::Ident(chars[start..i].iter().collect()));
+                continue;
+            }
+            _ => return Err(syntax(expr)),
+        }
+        i += 1;
+        if toks.len() > MAX_TOKENS {
+            return Err(syntax(expr));
+        }
+    }
+    Ok(toks)
+}
+
+fn prec(t: &Tok) -> i32 {
+    match t {
+        Tok::Plus | Tok::Minus => 1,
+        Tok::Mul | Tok::Div => 2,
+        Tok::Neg => 3,
+        _ => 0,
+    }
+}
+
+fn to_rpn(toks: Vec<Tok>, expr: &str) -> Result<Vec<Tok>> {
+    let mut out = vec![];
+    let mut st: Vec<Tok> = vec![];
+    for t in toks {
+        match t {
+            Tok::Num(_) | Tok::Ident(_) => out.push(t),
+            // Prefix operators bind to what follows; nothing to pop yet.
+            Tok::Neg => st.push(t),
+            Tok::Plus | Tok::Minus | Tok::Mul | Tok::Div => {
+                while let Some(top) = st.last() {
+                    if prec(top) > 0 && prec(top) >= prec(&t) {
+                        out.push(st.pop().expect("operator present"));
+                    } else {
+                        break;
+                    }
+                }
+                st.push(t);
+            }
+            Tok::LParen => st.push(t),
+            Tok::RParen => loop {
+                match st.pop() {
+                    Some(Tok::LParen) => break,
+                    Some(top) => out.push(top),
+                    None => return Err(syntax(expr)),
+                }
+            },
+        }
+    }
+    while let Some(t) = st.pop() {
+        if matches!(t, Tok::LParen) {
+            return Err(syntax(expr));
+        }
+        out.push(t);
+    }
+    Ok(out)
+}
+
+fn doc_unit_factor(doc_units: &str) -> Result<f64> {
+    match doc_units {
+        "mm" => Ok(1.0),
+        "inch" => Ok(25.4),
+        other => Err(named(ReasonCode::DrawInputUnknownUnit, "unit", other)),
+    }
+}
+
+fn eval_in(expr: &str, doc_factor: f64, angle: bool, vars: &BTreeMap<String, f64>) -> Result<f64> {
+    let rpn = to_rpn(tokenize(expr, doc_factor, angle)?, expr)?;
+    let mut st: Vec<f64> = vec![];
+    for t in rpn {
+        match t {
+            Tok::Num(n) => st.push(n),
+            Tok::Ident(k) => {
+                let v = vars
+                    .get(&k)
+                    .ok_or_else(|| named(ReasonCode::DrawInputUnknownVariable, "name", &k))?;
+                st.push(*v);
+            }
+            Tok::Neg => {
+                let a = st.pop().ok_or_else(|| syntax(expr))?;
+                st.push(-a);
+            }
+            Tok::Plus | Tok::Minus | Tok::Mul | Tok::Div => {
+                let b = st.pop().ok_or_else(|| syntax(expr))?;
+                let a = st.pop().ok_or_else(|| syntax(expr))?;
+                st.push(match t {
+                    Tok::Plus => a + b,
+                    Tok::Minus => a - b,
+                    Tok::Mul => a * b,
+                    _ => a / b,
+                });
+            }
+            Tok::LParen | Tok::RParen => unreachable!(),
+        }
+    }
+    match st.as_slice() {
+        [v] if v.is_finite() => Ok(*v),
+        [_] => Err(input_error(ReasonCode::DrawInvalidNumeric, expr)),
+        _ => Err(syntax(expr)),
+    }
+}
+
+/// Evaluates a length expression in `doc_units` (`"mm"` or `"inch"`).
+/// `vars` hold values already in document units.
+pub fn eval_length(expr: &str, doc_units: &str, vars: &BTreeMap<String, f64>) -> Result<f64> {
+    eval_in(expr, doc_unit_factor(doc_units)?, false, vars)
+}
+
+/// Parses a complete HUD entry: `x,y`, `@dx,dy`, `@dist<deg` or a single
+/// value, each part an expression. A leading `=` is allowed and ignored.
+pub fn parse(input: &str, doc_units: &str, vars: &BTreeMap<String, f64>) -> Result<HudInput> {
+    let factor = doc_unit_factor(doc_units)?;
+    let text = input.trim();
+    let text = text.strip_prefix('=').unwrap_or(text).trim();
+    let (relative, body) = match text.strip_prefix('@') {
+        Some(rest) => (true, rest.trim()),
+        None => (false, text),
+    };
+    if body.is_empty() {
+        return Err(syntax(input));
+    }
+    if let Some((dist, deg)) = body.split_once('<') {
+        let d = eval_in(dist, factor, false, vars)?;
+        let a = eval_in(deg, factor, true, vars)?.to_radians();
+        let off = Pt {
+            x: d * a.cos(),
+            y: d * a.sin(),
+        };
+        return Ok(if relative {
+            HudInput::Relative(off)
+        } else {
+            HudInput::Point(off)
+        });
+    }
+    let parts: Vec<&str> = body.split(',').collect();
+    match parts.as_slice() {
+        [v] if !relative => Ok(HudInput::Value(eval_in(v, factor, false, vars)?)),
+        [x, y] => {
+            let p = Pt {
+                x: eval_in(x, factor, false, vars)?,
+                y: eval_in(y, factor, false, vars)?,
+            };
+            Ok(if relative {
+                HudInput::Relative(p)
+            } else {
+                HudInput::Point(p)
+            })
+        }
+        _ => Err(syntax(input)),
+    }
+}
@@ -1,6 +1,20 @@
 #[derive(Clone, Debug)]
 pub enum InputEvent {
-    MouseDown { x: f64, y: f64 },
-    MouseMove { x: f64, y: f64 },
+    MouseDown {
+        x: f64,
+        y: f64,
+    },
+    MouseMove {
+        x: f64,
+        y: f64,
+    },
+    MouseUp {
+        x: f64,
+        y: f64,
+    },
     Key(char),
+    /// Abandons the tool step in progress.
+    Escape,
+    /// Ends a chained tool such as the line tool.
+    Enter,
 }
@@ -1,3 +1,6 @@
+#![allow(clippy::result_large_err)]
+
+pub mod hud;
 pub mod input;
 pub mod selection;
 pub mod serialize_bridge;
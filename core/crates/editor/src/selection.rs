@@ -1,3 +1,10 @@
+use craftcad_commands::commands::blocks::instance_entities;
+use craftcad_commands::commands::transform_selection::{Transform, TransformSelectionInput};
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{BlockInstance, Document, Entity, Reason, ReasonCode, Result};
+use diycad_geom::EpsilonPolicy;
+use uuid::Uuid;
+
 pub fn merge_selection(current: &mut Vec<String>, incoming: &[String], additive: bool) {
     if !additive {
         current.clear();
@@ -9,3 +16,294 @@ pub fn merge_selection(current: &mut Vec<String>, incoming: &[String], additive:
     }
     current.sort();
 }
+
+/// How a pick or region combines with the current selection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectMode {
+    #[default]
+    Replace,
+    Add,
+    Remove,
+    Toggle,
+}
+
+#[derive(Clone, Debug)]
+pub enum Region {
+    /// Entities fully inside the rectangle spanned by two corners.
+    Window(Pt, Pt),
+    /// Entities inside or touching the rectangle.
+    Crossing(Pt, Pt),
+    /// Entities fully inside the closed polygon.
+    Lasso(Vec<Pt>),
+    /// Entities inside or touching the closed polygon.
+    CrossingLasso(Vec<Pt>),
+}
+
+/// Which entities a selection may take. Empty lists allow everything;
+/// entities on locked or hidden layers are skipped unless included.
+#[derive(Clone, Debug, Default)]
+pub struct SelectionFilter {
+    pub layers: Vec<Uuid>,
+    /// Geometry kinds as tagged in the document (`"Line"`, `"Arc"`, ...).
+    pub kinds: Vec<String>,
+    /// Entities carrying at least one of these tags.
+    pub tags: Vec<String>,
+    pub include_locked: bool,
+    pub include_hidden: bool,
+}
+
+/// Geometry kind of `e` as tagged in the document JSON.
+pub fn entity_kind(e: &Entity) -> String {
+    serde_json::to_value(&e.geom)
+        .ok()
+        .and_then(|v| v.get("type").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_default()
+}
+
+impl SelectionFilter {
+    fn accepts_layer(&self, doc: &Document, layer_id: Uuid) -> bool {
+        let Some(layer) = doc.layers.iter().find(|l| l.id == layer_id) else {
+            return false;
+        };
+        if !self.include_hidden && !layer.visible {
+            return false;
+        }
+        if !self.include_locked && (layer.locked || !layer.editable) {
+            return false;
+        }
+        self.layers.is_empty() || self.layers.contains(&layer_id)
+    }
+
+    pub fn accepts(&self, doc: &Document, e: &Entity) -> bool {
+        self.accepts_layer(doc, e.layer_id)
+            && (self.kinds.is_empty() || self.kinds.contains(&entity_kind(e)))
+            && (self.tags.is_empty() || e.tags.iter().any(|t| self.tags.contains(t)))
+    }
+
+    /// Block instances select as a whole, by their own layer; they carry
+    /// no tags and have the kind `"BlockInstance"`.
+    pub fn accepts_instance(&self, doc: &Document, i: &BlockInstance) -> bool {
+        self.accepts_layer(doc, i.layer_id)
+            && (self.kinds.is_empty() || self.kinds.iter().any(|k| k == "BlockInstance"))
+            && self.tags.is_empty()
+    }
+}
+
+/// Accepted selectables in document order with their world geometry:
+/// entities, then block instances. Instances that cannot be resolved are
+/// skipped.
+fn selectables(doc: &Document, filter: &SelectionFilter) -> Vec<(Uuid, Vec<diycad_geom::Geom2D>)> {
+    let entities = doc
+        .entities
+        .iter()
+        .filter(|e| filter.accepts(doc, e))
+        .map(|e| (e.id, vec![diycad_geom::Geom2D::from(&e.geom)]));
+    let instances = doc
+        .block_instances
+        .iter()
+        .filter(|i| filter.accepts_instance(doc, i))
+        .filter_map(|i| {
+            let placed = instance_entities(doc, i.id).ok()?;
+            let geoms = placed.iter().map(|e| diycad_geom::Geom2D::from(&e.geom));
+            Some((i.id, geoms.collect()))
+        });
+    entities.chain(instances).collect()
+}
+
+/// Closest accepted entity to `p` within `radius`; ties go to the earlier
+/// entity.
+pub fn pick(
+    doc: &Document,
+    p: Pt,
+    radius: f64,
+    filter: &SelectionFilter,
+    eps: &EpsilonPolicy,
+) -> Option<Uuid> {
+    let q = diycad_geom::Vec2 { x: p.x, y: p.y };
+    let mut best: Option<(f64, Uuid)> = None;
+    for (id, geoms) in selectables(doc, filter) {
+        for g in &geoms {
+            let Ok(hit) = diycad_geom::project_point(g, q, eps) else {
+                continue;
+            };
+            if hit.dist <= radius && best.is_none_or(|(d, _)| hit.dist < d) {
+                best = Some((hit.dist, id));
+            }
+        }
+    }
+    best.map(|(_, id)| id)
+}
+
+fn cross(o: Pt, a: Pt, b: Pt) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+/// Whether segments `p` and `q` meet; `proper` only counts crossings
+/// through both interiors.
+fn segments_meet(p: (Pt, Pt), q: (Pt, Pt), proper: bool) -> bool {
+    let (d1, d2) = (cross(q.0, q.1, p.0), cross(q.0, q.1, p.1));
+    let (d3, d4) = (cross(p.0, p.1, q.0), cross(p.0, p.1, q.1));
+    if proper {
+        return d1 * d2 < 0.0 && d3 * d4 < 0.0;
+    }
+    let overlap = p.0.x.min(p.1.x) <= q.0.x.max(q.1.x)
+        && q.0.x.min(q.1.x) <= p.0.x.max(p.1.x)
+        && p.0.y.min(p.1.y) <= q.0.y.max(q.1.y)
+        && q.0.y.min(q.1.y) <= p.0.y.max(p.1.y);
+    overlap && d1 * d2 <= 0.0 && d3 * d4 <= 0.0
+}
+
+fn inside_polygon(poly: &[Pt], p: Pt) -> bool {
+    let mut inside = false;
+    let mut j = poly.len() - 1;
+    for i in 0..poly.len() {
+        let (a, b) = (poly[i], poly[j]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn edges(pts: &[Pt], closed: bool) -> Vec<(Pt, Pt)> {
+    let mut out: Vec<(Pt, Pt)> = pts.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed && pts.len() > 2 {
+        out.push((pts[pts.len() - 1], pts[0]));
+    }
+    out
+}
+
+/// Accepted entities in `region`, in document order. Curves are tested as
+/// polylines flattened to a thousandth of the region's size.
+pub fn select(
+    doc: &Document,
+    region: &Region,
+    filter: &SelectionFilter,
+    eps: &EpsilonPolicy,
+) -> Vec<Uuid> {
+    let (poly, crossing) = match region {
+        Region::Window(a, b) | Region::Crossing(a, b) => {
+            let (x0, x1) = (a.x.min(b.x), a.x.max(b.x));
+            let (y0, y1) = (a.y.min(b.y), a.y.max(b.y));
+            let rect = vec![
+                Pt { x: x0, y: y0 },
+                Pt { x: x1, y: y0 },
+                Pt { x: x1, y: y1 },
+                Pt { x: x0, y: y1 },
+            ];
+            (rect, matches!(region, Region::Crossing(..)))
+        }
+        Region::Lasso(pts) => (pts.clone(), false),
+        Region::CrossingLasso(pts) => (pts.clone(), true),
+    };
+    if poly.len() < 3 {
+        return vec![];
+    }
+    let border = edges(&poly, true);
+    let (min, max) = poly.iter().fold((poly[0], poly[0]), |(lo, hi), p| {
+        (
+            Pt {
+                x: lo.x.min(p.x),
+                y: lo.y.min(p.y),
+            },
+            Pt {
+                x: hi.x.max(p.x),
+                y: hi.y.max(p.y),
+            },
+        )
+    });
+    let tol = (craftcad_geom2d::dist(min, max) * 1e-3).max(eps.eq_dist);
+
+    let mut out = vec![];
+    let inside = |g: &diycad_geom::Geom2D| -> Option<bool> {
+        let Ok(diycad_geom::Geom2D::Polyline { pts, closed }) = diycad_geom::flatten(g, tol, eps)
+        else {
+            return None;
+        };
+        let pts: Vec<Pt> = pts.iter().map(|v| Pt { x: v.x, y: v.y }).collect();
+        let segs = edges(&pts, closed);
+        Some(if crossing {
+            pts.iter().any(|p| inside_polygon(&poly, *p))
+                || segs
+                    .iter()
+                    .any(|s| border.iter().any(|b| segments_meet(*s, *b, false)))
+        } else {
+            pts.iter().all(|p| inside_polygon(&poly, *p))
+                && !segs
+                    .iter()
+                    .any(|s| border.iter().any(|b| segments_meet(*s, *b, true)))
+        })
+    };
+    for (id, geoms) in selectables(doc, filter) {
+        let tests: Vec<bool> = geoms.iter().filter_map(&inside).collect();
+        // A block is crossed by any of its pieces but only windowed whole.
+        let hit = if crossing {
+            tests.iter().any(|t| *t)
+        } else {
+            !tests.is_empty() && tests.iter().all(|t| *t)
+        };
+        if hit {
+            out.push(id);
+        }
+    }
+    out
+}
+
+/// Combines `hits` into `current` and reports whether it changed.
+pub fn apply_mode(current: &mut Vec<String>, hits: &[Uuid], mode: SelectMode) -> bool {
+    let before = current.clone();
+    let hits: Vec<String> = hits.iter().map(Uuid::to_string).collect();
+    match mode {
+        SelectMode::Replace => merge_selection(current, &hits, false),
+        SelectMode::Add => merge_selection(current, &hits, true),
+        SelectMode::Remove => current.retain(|id| !hits.contains(id)),
+        SelectMode::Toggle => {
+            for id in hits {
+                match current.iter().position(|c| *c == id) {
+                    Some(i) => {
+                        current.remove(i);
+                    }
+                    None => current.push(id),
+                }
+            }
+            current.sort();
+        }
+    }
+    *current != before
+}
+
+/// Entity ids of a selection held as strings.
+pub fn selection_ids(selection: &[String]) -> Result<Vec<Uuid>> {
+    selection
+        .iter()
+        .map(|s| {
+            Uuid::parse_str(s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+        })
+        .collect()
+}
+
+/// Entities of the named set saved in `doc` that still exist, for loading
+/// into the editor selection.
+pub fn load_set(doc: &Document, name: &str) -> Option<Vec<String>> {
+    let set = doc.selection_sets.iter().find(|s| s.name == name)?;
+    let mut out: Vec<String> = set
+        .entity_ids
+        .iter()
+        .filter(|id| doc.entities.iter().any(|e| e.id == **id))
+        .map(Uuid::to_string)
+        .collect();
+    out.sort();
+    Some(out)
+}
+
+/// `TransformSelectionCommand` input for the editor selection.
+pub fn transform_input(
+    selection: &[String],
+    transform: Transform,
+) -> Result<TransformSelectionInput> {
+    Ok(TransformSelectionInput {
+        selection_ids: selection_ids(selection)?,
+        transform,
+    })
+}
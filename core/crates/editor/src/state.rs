@@ -1,3 +1,7 @@
+use craftcad_snapping::tracking::Tracker;
+use craftcad_snapping::types::SnapPoint;
+use uuid::Uuid;
+
 #[derive(Clone, Debug)]
 pub enum Mode {
     Select,
@@ -20,4 +24,27 @@ pub struct EditorState {
     pub grid_enabled: bool,
     pub angle_snap_enabled: bool,
     pub input_buffer: String,
+    /// Layer that drawing tools create entities on.
+    pub active_layer: Uuid,
+    /// Snap applied to `cursor_world`, for the front end's marker.
+    pub snapped: Option<SnapPoint>,
+    /// Points acquired for alignment tracking; the front end feeds these.
+    pub tracker: Tracker,
+}
+
+impl EditorState {
+    pub fn new(active_layer: Uuid) -> Self {
+        Self {
+            mode: Mode::Select,
+            selection: vec![],
+            cursor_world: craftcad_geom2d::Pt { x: 0.0, y: 0.0 },
+            snap_enabled: true,
+            grid_enabled: false,
+            angle_snap_enabled: false,
+            input_buffer: String::new(),
+            active_layer,
+            snapped: None,
+            tracker: Tracker::default(),
+        }
+    }
 }
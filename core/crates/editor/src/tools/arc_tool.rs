@@ -1,3 +1,150 @@
+use crate::hud::HudInput;
+use crate::input::InputEvent;
 use crate::state::EditorState;
+use crate::tools::{snap, typed, vec2, ToolContext, ToolEvent, Typed};
+use craftcad_commands::commands::create_shapes::{ArcParams, CreateArcCommand, CreateArcInput};
+use craftcad_commands::{Command, CommandContext};
+use craftcad_geom2d::{dist, Pt};
+use craftcad_serialize::{Geom2D, Reason, ReasonCode, Result};
 
-pub fn handle(_state: &mut EditorState) {}
+/// Click the center, then the start point, then a point on the end ray.
+/// The arc runs counter-clockwise from start to end. A typed value is the
+/// radius (toward the cursor) before the start point, then the swept angle
+/// in degrees.
+#[derive(Clone, Debug, Default)]
+pub struct ArcTool {
+    center: Option<Pt>,
+    start: Option<Pt>,
+    cursor: Option<Pt>,
+}
+
+fn angle(c: Pt, p: Pt) -> f64 {
+    (p.y - c.y).atan2(p.x - c.x)
+}
+
+impl ArcTool {
+    pub fn handle(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        ev: &InputEvent,
+    ) -> Result<ToolEvent> {
+        match typed(state, ctx, ev)? {
+            Typed::Pass => {}
+            Typed::Edited => return Ok(ToolEvent::Idle),
+            Typed::Entered(input) => {
+                let out = self.entered(state, ctx, input)?;
+                state.input_buffer.clear();
+                return Ok(out);
+            }
+        }
+        match *ev {
+            InputEvent::MouseMove { x, y } => {
+                self.cursor = Some(snap(state, ctx, Pt { x, y }, self.center));
+                Ok(if self.center.is_some() {
+                    ToolEvent::Preview
+                } else {
+                    ToolEvent::Idle
+                })
+            }
+            InputEvent::MouseDown { x, y } => {
+                let p = snap(state, ctx, Pt { x, y }, self.center);
+                self.place(state, ctx, p)
+            }
+            InputEvent::Escape => {
+                let pending = self.center.take().is_some();
+                self.start = None;
+                Ok(if pending {
+                    ToolEvent::Cancelled
+                } else {
+                    ToolEvent::Idle
+                })
+            }
+            InputEvent::MouseUp { .. } | InputEvent::Enter | InputEvent::Key(_) => {
+                Ok(ToolEvent::Idle)
+            }
+        }
+    }
+
+    fn entered(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        input: HudInput,
+    ) -> Result<ToolEvent> {
+        let insufficient = || Reason::from_code(ReasonCode::DrawInsufficientInput);
+        let HudInput::Value(v) = input else {
+            let p = input.point(self.start.or(self.center))?;
+            state.cursor_world = p;
+            return self.place(state, ctx, p);
+        };
+        let c = self.center.ok_or_else(insufficient)?;
+        match self.start {
+            None => {
+                let dir = self
+                    .cursor
+                    .filter(|p| dist(c, *p) > ctx.eps.eq_dist)
+                    .ok_or_else(insufficient)?;
+                let a = angle(c, dir);
+                let s = Pt {
+                    x: c.x + v * a.cos(),
+                    y: c.y + v * a.sin(),
+                };
+                self.place(state, ctx, s)
+            }
+            Some(s) => self.commit(state, c, s, angle(c, s) + v.to_radians()),
+        }
+    }
+
+    fn place(&mut self, state: &EditorState, ctx: &ToolContext, p: Pt) -> Result<ToolEvent> {
+        self.cursor = Some(p);
+        let Some(c) = self.center else {
+            self.center = Some(p);
+            return Ok(ToolEvent::Preview);
+        };
+        let Some(s) = self.start else {
+            if dist(c, p) <= ctx.eps.eq_dist {
+                return Ok(ToolEvent::Idle);
+            }
+            self.start = Some(p);
+            return Ok(ToolEvent::Preview);
+        };
+        self.commit(state, c, s, angle(c, p))
+    }
+
+    fn commit(&mut self, state: &EditorState, c: Pt, s: Pt, end_angle: f64) -> Result<ToolEvent> {
+        let mut cmd = CreateArcCommand::new(state.active_layer);
+        cmd.begin(&CommandContext)?;
+        cmd.update(CreateArcInput {
+            params: ArcParams::Center {
+                c: vec2(c),
+                r: dist(c, s),
+                start_angle: angle(c, s),
+                end_angle,
+                ccw: true,
+            },
+        })?;
+        let delta = cmd.commit()?;
+        self.center = None;
+        self.start = None;
+        Ok(ToolEvent::Committed(delta))
+    }
+
+    /// The radius line until the start point is picked, then the arc.
+    pub fn preview(&self) -> Option<Geom2D> {
+        let (c, p) = (self.center?, self.cursor?);
+        Some(match self.start {
+            None => Geom2D::Line {
+                a: vec2(c),
+                b: vec2(p),
+            },
+            Some(s) => Geom2D::Arc {
+                c: vec2(c),
+                r: dist(c, s),
+                start_angle: angle(c, s),
+                end_angle: angle(c, p),
+                ccw: true,
+            },
+        })
+    }
+}
@@ -1,3 +1,100 @@
+use crate::hud::HudInput;
+use crate::input::InputEvent;
 use crate::state::EditorState;
+use crate::tools::{snap, typed, vec2, ToolContext, ToolEvent, Typed};
+use craftcad_commands::commands::create_shapes::{
+    CircleParams, CreateCircleCommand, CreateCircleInput,
+};
+use craftcad_commands::{Command, CommandContext};
+use craftcad_geom2d::{dist, Pt};
+use craftcad_serialize::{Geom2D, Reason, ReasonCode, Result};
 
-pub fn handle(_state: &mut EditorState) {}
+/// Click the center, then a point on the circle. Once the center is
+/// placed, a typed value is the radius.
+#[derive(Clone, Debug, Default)]
+pub struct CircleTool {
+    center: Option<Pt>,
+    cursor: Option<Pt>,
+}
+
+impl CircleTool {
+    pub fn handle(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        ev: &InputEvent,
+    ) -> Result<ToolEvent> {
+        match typed(state, ctx, ev)? {
+            Typed::Pass => {}
+            Typed::Edited => return Ok(ToolEvent::Idle),
+            Typed::Entered(input) => {
+                let out = self.entered(state, input)?;
+                state.input_buffer.clear();
+                return Ok(out);
+            }
+        }
+        match *ev {
+            InputEvent::MouseMove { x, y } => {
+                self.cursor = Some(snap(state, ctx, Pt { x, y }, self.center));
+                Ok(if self.center.is_some() {
+                    ToolEvent::Preview
+                } else {
+                    ToolEvent::Idle
+                })
+            }
+            InputEvent::MouseDown { x, y } => {
+                let p = snap(state, ctx, Pt { x, y }, self.center);
+                self.place(state, p)
+            }
+            InputEvent::Escape => Ok(match self.center.take() {
+                Some(_) => ToolEvent::Cancelled,
+                None => ToolEvent::Idle,
+            }),
+            InputEvent::MouseUp { .. } | InputEvent::Enter | InputEvent::Key(_) => {
+                Ok(ToolEvent::Idle)
+            }
+        }
+    }
+
+    fn entered(&mut self, state: &mut EditorState, input: HudInput) -> Result<ToolEvent> {
+        if let HudInput::Value(r) = input {
+            let c = self
+                .center
+                .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+            return self.commit(state, c, r);
+        }
+        let p = input.point(self.center)?;
+        state.cursor_world = p;
+        self.place(state, p)
+    }
+
+    fn place(&mut self, state: &EditorState, p: Pt) -> Result<ToolEvent> {
+        self.cursor = Some(p);
+        match self.center {
+            None => {
+                self.center = Some(p);
+                Ok(ToolEvent::Preview)
+            }
+            Some(c) => self.commit(state, c, dist(c, p)),
+        }
+    }
+
+    fn commit(&mut self, state: &EditorState, c: Pt, r: f64) -> Result<ToolEvent> {
+        let mut cmd = CreateCircleCommand::new(state.active_layer);
+        cmd.begin(&CommandContext)?;
+        cmd.update(CreateCircleInput {
+            params: CircleParams::CenterRadius { c: vec2(c), r },
+        })?;
+        let delta = cmd.commit()?;
+        self.center = None;
+        Ok(ToolEvent::Committed(delta))
+    }
+
+    pub fn preview(&self) -> Option<Geom2D> {
+        let (c, p) = (self.center?, self.cursor?);
+        Some(Geom2D::Circle {
+            c: vec2(c),
+            r: dist(c, p),
+        })
+    }
+}
@@ -1,3 +1,112 @@
+use crate::hud::HudInput;
+use crate::input::InputEvent;
 use crate::state::EditorState;
+use crate::tools::{snap, typed, vec2, ToolContext, ToolEvent, Typed};
+use craftcad_commands::commands::create_line::{CreateLineCommand, CreateLineInput};
+use craftcad_commands::{Command, CommandContext};
+use craftcad_geom2d::{dist, Pt};
+use craftcad_serialize::{Geom2D, Reason, ReasonCode, Result};
 
-pub fn handle(_state: &mut EditorState) {}
+/// Click a start point, then click each next point; every click commits a
+/// line and starts the next one at its end. Enter or Escape ends the chain.
+/// Typed points are absolute or relative to the last point, and a typed
+/// length runs along the rubber band.
+#[derive(Clone, Debug, Default)]
+pub struct LineTool {
+    start: Option<Pt>,
+    cursor: Option<Pt>,
+}
+
+impl LineTool {
+    pub fn handle(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        ev: &InputEvent,
+    ) -> Result<ToolEvent> {
+        match typed(state, ctx, ev)? {
+            Typed::Pass => {}
+            Typed::Edited => return Ok(ToolEvent::Idle),
+            Typed::Entered(input) => {
+                let out = self.entered(state, ctx, input)?;
+                state.input_buffer.clear();
+                return Ok(out);
+            }
+        }
+        match *ev {
+            InputEvent::MouseMove { x, y } => {
+                self.cursor = Some(snap(state, ctx, Pt { x, y }, self.start));
+                Ok(if self.start.is_some() {
+                    ToolEvent::Preview
+                } else {
+                    ToolEvent::Idle
+                })
+            }
+            InputEvent::MouseDown { x, y } => {
+                let p = snap(state, ctx, Pt { x, y }, self.start);
+                self.place(state, ctx, p)
+            }
+            InputEvent::Escape => Ok(match self.start.take() {
+                Some(_) => ToolEvent::Cancelled,
+                None => ToolEvent::Idle,
+            }),
+            InputEvent::Enter => {
+                self.start = None;
+                Ok(ToolEvent::Idle)
+            }
+            InputEvent::MouseUp { .. } | InputEvent::Key(_) => Ok(ToolEvent::Idle),
+        }
+    }
+
+    fn entered(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        input: HudInput,
+    ) -> Result<ToolEvent> {
+        let p = match input {
+            HudInput::Value(len) => {
+                let (a, c) = self
+                    .start
+                    .zip(self.cursor)
+                    .filter(|(a, c)| dist(*a, *c) > ctx.eps.eq_dist)
+                    .ok_or_else(|| Reason::from_code(ReasonCode::DrawInsufficientInput))?;
+                let k = len / dist(a, c);
+                Pt {
+                    x: a.x + (c.x - a.x) * k,
+                    y: a.y + (c.y - a.y) * k,
+                }
+            }
+            _ => input.point(self.start)?,
+        };
+        state.cursor_world = p;
+        self.place(state, ctx, p)
+    }
+
+    fn place(&mut self, state: &EditorState, ctx: &ToolContext, p: Pt) -> Result<ToolEvent> {
+        self.cursor = Some(p);
+        let Some(a) = self.start else {
+            self.start = Some(p);
+            return Ok(ToolEvent::Preview);
+        };
+        if dist(a, p) <= ctx.eps.eq_dist {
+            return Ok(ToolEvent::Idle);
+        }
+        let mut cmd = CreateLineCommand::new(state.active_layer);
+        cmd.begin(&CommandContext)?;
+        cmd.update(CreateLineInput {
+            a: vec2(a),
+            b: vec2(p),
+        })?;
+        let delta = cmd.commit()?;
+        self.start = Some(p);
+        Ok(ToolEvent::Committed(delta))
+    }
+
+    pub fn preview(&self) -> Option<Geom2D> {
+        Some(Geom2D::Line {
+            a: vec2(self.start?),
+            b: vec2(self.cursor?),
+        })
+    }
+}
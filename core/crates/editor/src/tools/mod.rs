@@ -2,3 +2,165 @@ pub mod arc_tool;
 pub mod circle_tool;
 pub mod line_tool;
 pub mod select_tool;
+
+use crate::hud::{self, HudInput};
+use crate::input::InputEvent;
+use crate::state::{EditorState, Mode};
+use craftcad_commands::Delta;
+use craftcad_geom2d::math::round_step;
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{Document, Geom2D, Result, Vec2};
+use craftcad_snapping::candidates::{kind_score, CandidateSource, SnapQuery};
+use craftcad_snapping::resolve::{resolve, SnapPolicy};
+use craftcad_snapping::types::{SnapKind, SnapPoint};
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+
+/// Document view shared by the tools for one input event.
+pub struct ToolContext<'a> {
+    pub doc: &'a Document,
+    /// Snap candidates for `doc`; rebuild when the document changes.
+    pub snaps: &'a CandidateSource,
+    pub eps: &'a EpsilonPolicy,
+    /// Snap and pick radius around the cursor, in world units.
+    pub snap_radius: f64,
+    pub grid: f64,
+    pub angle_step_deg: f64,
+    /// Variables for typed expressions, in document units.
+    pub vars: &'a BTreeMap<String, f64>,
+}
+
+/// What a tool did with an event.
+pub enum ToolEvent {
+    Idle,
+    /// The preview geometry changed.
+    Preview,
+    /// A command was committed; push the delta onto the history.
+    Committed(Box<dyn Delta>),
+    SelectionChanged,
+    /// The step in progress was abandoned.
+    Cancelled,
+}
+
+pub(crate) enum Typed {
+    /// Not a typing event; the tool handles it.
+    Pass,
+    /// `EditorState.input_buffer` changed.
+    Edited,
+    Entered(HudInput),
+}
+
+/// Routes typing for the draw tools through `state.input_buffer`. Backspace
+/// edits, Escape clears a non-empty buffer and Enter parses it. The tool
+/// clears the buffer once it has used the input, so a failed entry stays
+/// for correction.
+pub(crate) fn typed(state: &mut EditorState, ctx: &ToolContext, ev: &InputEvent) -> Result<Typed> {
+    match *ev {
+        InputEvent::Key('\u{8}') => {
+            state.input_buffer.pop();
+            Ok(Typed::Edited)
+        }
+        InputEvent::Key(c) => {
+            state.input_buffer.push(c);
+            Ok(Typed::Edited)
+        }
+        InputEvent::Escape if !state.input_buffer.is_empty() => {
+            state.input_buffer.clear();
+            Ok(Typed::Edited)
+        }
+        InputEvent::Enter if !state.input_buffer.is_empty() => {
+            let input = hud::parse(&state.input_buffer, &ctx.doc.units, ctx.vars)?;
+            Ok(Typed::Entered(input))
+        }
+        _ => Ok(Typed::Pass),
+    }
+}
+
+pub(crate) fn vec2(p: Pt) -> Vec2 {
+    Vec2 { x: p.x, y: p.y }
+}
+
+/// Snaps `raw` with the toggles in `state` and stores the result as the
+/// editor cursor. `from` is the last point of the tool in progress.
+pub fn snap(state: &mut EditorState, ctx: &ToolContext, raw: Pt, from: Option<Pt>) -> Pt {
+    let policy = SnapPolicy {
+        max_dist: ctx.snap_radius,
+        angle_step_deg: if state.angle_snap_enabled {
+            ctx.angle_step_deg
+        } else {
+            0.0
+        },
+        prefer: vec![],
+    };
+    let grid = state.grid_enabled.then_some(ctx.grid);
+    let mut cands = vec![];
+    if state.snap_enabled {
+        let q = SnapQuery {
+            cursor: raw,
+            radius: ctx.snap_radius,
+            grid,
+            from,
+        };
+        cands = ctx.snaps.candidates(&q, ctx.eps);
+    } else if let Some(step) = grid.filter(|s| s.is_finite() && *s > 0.0) {
+        cands.push(SnapPoint {
+            kind: SnapKind::Grid,
+            pt: Pt {
+                x: round_step(raw.x, step),
+                y: round_step(raw.y, step),
+            },
+            score: kind_score(SnapKind::Grid),
+        });
+    }
+    if state.angle_snap_enabled || !state.tracker.acquired().is_empty() {
+        cands.extend(state.tracker.track(raw, from, &policy).snapped);
+    }
+    state.snapped = resolve(cands, raw, &policy).snapped;
+    state.cursor_world = state.snapped.map_or(raw, |s| s.pt);
+    state.cursor_world
+}
+
+/// The tool bound to an editor mode.
+pub enum ActiveTool {
+    Select(select_tool::SelectTool),
+    Line(line_tool::LineTool),
+    Circle(circle_tool::CircleTool),
+    Arc(arc_tool::ArcTool),
+}
+
+impl ActiveTool {
+    /// Fresh tool for `mode`, or `None` for modes driven by the edit ops.
+    pub fn for_mode(mode: &Mode) -> Option<Self> {
+        match mode {
+            Mode::Select => Some(Self::Select(Default::default())),
+            Mode::DrawLine => Some(Self::Line(Default::default())),
+            Mode::DrawCircle => Some(Self::Circle(Default::default())),
+            Mode::DrawArc => Some(Self::Arc(Default::default())),
+            _ => None,
+        }
+    }
+
+    pub fn handle(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        ev: &InputEvent,
+    ) -> Result<ToolEvent> {
+        match self {
+            Self::Select(t) => t.handle(state, ctx, ev),
+            Self::Line(t) => t.handle(state, ctx, ev),
+            Self::Circle(t) => t.handle(state, ctx, ev),
+            Self::Arc(t) => t.handle(state, ctx, ev),
+        }
+    }
+
+    /// Geometry to draw under the cursor while a step is in progress.
+    pub fn preview(&self) -> Option<Geom2D> {
+        match self {
+            Self::Select(t) => t.preview(),
+            Self::Line(t) => t.preview(),
+            Self::Circle(t) => t.preview(),
+            Self::Arc(t) => t.preview(),
+        }
+    }
+}
@@ -1,3 +1,124 @@
+use crate::input::InputEvent;
+use crate::selection::{apply_mode, pick, select, Region, SelectMode, SelectionFilter};
 use crate::state::EditorState;
+use crate::tools::{vec2, ToolContext, ToolEvent};
+use craftcad_geom2d::{dist, Pt};
+use craftcad_serialize::{Geom2D, Result};
+use uuid::Uuid;
 
-pub fn handle(_state: &mut EditorState) {}
+/// Click picks the closest entity within the snap radius. Dragging selects
+/// a window when moving right and a crossing when moving left. Escape
+/// abandons a drag, or else clears the selection.
+#[derive(Clone, Debug, Default)]
+pub struct SelectTool {
+    pub mode: SelectMode,
+    pub filter: SelectionFilter,
+    anchor: Option<Pt>,
+    /// Anchor and cursor corners while dragging.
+    band: Option<(Pt, Pt)>,
+    hover: Option<Uuid>,
+}
+
+impl SelectTool {
+    /// Entity under the cursor, for highlighting.
+    pub fn hover(&self) -> Option<Uuid> {
+        self.hover
+    }
+
+    fn dragging(&self, ctx: &ToolContext, p: Pt) -> bool {
+        self.anchor.is_some_and(|a| dist(a, p) > ctx.snap_radius)
+    }
+
+    pub fn handle(
+        &mut self,
+        state: &mut EditorState,
+        ctx: &ToolContext,
+        ev: &InputEvent,
+    ) -> Result<ToolEvent> {
+        match *ev {
+            InputEvent::MouseMove { x, y } => {
+                state.cursor_world = Pt { x, y };
+                state.snapped = None;
+                if let Some(a) = self
+                    .anchor
+                    .filter(|_| self.dragging(ctx, state.cursor_world))
+                {
+                    self.hover = None;
+                    self.band = Some((a, state.cursor_world));
+                    return Ok(ToolEvent::Preview);
+                }
+                self.hover = pick(
+                    ctx.doc,
+                    state.cursor_world,
+                    ctx.snap_radius,
+                    &self.filter,
+                    ctx.eps,
+                );
+                Ok(ToolEvent::Idle)
+            }
+            InputEvent::MouseDown { x, y } => {
+                state.cursor_world = Pt { x, y };
+                self.anchor = Some(state.cursor_world);
+                Ok(ToolEvent::Idle)
+            }
+            InputEvent::MouseUp { x, y } => {
+                let p = Pt { x, y };
+                state.cursor_world = p;
+                let dragging = self.dragging(ctx, p);
+                self.band = None;
+                let Some(a) = self.anchor.take() else {
+                    return Ok(ToolEvent::Idle);
+                };
+                let hits: Vec<Uuid> = if dragging {
+                    let region = if p.x >= a.x {
+                        Region::Window(a, p)
+                    } else {
+                        Region::Crossing(a, p)
+                    };
+                    select(ctx.doc, &region, &self.filter, ctx.eps)
+                } else {
+                    pick(ctx.doc, p, ctx.snap_radius, &self.filter, ctx.eps)
+                        .into_iter()
+                        .collect()
+                };
+                Ok(if apply_mode(&mut state.selection, &hits, self.mode) {
+                    ToolEvent::SelectionChanged
+                } else {
+                    ToolEvent::Idle
+                })
+            }
+            InputEvent::Escape => {
+                self.band = None;
+                if self.anchor.take().is_some() {
+                    return Ok(ToolEvent::Cancelled);
+                }
+                if state.selection.is_empty() {
+                    return Ok(ToolEvent::Idle);
+                }
+                state.selection.clear();
+                Ok(ToolEvent::SelectionChanged)
+            }
+            InputEvent::Enter | InputEvent::Key(_) => Ok(ToolEvent::Idle),
+        }
+    }
+
+    /// Rubber-band rectangle while dragging.
+    pub fn preview(&self) -> Option<Geom2D> {
+        let (a, cursor) = self.band?;
+        Some(Geom2D::Polyline {
+            pts: vec![
+                vec2(a),
+                vec2(Pt {
+                    x: cursor.x,
+                    y: a.y,
+                }),
+                vec2(cursor),
+                vec2(Pt {
+                    x: a.x,
+                    y: cursor.y,
+                }),
+            ],
+            closed: true,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use craftcad_editor::hud::{eval_length, parse, HudInput};
+use craftcad_geom2d::Pt;
+use std::collections::BTreeMap;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+fn value(input: &str, units: &str) -> f64 {
+    match parse(input, units, &BTreeMap::new()).expect("parse") {
+        HudInput::Value(v) => v,
+        other => panic!("expected value, got {other:?}"),
+    }
+}
+
+fn code(input: &str, vars: &BTreeMap<String, f64>) -> String {
+    parse(input, "mm", vars).unwrap_err().code
+}
+
+#[test]
+fn lengths_convert_to_document_units() {
+    assert!(close(value("120mm", "mm"), 120.0));
+    assert!(close(value("12 cm", "mm"), 120.0));
+    assert!(close(value("4 3/4\"", "mm"), 120.65));
+    assert!(close(value("4 3/4\"", "inch"), 4.75));
+    assert!(close(value("5'6\"", "inch"), 66.0));
+    assert!(close(value("3/4in", "inch"), 0.75));
+    assert!(close(value("1200 - 2*18", "mm"), 1164.0));
+    assert!(close(value("-(2 + 3) * 2", "mm"), -10.0));
+    assert!(close(value("25.4mm + 1", "inch"), 2.0));
+
+    let mut vars = BTreeMap::new();
+    vars.insert("width".to_string(), 600.0);
+    assert!(close(
+        eval_length("width/2+3", "mm", &vars).expect("eval"),
+        303.0
+    ));
+    assert_eq!(
+        parse("=width/2+3", "mm", &vars).expect("parse"),
+        HudInput::Value(303.0)
+    );
+}
+
+#[test]
+fn points_absolute_relative_and_polar() {
+    let vars = BTreeMap::new();
+    assert_eq!(
+        parse("10, -5", "mm", &vars).expect("parse"),
+        HudInput::Point(Pt { x: 10.0, y: -5.0 })
+    );
+    let rel = parse("@50<30", "mm", &vars).expect("parse");
+    let p = rel.point(Some(Pt { x: 1.0, y: 1.0 })).expect("point");
+    assert!(close(p.x, 1.0 + 50.0 * 30f64.to_radians().cos()));
+    assert!(close(p.y, 1.0 + 25.0));
+    assert!(rel.point(None).is_err());
+    assert_eq!(
+        parse("@1in,0", "mm", &vars).expect("parse"),
+        HudInput::Relative(Pt { x: 25.4, y: 0.0 })
+    );
+}
+
+#[test]
+fn errors_are_stable_reason_codes() {
+    let vars = BTreeMap::new();
+    assert_eq!(code("2 +", &vars), "DRAW_INPUT_SYNTAX");
+    assert_eq!(code("(1", &vars), "DRAW_INPUT_SYNTAX");
+    assert_eq!(code("@5", &vars), "DRAW_INPUT_SYNTAX");
+    assert_eq!(code("1,2,3", &vars), "DRAW_INPUT_SYNTAX");
+    assert_eq!(code("3yd", &vars), "DRAW_INPUT_UNKNOWN_UNIT");
+    assert_eq!(code("depth*2", &vars), "DRAW_INPUT_UNKNOWN_VARIABLE");
+    assert_eq!(code("1/0", &vars), "DRAW_INVALID_NUMERIC");
+    assert_eq!(code("4/(2-2)", &vars), "DRAW_INVALID_NUMERIC");
+    assert_eq!(
+        parse("1", "furlong", &vars).unwrap_err().code,
+        "DRAW_INPUT_UNKNOWN_UNIT"
+    );
+}
@@ -0,0 +1,235 @@
+use craftcad_commands::commands::transform_selection::Transform;
+use craftcad_editor::selection::{
+    apply_mode, load_set, pick, select, transform_input, Region, SelectMode, SelectionFilter,
+};
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{
+    BlockDef, BlockInstance, BlockTransform, Document, Entity, Geom2D, Layer, ProjectSettings,
+    SelectionSet, Vec2,
+};
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn p(x: f64, y: f64) -> Pt {
+    Pt { x, y }
+}
+
+fn layer(n: u128, visible: bool, locked: bool) -> Layer {
+    Layer {
+        id: Uuid::from_u128(n),
+        name: format!("L{n}"),
+        visible,
+        locked,
+        editable: true,
+        color: None,
+        linetype: "CONTINUOUS".into(),
+        print: true,
+    }
+}
+
+fn entity(n: u128, layer: u128, geom: Geom2D, tags: &[&str]) -> Entity {
+    Entity {
+        id: Uuid::from_u128(n),
+        layer_id: Uuid::from_u128(layer),
+        geom,
+        style: serde_json::json!({}),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        meta: BTreeMap::new(),
+    }
+}
+
+fn sample() -> Document {
+    let line = |ax, ay, bx, by| Geom2D::Line {
+        a: Vec2 { x: ax, y: ay },
+        b: Vec2 { x: bx, y: by },
+    };
+    Document {
+        schema_version: 3,
+        id: Uuid::from_u128(99),
+        units: "mm".into(),
+        layers: vec![
+            layer(1, true, false),
+            layer(2, true, true),
+            layer(3, false, false),
+        ],
+        entities: vec![
+            entity(100, 1, line(1.0, 1.0, 4.0, 1.0), &["cut"]),
+            entity(101, 1, line(3.0, 3.0, 9.0, 3.0), &[]),
+            entity(
+                102,
+                1,
+                Geom2D::Circle {
+                    c: Vec2 { x: 2.0, y: 6.0 },
+                    r: 1.0,
+                },
+                &["cut"],
+            ),
+            entity(103, 2, line(1.0, 2.0, 2.0, 2.0), &[]),
+            entity(104, 3, line(1.0, 2.5, 2.0, 2.5), &[]),
+        ],
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn ids(v: &[Uuid]) -> Vec<u128> {
+    v.iter().map(|id| id.as_u128()).collect()
+}
+
+#[test]
+fn window_crossing_and_lasso_regions() {
+    let doc = sample();
+    let eps = EpsilonPolicy::default();
+    let all = SelectionFilter::default();
+    let (a, b) = (p(0.0, 0.0), p(5.0, 7.5));
+
+    let window = select(&doc, &Region::Window(a, b), &all, &eps);
+    assert_eq!(ids(&window), [100, 102]);
+    let crossing = select(&doc, &Region::Crossing(b, a), &all, &eps);
+    assert_eq!(ids(&crossing), [100, 101, 102]);
+
+    // A triangle holding the first line and the circle's left half only.
+    let lasso = vec![p(0.0, 0.0), p(6.0, 0.0), p(0.0, 12.0)];
+    assert_eq!(
+        ids(&select(&doc, &Region::Lasso(lasso.clone()), &all, &eps)),
+        [100]
+    );
+    assert_eq!(
+        ids(&select(&doc, &Region::CrossingLasso(lasso), &all, &eps)),
+        [100, 101, 102]
+    );
+}
+
+#[test]
+fn filters_restrict_kind_tag_and_layer_state() {
+    let doc = sample();
+    let eps = EpsilonPolicy::default();
+    let region = Region::Crossing(p(0.0, 0.0), p(10.0, 10.0));
+
+    let lines = SelectionFilter {
+        kinds: vec!["Line".into()],
+        ..Default::default()
+    };
+    assert_eq!(ids(&select(&doc, &region, &lines, &eps)), [100, 101]);
+    let tagged = SelectionFilter {
+        tags: vec!["cut".into()],
+        ..Default::default()
+    };
+    assert_eq!(ids(&select(&doc, &region, &tagged, &eps)), [100, 102]);
+    let everything = SelectionFilter {
+        include_locked: true,
+        include_hidden: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(&select(&doc, &region, &everything, &eps)),
+        [100, 101, 102, 103, 104]
+    );
+    let locked_layer = SelectionFilter {
+        layers: vec![Uuid::from_u128(2)],
+        include_locked: true,
+        ..Default::default()
+    };
+    assert_eq!(ids(&select(&doc, &region, &locked_layer, &eps)), [103]);
+}
+
+#[test]
+fn block_instances_select_by_their_placed_geometry() {
+    let mut doc = sample();
+    let eps = EpsilonPolicy::default();
+    let all = SelectionFilter::default();
+    let def = doc.entities.remove(1);
+    doc.blocks.push(BlockDef {
+        id: Uuid::from_u128(200),
+        name: "B".into(),
+        base_point: Vec2 { x: 3.0, y: 3.0 },
+        entities: vec![def],
+        instances: vec![],
+    });
+    doc.block_instances.push(BlockInstance {
+        id: Uuid::from_u128(201),
+        block_id: Uuid::from_u128(200),
+        name: String::new(),
+        layer_id: Uuid::from_u128(1),
+        transform: BlockTransform {
+            translate: Vec2 { x: 20.0, y: 20.0 },
+            ..Default::default()
+        },
+    });
+
+    // The definition's own coordinates hold nothing any more.
+    assert_eq!(pick(&doc, p(5.0, 3.0), 0.5, &all, &eps), None);
+    assert_eq!(
+        pick(&doc, p(22.0, 20.2), 0.5, &all, &eps),
+        Some(Uuid::from_u128(201))
+    );
+    let region = Region::Window(p(19.0, 19.0), p(27.0, 21.0));
+    assert_eq!(ids(&select(&doc, &region, &all, &eps)), [201]);
+    let half = Region::Window(p(19.0, 19.0), p(23.0, 21.0));
+    assert!(select(&doc, &half, &all, &eps).is_empty());
+    assert_eq!(
+        ids(&select(
+            &doc,
+            &Region::Crossing(p(19.0, 19.0), p(23.0, 21.0)),
+            &all,
+            &eps
+        )),
+        [201]
+    );
+    let lines = SelectionFilter {
+        kinds: vec!["Line".into()],
+        ..Default::default()
+    };
+    assert!(select(&doc, &region, &lines, &eps).is_empty());
+}
+
+#[test]
+fn modes_named_sets_and_transform_input() {
+    let mut doc = sample();
+    let (a, b, c) = (
+        Uuid::from_u128(100),
+        Uuid::from_u128(101),
+        Uuid::from_u128(102),
+    );
+    let mut sel = vec![];
+    assert!(apply_mode(&mut sel, &[b, a], SelectMode::Replace));
+    assert!(apply_mode(&mut sel, &[c], SelectMode::Add));
+    assert!(apply_mode(
+        &mut sel,
+        &[a, Uuid::from_u128(7)],
+        SelectMode::Toggle
+    ));
+    assert_eq!(
+        sel,
+        [Uuid::from_u128(7).to_string(), b.to_string(), c.to_string()]
+    );
+    assert!(apply_mode(
+        &mut sel,
+        &[Uuid::from_u128(7)],
+        SelectMode::Remove
+    ));
+    assert!(!apply_mode(&mut sel, &[a], SelectMode::Remove));
+
+    doc.selection_sets.push(SelectionSet {
+        name: "rails".into(),
+        entity_ids: vec![c, Uuid::from_u128(55), a],
+    });
+    assert_eq!(
+        load_set(&doc, "rails").unwrap(),
+        [a.to_string(), c.to_string()]
+    );
+    assert!(load_set(&doc, "legs").is_none());
+
+    let input = transform_input(&sel, Transform::Translate { dx: 1.0, dy: 0.0 }).unwrap();
+    assert_eq!(input.selection_ids, [b, c]);
+    assert!(transform_input(&["bogus".into()], Transform::Translate { dx: 1.0, dy: 0.0 }).is_err());
+}
@@ -0,0 +1,312 @@
+use craftcad_editor::input::InputEvent;
+use craftcad_editor::state::{EditorState, Mode};
+use craftcad_editor::tools::{ActiveTool, ToolContext, ToolEvent};
+use craftcad_serialize::{Document, Entity, Geom2D, Layer, ProjectSettings, Vec2};
+use craftcad_snapping::candidates::CandidateSource;
+use craftcad_snapping::types::SnapKind;
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn doc(geoms: Vec<Geom2D>) -> Document {
+    Document {
+        schema_version: 3,
+        id: Uuid::from_u128(99),
+        units: "mm".into(),
+        layers: vec![Layer {
+            id: Uuid::from_u128(1),
+            name: "L1".into(),
+            visible: true,
+            locked: false,
+            editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
+        }],
+        entities: geoms
+            .into_iter()
+            .enumerate()
+            .map(|(i, geom)| Entity {
+                id: Uuid::from_u128(100 + i as u128),
+                layer_id: Uuid::from_u128(1),
+                geom,
+                style: serde_json::json!({}),
+                tags: vec![],
+                meta: BTreeMap::new(),
+            })
+            .collect(),
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn line(ax: f64, ay: f64, bx: f64, by: f64) -> Geom2D {
+    Geom2D::Line {
+        a: Vec2 { x: ax, y: ay },
+        b: Vec2 { x: bx, y: by },
+    }
+}
+
+/// Feeds `events` to a fresh tool for `mode` and applies every committed
+/// delta to `doc`.
+fn run(
+    doc: &mut Document,
+    state: &mut EditorState,
+    mode: Mode,
+    events: &[InputEvent],
+) -> (ActiveTool, Vec<&'static str>) {
+    let eps = EpsilonPolicy::default();
+    let mut tool = ActiveTool::for_mode(&mode).expect("interactive mode");
+    let mut seen = vec![];
+    for ev in events {
+        let snaps = CandidateSource::build(doc, 8.0, &eps);
+        let ctx = ToolContext {
+            doc,
+            snaps: &snaps,
+            eps: &eps,
+            snap_radius: 2.0,
+            grid: 5.0,
+            angle_step_deg: 15.0,
+            vars: &BTreeMap::new(),
+        };
+        let out = tool.handle(state, &ctx, ev).expect("tool event");
+        let name = match out {
+            ToolEvent::Idle => "idle",
+            ToolEvent::Preview => "preview",
+            ToolEvent::Committed(delta) => {
+                delta.apply(doc).expect("apply");
+                "committed"
+            }
+            ToolEvent::SelectionChanged => "selection",
+            ToolEvent::Cancelled => "cancelled",
+        };
+        seen.push(name);
+    }
+    (tool, seen)
+}
+
+#[test]
+fn line_tool_snaps_chains_and_cancels() {
+    let mut d = doc(vec![line(0.0, 0.0, 10.0, 0.0)]);
+    let mut state = EditorState::new(Uuid::from_u128(1));
+    let (tool, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::DrawLine,
+        &[
+            InputEvent::MouseDown { x: 9.6, y: 0.3 },
+            InputEvent::MouseMove { x: 10.2, y: 19.0 },
+            InputEvent::MouseDown { x: 10.0, y: 20.0 },
+            InputEvent::MouseDown { x: 30.0, y: 20.0 },
+            InputEvent::MouseMove { x: 40.0, y: 40.0 },
+        ],
+    );
+    assert_eq!(
+        seen,
+        ["preview", "preview", "committed", "committed", "preview"]
+    );
+    assert_eq!(d.entities.len(), 3);
+    match &d.entities[1].geom {
+        Geom2D::Line { a, b } => {
+            assert_eq!((a.x, a.y), (10.0, 0.0));
+            assert_eq!((b.x, b.y), (10.0, 20.0));
+        }
+        other => panic!("expected line, got {other:?}"),
+    }
+    assert!(matches!(
+        tool.preview(),
+        Some(Geom2D::Line { a, .. }) if a.x == 30.0 && a.y == 20.0
+    ));
+
+    let mut tool = tool;
+    let snaps = CandidateSource::build(&d, 8.0, &EpsilonPolicy::default());
+    let eps = EpsilonPolicy::default();
+    let ctx = ToolContext {
+        doc: &d,
+        snaps: &snaps,
+        eps: &eps,
+        snap_radius: 2.0,
+        grid: 5.0,
+        angle_step_deg: 15.0,
+        vars: &BTreeMap::new(),
+    };
+    let out = tool.handle(&mut state, &ctx, &InputEvent::Escape).unwrap();
+    assert!(matches!(out, ToolEvent::Cancelled));
+    assert!(tool.preview().is_none());
+}
+
+#[test]
+fn circle_and_arc_tools_commit_from_picked_points() {
+    let mut d = doc(vec![]);
+    let mut state = EditorState::new(Uuid::from_u128(1));
+    state.snap_enabled = false;
+    state.grid_enabled = true;
+    let (_, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::DrawCircle,
+        &[
+            InputEvent::MouseDown { x: 0.4, y: -0.3 },
+            InputEvent::MouseDown { x: 9.0, y: 1.0 },
+        ],
+    );
+    assert_eq!(seen, ["preview", "committed"]);
+    assert_eq!(state.snapped.map(|s| s.kind), Some(SnapKind::Grid));
+    assert!(matches!(
+        &d.entities[0].geom,
+        Geom2D::Circle { c, r } if c.x == 0.0 && c.y == 0.0 && *r == 10.0
+    ));
+
+    let (_, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::DrawArc,
+        &[
+            InputEvent::MouseDown { x: 0.0, y: 0.0 },
+            InputEvent::MouseDown { x: 5.0, y: 0.0 },
+            InputEvent::MouseDown { x: 0.0, y: 10.0 },
+        ],
+    );
+    assert_eq!(seen, ["preview", "preview", "committed"]);
+    match &d.entities[1].geom {
+        Geom2D::Arc {
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+            ..
+        } => {
+            assert_eq!(*r, 5.0);
+            assert_eq!(*start_angle, 0.0);
+            assert!((end_angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+            assert!(ccw);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn select_tool_picks_drags_and_clears_on_escape() {
+    let mut d = doc(vec![line(0.0, 0.0, 10.0, 0.0), line(0.0, 3.0, 10.0, 3.0)]);
+    let mut state = EditorState::new(Uuid::from_u128(1));
+    let (_, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::Select,
+        &[
+            InputEvent::MouseDown { x: 5.0, y: 2.0 },
+            InputEvent::MouseUp { x: 5.0, y: 2.0 },
+            InputEvent::MouseDown { x: 5.0, y: 2.0 },
+            InputEvent::MouseUp { x: 5.0, y: 2.0 },
+        ],
+    );
+    assert_eq!(seen, ["idle", "selection", "idle", "idle"]);
+    assert_eq!(state.selection, [Uuid::from_u128(101).to_string()]);
+
+    let (_, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::Select,
+        &[
+            InputEvent::Escape,
+            InputEvent::MouseDown { x: 5.0, y: 1.0 },
+            InputEvent::MouseUp { x: 5.0, y: 1.0 },
+        ],
+    );
+    assert_eq!(seen, ["selection", "idle", "selection"]);
+    assert_eq!(state.selection, [Uuid::from_u128(100).to_string()]);
+
+    // Right-to-left drag is a crossing selection: both lines touch it.
+    let (tool, seen) = run(
+        &mut d,
+        &mut state,
+        Mode::Select,
+        &[
+            InputEvent::MouseDown { x: 8.0, y: -1.0 },
+            InputEvent::MouseMove { x: 4.0, y: 4.0 },
+            InputEvent::MouseUp { x: 4.0, y: 4.0 },
+        ],
+    );
+    assert_eq!(seen, ["idle", "preview", "selection"]);
+    assert_eq!(state.selection.len(), 2);
+    assert!(tool.preview().is_none());
+    assert!(ActiveTool::for_mode(&Mode::Trim).is_none());
+}
+
+fn typed(text: &str) -> Vec<InputEvent> {
+    let mut out: Vec<InputEvent> = text.chars().map(InputEvent::Key).collect();
+    out.push(InputEvent::Enter);
+    out
+}
+
+#[test]
+fn line_tool_takes_typed_coordinates() {
+    let mut d = doc(vec![]);
+    let mut state = EditorState::new(Uuid::from_u128(1));
+    let mut events = typed("10,5");
+    events.extend(typed("@20<90"));
+    events.extend(typed("@1 1/2\",0"));
+    events.push(InputEvent::MouseMove { x: 60.0, y: 25.0 });
+    events.extend(typed("2*5"));
+    let (_, seen) = run(&mut d, &mut state, Mode::DrawLine, &events);
+    assert_eq!(seen.iter().filter(|s| **s == "committed").count(), 3);
+    let ends: Vec<(f64, f64)> = d
+        .entities
+        .iter()
+        .map(|e| match &e.geom {
+            Geom2D::Line { b, .. } => ((b.x * 1e6).round() / 1e6, (b.y * 1e6).round() / 1e6),
+            other => panic!("expected line, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(ends, [(10.0, 25.0), (48.1, 25.0), (58.1, 25.0)]);
+    assert!(state.input_buffer.is_empty());
+}
+
+#[test]
+fn typed_errors_keep_the_buffer() {
+    let eps = EpsilonPolicy::default();
+    let d = doc(vec![]);
+    let snaps = CandidateSource::build(&d, 8.0, &eps);
+    let ctx = ToolContext {
+        doc: &d,
+        snaps: &snaps,
+        eps: &eps,
+        snap_radius: 2.0,
+        grid: 5.0,
+        angle_step_deg: 15.0,
+        vars: &BTreeMap::new(),
+    };
+    let mut state = EditorState::new(Uuid::from_u128(1));
+    let mut tool = ActiveTool::for_mode(&Mode::DrawCircle).unwrap();
+    let feed = |tool: &mut ActiveTool, state: &mut EditorState, text: &str| {
+        let mut last = Ok(ToolEvent::Idle);
+        for ev in typed(text) {
+            last = tool.handle(state, &ctx, &ev);
+        }
+        last.err().map(|r| r.code)
+    };
+
+    // A radius before the center, then a bad unit: both stay in the buffer.
+    let code = feed(&mut tool, &mut state, "5");
+    assert_eq!(code.as_deref(), Some("DRAW_INSUFFICIENT_INPUT"));
+    assert_eq!(state.input_buffer, "5");
+    tool.handle(&mut state, &ctx, &InputEvent::Escape).unwrap();
+    let code = feed(&mut tool, &mut state, "12furlong");
+    assert_eq!(code.as_deref(), Some("DRAW_INPUT_UNKNOWN_UNIT"));
+    assert_eq!(state.input_buffer, "12furlong");
+    tool.handle(&mut state, &ctx, &InputEvent::Escape).unwrap();
+
+    assert_eq!(feed(&mut tool, &mut state, "0,0"), None);
+    assert!(state.input_buffer.is_empty());
+    let out = tool.handle(&mut state, &ctx, &InputEvent::MouseMove { x: 3.0, y: 4.0 });
+    assert!(matches!(out, Ok(ToolEvent::Preview)));
+    assert!(matches!(tool.preview(), Some(Geom2D::Circle { r, .. }) if r == 5.0));
+}
@@ -276,12 +276,62 @@ impl Default for Metadata {
     }
 }
 
+/// Placement of a block (DXF INSERT): scale about the block's base point,
+/// rotate, then move the base point to `pos`. A negative `scale_x` mirrors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockInsert {
+    pub id: String,
+    pub block: String,
+    pub layer: String,
+    pub pos: Point2D,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub rotation_rad: f64,
+}
+
+/// Named group of entities in block coordinates (DXF BLOCK). `inserts`
+/// places other blocks inside this one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockDef {
+    pub name: String,
+    pub base: Point2D,
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub inserts: Vec<BlockInsert>,
+}
+
+fn continuous() -> String {
+    "CONTINUOUS".to_string()
+}
+fn plotted() -> bool {
+    true
+}
+
+/// Layer table entry (DXF LAYER). Entities refer to it by `StrokeStyle.layer`;
+/// layers without an entry are drawn black, continuous and plotted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerStyle {
+    pub name: String,
+    #[serde(default)]
+    pub rgb: Option<(u8, u8, u8)>,
+    #[serde(default = "continuous")]
+    pub linetype: String,
+    #[serde(default = "plotted")]
+    pub print: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InternalModel {
     pub units: Units,
     pub entities: Vec<Entity>,
     pub texts: Vec<TextEntity>,
     pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocks: Vec<BlockDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inserts: Vec<BlockInsert>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<LayerStyle>,
 }
 
 impl InternalModel {
@@ -291,6 +341,13 @@ impl InternalModel {
             entities: vec![],
             texts: vec![],
             metadata: Metadata::default(),
+            blocks: vec![],
+            inserts: vec![],
+            layers: vec![],
         }
     }
+
+    pub fn layer(&self, name: &str) -> Option<&LayerStyle> {
+        self.layers.iter().find(|l| l.name == name)
+    }
 }
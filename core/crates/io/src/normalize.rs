@@ -24,7 +24,8 @@ fn sanitize_point(p: Point2D) -> Option<Point2D> {
 }
 
 pub fn normalize_model(model: &mut InternalModel, step: f64, warnings: &mut Vec<AppError>) {
-    for ent in &mut model.entities {
+    let block_entities = model.blocks.iter_mut().flat_map(|b| b.entities.iter_mut());
+    for ent in model.entities.iter_mut().chain(block_entities) {
         if let Entity::Path(p) = ent {
             let mut changed = false;
             p.segments.retain_mut(|seg| match seg {
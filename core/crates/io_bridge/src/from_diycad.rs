@@ -25,6 +25,10 @@ enum BridgeEntity {
         linetype: String,
         closed: bool,
         points: Vec<(f64, f64)>,
+        /// The exact segments, for paths with arcs, circles or Beziers;
+        /// `points` is then empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        segments: Vec<Segment2D>,
     },
     Text {
         id: String,
@@ -34,6 +38,12 @@ enum BridgeEntity {
         text: String,
         size: f32,
     },
+    /// A block definition (DXF BLOCK), nested placements included.
+    Block(BlockDef),
+    /// A top-level block placement (DXF INSERT).
+    Insert(BlockInsert),
+    /// Colour, line type and print flag of a layer (DXF layer table).
+    Layer(LayerStyle),
 }
 
 pub fn load_diycad_to_internal_model(
@@ -81,6 +91,7 @@ pub fn load_diycad_to_internal_model(
                 linetype,
                 closed,
                 points,
+                segments,
             } => {
                 let mut p = PathEntity::new(
                     id,
@@ -92,6 +103,7 @@ pub fn load_diycad_to_internal_model(
                     },
                 );
                 p.closed = closed;
+                p.segments = segments;
                 for w in points.windows(2) {
                     p.segments.push(Segment2D::Line {
                         a: Point2D {
@@ -124,10 +136,13 @@ pub fn load_diycad_to_internal_model(
                     rotation_rad: 0.0,
                 }));
             }
+            BridgeEntity::Block(b) => model.blocks.push(b),
+            BridgeEntity::Insert(i) => model.inserts.push(i),
+            BridgeEntity::Layer(l) => model.layers.push(l),
         }
     }
 
-    if model.entities.is_empty() {
+    if model.entities.is_empty() && model.inserts.is_empty() {
         warnings.push(
             AppError::new(
                 ReasonCode::LOAD_DIYCAD_EMPTY,
@@ -0,0 +1,29 @@
+use craftcad_io::model::LayerStyle;
+use craftcad_serialize::Layer;
+
+/// The DXF/SVG style of a document layer. Colours that are not `#RRGGBB`
+/// are left unset.
+pub fn layer_style(layer: &Layer) -> LayerStyle {
+    LayerStyle {
+        name: layer.name.clone(),
+        rgb: layer.color.as_deref().and_then(parse_rgb),
+        linetype: layer.linetype.clone(),
+        print: layer.print,
+    }
+}
+
+/// Applies an imported style to `layer`, keeping its id and edit state.
+pub fn apply_layer_style(layer: &mut Layer, style: &LayerStyle) {
+    layer.color = style.rgb.map(|(r, g, b)| format!("#{r:02X}{g:02X}{b:02X}"));
+    layer.linetype = style.linetype.clone();
+    layer.print = style.print;
+}
+
+fn parse_rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
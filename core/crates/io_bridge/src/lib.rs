@@ -1,6 +1,7 @@
 #![forbid(unsafe_code)]
 
 mod from_diycad;
+mod layers;
 mod to_diycad;
 
 use craftcad_io::IoEngine;
@@ -9,6 +10,7 @@ use craftcad_io_json::JsonIo;
 use craftcad_io_svg::SvgIo;
 
 pub use from_diycad::{load_diycad_to_internal_model, LoadDiycadOptions};
+pub use layers::{apply_layer_style, layer_style};
 pub use to_diycad::{save_internal_model_to_diycad, SaveDiycadOptions};
 
 pub fn default_engine() -> IoEngine {
@@ -31,6 +31,10 @@ enum BridgeEntity {
         linetype: String,
         closed: bool,
         points: Vec<(f64, f64)>,
+        /// The exact segments, for paths with arcs, circles or Beziers;
+        /// `points` is then empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        segments: Vec<Segment2D>,
     },
     Text {
         id: String,
@@ -40,6 +44,23 @@ enum BridgeEntity {
         text: String,
         size: f32,
     },
+    /// A block definition (DXF BLOCK), nested placements included.
+    Block(BlockDef),
+    /// A top-level block placement (DXF INSERT).
+    Insert(BlockInsert),
+    /// Colour, line type and print flag of a layer (DXF layer table).
+    Layer(LayerStyle),
+}
+
+fn to_record(be: &BridgeEntity, what: &str) -> AppResult<String> {
+    serde_json::to_string(be).map_err(|e| {
+        AppError::new(
+            ReasonCode::SAVE_PART_IMPORT_FAILED,
+            format!("failed to serialize {what} bridge entity"),
+        )
+        .with_context("error", e.to_string())
+        .fatal()
+    })
 }
 
 pub fn save_internal_model_to_diycad(
@@ -56,8 +77,7 @@ pub fn save_internal_model_to_diycad(
         match e {
             Entity::Path(p) => {
                 let mut points: Vec<(f64, f64)> = Vec::new();
-                let mut ok = true;
-
+                let mut segments = Vec::new();
                 for s in &p.segments {
                     match s {
                         Segment2D::Line { a, b } => {
@@ -66,46 +86,26 @@ pub fn save_internal_model_to_diycad(
                             }
                             points.push((b.x, b.y));
                         }
-                        other => {
-                            ok = false;
-                            if opts.allow_drop {
-                                warnings.push(
-                                    AppError::new(
-                                        ReasonCode::SAVE_GEOM_DROPPED,
-                                        "non-line segment dropped for diycad save",
-                                    )
-                                    .with_context("path_id", p.id.clone())
-                                    .with_context("segment_kind", format!("{other:?}"))
-                                    .with_hint("enable_approx=true で事前Line化すると保存品質が向上します。"),
-                                );
-                            } else {
-                                return Err(AppError::new(
-                                    ReasonCode::SAVE_PART_IMPORT_FAILED,
-                                    "cannot save non-line segments without allow_drop",
-                                )
-                                .with_context("path_id", p.id.clone())
-                                .fatal());
-                            }
+                        // Curves keep their shape; the whole path is then
+                        // stored segment by segment.
+                        _ => {
+                            segments = p.segments.clone();
+                            points.clear();
+                            break;
                         }
                     }
                 }
 
-                if ok && points.len() >= 2 {
+                if points.len() >= 2 || !segments.is_empty() {
                     let be = BridgeEntity::Path {
                         id: p.id.clone(),
                         layer: p.stroke.layer.clone(),
                         linetype: p.stroke.linetype.clone(),
                         closed: p.closed,
                         points,
+                        segments,
                     };
-                    entities.push(serde_json::to_string(&be).map_err(|e| {
-                        AppError::new(
-                            ReasonCode::SAVE_PART_IMPORT_FAILED,
-                            "failed to serialize path bridge entity",
-                        )
-                        .with_context("error", e.to_string())
-                        .fatal()
-                    })?);
+                    entities.push(to_record(&be, "path")?);
                 }
             }
             Entity::Text(t) => {
@@ -124,18 +124,21 @@ pub fn save_internal_model_to_diycad(
                     text: t.text.clone(),
                     size: t.size,
                 };
-                entities.push(serde_json::to_string(&be).map_err(|e| {
-                    AppError::new(
-                        ReasonCode::SAVE_PART_IMPORT_FAILED,
-                        "failed to serialize text bridge entity",
-                    )
-                    .with_context("error", e.to_string())
-                    .fatal()
-                })?);
+                entities.push(to_record(&be, "text")?);
             }
         }
     }
 
+    for l in &model.layers {
+        entities.push(to_record(&BridgeEntity::Layer(l.clone()), "layer")?);
+    }
+    for b in &model.blocks {
+        entities.push(to_record(&BridgeEntity::Block(b.clone()), "block")?);
+    }
+    for i in &model.inserts {
+        entities.push(to_record(&BridgeEntity::Insert(i.clone()), "insert")?);
+    }
+
     project.data = DataJson { entities };
     project.manifest.app_version = opts.project_name.clone();
 
@@ -63,3 +63,160 @@ fn e2e_dxf_import_save_reopen_export_svg_reimport_hash_match() {
         "normalize hash should match after dxf->save(.diycad)->reopen->export svg->reimport"
     );
 }
+
+#[test]
+fn curves_survive_save_and_reopen() {
+    use craftcad_io::model::*;
+    let pt = |x: f64, y: f64| Point2D { x, y };
+    let mut model = InternalModel::new(Units::Mm);
+    let mut path = PathEntity::new("handle".into(), StrokeStyle::default());
+    path.segments = vec![
+        Segment2D::Line {
+            a: pt(0.0, 0.0),
+            b: pt(40.0, 0.0),
+        },
+        Segment2D::Arc {
+            center: pt(40.0, 10.0),
+            radius: 10.0,
+            start_rad: -std::f64::consts::FRAC_PI_2,
+            end_rad: std::f64::consts::FRAC_PI_2,
+            ccw: true,
+        },
+        Segment2D::CubicBezier {
+            a: pt(40.0, 20.0),
+            c1: pt(25.0, 30.0),
+            c2: pt(15.0, 10.0),
+            b: pt(0.0, 20.0),
+        },
+    ];
+    model.entities.push(Entity::Path(path.clone()));
+
+    let tmp = std::env::temp_dir().join("craftcad_bridge_curves.diycad");
+    let mut sopts = SaveDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    sopts.allow_drop = false;
+    let warnings = save_internal_model_to_diycad(&model, &sopts).unwrap();
+    assert!(warnings.is_empty(), "{warnings:?}");
+
+    let lopts = LoadDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    let (reopened, _) = load_diycad_to_internal_model(&lopts).unwrap();
+    match &reopened.entities[..] {
+        [Entity::Path(p)] => assert_eq!(p.segments, path.segments),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn dxf_blocks_and_inserts_survive_save_and_reopen() {
+    let dxf = "0\nSECTION\n2\nBLOCKS\n\
+0\nBLOCK\n8\n0\n2\nHINGE\n70\n0\n10\n5\n20\n5\n\
+0\nLINE\n8\nCUT\n10\n5\n20\n5\n11\n25\n21\n5\n\
+0\nENDBLK\n8\n0\n\
+0\nBLOCK\n8\n0\n2\nDOOR\n70\n0\n10\n0\n20\n0\n\
+0\nINSERT\n8\nCUT\n2\nHINGE\n10\n0\n20\n100\n\
+0\nENDBLK\n8\n0\n\
+0\nENDSEC\n\
+0\nSECTION\n2\nENTITIES\n\
+0\nINSERT\n8\nCUT\n2\nHINGE\n10\n200\n20\n50\n41\n-1\n42\n1\n50\n90\n\
+0\nINSERT\n8\nCUT\n2\nDOOR\n10\n400\n20\n0\n\
+0\nENDSEC\n0\nEOF\n";
+    let eng = IoEngine::new()
+        .register_importer(Box::new(DxfIo::new()))
+        .register_exporter(Box::new(DxfIo::new()));
+    let iopts = ImportOptions::default_for_tests();
+    let r1 = eng.import("dxf", dxf.as_bytes(), &iopts).unwrap();
+    assert_eq!(r1.model.blocks.len(), 2);
+    assert_eq!(r1.model.inserts.len(), 2);
+
+    let tmp = std::env::temp_dir().join("craftcad_bridge_blocks.diycad");
+    let mut sopts = SaveDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    sopts.allow_drop = false;
+    let warnings = save_internal_model_to_diycad(&r1.model, &sopts).unwrap();
+    assert!(warnings.is_empty(), "{warnings:?}");
+
+    let lopts = LoadDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    let (m2, load_warn) = load_diycad_to_internal_model(&lopts).unwrap();
+    assert!(load_warn.is_empty(), "{load_warn:?}");
+    assert_eq!(m2.blocks, r1.model.blocks);
+    assert_eq!(m2.inserts, r1.model.inserts);
+
+    let out = eng
+        .export("dxf", &m2, &ExportOptions::default_for_tests())
+        .unwrap();
+    let r3 = eng.import("dxf", &out.bytes, &iopts).unwrap();
+    let placed: Vec<_> = r3.model.inserts.iter().map(|i| i.block.as_str()).collect();
+    assert_eq!(placed, ["HINGE", "DOOR"]);
+    assert_eq!(r3.model.blocks[1].inserts[0].block, "HINGE");
+}
+
+#[test]
+fn document_layer_styles_roundtrip_through_dxf_and_diycad() {
+    use craftcad_io::model::*;
+    use craftcad_io_bridge::{apply_layer_style, layer_style};
+
+    let layer = craftcad_serialize::Layer {
+        id: uuid::Uuid::from_u128(1),
+        name: "CUT".into(),
+        visible: true,
+        locked: false,
+        editable: true,
+        color: Some("#FF8000".into()),
+        linetype: "DASHED".into(),
+        print: false,
+    };
+    let mut model = InternalModel::new(Units::Mm);
+    model.layers.push(layer_style(&layer));
+    let mut path = PathEntity::new(
+        "edge".into(),
+        StrokeStyle {
+            layer: "CUT".into(),
+            ..StrokeStyle::default()
+        },
+    );
+    path.segments.push(Segment2D::Line {
+        a: Point2D { x: 0.0, y: 0.0 },
+        b: Point2D { x: 10.0, y: 0.0 },
+    });
+    model.entities.push(Entity::Path(path));
+
+    let eng = IoEngine::new()
+        .register_importer(Box::new(DxfIo::new()))
+        .register_exporter(Box::new(DxfIo::new()));
+    let out = eng
+        .export("dxf", &model, &ExportOptions::default_for_tests())
+        .unwrap();
+    let text = String::from_utf8(out.bytes.clone()).unwrap();
+    let table = &text[text.find("0\nLAYER\n").unwrap()..text.find("0\nENDTAB").unwrap()];
+    let groups: Vec<&str> = table.lines().map(str::trim).collect();
+    for (code, value) in [
+        ("62", "7"),
+        ("420", "16744448"),
+        ("6", "DASHED"),
+        ("290", "0"),
+    ] {
+        assert!(
+            groups.windows(2).any(|w| w == [code, value]),
+            "group {code} {value} missing in {table}"
+        );
+    }
+
+    let reimported = eng
+        .import("dxf", &out.bytes, &ImportOptions::default_for_tests())
+        .unwrap();
+    let tmp = std::env::temp_dir().join("craftcad_bridge_layers.diycad");
+    let sopts = SaveDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    save_internal_model_to_diycad(&reimported.model, &sopts).unwrap();
+    let lopts = LoadDiycadOptions::default_for_tests(tmp.to_str().unwrap());
+    let (reopened, _) = load_diycad_to_internal_model(&lopts).unwrap();
+    let style = reopened.layers.iter().find(|l| l.name == "CUT").unwrap();
+
+    let mut back = craftcad_serialize::Layer {
+        color: None,
+        linetype: "CONTINUOUS".into(),
+        print: true,
+        ..layer.clone()
+    };
+    apply_layer_style(&mut back, style);
+    assert_eq!(back.color, layer.color);
+    assert_eq!(back.linetype, layer.linetype);
+    assert_eq!(back.print, layer.print);
+}
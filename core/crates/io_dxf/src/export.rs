@@ -42,6 +42,50 @@ fn push_group(out: &mut String, code: i32, value: &str) {
     out.push_str(&format!("{}\n{}\n", code, value));
 }
 
+/// ACI 1..=6 are the primary colours; anything else is written as 7
+/// (black/white) with the exact colour in group 420.
+fn aci(rgb: (u8, u8, u8)) -> i32 {
+    match rgb {
+        (255, 0, 0) => 1,
+        (255, 255, 0) => 2,
+        (0, 255, 0) => 3,
+        (0, 255, 255) => 4,
+        (0, 0, 255) => 5,
+        (255, 0, 255) => 6,
+        _ => 7,
+    }
+}
+
+fn emit_layer_table(out: &mut String, mr: &MappingRules, layers: &[LayerStyle]) {
+    out.push_str("0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n");
+    push_group(out, 70, &layers.len().to_string());
+    for l in layers {
+        out.push_str("0\nLAYER\n");
+        push_group(out, 2, &mr.map_layer(&l.name));
+        push_group(out, 70, "0");
+        push_group(out, 62, &l.rgb.map_or(7, aci).to_string());
+        if let Some((r, g, b)) = l.rgb {
+            let true_color = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+            push_group(out, 420, &true_color.to_string());
+        }
+        push_group(out, 6, &mr.map_linetype(&l.linetype));
+        push_group(out, 290, if l.print { "1" } else { "0" });
+    }
+    out.push_str("0\nENDTAB\n0\nENDSEC\n");
+}
+
+/// Continuous entities on a layer with its own line type follow the layer.
+fn entity_linetype(mr: &MappingRules, layers: &[LayerStyle], stroke: &StrokeStyle) -> String {
+    let linetype = mr.map_linetype(&stroke.linetype);
+    let by_layer = layers
+        .iter()
+        .any(|l| l.name == stroke.layer && mr.map_linetype(&l.linetype) != linetype);
+    if linetype == mr.map_linetype("CONTINUOUS") && by_layer {
+        return "BYLAYER".to_string();
+    }
+    linetype
+}
+
 fn point_scaled(p: Point2D, s: f64) -> Point2D {
     Point2D {
         x: p.x * s,
@@ -175,251 +219,322 @@ fn cubic_flatten_uniform(
     pts
 }
 
-pub fn export_dxf(
-    model: &InternalModel,
+#[allow(clippy::too_many_arguments)]
+fn emit_entity(
+    out: &mut String,
+    e: &Entity,
+    layers: &[LayerStyle],
+    sm: &SupportMatrix,
+    mr: &MappingRules,
     opts: &ExportOptions,
-) -> AppResult<(Vec<u8>, Vec<AppError>, IoReport)> {
-    let sm = SupportMatrix::load_from_ssot()?;
-    let mr = MappingRules::load_from_ssot()?;
-
-    let dp = mr.export_decimal_places() as usize;
-    let scale = unit_scale(model.units, opts.target_units);
-
-    let mut warnings = Vec::new();
-    let mut report = IoReport::new("dxf");
-    let mut out = String::new();
-
-    out.push_str("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n");
-    out.push_str(&format!("{}\n", header_units(opts.target_units)));
-    out.push_str("0\nENDSEC\n0\nSECTION\n2\nENTITIES\n");
-
-    for e in &model.entities {
-        match e {
-            Entity::Path(p) => {
-                let layer = mr.map_layer(&p.stroke.layer);
-                let linetype = mr.map_linetype(&p.stroke.linetype);
-
-                if p.segments.len() == 1 {
-                    match p.segments[0] {
-                        Segment2D::Line { a, b } => {
-                            if sm.level("dxf", "entity_line", "export")
-                                != SupportLevel::NotSupported
-                            {
-                                emit_line(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(a, scale),
-                                    point_scaled(b, scale),
-                                    dp,
-                                );
-                            }
-                            continue;
+    scale: f64,
+    dp: usize,
+    warnings: &mut Vec<AppError>,
+) {
+    match e {
+        Entity::Path(p) => {
+            let layer = mr.map_layer(&p.stroke.layer);
+            let linetype = entity_linetype(mr, layers, &p.stroke);
+
+            if p.segments.len() == 1 {
+                match p.segments[0] {
+                    Segment2D::Line { a, b } => {
+                        if sm.level("dxf", "entity_line", "export") != SupportLevel::NotSupported {
+                            emit_line(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(a, scale),
+                                point_scaled(b, scale),
+                                dp,
+                            );
                         }
-                        Segment2D::Arc {
-                            center,
-                            radius,
-                            start_rad,
-                            end_rad,
-                            ccw,
-                        } => {
-                            if sm.level("dxf", "entity_arc", "export") != SupportLevel::NotSupported
-                            {
-                                emit_arc(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(center, scale),
-                                    radius * scale.abs(),
-                                    start_rad,
-                                    end_rad,
-                                    ccw,
-                                    dp,
-                                );
-                            }
-                            continue;
+                        return;
+                    }
+                    Segment2D::Arc {
+                        center,
+                        radius,
+                        start_rad,
+                        end_rad,
+                        ccw,
+                    } => {
+                        if sm.level("dxf", "entity_arc", "export") != SupportLevel::NotSupported {
+                            emit_arc(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(center, scale),
+                                radius * scale.abs(),
+                                start_rad,
+                                end_rad,
+                                ccw,
+                                dp,
+                            );
                         }
-                        Segment2D::Circle { center, radius } => {
-                            if sm.level("dxf", "entity_circle", "export")
-                                != SupportLevel::NotSupported
-                            {
-                                emit_circle(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(center, scale),
-                                    radius * scale.abs(),
-                                    dp,
-                                );
-                            }
-                            continue;
+                        return;
+                    }
+                    Segment2D::Circle { center, radius } => {
+                        if sm.level("dxf", "entity_circle", "export") != SupportLevel::NotSupported
+                        {
+                            emit_circle(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(center, scale),
+                                radius * scale.abs(),
+                                dp,
+                            );
                         }
-                        Segment2D::CubicBezier { a, c1, c2, b } => {
-                            let lvl = sm.level("dxf", "entity_cubic_bezier", "export");
-                            let seg = opts
-                                .determinism
-                                .approx_min_segments
-                                .max(8)
-                                .min(opts.determinism.approx_max_segments.max(8));
-                            let pts = cubic_flatten_uniform(a, c1, c2, b, seg)
-                                .into_iter()
-                                .map(|p| point_scaled(p, scale))
-                                .collect::<Vec<_>>();
-
-                            if lvl != SupportLevel::NotSupported {
-                                for r in sm.reasons("dxf", "entity_cubic_bezier", "export") {
-                                    warnings.push(
-                                        AppError::new(
-                                            r,
-                                            "cubic bezier approximated for DXF export",
-                                        )
+                        return;
+                    }
+                    Segment2D::CubicBezier { a, c1, c2, b } => {
+                        let lvl = sm.level("dxf", "entity_cubic_bezier", "export");
+                        let seg = opts
+                            .determinism
+                            .approx_min_segments
+                            .max(8)
+                            .min(opts.determinism.approx_max_segments.max(8));
+                        let pts = cubic_flatten_uniform(a, c1, c2, b, seg)
+                            .into_iter()
+                            .map(|p| point_scaled(p, scale))
+                            .collect::<Vec<_>>();
+
+                        if lvl != SupportLevel::NotSupported {
+                            for r in sm.reasons("dxf", "entity_cubic_bezier", "export") {
+                                warnings.push(
+                                    AppError::new(r, "cubic bezier approximated for DXF export")
                                         .with_context("path_id", p.id.clone())
                                         .with_context("segments", seg.to_string()),
-                                    );
-                                }
-                                emit_lwpolyline(&mut out, &layer, &linetype, &pts, false, dp);
+                                );
                             }
-                            continue;
+                            emit_lwpolyline(out, &layer, &linetype, &pts, false, dp);
                         }
+                        return;
                     }
                 }
+            }
 
-                let all_line = p
-                    .segments
-                    .iter()
-                    .all(|s| matches!(s, Segment2D::Line { .. }));
-                if all_line {
-                    if sm.level("dxf", "entity_polyline", "export") == SupportLevel::NotSupported {
-                        continue;
-                    }
-                    let mut pts: Vec<Point2D> = Vec::new();
-                    for (idx, s) in p.segments.iter().enumerate() {
-                        if let Segment2D::Line { a, b } = s {
-                            if idx == 0 {
-                                pts.push(point_scaled(*a, scale));
-                            }
-                            pts.push(point_scaled(*b, scale));
+            let all_line = p
+                .segments
+                .iter()
+                .all(|s| matches!(s, Segment2D::Line { .. }));
+            if all_line {
+                if sm.level("dxf", "entity_polyline", "export") == SupportLevel::NotSupported {
+                    return;
+                }
+                let mut pts: Vec<Point2D> = Vec::new();
+                for (idx, s) in p.segments.iter().enumerate() {
+                    if let Segment2D::Line { a, b } = s {
+                        if idx == 0 {
+                            pts.push(point_scaled(*a, scale));
                         }
+                        pts.push(point_scaled(*b, scale));
                     }
-                    if p.closed && pts.len() >= 2 {
-                        let first = pts[0];
-                        let last = pts[pts.len() - 1];
-                        if (first.x - last.x).abs() <= 1e-12 && (first.y - last.y).abs() <= 1e-12 {
-                            pts.pop();
-                        }
+                }
+                if p.closed && pts.len() >= 2 {
+                    let first = pts[0];
+                    let last = pts[pts.len() - 1];
+                    if (first.x - last.x).abs() <= 1e-12 && (first.y - last.y).abs() <= 1e-12 {
+                        pts.pop();
                     }
+                }
 
-                    if pts.len() >= 2 {
-                        emit_lwpolyline(&mut out, &layer, &linetype, &pts, p.closed, dp);
-                    }
-                    continue;
+                if pts.len() >= 2 {
+                    emit_lwpolyline(out, &layer, &linetype, &pts, p.closed, dp);
                 }
+                return;
+            }
 
-                for s in &p.segments {
-                    match *s {
-                        Segment2D::Line { a, b } => {
-                            if sm.level("dxf", "entity_line", "export")
-                                != SupportLevel::NotSupported
-                            {
-                                emit_line(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(a, scale),
-                                    point_scaled(b, scale),
-                                    dp,
-                                );
-                            }
+            for s in &p.segments {
+                match *s {
+                    Segment2D::Line { a, b } => {
+                        if sm.level("dxf", "entity_line", "export") != SupportLevel::NotSupported {
+                            emit_line(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(a, scale),
+                                point_scaled(b, scale),
+                                dp,
+                            );
                         }
-                        Segment2D::Arc {
-                            center,
-                            radius,
-                            start_rad,
-                            end_rad,
-                            ccw,
-                        } => {
-                            if sm.level("dxf", "entity_arc", "export") != SupportLevel::NotSupported
-                            {
-                                emit_arc(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(center, scale),
-                                    radius * scale.abs(),
-                                    start_rad,
-                                    end_rad,
-                                    ccw,
-                                    dp,
-                                );
-                            }
+                    }
+                    Segment2D::Arc {
+                        center,
+                        radius,
+                        start_rad,
+                        end_rad,
+                        ccw,
+                    } => {
+                        if sm.level("dxf", "entity_arc", "export") != SupportLevel::NotSupported {
+                            emit_arc(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(center, scale),
+                                radius * scale.abs(),
+                                start_rad,
+                                end_rad,
+                                ccw,
+                                dp,
+                            );
                         }
-                        Segment2D::Circle { center, radius } => {
-                            if sm.level("dxf", "entity_circle", "export")
-                                != SupportLevel::NotSupported
-                            {
-                                emit_circle(
-                                    &mut out,
-                                    &layer,
-                                    &linetype,
-                                    point_scaled(center, scale),
-                                    radius * scale.abs(),
-                                    dp,
-                                );
-                            }
+                    }
+                    Segment2D::Circle { center, radius } => {
+                        if sm.level("dxf", "entity_circle", "export") != SupportLevel::NotSupported
+                        {
+                            emit_circle(
+                                out,
+                                &layer,
+                                &linetype,
+                                point_scaled(center, scale),
+                                radius * scale.abs(),
+                                dp,
+                            );
                         }
-                        Segment2D::CubicBezier { a, c1, c2, b } => {
-                            let lvl = sm.level("dxf", "entity_path_unhandled_segment", "export");
-                            if lvl == SupportLevel::NotSupported {
-                                continue;
-                            }
-                            let seg = opts
-                                .determinism
-                                .approx_min_segments
-                                .max(8)
-                                .min(opts.determinism.approx_max_segments.max(8));
-                            let pts = cubic_flatten_uniform(a, c1, c2, b, seg)
-                                .into_iter()
-                                .map(|p| point_scaled(p, scale))
-                                .collect::<Vec<_>>();
-
-                            for r in sm.reasons("dxf", "entity_path_unhandled_segment", "export") {
-                                warnings.push(
-                                    AppError::new(
-                                        r,
-                                        "unhandled segment approximated for DXF export",
-                                    )
+                    }
+                    Segment2D::CubicBezier { a, c1, c2, b } => {
+                        let lvl = sm.level("dxf", "entity_path_unhandled_segment", "export");
+                        if lvl == SupportLevel::NotSupported {
+                            return;
+                        }
+                        let seg = opts
+                            .determinism
+                            .approx_min_segments
+                            .max(8)
+                            .min(opts.determinism.approx_max_segments.max(8));
+                        let pts = cubic_flatten_uniform(a, c1, c2, b, seg)
+                            .into_iter()
+                            .map(|p| point_scaled(p, scale))
+                            .collect::<Vec<_>>();
+
+                        for r in sm.reasons("dxf", "entity_path_unhandled_segment", "export") {
+                            warnings.push(
+                                AppError::new(r, "unhandled segment approximated for DXF export")
                                     .with_context("path_id", p.id.clone())
                                     .with_context("segments", seg.to_string()),
-                                );
-                            }
-                            emit_lwpolyline(&mut out, &layer, &linetype, &pts, false, dp);
+                            );
                         }
+                        emit_lwpolyline(out, &layer, &linetype, &pts, false, dp);
                     }
                 }
             }
+        }
 
-            Entity::Text(t) => {
-                let lvl = sm.level("dxf", "entity_text", "export");
-                if lvl == SupportLevel::NotSupported {
-                    continue;
-                }
+        Entity::Text(t) => {
+            let lvl = sm.level("dxf", "entity_text", "export");
+            if lvl == SupportLevel::NotSupported {
+                return;
+            }
 
-                let layer = mr.map_layer(&t.layer);
-                let linetype = mr.map_linetype("CONTINUOUS");
-                let pos = point_scaled(t.pos, scale);
-                let size = (t.size as f64) * scale.abs();
-                let rot_deg = rad_to_deg(t.rotation_rad);
+            let layer = mr.map_layer(&t.layer);
+            let linetype = mr.map_linetype("CONTINUOUS");
+            let pos = point_scaled(t.pos, scale);
+            let size = (t.size as f64) * scale.abs();
+            let rot_deg = rad_to_deg(t.rotation_rad);
 
-                emit_text(&mut out, &layer, &linetype, pos, size, rot_deg, &t.text, dp);
+            emit_text(out, &layer, &linetype, pos, size, rot_deg, &t.text, dp);
 
-                if lvl == SupportLevel::BestEffort {
-                    for r in sm.reasons("dxf", "entity_text", "export") {
-                        warnings.push(AppError::new(r, "DXF text exported best-effort"));
-                    }
+            if lvl == SupportLevel::BestEffort {
+                for r in sm.reasons("dxf", "entity_text", "export") {
+                    warnings.push(AppError::new(r, "DXF text exported best-effort"));
                 }
             }
         }
     }
+}
+
+fn emit_insert(out: &mut String, mr: &MappingRules, i: &BlockInsert, scale: f64, dp: usize) {
+    out.push_str("0\nINSERT\n");
+    push_group(out, 8, &mr.map_layer(&i.layer));
+    push_group(out, 2, &i.block);
+    push_group(out, 10, &fmt_fixed(i.pos.x * scale, dp));
+    push_group(out, 20, &fmt_fixed(i.pos.y * scale, dp));
+    push_group(out, 41, &fmt_fixed(i.scale_x, dp));
+    push_group(out, 42, &fmt_fixed(i.scale_y, dp));
+    push_group(
+        out,
+        50,
+        &fmt_fixed(norm_deg(rad_to_deg(i.rotation_rad)), dp),
+    );
+}
+
+pub fn export_dxf(
+    model: &InternalModel,
+    opts: &ExportOptions,
+) -> AppResult<(Vec<u8>, Vec<AppError>, IoReport)> {
+    let sm = SupportMatrix::load_from_ssot()?;
+    let mr = MappingRules::load_from_ssot()?;
+
+    let dp = mr.export_decimal_places() as usize;
+    let scale = unit_scale(model.units, opts.target_units);
+
+    let mut warnings = Vec::new();
+    let mut report = IoReport::new("dxf");
+    let mut out = String::new();
+
+    out.push_str("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n");
+    out.push_str(&format!("{}\n", header_units(opts.target_units)));
+    out.push_str("0\nENDSEC\n");
+
+    if !model.layers.is_empty()
+        && sm.level("dxf", "layer_table", "export") != SupportLevel::NotSupported
+    {
+        emit_layer_table(&mut out, &mr, &model.layers);
+    }
+
+    let blocks = sm.level("dxf", "entity_insert", "export") != SupportLevel::NotSupported;
+    if blocks && !model.blocks.is_empty() {
+        out.push_str("0\nSECTION\n2\nBLOCKS\n");
+        for b in &model.blocks {
+            out.push_str("0\nBLOCK\n");
+            push_group(&mut out, 8, "0");
+            push_group(&mut out, 2, &b.name);
+            push_group(&mut out, 70, "0");
+            push_group(&mut out, 10, &fmt_fixed(b.base.x * scale, dp));
+            push_group(&mut out, 20, &fmt_fixed(b.base.y * scale, dp));
+            push_group(&mut out, 3, &b.name);
+            for e in &b.entities {
+                emit_entity(
+                    &mut out,
+                    e,
+                    &model.layers,
+                    &sm,
+                    &mr,
+                    opts,
+                    scale,
+                    dp,
+                    &mut warnings,
+                );
+            }
+            for i in &b.inserts {
+                emit_insert(&mut out, &mr, i, scale, dp);
+            }
+            out.push_str("0\nENDBLK\n");
+            push_group(&mut out, 8, "0");
+        }
+        out.push_str("0\nENDSEC\n");
+    }
+    out.push_str("0\nSECTION\n2\nENTITIES\n");
+
+    for e in &model.entities {
+        emit_entity(
+            &mut out,
+            e,
+            &model.layers,
+            &sm,
+            &mr,
+            opts,
+            scale,
+            dp,
+            &mut warnings,
+        );
+    }
+    if blocks {
+        for i in &model.inserts {
+            emit_insert(&mut out, &mr, i, scale, dp);
+        }
+    }
 
     out.push_str("0\nENDSEC\n0\nEOF\n");
 
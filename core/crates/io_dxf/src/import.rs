@@ -1,5 +1,8 @@
 use crate::mapping::map_stroke;
-use crate::parse::{parse_dxf_groups, parse_header_insunits, split_entities};
+use crate::parse::{
+    parse_dxf_groups, parse_header_insunits, split_blocks, split_entities, split_layers, DxfEntity,
+    DxfLayer,
+};
 use craftcad_io::model::*;
 use craftcad_io::options::ImportOptions;
 use craftcad_io::reasons::{AppError, AppResult, ReasonCode};
@@ -13,6 +16,13 @@ fn get_f64(groups: &[(i32, String)], code: i32) -> Option<f64> {
         .and_then(|(_, v)| v.trim().parse::<f64>().ok())
 }
 
+fn get_str(groups: &[(i32, String)], code: i32) -> Option<String> {
+    groups
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, v)| v.trim().to_string())
+}
+
 fn get_i32(groups: &[(i32, String)], code: i32) -> Option<i32> {
     groups
         .iter()
@@ -133,6 +143,308 @@ fn parse_polyline_vertices(g2: &[(i32, String)]) -> (Vec<Vertex>, bool) {
     (verts, closed)
 }
 
+/// Converts one DXF entity, appending geometry to `out` and INSERTs to
+/// `inserts`.
+fn import_entity(
+    e: &DxfEntity,
+    sm: &SupportMatrix,
+    mr: &MappingRules,
+    out: &mut Vec<Entity>,
+    inserts: &mut Vec<BlockInsert>,
+    warnings: &mut Vec<AppError>,
+) {
+    let kind = e.kind.to_uppercase();
+    let stroke = map_stroke(
+        mr,
+        StrokeStyle {
+            layer: e.layer.clone(),
+            linetype: e.linetype.clone(),
+            ..StrokeStyle::default()
+        },
+    );
+    let g2: Vec<(i32, String)> = e.groups.iter().map(|g| (g.code, g.value.clone())).collect();
+
+    match kind.as_str() {
+        "LINE" => {
+            if sm.level("dxf", "entity_line", "import") == SupportLevel::NotSupported {
+                return;
+            }
+            let x1 = get_f64(&g2, 10).unwrap_or(0.0);
+            let y1 = get_f64(&g2, 20).unwrap_or(0.0);
+            let x2 = get_f64(&g2, 11).unwrap_or(0.0);
+            let y2 = get_f64(&g2, 21).unwrap_or(0.0);
+            let mut p = PathEntity::new(format!("dxf_line_{}", out.len()), stroke);
+            p.segments.push(Segment2D::Line {
+                a: Point2D { x: x1, y: y1 },
+                b: Point2D { x: x2, y: y2 },
+            });
+            out.push(Entity::Path(p));
+        }
+
+        "LWPOLYLINE" | "POLYLINE" => {
+            if sm.level("dxf", "entity_polyline", "import") == SupportLevel::NotSupported {
+                return;
+            }
+            let (verts, closed) = parse_polyline_vertices(&g2);
+            if verts.len() < 2 {
+                return;
+            }
+
+            let mut p = PathEntity::new(format!("dxf_pl_{}", out.len()), stroke);
+            p.closed = closed;
+
+            for i in 0..(verts.len() - 1) {
+                let a = verts[i].p;
+                let b = verts[i + 1].p;
+                let bulge = verts[i].bulge;
+                if let Some(seg) = bulge_to_arc(a, b, bulge) {
+                    p.segments.push(seg);
+                }
+            }
+            if closed {
+                let a = verts[verts.len() - 1].p;
+                let b = verts[0].p;
+                let bulge = verts[verts.len() - 1].bulge;
+                if let Some(seg) = bulge_to_arc(a, b, bulge) {
+                    p.segments.push(seg);
+                }
+            }
+
+            if !p.segments.is_empty() {
+                out.push(Entity::Path(p));
+            }
+        }
+
+        "ARC" => {
+            if sm.level("dxf", "entity_arc", "import") == SupportLevel::NotSupported {
+                return;
+            }
+            let cx = get_f64(&g2, 10).unwrap_or(0.0);
+            let cy = get_f64(&g2, 20).unwrap_or(0.0);
+            let r = get_f64(&g2, 40).unwrap_or(0.0);
+            let a0 = get_f64(&g2, 50).unwrap_or(0.0).to_radians();
+            let a1 = get_f64(&g2, 51).unwrap_or(0.0).to_radians();
+            let mut p = PathEntity::new(format!("dxf_arc_{}", out.len()), stroke);
+            p.segments.push(Segment2D::Arc {
+                center: Point2D { x: cx, y: cy },
+                radius: r,
+                start_rad: a0,
+                end_rad: a1,
+                ccw: true,
+            });
+            out.push(Entity::Path(p));
+        }
+
+        "CIRCLE" => {
+            if sm.level("dxf", "entity_circle", "import") == SupportLevel::NotSupported {
+                return;
+            }
+            let cx = get_f64(&g2, 10).unwrap_or(0.0);
+            let cy = get_f64(&g2, 20).unwrap_or(0.0);
+            let r = get_f64(&g2, 40).unwrap_or(0.0);
+            let mut p = PathEntity::new(format!("dxf_circle_{}", out.len()), stroke);
+            p.segments.push(Segment2D::Circle {
+                center: Point2D { x: cx, y: cy },
+                radius: r,
+            });
+            out.push(Entity::Path(p));
+        }
+
+        "TEXT" | "MTEXT" => {
+            let lvl = sm.level("dxf", "entity_text", "import");
+            if lvl == SupportLevel::NotSupported {
+                return;
+            }
+            let t = TextEntity {
+                id: format!("dxf_text_{}", out.len()),
+                layer: stroke.layer.clone(),
+                pos: Point2D {
+                    x: get_f64(&g2, 10).unwrap_or(0.0),
+                    y: get_f64(&g2, 20).unwrap_or(0.0),
+                },
+                text: collect_text(&g2),
+                size: get_f64(&g2, 40).unwrap_or(12.0) as f32,
+                font_hint: None,
+                rotation_rad: get_f64(&g2, 50).unwrap_or(0.0).to_radians(),
+            };
+            if lvl == SupportLevel::BestEffort {
+                for r in sm.reasons("dxf", "entity_text", "import") {
+                    warnings.push(AppError::new(r, "DXF TEXT imported best-effort"));
+                }
+            }
+            out.push(Entity::Text(t));
+        }
+
+        "SPLINE" => {
+            let lvl = sm.level("dxf", "entity_spline", "import");
+            if lvl == SupportLevel::NotSupported {
+                return;
+            }
+
+            let xs: Vec<f64> = g2
+                .iter()
+                .filter_map(|(c, v)| {
+                    if *c == 10 {
+                        v.parse::<f64>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let ys: Vec<f64> = g2
+                .iter()
+                .filter_map(|(c, v)| {
+                    if *c == 20 {
+                        v.parse::<f64>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            let mut pts: Vec<Point2D> = Vec::new();
+            let n = xs.len().min(ys.len());
+            for i in 0..n {
+                pts.push(Point2D { x: xs[i], y: ys[i] });
+            }
+
+            let mut p = PathEntity::new(format!("dxf_spline_{}", out.len()), stroke);
+
+            if pts.len() >= 4 {
+                let mut i = 0usize;
+                while i + 3 < pts.len() {
+                    p.segments.push(Segment2D::CubicBezier {
+                        a: pts[i],
+                        c1: pts[i + 1],
+                        c2: pts[i + 2],
+                        b: pts[i + 3],
+                    });
+                    i += 3;
+                }
+                warnings.push(
+                    AppError::new(
+                        ReasonCode::IO_DXF_SPLINE_CONVERTED,
+                        "DXF SPLINE converted to cubic beziers",
+                    )
+                    .with_context("bezier_segments", p.segments.len().to_string()),
+                );
+            } else if pts.len() >= 2 {
+                for w in pts.windows(2) {
+                    p.segments.push(Segment2D::Line { a: w[0], b: w[1] });
+                }
+                warnings.push(
+                    AppError::new(
+                        ReasonCode::IO_DXF_SPLINE_CONVERTED,
+                        "DXF SPLINE converted to polyline fallback",
+                    )
+                    .with_context("line_segments", p.segments.len().to_string()),
+                );
+            }
+
+            if lvl == SupportLevel::BestEffort {
+                for r in sm.reasons("dxf", "entity_spline", "import") {
+                    warnings.push(AppError::new(r, "DXF SPLINE best-effort conversion"));
+                }
+            }
+
+            if !p.segments.is_empty() {
+                out.push(Entity::Path(p));
+            }
+        }
+
+        "INSERT" => {
+            if sm.level("dxf", "entity_insert", "import") == SupportLevel::NotSupported {
+                return;
+            }
+            inserts.push(BlockInsert {
+                id: format!("dxf_insert_{}", inserts.len()),
+                block: get_str(&g2, 2).unwrap_or_default(),
+                layer: stroke.layer.clone(),
+                pos: Point2D {
+                    x: get_f64(&g2, 10).unwrap_or(0.0),
+                    y: get_f64(&g2, 20).unwrap_or(0.0),
+                },
+                scale_x: get_f64(&g2, 41).unwrap_or(1.0),
+                scale_y: get_f64(&g2, 42).unwrap_or(1.0),
+                rotation_rad: get_f64(&g2, 50).unwrap_or(0.0).to_radians(),
+            });
+        }
+
+        other => {
+            warnings.push(
+                AppError::new(
+                    ReasonCode::IO_DXF_ENTITY_UNKNOWN_DROPPED,
+                    "unknown DXF entity dropped",
+                )
+                .with_context("entity", other.to_string()),
+            );
+        }
+    }
+}
+
+/// Whether block `from` places `target`, directly or through nesting.
+fn places(blocks: &[BlockDef], from: &str, target: &str) -> bool {
+    let mut stack = vec![(from.to_string(), 0usize)];
+    while let Some((name, depth)) = stack.pop() {
+        if name == target {
+            return true;
+        }
+        if depth > blocks.len() {
+            return true;
+        }
+        if let Some(b) = blocks.iter().find(|b| b.name == name) {
+            stack.extend(b.inserts.iter().map(|i| (i.block.clone(), depth + 1)));
+        }
+    }
+    false
+}
+
+/// Drops INSERTs of undefined blocks and those that would place a block
+/// inside itself.
+fn drop_bad_inserts(model: &mut InternalModel, warnings: &mut Vec<AppError>) {
+    let names: Vec<String> = model.blocks.iter().map(|b| b.name.clone()).collect();
+    let (kept, mut dropped): (Vec<_>, Vec<_>) = std::mem::take(&mut model.inserts)
+        .into_iter()
+        .partition(|i| names.contains(&i.block));
+    model.inserts = kept;
+    for at in 0..model.blocks.len() {
+        let owner = model.blocks[at].name.clone();
+        let (kept, bad): (Vec<_>, Vec<_>) = std::mem::take(&mut model.blocks[at].inserts)
+            .into_iter()
+            .partition(|i| names.contains(&i.block) && !places(&model.blocks, &i.block, &owner));
+        model.blocks[at].inserts = kept;
+        dropped.extend(bad);
+    }
+    for i in dropped {
+        warnings.push(
+            AppError::new(
+                ReasonCode::IO_DXF_ENTITY_UNKNOWN_DROPPED,
+                "INSERT of an undefined or self-containing block dropped",
+            )
+            .with_context("entity", "INSERT")
+            .with_context("insert_id", i.id)
+            .with_context("block", i.block),
+        );
+    }
+}
+
+/// Colour of a layer record: group 420 when present, else ACI 1..=6.
+/// Other indexes, including 7 (black/white), mean "no colour".
+fn layer_rgb(l: &DxfLayer) -> Option<(u8, u8, u8)> {
+    if let Some(c) = l.true_color {
+        return Some(((c >> 16) as u8, (c >> 8) as u8, c as u8));
+    }
+    match l.color_index {
+        1 => Some((255, 0, 0)),
+        2 => Some((255, 255, 0)),
+        3 => Some((0, 255, 0)),
+        4 => Some((0, 255, 255)),
+        5 => Some((0, 0, 255)),
+        6 => Some((255, 0, 255)),
+        _ => None,
+    }
+}
+
 pub fn import_dxf(
     bytes: &[u8],
     opts: &ImportOptions,
@@ -182,216 +494,57 @@ pub fn import_dxf(
         model.metadata.unit_guess = Some(format!("header:$INSUNITS={}", u));
     }
 
-    for e in ents {
-        let kind = e.kind.to_uppercase();
-        let stroke = map_stroke(
+    for e in &ents {
+        import_entity(
+            e,
+            &sm,
             &mr,
-            StrokeStyle {
-                layer: e.layer.clone(),
-                linetype: e.linetype.clone(),
-                ..StrokeStyle::default()
-            },
+            &mut model.entities,
+            &mut model.inserts,
+            &mut warnings,
         );
-        let g2: Vec<(i32, String)> = e.groups.iter().map(|g| (g.code, g.value.clone())).collect();
-
-        match kind.as_str() {
-            "LINE" => {
-                if sm.level("dxf", "entity_line", "import") == SupportLevel::NotSupported {
-                    continue;
-                }
-                let x1 = get_f64(&g2, 10).unwrap_or(0.0);
-                let y1 = get_f64(&g2, 20).unwrap_or(0.0);
-                let x2 = get_f64(&g2, 11).unwrap_or(0.0);
-                let y2 = get_f64(&g2, 21).unwrap_or(0.0);
-                let mut p = PathEntity::new(format!("dxf_line_{}", model.entities.len()), stroke);
-                p.segments.push(Segment2D::Line {
-                    a: Point2D { x: x1, y: y1 },
-                    b: Point2D { x: x2, y: y2 },
-                });
-                model.entities.push(Entity::Path(p));
-            }
-
-            "LWPOLYLINE" | "POLYLINE" => {
-                if sm.level("dxf", "entity_polyline", "import") == SupportLevel::NotSupported {
-                    continue;
-                }
-                let (verts, closed) = parse_polyline_vertices(&g2);
-                if verts.len() < 2 {
-                    continue;
-                }
-
-                let mut p = PathEntity::new(format!("dxf_pl_{}", model.entities.len()), stroke);
-                p.closed = closed;
-
-                for i in 0..(verts.len() - 1) {
-                    let a = verts[i].p;
-                    let b = verts[i + 1].p;
-                    let bulge = verts[i].bulge;
-                    if let Some(seg) = bulge_to_arc(a, b, bulge) {
-                        p.segments.push(seg);
-                    }
-                }
-                if closed {
-                    let a = verts[verts.len() - 1].p;
-                    let b = verts[0].p;
-                    let bulge = verts[verts.len() - 1].bulge;
-                    if let Some(seg) = bulge_to_arc(a, b, bulge) {
-                        p.segments.push(seg);
-                    }
-                }
-
-                if !p.segments.is_empty() {
-                    model.entities.push(Entity::Path(p));
-                }
-            }
-
-            "ARC" => {
-                if sm.level("dxf", "entity_arc", "import") == SupportLevel::NotSupported {
-                    continue;
-                }
-                let cx = get_f64(&g2, 10).unwrap_or(0.0);
-                let cy = get_f64(&g2, 20).unwrap_or(0.0);
-                let r = get_f64(&g2, 40).unwrap_or(0.0);
-                let a0 = get_f64(&g2, 50).unwrap_or(0.0).to_radians();
-                let a1 = get_f64(&g2, 51).unwrap_or(0.0).to_radians();
-                let mut p = PathEntity::new(format!("dxf_arc_{}", model.entities.len()), stroke);
-                p.segments.push(Segment2D::Arc {
-                    center: Point2D { x: cx, y: cy },
-                    radius: r,
-                    start_rad: a0,
-                    end_rad: a1,
-                    ccw: true,
-                });
-                model.entities.push(Entity::Path(p));
-            }
-
-            "CIRCLE" => {
-                if sm.level("dxf", "entity_circle", "import") == SupportLevel::NotSupported {
-                    continue;
-                }
-                let cx = get_f64(&g2, 10).unwrap_or(0.0);
-                let cy = get_f64(&g2, 20).unwrap_or(0.0);
-                let r = get_f64(&g2, 40).unwrap_or(0.0);
-                let mut p = PathEntity::new(format!("dxf_circle_{}", model.entities.len()), stroke);
-                p.segments.push(Segment2D::Circle {
-                    center: Point2D { x: cx, y: cy },
-                    radius: r,
-                });
-                model.entities.push(Entity::Path(p));
-            }
-
-            "TEXT" | "MTEXT" => {
-                let lvl = sm.level("dxf", "entity_text", "import");
-                if lvl == SupportLevel::NotSupported {
-                    continue;
-                }
-                let t = TextEntity {
-                    id: format!("dxf_text_{}", model.entities.len()),
-                    layer: stroke.layer.clone(),
-                    pos: Point2D {
-                        x: get_f64(&g2, 10).unwrap_or(0.0),
-                        y: get_f64(&g2, 20).unwrap_or(0.0),
-                    },
-                    text: collect_text(&g2),
-                    size: get_f64(&g2, 40).unwrap_or(12.0) as f32,
-                    font_hint: None,
-                    rotation_rad: get_f64(&g2, 50).unwrap_or(0.0).to_radians(),
-                };
-                if lvl == SupportLevel::BestEffort {
-                    for r in sm.reasons("dxf", "entity_text", "import") {
-                        warnings.push(AppError::new(r, "DXF TEXT imported best-effort"));
-                    }
-                }
-                model.entities.push(Entity::Text(t));
-            }
-
-            "SPLINE" => {
-                let lvl = sm.level("dxf", "entity_spline", "import");
-                if lvl == SupportLevel::NotSupported {
-                    continue;
-                }
-
-                let xs: Vec<f64> = g2
-                    .iter()
-                    .filter_map(|(c, v)| {
-                        if *c == 10 {
-                            v.parse::<f64>().ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                let ys: Vec<f64> = g2
-                    .iter()
-                    .filter_map(|(c, v)| {
-                        if *c == 20 {
-                            v.parse::<f64>().ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                let mut pts: Vec<Point2D> = Vec::new();
-                let n = xs.len().min(ys.len());
-                for i in 0..n {
-                    pts.push(Point2D { x: xs[i], y: ys[i] });
-                }
-
-                let mut p = PathEntity::new(format!("dxf_spline_{}", model.entities.len()), stroke);
-
-                if pts.len() >= 4 {
-                    let mut i = 0usize;
-                    while i + 3 < pts.len() {
-                        p.segments.push(Segment2D::CubicBezier {
-                            a: pts[i],
-                            c1: pts[i + 1],
-                            c2: pts[i + 2],
-                            b: pts[i + 3],
-                        });
-                        i += 3;
-                    }
-                    warnings.push(
-                        AppError::new(
-                            ReasonCode::IO_DXF_SPLINE_CONVERTED,
-                            "DXF SPLINE converted to cubic beziers",
-                        )
-                        .with_context("bezier_segments", p.segments.len().to_string()),
-                    );
-                } else if pts.len() >= 2 {
-                    for w in pts.windows(2) {
-                        p.segments.push(Segment2D::Line { a: w[0], b: w[1] });
-                    }
-                    warnings.push(
-                        AppError::new(
-                            ReasonCode::IO_DXF_SPLINE_CONVERTED,
-                            "DXF SPLINE converted to polyline fallback",
-                        )
-                        .with_context("line_segments", p.segments.len().to_string()),
-                    );
-                }
-
-                if lvl == SupportLevel::BestEffort {
-                    for r in sm.reasons("dxf", "entity_spline", "import") {
-                        warnings.push(AppError::new(r, "DXF SPLINE best-effort conversion"));
-                    }
-                }
-
-                if !p.segments.is_empty() {
-                    model.entities.push(Entity::Path(p));
-                }
-            }
+    }
 
-            other => {
-                warnings.push(
-                    AppError::new(
-                        ReasonCode::IO_DXF_ENTITY_UNKNOWN_DROPPED,
-                        "unknown DXF entity dropped",
-                    )
-                    .with_context("entity", other.to_string()),
-                );
-            }
+    for b in split_blocks(&groups) {
+        // Layout blocks hold what ENTITIES already has.
+        let name = b.name.to_ascii_uppercase();
+        if b.name.is_empty() || name.starts_with("*MODEL_SPACE") || name.starts_with("*PAPER_SPACE")
+        {
+            continue;
         }
+        let mut def = BlockDef {
+            name: b.name.clone(),
+            base: Point2D {
+                x: b.base.0,
+                y: b.base.1,
+            },
+            entities: vec![],
+            inserts: vec![],
+        };
+        for e in &b.entities {
+            import_entity(
+                e,
+                &sm,
+                &mr,
+                &mut def.entities,
+                &mut def.inserts,
+                &mut warnings,
+            );
+        }
+        model.blocks.push(def);
+    }
+    drop_bad_inserts(&mut model, &mut warnings);
+
+    if sm.level("dxf", "layer_table", "import") != SupportLevel::NotSupported {
+        model.layers = split_layers(&groups)
+            .iter()
+            .map(|l| LayerStyle {
+                name: mr.map_layer(&l.name),
+                rgb: layer_rgb(l),
+                linetype: mr.map_linetype(&l.linetype),
+                print: l.plot,
+            })
+            .collect();
     }
 
     report.entities_in = model.entities.len();
@@ -14,6 +14,24 @@ pub struct DxfEntity {
     pub groups: Vec<DxfGroup>,
 }
 
+/// A BLOCK record with the entities up to its ENDBLK.
+#[derive(Debug, Clone)]
+pub struct DxfBlock {
+    pub name: String,
+    pub base: (f64, f64),
+    pub entities: Vec<DxfEntity>,
+}
+
+/// A LAYER record from the TABLES section.
+#[derive(Debug, Clone)]
+pub struct DxfLayer {
+    pub name: String,
+    pub color_index: i32,
+    pub true_color: Option<u32>,
+    pub linetype: String,
+    pub plot: bool,
+}
+
 fn upper(s: &str) -> String {
     s.trim().to_ascii_uppercase()
 }
@@ -22,6 +40,8 @@ fn upper(s: &str) -> String {
 enum Section {
     None,
     Header,
+    Tables,
+    Blocks,
     Entities,
     Other,
 }
@@ -114,11 +134,7 @@ pub fn parse_header_insunits(groups: &[DxfGroup]) -> Option<i32> {
             && i + 1 < groups.len()
             && groups[i + 1].code == 2
         {
-            sec = match upper(&groups[i + 1].value).as_str() {
-                "HEADER" => Section::Header,
-                "ENTITIES" => Section::Entities,
-                _ => Section::Other,
-            };
+            sec = section_kind(&groups[i + 1].value);
             i += 2;
             continue;
         }
@@ -147,6 +163,82 @@ pub fn parse_header_insunits(groups: &[DxfGroup]) -> Option<i32> {
     None
 }
 
+fn section_kind(name: &str) -> Section {
+    match upper(name).as_str() {
+        "HEADER" => Section::Header,
+        "TABLES" => Section::Tables,
+        "BLOCKS" => Section::Blocks,
+        "ENTITIES" => Section::Entities,
+        _ => Section::Other,
+    }
+}
+
+/// Reads the entity whose `0` group is at `*i`, leaving `*i` on the next
+/// `0` group. POLYLINE absorbs its VERTEX and SEQEND records.
+fn read_entity(groups: &[DxfGroup], i: &mut usize) -> DxfEntity {
+    let kind = upper(&groups[*i].value);
+    let mut e = DxfEntity {
+        kind: kind.clone(),
+        layer: "0".into(),
+        linetype: "CONTINUOUS".into(),
+        groups: Vec::new(),
+    };
+
+    *i += 1;
+    while *i < groups.len() {
+        let gg = &groups[*i];
+        if gg.code == 0 {
+            break;
+        }
+        if gg.code == 8 {
+            e.layer = gg.value.trim().to_string();
+        } else if gg.code == 6 {
+            e.linetype = gg.value.trim().to_string();
+        }
+        e.groups.push(gg.clone());
+        *i += 1;
+    }
+
+    if kind != "POLYLINE" {
+        return e;
+    }
+
+    while *i < groups.len() {
+        if groups[*i].code != 0 {
+            *i += 1;
+            continue;
+        }
+        let k = upper(&groups[*i].value);
+        if k == "VERTEX" {
+            *i += 1;
+            while *i < groups.len() {
+                let vg = &groups[*i];
+                if vg.code == 0 {
+                    break;
+                }
+                if vg.code == 10 || vg.code == 20 || vg.code == 42 {
+                    e.groups.push(vg.clone());
+                }
+                *i += 1;
+            }
+            continue;
+        }
+        if k == "SEQEND" {
+            *i += 1;
+            while *i < groups.len() {
+                if groups[*i].code == 0 {
+                    break;
+                }
+                *i += 1;
+            }
+            break;
+        }
+        break;
+    }
+
+    e
+}
+
 pub fn split_entities(groups: &[DxfGroup]) -> Vec<DxfEntity> {
     let mut ents: Vec<DxfEntity> = Vec::new();
     let mut sec = Section::None;
@@ -160,11 +252,7 @@ pub fn split_entities(groups: &[DxfGroup]) -> Vec<DxfEntity> {
             && i + 1 < groups.len()
             && groups[i + 1].code == 2
         {
-            sec = match upper(&groups[i + 1].value).as_str() {
-                "HEADER" => Section::Header,
-                "ENTITIES" => Section::Entities,
-                _ => Section::Other,
-            };
+            sec = section_kind(&groups[i + 1].value);
             i += 2;
             continue;
         }
@@ -174,101 +262,140 @@ pub fn split_entities(groups: &[DxfGroup]) -> Vec<DxfEntity> {
             continue;
         }
 
-        if sec != Section::Entities {
+        if sec != Section::Entities || g.code != 0 {
             i += 1;
             continue;
         }
 
-        if g.code != 0 {
+        ents.push(read_entity(groups, &mut i));
+    }
+
+    ents
+}
+
+/// Block definitions from the BLOCKS section, in file order.
+pub fn split_blocks(groups: &[DxfGroup]) -> Vec<DxfBlock> {
+    let mut blocks: Vec<DxfBlock> = Vec::new();
+    let mut sec = Section::None;
+    let mut open: Option<DxfBlock> = None;
+
+    let mut i = 0usize;
+    while i < groups.len() {
+        let g = &groups[i];
+
+        if g.code == 0
+            && upper(&g.value) == "SECTION"
+            && i + 1 < groups.len()
+            && groups[i + 1].code == 2
+        {
+            sec = section_kind(&groups[i + 1].value);
+            i += 2;
+            continue;
+        }
+        if g.code == 0 && upper(&g.value) == "ENDSEC" {
+            sec = Section::None;
+            blocks.extend(open.take());
             i += 1;
             continue;
         }
 
-        let kind = upper(&g.value);
-        if kind == "POLYLINE" {
-            let mut e = DxfEntity {
-                kind: kind.clone(),
-                layer: "0".into(),
-                linetype: "CONTINUOUS".into(),
-                groups: Vec::new(),
-            };
-
+        if sec != Section::Blocks || g.code != 0 {
             i += 1;
-            while i < groups.len() {
-                let gg = &groups[i];
-                if gg.code == 0 {
-                    break;
-                }
-                if gg.code == 8 {
-                    e.layer = gg.value.trim().to_string();
-                } else if gg.code == 6 {
-                    e.linetype = gg.value.trim().to_string();
-                }
-                e.groups.push(gg.clone());
-                i += 1;
-            }
+            continue;
+        }
 
-            while i < groups.len() {
-                if groups[i].code != 0 {
-                    i += 1;
-                    continue;
-                }
-                let k = upper(&groups[i].value);
-                if k == "VERTEX" {
-                    i += 1;
-                    while i < groups.len() {
-                        let vg = &groups[i];
-                        if vg.code == 0 {
-                            break;
-                        }
-                        if vg.code == 10 || vg.code == 20 || vg.code == 42 {
-                            e.groups.push(vg.clone());
-                        }
-                        i += 1;
+        match upper(&g.value).as_str() {
+            "BLOCK" => {
+                blocks.extend(open.take());
+                let mut b = DxfBlock {
+                    name: String::new(),
+                    base: (0.0, 0.0),
+                    entities: Vec::new(),
+                };
+                i += 1;
+                while i < groups.len() && groups[i].code != 0 {
+                    let v = groups[i].value.trim();
+                    match groups[i].code {
+                        2 => b.name = v.to_string(),
+                        10 => b.base.0 = v.parse().unwrap_or(0.0),
+                        20 => b.base.1 = v.parse().unwrap_or(0.0),
+                        _ => {}
                     }
-                    continue;
-                }
-                if k == "SEQEND" {
                     i += 1;
-                    while i < groups.len() {
-                        if groups[i].code == 0 {
-                            break;
-                        }
-                        i += 1;
-                    }
-                    break;
                 }
-                break;
+                open = Some(b);
             }
+            "ENDBLK" => {
+                blocks.extend(open.take());
+                i += 1;
+            }
+            _ => {
+                let e = read_entity(groups, &mut i);
+                if let Some(b) = open.as_mut() {
+                    b.entities.push(e);
+                }
+            }
+        }
+    }
 
-            ents.push(e);
+    blocks.extend(open);
+    blocks
+}
+
+/// LAYER records from the TABLES section, in file order.
+pub fn split_layers(groups: &[DxfGroup]) -> Vec<DxfLayer> {
+    let mut layers: Vec<DxfLayer> = Vec::new();
+    let mut sec = Section::None;
+
+    let mut i = 0usize;
+    while i < groups.len() {
+        let g = &groups[i];
+
+        if g.code == 0
+            && upper(&g.value) == "SECTION"
+            && i + 1 < groups.len()
+            && groups[i + 1].code == 2
+        {
+            sec = section_kind(&groups[i + 1].value);
+            i += 2;
+            continue;
+        }
+        if g.code == 0 && upper(&g.value) == "ENDSEC" {
+            sec = Section::None;
+            i += 1;
+            continue;
+        }
+
+        if sec != Section::Tables || g.code != 0 || upper(&g.value) != "LAYER" {
+            i += 1;
             continue;
         }
 
-        let mut e = DxfEntity {
-            kind: kind.clone(),
-            layer: "0".into(),
+        let mut l = DxfLayer {
+            name: String::new(),
+            color_index: 7,
+            true_color: None,
             linetype: "CONTINUOUS".into(),
-            groups: Vec::new(),
+            plot: true,
         };
-
         i += 1;
-        while i < groups.len() {
-            let gg = &groups[i];
-            if gg.code == 0 {
-                break;
-            }
-            if gg.code == 8 {
-                e.layer = gg.value.trim().to_string();
-            } else if gg.code == 6 {
-                e.linetype = gg.value.trim().to_string();
+        while i < groups.len() && groups[i].code != 0 {
+            let v = groups[i].value.trim();
+            match groups[i].code {
+                2 => l.name = v.to_string(),
+                // A negative index marks a layer that is switched off.
+                62 => l.color_index = v.parse::<i32>().map(i32::abs).unwrap_or(7),
+                420 => l.true_color = v.parse().ok(),
+                6 => l.linetype = v.to_string(),
+                290 => l.plot = v != "0",
+                _ => {}
             }
-            e.groups.push(gg.clone());
             i += 1;
         }
-
-        ents.push(e);
+        if !l.name.is_empty() {
+            layers.push(l);
+        }
     }
 
-    ents
+    layers
 }
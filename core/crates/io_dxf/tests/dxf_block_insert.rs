@@ -0,0 +1,79 @@
+use craftcad_io::model::{Entity, Segment2D};
+use craftcad_io::options::{ExportOptions, ImportOptions};
+use craftcad_io::reasons::ReasonCode;
+use craftcad_io::IoEngine;
+use craftcad_io_dxf::DxfIo;
+
+/// A hinge block placed mirrored and turned, a door block nesting it, and
+/// an INSERT of a block the file never defines.
+const DXF: &str = "0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n4\n0\nENDSEC\n\
+0\nSECTION\n2\nBLOCKS\n\
+0\nBLOCK\n8\n0\n2\n*Model_Space\n70\n0\n10\n0\n20\n0\n0\nENDBLK\n8\n0\n\
+0\nBLOCK\n8\n0\n2\nHINGE\n70\n0\n10\n5\n20\n5\n\
+0\nCIRCLE\n8\nCUT\n10\n5\n20\n5\n40\n17.5\n\
+0\nLINE\n8\nCUT\n10\n25\n20\n0\n11\n25\n21\n10\n\
+0\nENDBLK\n8\n0\n\
+0\nBLOCK\n8\n0\n2\nDOOR\n70\n0\n10\n0\n20\n0\n\
+0\nINSERT\n8\nCUT\n2\nHINGE\n10\n0\n20\n100\n\
+0\nINSERT\n8\nCUT\n2\nDOOR\n10\n0\n20\n0\n\
+0\nENDBLK\n8\n0\n\
+0\nENDSEC\n\
+0\nSECTION\n2\nENTITIES\n\
+0\nLINE\n8\nCUT\n10\n0\n20\n0\n11\n10\n21\n0\n\
+0\nINSERT\n8\nCUT\n2\nHINGE\n10\n200\n20\n50\n41\n-1\n42\n1\n50\n90\n\
+0\nINSERT\n8\nCUT\n2\nDOOR\n10\n400\n20\n0\n\
+0\nINSERT\n8\nCUT\n2\nLOGO\n10\n0\n20\n0\n\
+0\nENDSEC\n0\nEOF\n";
+
+fn engine() -> IoEngine {
+    IoEngine::new()
+        .register_importer(Box::new(DxfIo::new()))
+        .register_exporter(Box::new(DxfIo::new()))
+}
+
+#[test]
+fn blocks_and_inserts_import_and_roundtrip() {
+    let eng = engine();
+    let mut opts = ImportOptions::default_for_tests();
+    opts.enable_approx = false;
+    let res = eng.import("dxf", DXF.as_bytes(), &opts).unwrap();
+    let model = &res.model;
+
+    assert_eq!(model.entities.len(), 1);
+    let names: Vec<_> = model.blocks.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, ["HINGE", "DOOR"], "layout blocks are skipped");
+    let hinge = &model.blocks[0];
+    assert_eq!((hinge.base.x, hinge.base.y), (5.0, 5.0));
+    assert_eq!(hinge.entities.len(), 2);
+    assert!(matches!(
+        &hinge.entities[0],
+        Entity::Path(p) if matches!(p.segments[0], Segment2D::Circle { radius, .. } if radius == 17.5)
+    ));
+
+    // The door keeps its hinge; the door placed inside itself is dropped,
+    // as is the top-level INSERT of the undefined LOGO block.
+    let door = &model.blocks[1];
+    assert_eq!(door.inserts.len(), 1);
+    assert_eq!(door.inserts[0].block, "HINGE");
+    let blocks: Vec<_> = model.inserts.iter().map(|i| i.block.as_str()).collect();
+    assert_eq!(blocks, ["HINGE", "DOOR"]);
+    let mirrored = &model.inserts[0];
+    assert_eq!(mirrored.scale_x, -1.0);
+    assert!((mirrored.rotation_rad - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    let dropped: Vec<_> = res
+        .warnings
+        .iter()
+        .filter(|w| w.reason == ReasonCode::IO_DXF_ENTITY_UNKNOWN_DROPPED)
+        .filter_map(|w| w.context.get("block").cloned())
+        .collect();
+    assert_eq!(dropped, ["LOGO", "DOOR"]);
+
+    let out = eng
+        .export("dxf", model, &ExportOptions::default_for_tests())
+        .unwrap();
+    let text = String::from_utf8(out.bytes.clone()).unwrap();
+    assert!(text.find("BLOCKS").unwrap() < text.find("ENTITIES").unwrap());
+    let again = eng.import("dxf", &out.bytes, &opts).unwrap();
+    assert_eq!(again.model.blocks, model.blocks);
+    assert_eq!(again.model.inserts, model.inserts);
+}
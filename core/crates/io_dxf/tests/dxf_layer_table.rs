@@ -0,0 +1,48 @@
+use craftcad_io::options::{ExportOptions, ImportOptions};
+use craftcad_io::IoEngine;
+use craftcad_io_dxf::DxfIo;
+
+/// CUT is plain red; ENGRAVE is orange (true colour), dashed and not
+/// plotted, with a continuous line on it that should follow the layer.
+const DXF: &str = "0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n4\n0\nENDSEC\n\
+0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n70\n2\n\
+0\nLAYER\n2\nCUT\n70\n0\n62\n1\n6\nCONTINUOUS\n\
+0\nLAYER\n2\nENGRAVE\n70\n0\n62\n-7\n420\n16744448\n6\nDASHED\n290\n0\n\
+0\nENDTAB\n0\nENDSEC\n\
+0\nSECTION\n2\nENTITIES\n\
+0\nLINE\n8\nCUT\n10\n0\n20\n0\n11\n10\n21\n0\n\
+0\nLINE\n8\nENGRAVE\n6\nCONTINUOUS\n10\n0\n20\n5\n11\n10\n21\n5\n\
+0\nENDSEC\n0\nEOF\n";
+
+#[test]
+fn layer_table_imports_and_roundtrips() {
+    let eng = IoEngine::new()
+        .register_importer(Box::new(DxfIo::new()))
+        .register_exporter(Box::new(DxfIo::new()));
+    let opts = ImportOptions::default_for_tests();
+    let res = eng.import("dxf", DXF.as_bytes(), &opts).unwrap();
+    let model = &res.model;
+
+    let cut = model.layer("CUT").unwrap();
+    assert_eq!(cut.rgb, Some((255, 0, 0)));
+    assert!(cut.print);
+    let engrave = model.layer("ENGRAVE").unwrap();
+    assert_eq!(engrave.rgb, Some((255, 128, 0)));
+    assert_eq!(engrave.linetype, "DASHED");
+    assert!(!engrave.print);
+
+    let out = eng
+        .export("dxf", model, &ExportOptions::default_for_tests())
+        .unwrap();
+    let text = String::from_utf8(out.bytes.clone()).unwrap();
+    assert!(text.find("TABLES").unwrap() < text.find("ENTITIES").unwrap());
+    assert!(text.contains("420\n16744448\n6\nDASHED\n290\n0\n"));
+    assert!(
+        text.contains("8\nENGRAVE\n6\nBYLAYER\n"),
+        "continuous entity follows the dashed layer"
+    );
+    assert!(text.contains("8\nCUT\n6\nCONTINUOUS\n"));
+
+    let again = eng.import("dxf", &out.bytes, &opts).unwrap();
+    assert_eq!(again.model.layers, model.layers);
+}
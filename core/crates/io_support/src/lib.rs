@@ -3,6 +3,7 @@ use craftcad_io::reasons::{AppError, AppResult, ReasonCode};
 use regex::Regex;
 use serde::Deserialize;
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
 const SUPPORT_MATRIX: &str = include_str!("../../../../docs/specs/io/support_matrix.json");
 const MAPPING_RULES: &str = include_str!("../../../../docs/specs/io/mapping_rules.json");
@@ -133,6 +134,21 @@ pub struct MappingRules {
     export_force_locale: String,
 }
 
+/// SVG `stroke-dasharray` for a canonical linetype, from
+/// `linetype.svg_dash_arrays` in the mapping rules; `None` draws it solid.
+pub fn svg_dash_array(linetype: &str) -> Option<&'static str> {
+    static DASH_ARRAYS: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+    DASH_ARRAYS
+        .get_or_init(|| {
+            serde_json::from_str::<serde_json::Value>(MAPPING_RULES)
+                .ok()
+                .and_then(|v| serde_json::from_value(v["linetype"]["svg_dash_arrays"].clone()).ok())
+                .unwrap_or_default()
+        })
+        .get(linetype)
+        .map(String::as_str)
+}
+
 #[derive(Debug, Clone)]
 pub struct SupportMatrix {
     entries: Vec<SupportEntry>,
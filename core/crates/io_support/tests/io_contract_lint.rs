@@ -171,6 +171,21 @@ fn ssot_mapping_rules_schema_invariants() {
         );
     }
 
+    let dashes = v["linetype"]["svg_dash_arrays"]
+        .as_object()
+        .expect("linetype.svg_dash_arrays must be object");
+    for (name, dash) in dashes {
+        assert!(
+            v["linetype"]["aliases"]
+                .as_object()
+                .unwrap()
+                .values()
+                .any(|a| a.as_str() == Some(name)),
+            "svg_dash_arrays key {name} must be a canonical linetype"
+        );
+        assert!(dash.is_string(), "svg_dash_arrays.{name} must be a string");
+    }
+
     let ud = v["units"]["default"].as_str().unwrap_or("");
     let supported = v["units"]["supported"]
         .as_array()
@@ -2,7 +2,7 @@ use craftcad_io::model::*;
 use craftcad_io::options::ExportOptions;
 use craftcad_io::reasons::{AppError, AppResult};
 use craftcad_io::report::IoReport;
-use craftcad_io_support::{SupportLevel, SupportMatrix};
+use craftcad_io_support::{svg_dash_array, SupportLevel, SupportMatrix};
 
 fn fmt_f(v: f64, places: usize) -> String {
     format!("{v:.places$}")
@@ -31,6 +31,26 @@ fn seg_to_path_cmd(s: &Segment2D, places: usize) -> Option<String> {
     }
 }
 
+/// Presentation attributes for a path: layer colour, and the entity's line
+/// type unless it is continuous, then the layer's.
+fn stroke_attrs(model: &InternalModel, stroke: &StrokeStyle) -> String {
+    let layer = model.layer(&stroke.layer);
+    let rgb = match stroke.color_policy {
+        ColorPolicy::FixedRgb { r, g, b } => Some((r, g, b)),
+        ColorPolicy::ByLayer => layer.and_then(|l| l.rgb),
+    };
+    let mut attrs = String::new();
+    if let Some((r, g, b)) = rgb {
+        attrs.push_str(&format!(" stroke=\"#{r:02X}{g:02X}{b:02X}\""));
+    }
+    let dash = svg_dash_array(&stroke.linetype)
+        .or_else(|| layer.and_then(|l| svg_dash_array(&l.linetype)));
+    if let Some(d) = dash {
+        attrs.push_str(&format!(" stroke-dasharray=\"{d}\""));
+    }
+    attrs
+}
+
 pub fn export_svg(
     model: &InternalModel,
     opts: &ExportOptions,
@@ -40,8 +60,15 @@ pub fn export_svg(
     let mut report = IoReport::new("svg");
     let places = 4usize;
     let mut body = String::new();
+    let mut exported = 0usize;
+    let layers = sm.level("svg", "layer_table", "export") != SupportLevel::NotSupported;
 
     for e in &model.entities {
+        // Non-printing layers stay in the model but not in the output.
+        if layers && model.layer(e.layer_key()).is_some_and(|l| !l.print) {
+            continue;
+        }
+        exported += 1;
         match e {
             Entity::Path(p) => {
                 if sm.level("svg", "entity_path", "export") == SupportLevel::NotSupported {
@@ -65,9 +92,14 @@ pub fn export_svg(
                 }
                 if !parts.is_empty() {
                     body.push_str(&format!(
-                        "<path id=\"{}\" d=\"{}\" />\n",
+                        "<path id=\"{}\" d=\"{}\"{} />\n",
                         p.id,
-                        parts.join(" ")
+                        parts.join(" "),
+                        if layers {
+                            stroke_attrs(model, &p.stroke)
+                        } else {
+                            String::new()
+                        }
                     ));
                 }
             }
@@ -97,7 +129,7 @@ pub fn export_svg(
 
     let svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\">\n{body}\n</svg>");
     report.entities_in = model.entities.len();
-    report.entities_out = model.entities.len();
+    report.entities_out = exported;
     report.determinism_tag = opts.determinism_tag();
     Ok((svg.into_bytes(), warnings, report))
 }
@@ -0,0 +1,50 @@
+use craftcad_io::model::{
+    Entity, InternalModel, LayerStyle, PathEntity, Point2D, Segment2D, StrokeStyle, Units,
+};
+use craftcad_io::options::ExportOptions;
+use craftcad_io::IoEngine;
+use craftcad_io_svg::SvgIo;
+
+fn line(id: &str, layer: &str) -> Entity {
+    let mut p = PathEntity::new(
+        id.into(),
+        StrokeStyle {
+            layer: layer.into(),
+            ..StrokeStyle::default()
+        },
+    );
+    p.segments.push(Segment2D::Line {
+        a: Point2D { x: 0.0, y: 0.0 },
+        b: Point2D { x: 10.0, y: 0.0 },
+    });
+    Entity::Path(p)
+}
+
+#[test]
+fn svg_export_honours_layer_colour_linetype_and_print() {
+    let mut model = InternalModel::new(Units::Mm);
+    model.entities = vec![line("a", "CUT"), line("b", "GUIDE"), line("c", "0")];
+    model.layers = vec![
+        LayerStyle {
+            name: "CUT".into(),
+            rgb: Some((255, 0, 0)),
+            linetype: "DASHED".into(),
+            print: true,
+        },
+        LayerStyle {
+            name: "GUIDE".into(),
+            rgb: None,
+            linetype: "CONTINUOUS".into(),
+            print: false,
+        },
+    ];
+
+    let eng = IoEngine::new().register_exporter(Box::new(SvgIo::new()));
+    let out = eng
+        .export("svg", &model, &ExportOptions::default_for_tests())
+        .unwrap();
+    let svg = String::from_utf8(out.bytes).unwrap();
+    assert!(svg.contains("id=\"a\" d=\"M 0.0000 0.0000 L 10.0000 0.0000\" stroke=\"#FF0000\" stroke-dasharray=\"6 3\" />"));
+    assert!(!svg.contains("id=\"b\""), "GUIDE does not print");
+    assert!(svg.contains("id=\"c\" d=\"M 0.0000 0.0000 L 10.0000 0.0000\" />"));
+}
@@ -0,0 +1,391 @@
+//! Conversion between `SketchDoc` and the serialize `Document`.
+//!
+//! Sketch ids that are not UUIDs map to name-based UUIDs, and entities keep
+//! their sketch id under the [`SKETCH_ID_KEY`] meta key so that a round trip
+//! restores it. Whatever the target model cannot hold is listed in the
+//! returned [`LossReport`] instead of failing the conversion.
+//!
+//! [`load_sketch`] reads saved sketch JSON and migrates older versions.
+
+use crate::model::{Constraint, EntityId, Layer, Meta, SketchDoc, Units};
+use crate::ops::{entity_from_geom, layer_of, to_geom};
+use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_serialize as doc;
+use diycad_geom::{flatten, EpsilonPolicy, Geom2D};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Entity meta key holding the sketch id of an entity whose id is not a UUID.
+pub const SKETCH_ID_KEY: &str = "sketch_id";
+
+/// Schema version of documents built by [`to_document`].
+pub const DOCUMENT_SCHEMA_VERSION: u32 = 3;
+
+/// Schema version of sketches built by [`to_sketch`]. Version 2 stores the
+/// typed constraint in every `ConstraintRef`; version 1 stored only its id.
+pub const SKETCH_SCHEMA_VERSION: u32 = 2;
+
+/// Everything a conversion dropped, approximated or renamed, in model order.
+/// Each warning is a `MODEL_CONVERSION_LOSSY` error with `context.kind`
+/// (`dropped`, `approximated` or `renamed`) and `context.item`.
+#[derive(Clone, Debug, Default)]
+pub struct LossReport {
+    pub warnings: Vec<AppError>,
+}
+
+impl LossReport {
+    pub fn is_lossless(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    fn push(&mut self, kind: &str, item: String, message: &str) {
+        self.warnings.push(lossy(kind, item, message));
+    }
+}
+
+fn lossy(kind: &str, item: String, message: &str) -> AppError {
+    AppError::new(
+        ReasonCode::new("MODEL_CONVERSION_LOSSY"),
+        Severity::Warn,
+        message,
+    )
+    .with_context("kind", kind)
+    .with_context("item", item)
+}
+
+/// `key` itself when it is a UUID, otherwise a UUID derived from `scope` and `key`.
+fn uuid_for(scope: &str, key: &str) -> (Uuid, bool) {
+    if let Ok(id) = Uuid::parse_str(key) {
+        return (id, true);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hasher.update([0x1f]);
+    hasher.update(key.as_bytes());
+    let hash = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    (Uuid::from_bytes(bytes), false)
+}
+
+fn unreadable(e: serde_json::Error) -> AppError {
+    AppError::new(
+        ReasonCode::new("SERIALIZE_PACKAGE_CORRUPTED"),
+        Severity::Error,
+        "sketch JSON does not match the schema",
+    )
+    .with_context("detail", e.to_string())
+}
+
+/// Version 1 constraint refs carried no constraint. Refs that do carry a
+/// readable one are kept; the rest cannot be rebuilt and are dropped.
+fn migrate_v1(value: &mut serde_json::Value, report: &mut LossReport) {
+    if let Some(refs) = value.get_mut("constraints").and_then(|c| c.as_array_mut()) {
+        refs.retain(|r| {
+            let typed = r
+                .get("constraint")
+                .is_some_and(|c| serde_json::from_value::<Constraint>(c.clone()).is_ok());
+            if !typed {
+                let id = r.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                report.push(
+                    "dropped",
+                    format!("constraint:{id}"),
+                    "version 1 constraint refs have no constraint to restore",
+                );
+            }
+            typed
+        });
+    }
+    value["meta"]["schema_version"] = SKETCH_SCHEMA_VERSION.into();
+}
+
+/// Reads sketch JSON, migrating version 1 to [`SKETCH_SCHEMA_VERSION`].
+/// Newer versions fail with `SERIALIZE_UNSUPPORTED_SCHEMA_VERSION`.
+pub fn load_sketch(mut value: serde_json::Value) -> AppResult<(SketchDoc, LossReport)> {
+    let mut report = LossReport::default();
+    let version = value
+        .pointer("/meta/schema_version")
+        .and_then(|v| v.as_u64());
+    match version {
+        Some(1) => migrate_v1(&mut value, &mut report),
+        Some(v) if v == SKETCH_SCHEMA_VERSION as u64 => {}
+        other => {
+            return Err(AppError::new(
+                ReasonCode::new("SERIALIZE_UNSUPPORTED_SCHEMA_VERSION"),
+                Severity::Error,
+                "unsupported sketch schema version",
+            )
+            .with_context("schema_version", format!("{other:?}")));
+        }
+    }
+    let sketch = serde_json::from_value(value).map_err(unreadable)?;
+    Ok((sketch, report))
+}
+
+/// Converts a sketch into a standalone document. Layers are written in
+/// `order`, entities in sketch order. Text, constraints and the sketch meta
+/// have no place in a document and are reported as dropped.
+pub fn to_document(sketch: &SketchDoc) -> (doc::Document, LossReport) {
+    let mut report = LossReport::default();
+    let scope = |kind: &str| format!("{kind}/{}", sketch.id);
+
+    let (id, same) = uuid_for("sketch", &sketch.id);
+    if !same {
+        report.warnings.push(
+            lossy(
+                "renamed",
+                format!("sketch:{}", sketch.id),
+                "sketch id is not a UUID",
+            )
+            .with_context("uuid", id.to_string()),
+        );
+    }
+
+    let mut layers: Vec<&Layer> = sketch.layers.iter().collect();
+    layers.sort_by_key(|l| l.order);
+    let layer_uuid = |layer_id: &str| uuid_for(&scope("layer"), layer_id);
+    let mut doc_layers = Vec::with_capacity(layers.len());
+    for l in layers {
+        let (lid, same) = layer_uuid(&l.id);
+        if !same {
+            report.warnings.push(
+                lossy(
+                    "renamed",
+                    format!("layer:{}", l.id),
+                    "layer id is not a UUID",
+                )
+                .with_context("uuid", lid.to_string()),
+            );
+        }
+        doc_layers.push(doc::Layer {
+            id: lid,
+            name: l.name.clone(),
+            visible: l.visible,
+            locked: l.locked,
+            editable: !l.locked,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
+        });
+    }
+
+    let mut entities = Vec::with_capacity(sketch.entities.len());
+    for e in &sketch.entities {
+        let item = format!("entity:{}", e.id().0);
+        let Some(geom) = to_geom(e).as_ref().map(doc::Geom2D::from) else {
+            report.push("dropped", item, "documents have no text entities");
+            continue;
+        };
+        let (eid, same) = uuid_for(&scope("entity"), &e.id().0);
+        let mut meta = BTreeMap::new();
+        if !same {
+            meta.insert(SKETCH_ID_KEY.to_string(), e.id().0.clone().into());
+        }
+        entities.push(doc::Entity {
+            id: eid,
+            layer_id: layer_uuid(layer_of(e)).0,
+            geom,
+            style: serde_json::json!({}),
+            tags: vec![],
+            meta,
+        });
+    }
+
+    for c in &sketch.constraints {
+        report.push(
+            "dropped",
+            format!("constraint:{}", c.id),
+            "documents do not store constraints",
+        );
+    }
+    if !sketch.meta.created_by.is_empty() || !sketch.meta.updated_at.is_empty() {
+        report.push(
+            "dropped",
+            "meta".into(),
+            "documents do not store created_by/updated_at",
+        );
+    }
+
+    let out = doc::Document {
+        schema_version: DOCUMENT_SCHEMA_VERSION,
+        id,
+        units: match sketch.units {
+            Units::Mm => "mm",
+            Units::Inch => "inch",
+        }
+        .into(),
+        layers: doc_layers,
+        entities,
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: doc::ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    };
+    (out, report)
+}
+
+fn is_empty_style(v: &serde_json::Value) -> bool {
+    match v {
+        serde_json::Value::Null => true,
+        serde_json::Value::Object(m) => m.is_empty(),
+        _ => false,
+    }
+}
+
+/// Converts the drawing part of a document into a sketch. Layer order
+/// follows the document; a non-editable layer becomes locked. Ellipses,
+/// elliptical arcs and Beziers are flattened to polylines within `tol`.
+/// Parts, jobs, materials and the other project data are reported as dropped.
+pub fn to_sketch(
+    document: &doc::Document,
+    tol: f64,
+    eps: &EpsilonPolicy,
+) -> (SketchDoc, LossReport) {
+    let mut report = LossReport::default();
+
+    let units = match document.units.as_str() {
+        "mm" => Units::Mm,
+        "inch" => Units::Inch,
+        other => {
+            report.push(
+                "approximated",
+                format!("units:{other}"),
+                "unknown units; using mm",
+            );
+            Units::Mm
+        }
+    };
+
+    let mut layers = Vec::with_capacity(document.layers.len());
+    for (i, l) in document.layers.iter().enumerate() {
+        if !l.editable && !l.locked {
+            report.push(
+                "approximated",
+                format!("layer:{}", l.id),
+                "non-editable layer becomes locked",
+            );
+        }
+        if l.color.is_some() || l.linetype != "CONTINUOUS" || !l.print {
+            report.push(
+                "dropped",
+                format!("layer:{}", l.id),
+                "sketch layers have no colour, line type or print flag",
+            );
+        }
+        layers.push(Layer {
+            id: l.id.to_string(),
+            name: l.name.clone(),
+            visible: l.visible,
+            locked: l.locked || !l.editable,
+            order: i as i32,
+        });
+    }
+
+    let mut entities = Vec::with_capacity(document.entities.len());
+    for e in &document.entities {
+        let id = match e.meta.get(SKETCH_ID_KEY).and_then(|v| v.as_str()) {
+            Some(s) => s.to_string(),
+            None => e.id.to_string(),
+        };
+        let item = format!("entity:{id}");
+        let g = Geom2D::from(&e.geom);
+        let g = match g {
+            Geom2D::Line { .. }
+            | Geom2D::Circle { .. }
+            | Geom2D::Arc { .. }
+            | Geom2D::Polyline { .. } => g,
+            _ => match flatten(&g, tol, eps) {
+                Ok(p) => {
+                    report.push(
+                        "approximated",
+                        item.clone(),
+                        "sketches have no ellipse or Bezier; flattened to a polyline",
+                    );
+                    p
+                }
+                Err(r) => {
+                    report.warnings.push(
+                        lossy("dropped", item, "curve could not be flattened")
+                            .with_context("reason", r.code),
+                    );
+                    continue;
+                }
+            },
+        };
+        let Ok(entity) = entity_from_geom(EntityId(id), e.layer_id.to_string(), g) else {
+            report.push("dropped", item, "sketches have no entity for this geometry");
+            continue;
+        };
+        let mut extra: Vec<&str> = vec![];
+        if !is_empty_style(&e.style) {
+            extra.push("style");
+        }
+        if !e.tags.is_empty() {
+            extra.push("tags");
+        }
+        if e.meta.keys().any(|k| k != SKETCH_ID_KEY) {
+            extra.push("meta");
+        }
+        if !extra.is_empty() {
+            report.warnings.push(
+                lossy(
+                    "dropped",
+                    item,
+                    "sketch entities have no style, tags or meta",
+                )
+                .with_context("fields", extra.join(",")),
+            );
+        }
+        entities.push(entity);
+    }
+
+    let project: [(&str, usize); 9] = [
+        ("parts", document.parts.len()),
+        ("jobs", document.jobs.len()),
+        ("materials", document.materials.len()),
+        ("used_presets", document.used_presets.len()),
+        ("used_templates", document.used_templates.len()),
+        ("wizard_runs", document.wizard_runs.len()),
+        ("selection_sets", document.selection_sets.len()),
+        ("blocks", document.blocks.len()),
+        ("block_instances", document.block_instances.len()),
+    ];
+    for (name, n) in project {
+        if n > 0 {
+            report.warnings.push(
+                lossy("dropped", name.into(), "sketches hold drawing data only")
+                    .with_context("count", n.to_string()),
+            );
+        }
+    }
+    if document.settings.bom_delimiter.is_some() {
+        report.push(
+            "dropped",
+            "settings".into(),
+            "sketches hold drawing data only",
+        );
+    }
+
+    let sketch = SketchDoc {
+        id: document.id.to_string(),
+        units,
+        layers,
+        entities,
+        constraints: vec![],
+        meta: Meta {
+            schema_version: SKETCH_SCHEMA_VERSION,
+            created_by: String::new(),
+            updated_at: String::new(),
+        },
+    };
+    (sketch, report)
+}
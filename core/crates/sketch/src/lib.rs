@@ -1,3 +1,4 @@
+pub mod convert;
 pub mod id;
 pub mod model;
 pub mod ops;
@@ -97,6 +97,120 @@ pub struct TextEntity {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConstraintRef {
     pub id: String,
+    pub constraint: Constraint,
+}
+
+/// Which point of an entity a constraint acts on. `Start`/`End` are the ends
+/// of a segment or arc, `Center` the center of a circle or arc.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PointKind {
+    Start,
+    End,
+    Center,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PointRef {
+    pub entity: EntityId,
+    pub kind: PointKind,
+}
+
+/// Sketch constraint. Lengths are in document units, angles in degrees.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Constraint {
+    Horizontal {
+        seg: EntityId,
+    },
+    Vertical {
+        seg: EntityId,
+    },
+    Perpendicular {
+        a: EntityId,
+        b: EntityId,
+    },
+    Parallel {
+        a: EntityId,
+        b: EntityId,
+    },
+    LengthFixed {
+        seg: EntityId,
+        len: f64,
+    },
+    AngleFixed {
+        a: EntityId,
+        b: EntityId,
+        deg: f64,
+    },
+    Coincident {
+        p: PointRef,
+        q: PointRef,
+    },
+    /// Point lies on a segment's line or on a circle/arc's circle.
+    PointOnCurve {
+        p: PointRef,
+        curve: EntityId,
+    },
+    /// Segment/circle, segment/arc, or circle/arc pairs touch.
+    Tangent {
+        a: EntityId,
+        b: EntityId,
+    },
+    Concentric {
+        a: EntityId,
+        b: EntityId,
+    },
+    EqualLength {
+        a: EntityId,
+        b: EntityId,
+    },
+    EqualRadius {
+        a: EntityId,
+        b: EntityId,
+    },
+    /// `p` and `q` mirror each other about the line of segment `axis`.
+    Symmetric {
+        p: PointRef,
+        q: PointRef,
+        axis: EntityId,
+    },
+    /// Signed `q.x - p.x`.
+    HorizontalDistance {
+        p: PointRef,
+        q: PointRef,
+        dist: f64,
+    },
+    /// Signed `q.y - p.y`.
+    VerticalDistance {
+        p: PointRef,
+        q: PointRef,
+        dist: f64,
+    },
+    Radius {
+        curve: EntityId,
+        r: f64,
+    },
+    Diameter {
+        curve: EntityId,
+        d: f64,
+    },
+    Fixed {
+        p: PointRef,
+        at: Pt,
+    },
+}
+
+impl Constraint {
+    /// The entity points this constraint acts on.
+    pub fn point_refs_mut(&mut self) -> Vec<&mut PointRef> {
+        match self {
+            Constraint::Coincident { p, q }
+            | Constraint::Symmetric { p, q, .. }
+            | Constraint::HorizontalDistance { p, q, .. }
+            | Constraint::VerticalDistance { p, q, .. } => vec![p, q],
+            Constraint::PointOnCurve { p, .. } | Constraint::Fixed { p, .. } => vec![p],
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1,5 +1,79 @@
-use crate::model::SketchDoc;
+use super::{editable, geom_error, no_selection, to_geom, transform};
+use crate::model::{Entity, EntityId, SketchDoc};
+use craftcad_errors::AppResult;
+use diycad_geom::{bbox, EpsilonPolicy};
 
-pub fn apply(_doc: &SketchDoc) -> SketchDoc {
-    _doc.clone()
+/// Edge or center line the selection lines up on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    HCenter,
+    Right,
+    Bottom,
+    VCenter,
+    Top,
+}
+
+/// `(min_x, min_y, max_x, max_y)` of an entity; text counts as its anchor.
+fn bounds(e: &Entity, eps: &EpsilonPolicy) -> AppResult<(f64, f64, f64, f64)> {
+    match (e, to_geom(e)) {
+        (_, Some(g)) => {
+            let b = bbox(&g, eps).map_err(geom_error)?;
+            Ok((b.min.x, b.min.y, b.max.x, b.max.y))
+        }
+        (Entity::Text(t), None) => Ok((t.at.x, t.at.y, t.at.x, t.at.y)),
+        (_, None) => unreachable!("only text has no geometry"),
+    }
+}
+
+/// Moves each entity in `ids` along one axis so that its bounds line up
+/// with the same edge (or center) of the selection's combined bounds.
+pub fn apply(
+    doc: &SketchDoc,
+    ids: &[EntityId],
+    mode: Align,
+    eps: &EpsilonPolicy,
+) -> AppResult<SketchDoc> {
+    let mut unique: Vec<&EntityId> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+    if unique.is_empty() {
+        return Err(no_selection());
+    }
+    let mut boxes = Vec::with_capacity(unique.len());
+    for id in &unique {
+        boxes.push(bounds(&doc.entities[editable(doc, id)?], eps)?);
+    }
+    let all = boxes.iter().fold(
+        (f64::INFINITY, f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+        |a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)),
+    );
+    // Coordinate of the aligned feature, for a box and for the selection.
+    let feature = |b: (f64, f64, f64, f64)| match mode {
+        Align::Left => b.0,
+        Align::HCenter => (b.0 + b.2) / 2.0,
+        Align::Right => b.2,
+        Align::Bottom => b.1,
+        Align::VCenter => (b.1 + b.3) / 2.0,
+        Align::Top => b.3,
+    };
+    let horizontal = matches!(mode, Align::Left | Align::HCenter | Align::Right);
+
+    let mut out = doc.clone();
+    for (id, b) in unique.iter().zip(&boxes) {
+        let shift = feature(all) - feature(*b);
+        if shift == 0.0 {
+            continue;
+        }
+        let t = if horizontal {
+            transform::Transform::Translate { dx: shift, dy: 0.0 }
+        } else {
+            transform::Transform::Translate { dx: 0.0, dy: shift }
+        };
+        out = transform::apply(&out, std::slice::from_ref(*id), &t)?;
+    }
+    Ok(out)
 }
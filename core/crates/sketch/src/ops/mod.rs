@@ -1,4 +1,183 @@
+//! Edit operations on `SketchDoc`. Every operation returns a new document
+//! and keeps the ids (and so the constraint references) of the entities it
+//! edits; offset adds a new entity instead of replacing its source.
+
 pub mod align;
 pub mod offset;
 pub mod transform;
 pub mod trim_extend;
+
+use crate::model::{
+    ArcEntity, CircleEntity, Entity, EntityId, PolylineEntity, SegmentEntity, SketchDoc,
+};
+use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_geom2d::Pt;
+use craftcad_serialize::Reason;
+use diycad_geom::{Geom2D, Vec2};
+
+pub(crate) fn vec2(p: Pt) -> Vec2 {
+    Vec2 { x: p.x, y: p.y }
+}
+
+pub(crate) fn pt(v: Vec2) -> Pt {
+    Pt { x: v.x, y: v.y }
+}
+
+/// Kernel failure as an `AppError`, keeping the kernel reason code and its
+/// debug fields as context.
+pub(crate) fn geom_error(r: Reason) -> AppError {
+    let mut err = AppError::new(
+        ReasonCode::owned(r.code.clone()),
+        Severity::Error,
+        "geometry operation failed",
+    );
+    for (k, v) in r.debug {
+        err = err.with_context(k, v.to_string());
+    }
+    err
+}
+
+/// Index of `id`, which must exist on a visible, unlocked layer.
+pub(crate) fn editable(doc: &SketchDoc, id: &EntityId) -> AppResult<usize> {
+    let pos = doc
+        .entities
+        .iter()
+        .position(|e| e.id() == id)
+        .ok_or_else(|| {
+            AppError::new(
+                ReasonCode::new("MODEL_REFERENCE_NOT_FOUND"),
+                Severity::Error,
+                "entity not found",
+            )
+            .with_context("entity_id", id.0.clone())
+        })?;
+    let layer_id = layer_of(&doc.entities[pos]);
+    if let Some(layer) = doc.layers.iter().find(|l| l.id == layer_id) {
+        if layer.locked || !layer.visible {
+            return Err(AppError::new(
+                ReasonCode::new("EDIT_TARGET_LOCKED_OR_HIDDEN"),
+                Severity::Error,
+                "entity is on a locked or hidden layer",
+            )
+            .with_context("entity_id", id.0.clone()));
+        }
+    }
+    Ok(pos)
+}
+
+pub(crate) fn no_selection() -> AppError {
+    AppError::new(
+        ReasonCode::new("EDIT_NO_SELECTION"),
+        Severity::Error,
+        "no entities selected",
+    )
+}
+
+pub(crate) fn layer_of(e: &Entity) -> &str {
+    match e {
+        Entity::Segment(v) => &v.layer_id,
+        Entity::Circle(v) => &v.layer_id,
+        Entity::Polyline(v) => &v.layer_id,
+        Entity::Arc(v) => &v.layer_id,
+        Entity::Text(v) => &v.layer_id,
+    }
+}
+
+/// Kernel geometry of a sketch entity. Sketch arcs run counter-clockwise
+/// from `start_deg` to `end_deg`. Text has no geometry.
+pub(crate) fn to_geom(e: &Entity) -> Option<Geom2D> {
+    match e {
+        Entity::Segment(s) => Some(Geom2D::Line {
+            a: vec2(s.a),
+            b: vec2(s.b),
+        }),
+        Entity::Circle(c) => Some(Geom2D::Circle {
+            c: vec2(c.c),
+            r: c.r,
+        }),
+        Entity::Arc(a) => Some(Geom2D::Arc {
+            c: vec2(a.c),
+            r: a.r,
+            start_angle: a.start_deg.to_radians(),
+            end_angle: a.end_deg.to_radians(),
+            ccw: true,
+        }),
+        Entity::Polyline(p) => Some(Geom2D::Polyline {
+            pts: p.pts.iter().copied().map(vec2).collect(),
+            closed: p.closed,
+        }),
+        Entity::Text(_) => None,
+    }
+}
+
+/// Sketch entity for kernel geometry, with the layer of `template`.
+pub(crate) fn from_geom(template: &Entity, id: EntityId, g: Geom2D) -> AppResult<Entity> {
+    entity_from_geom(id, layer_of(template).to_string(), g)
+}
+
+/// Sketch entity for kernel geometry. Clockwise arcs are stored with their
+/// ends swapped; ellipses and Beziers have no sketch entity.
+pub(crate) fn entity_from_geom(id: EntityId, layer_id: String, g: Geom2D) -> AppResult<Entity> {
+    Ok(match g {
+        Geom2D::Line { a, b } => Entity::Segment(SegmentEntity {
+            id,
+            layer_id,
+            a: pt(a),
+            b: pt(b),
+        }),
+        Geom2D::Circle { c, r } => Entity::Circle(CircleEntity {
+            id,
+            layer_id,
+            c: pt(c),
+            r,
+        }),
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            let (s, e) = if ccw {
+                (start_angle, end_angle)
+            } else {
+                (end_angle, start_angle)
+            };
+            Entity::Arc(ArcEntity {
+                id,
+                layer_id,
+                c: pt(c),
+                r,
+                start_deg: s.to_degrees(),
+                end_deg: e.to_degrees(),
+            })
+        }
+        Geom2D::Polyline { pts, closed } => Entity::Polyline(PolylineEntity {
+            id,
+            layer_id,
+            closed,
+            pts: pts.into_iter().map(pt).collect(),
+        }),
+        _ => {
+            return Err(AppError::new(
+                ReasonCode::new("CORE_INVARIANT_VIOLATION"),
+                Severity::Error,
+                "sketch has no entity for this geometry",
+            ))
+        }
+    })
+}
+
+/// Kernel geometry of an editable entity; text is rejected.
+pub(crate) fn editable_geom(doc: &SketchDoc, id: &EntityId) -> AppResult<(usize, Geom2D)> {
+    let pos = editable(doc, id)?;
+    let g = to_geom(&doc.entities[pos]).ok_or_else(|| {
+        AppError::new(
+            ReasonCode::new("GEOM_OFFSET_NOT_SUPPORTED"),
+            Severity::Error,
+            "text has no editable geometry",
+        )
+        .with_context("entity_id", id.0.clone())
+    })?;
+    Ok((pos, g))
+}
@@ -1,5 +1,23 @@
-use crate::model::SketchDoc;
+use super::{editable_geom, from_geom, geom_error};
+use crate::id::next_stable_id;
+use crate::model::{EntityId, SketchDoc};
+use craftcad_errors::AppResult;
+use diycad_geom::{offset, EpsilonPolicy};
 
-pub fn apply(_doc: &SketchDoc) -> SketchDoc {
-    _doc.clone()
+/// Adds an offset copy of `id` at distance `dist` (positive to the left of
+/// the direction of travel, so inwards for circles and arcs) and returns the
+/// new document with the id of the copy. The source entity is untouched.
+pub fn apply(
+    doc: &SketchDoc,
+    id: &EntityId,
+    dist: f64,
+    eps: &EpsilonPolicy,
+) -> AppResult<(SketchDoc, EntityId)> {
+    let (pos, g) = editable_geom(doc, id)?;
+    let moved = offset(&g, dist, eps).map_err(geom_error)?;
+    let new_id = EntityId(next_stable_id("ent"));
+    let entity = from_geom(&doc.entities[pos], new_id.clone(), moved)?;
+    let mut out = doc.clone();
+    out.entities.insert(pos + 1, entity);
+    Ok((out, new_id))
 }
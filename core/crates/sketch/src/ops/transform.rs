@@ -1,5 +1,188 @@
-use crate::model::SketchDoc;
+use super::{editable, no_selection};
+use crate::model::{Entity, EntityId, PointKind, SketchDoc};
+use craftcad_errors::{AppError, AppResult, ReasonCode, Severity};
+use craftcad_geom2d::Pt;
 
-pub fn apply(_doc: &SketchDoc) -> SketchDoc {
-    _doc.clone()
+/// Rigid or uniformly scaled motion. Angles are in degrees, counter-clockwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    Translate {
+        dx: f64,
+        dy: f64,
+    },
+    Rotate {
+        center: Pt,
+        deg: f64,
+    },
+    Scale {
+        center: Pt,
+        factor: f64,
+    },
+    /// Reflect across the line through `a` and `b`.
+    Mirror {
+        a: Pt,
+        b: Pt,
+    },
+}
+
+/// `p -> m * p + t`.
+struct Affine {
+    m: [[f64; 2]; 2],
+    t: [f64; 2],
+}
+
+impl Affine {
+    fn point(&self, p: Pt) -> Pt {
+        Pt {
+            x: self.m[0][0] * p.x + self.m[0][1] * p.y + self.t[0],
+            y: self.m[1][0] * p.x + self.m[1][1] * p.y + self.t[1],
+        }
+    }
+
+    /// Direction of angle `deg` after the map, in degrees.
+    fn angle(&self, deg: f64) -> f64 {
+        let (s, c) = deg.to_radians().sin_cos();
+        let x = self.m[0][0] * c + self.m[0][1] * s;
+        let y = self.m[1][0] * c + self.m[1][1] * s;
+        y.atan2(x).to_degrees()
+    }
+
+    fn scale(&self) -> f64 {
+        self.m[0][0].hypot(self.m[1][0])
+    }
+
+    fn mirrors(&self) -> bool {
+        self.m[0][0] * self.m[1][1] - self.m[0][1] * self.m[1][0] < 0.0
+    }
+}
+
+fn invalid_numeric() -> AppError {
+    AppError::new(
+        ReasonCode::new("EDIT_INVALID_NUMERIC"),
+        Severity::Error,
+        "transform parameters must be finite",
+    )
+}
+
+fn affine(t: &Transform) -> AppResult<Affine> {
+    let around = |center: Pt, m: [[f64; 2]; 2]| Affine {
+        t: [
+            center.x - m[0][0] * center.x - m[0][1] * center.y,
+            center.y - m[1][0] * center.x - m[1][1] * center.y,
+        ],
+        m,
+    };
+    match *t {
+        Transform::Translate { dx, dy } => {
+            if !dx.is_finite() || !dy.is_finite() {
+                return Err(invalid_numeric());
+            }
+            Ok(Affine {
+                m: [[1.0, 0.0], [0.0, 1.0]],
+                t: [dx, dy],
+            })
+        }
+        Transform::Rotate { center, deg } => {
+            if !center.x.is_finite() || !center.y.is_finite() || !deg.is_finite() {
+                return Err(invalid_numeric());
+            }
+            let (s, c) = deg.to_radians().sin_cos();
+            Ok(around(center, [[c, -s], [s, c]]))
+        }
+        Transform::Scale { center, factor } => {
+            if !center.x.is_finite() || !center.y.is_finite() || !factor.is_finite() {
+                return Err(invalid_numeric());
+            }
+            if factor <= 0.0 {
+                return Err(AppError::new(
+                    ReasonCode::new("EDIT_TRANSFORM_WOULD_DEGENERATE"),
+                    Severity::Error,
+                    "scale factor must be positive",
+                ));
+            }
+            Ok(around(center, [[factor, 0.0], [0.0, factor]]))
+        }
+        Transform::Mirror { a, b } => {
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let len = dx.hypot(dy);
+            if !len.is_finite() || len <= f64::EPSILON {
+                return Err(AppError::new(
+                    ReasonCode::new("EDIT_MIRROR_AXIS_INVALID"),
+                    Severity::Error,
+                    "mirror axis must be two distinct finite points",
+                ));
+            }
+            let (ux, uy) = (dx / len, dy / len);
+            let m = [
+                [ux * ux - uy * uy, 2.0 * ux * uy],
+                [2.0 * ux * uy, uy * uy - ux * ux],
+            ];
+            Ok(around(a, m))
+        }
+    }
+}
+
+fn map_entity(e: &mut Entity, f: &Affine) {
+    match e {
+        Entity::Segment(s) => {
+            s.a = f.point(s.a);
+            s.b = f.point(s.b);
+        }
+        Entity::Circle(c) => {
+            c.c = f.point(c.c);
+            c.r *= f.scale();
+        }
+        Entity::Polyline(p) => {
+            for q in &mut p.pts {
+                *q = f.point(*q);
+            }
+        }
+        Entity::Arc(a) => {
+            a.c = f.point(a.c);
+            a.r *= f.scale();
+            let (s, e) = (f.angle(a.start_deg), f.angle(a.end_deg));
+            // A reflection turns the counter-clockwise sweep around, so the
+            // ends swap to keep arcs counter-clockwise.
+            (a.start_deg, a.end_deg) = if f.mirrors() { (e, s) } else { (s, e) };
+        }
+        Entity::Text(t) => {
+            t.at = f.point(t.at);
+            t.rotation_deg = f.angle(t.rotation_deg as f64) as f32;
+            t.size_mm *= f.scale() as f32;
+        }
+    }
+}
+
+/// Applies `t` to the entities `ids`, in place. Mirrored arcs swap their
+/// start and end, and constraint point references to them swap too so
+/// they keep acting on the same geometric point.
+pub fn apply(doc: &SketchDoc, ids: &[EntityId], t: &Transform) -> AppResult<SketchDoc> {
+    if ids.is_empty() {
+        return Err(no_selection());
+    }
+    let f = affine(t)?;
+    let mut out = doc.clone();
+    let mut done = vec![false; doc.entities.len()];
+    let mut flipped = vec![];
+    for id in ids {
+        let pos = editable(doc, id)?;
+        if !std::mem::replace(&mut done[pos], true) {
+            map_entity(&mut out.entities[pos], &f);
+            if f.mirrors() && matches!(out.entities[pos], Entity::Arc(_)) {
+                flipped.push(id.clone());
+            }
+        }
+    }
+    for c in &mut out.constraints {
+        for r in c.constraint.point_refs_mut() {
+            if flipped.contains(&r.entity) {
+                r.kind = match r.kind {
+                    PointKind::Start => PointKind::End,
+                    PointKind::End => PointKind::Start,
+                    PointKind::Center => PointKind::Center,
+                };
+            }
+        }
+    }
+    Ok(out)
 }
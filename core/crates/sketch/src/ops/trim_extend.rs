@@ -1,5 +1,59 @@
-use crate::model::SketchDoc;
+use super::{editable_geom, from_geom, geom_error, no_selection, vec2};
+use crate::model::{EntityId, SketchDoc};
+use craftcad_errors::AppResult;
+use craftcad_geom2d::Pt;
+use diycad_geom::{
+    extend_to_boundary, trim_line_to_intersection, trim_polyline_to_intersection, EpsilonPolicy,
+    Geom2D,
+};
 
-pub fn apply(_doc: &SketchDoc) -> SketchDoc {
-    _doc.clone()
+/// Trims the segment or open polyline `target` back to where it crosses
+/// `cutter`, keeping the side of the crossing that contains `pick`.
+/// `candidate_index` picks among several crossings, nearest to `pick` first.
+pub fn trim(
+    doc: &SketchDoc,
+    target: &EntityId,
+    cutter: &EntityId,
+    pick: Pt,
+    eps: &EpsilonPolicy,
+    candidate_index: Option<usize>,
+) -> AppResult<SketchDoc> {
+    let (pos, g) = editable_geom(doc, target)?;
+    let (_, cut) = editable_geom(doc, cutter)?;
+    let trimmed = match g {
+        Geom2D::Polyline { .. } => {
+            trim_polyline_to_intersection(&g, &cut, vec2(pick), eps, candidate_index)
+        }
+        _ => trim_line_to_intersection(&g, &cut, vec2(pick), eps, candidate_index),
+    }
+    .map_err(geom_error)?;
+    let mut out = doc.clone();
+    out.entities[pos] = from_geom(&doc.entities[pos], target.clone(), trimmed)?;
+    Ok(out)
+}
+
+/// Extends the end of `target` nearest `pick` to the first of `boundaries`
+/// it meets (or the `candidate_index`-th). Segments and open polylines grow
+/// along their end direction, arcs along their circle.
+pub fn extend(
+    doc: &SketchDoc,
+    target: &EntityId,
+    boundaries: &[EntityId],
+    pick: Pt,
+    eps: &EpsilonPolicy,
+    candidate_index: Option<usize>,
+) -> AppResult<SketchDoc> {
+    let (pos, g) = editable_geom(doc, target)?;
+    let mut walls = Vec::with_capacity(boundaries.len());
+    for id in boundaries.iter().filter(|id| *id != target) {
+        walls.push(editable_geom(doc, id)?.1);
+    }
+    if walls.is_empty() {
+        return Err(no_selection());
+    }
+    let grown =
+        extend_to_boundary(&g, &walls, vec2(pick), eps, candidate_index).map_err(geom_error)?;
+    let mut out = doc.clone();
+    out.entities[pos] = from_geom(&doc.entities[pos], target.clone(), grown)?;
+    Ok(out)
 }
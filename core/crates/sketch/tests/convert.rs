@@ -0,0 +1,177 @@
+use craftcad_geom2d::Pt;
+use craftcad_serialize::Geom2D;
+use craftcad_sketch::convert::{
+    load_sketch, to_document, to_sketch, SKETCH_ID_KEY, SKETCH_SCHEMA_VERSION,
+};
+use craftcad_sketch::model::{
+    ArcEntity, Constraint, ConstraintRef, Entity, EntityId, Layer, Meta, SegmentEntity, SketchDoc,
+    TextEntity, Units,
+};
+use diycad_geom::EpsilonPolicy;
+
+fn p(x: f64, y: f64) -> Pt {
+    Pt { x, y }
+}
+
+fn layer(id: &str, order: i32, locked: bool) -> Layer {
+    Layer {
+        id: id.into(),
+        name: id.to_uppercase(),
+        visible: true,
+        locked,
+        order,
+    }
+}
+
+fn sample() -> SketchDoc {
+    SketchDoc {
+        id: "sketch".into(),
+        units: Units::Inch,
+        layers: vec![layer("top", 2, true), layer("base", 0, false)],
+        entities: vec![
+            Entity::Segment(SegmentEntity {
+                id: EntityId("s1".into()),
+                layer_id: "base".into(),
+                a: p(0.0, 0.0),
+                b: p(10.0, 0.0),
+            }),
+            Entity::Text(TextEntity {
+                id: EntityId("t1".into()),
+                layer_id: "base".into(),
+                text: "label".into(),
+                at: p(1.0, 1.0),
+                size_mm: 3.0,
+                rotation_deg: 0.0,
+            }),
+            Entity::Arc(ArcEntity {
+                id: EntityId("a1".into()),
+                layer_id: "top".into(),
+                c: p(0.0, 0.0),
+                r: 5.0,
+                start_deg: 0.0,
+                end_deg: 90.0,
+            }),
+        ],
+        constraints: vec![ConstraintRef {
+            id: "c1".into(),
+            constraint: Constraint::Horizontal {
+                seg: EntityId("s1".into()),
+            },
+        }],
+        meta: Meta {
+            schema_version: 1,
+            created_by: String::new(),
+            updated_at: String::new(),
+        },
+    }
+}
+
+fn losses(report: &craftcad_sketch::convert::LossReport, kind: &str) -> Vec<String> {
+    report
+        .warnings
+        .iter()
+        .filter(|w| w.context.iter().any(|(k, v)| k == "kind" && v == kind))
+        .filter_map(|w| {
+            w.context
+                .iter()
+                .find(|(k, _)| k == "item")
+                .map(|(_, v)| v.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn sketch_round_trip_keeps_ids_order_and_units() {
+    let (doc, report) = to_document(&sample());
+    assert_eq!(doc.units, "inch");
+    let names: Vec<&str> = doc.layers.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["BASE", "TOP"]);
+    assert!(doc.layers[1].locked && !doc.layers[1].editable);
+    assert_eq!(doc.entities.len(), 2);
+    assert_eq!(doc.entities[0].meta[SKETCH_ID_KEY], "s1");
+    assert_eq!(doc.entities[1].layer_id, doc.layers[1].id);
+    match &doc.entities[1].geom {
+        Geom2D::Arc { end_angle, ccw, .. } => {
+            assert!((end_angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+            assert!(ccw);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+    assert_eq!(losses(&report, "dropped"), ["entity:t1", "constraint:c1"]);
+    assert_eq!(
+        losses(&report, "renamed"),
+        ["sketch:sketch", "layer:base", "layer:top"]
+    );
+
+    let (back, report) = to_sketch(&doc, 0.1, &EpsilonPolicy::default());
+    assert!(report.is_lossless());
+    assert!(matches!(back.units, Units::Inch));
+    let ids: Vec<&str> = back.entities.iter().map(|e| e.id().0.as_str()).collect();
+    assert_eq!(ids, ["s1", "a1"]);
+    assert_eq!(back.layers[1].order, 1);
+    assert!(back.layers[1].locked);
+    match &back.entities[1] {
+        Entity::Arc(a) => assert!((a.end_deg - 90.0).abs() < 1e-9),
+        other => panic!("expected arc, got {other:?}"),
+    }
+}
+
+#[test]
+fn document_curves_and_project_data_are_reported() {
+    let (mut doc, _) = to_document(&sample());
+    doc.entities[0].geom = Geom2D::Ellipse {
+        c: craftcad_serialize::Vec2 { x: 0.0, y: 0.0 },
+        rx: 4.0,
+        ry: 2.0,
+        rot: 0.0,
+    };
+    doc.entities[1].tags.push("cut".into());
+    doc.layers[0].editable = false;
+
+    let (sketch, report) = to_sketch(&doc, 0.05, &EpsilonPolicy::default());
+    match &sketch.entities[0] {
+        Entity::Polyline(pl) => assert!(pl.closed && pl.pts.len() > 8),
+        other => panic!("expected polyline, got {other:?}"),
+    }
+    assert_eq!(sketch.entities[0].id().0, "s1");
+    assert!(sketch.layers[0].locked);
+    let approximated = losses(&report, "approximated");
+    assert_eq!(approximated.len(), 2);
+    assert!(approximated.contains(&"entity:s1".to_string()));
+    assert_eq!(losses(&report, "dropped"), ["entity:a1"]);
+    assert!(report
+        .warnings
+        .iter()
+        .all(|w| w.code == "MODEL_CONVERSION_LOSSY"));
+}
+
+#[test]
+fn version_1_sketches_load_and_drop_untyped_constraints() {
+    let v1: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/sketch_v1.json")).unwrap();
+    let (sketch, report) = load_sketch(v1.clone()).unwrap();
+    assert_eq!(sketch.meta.schema_version, SKETCH_SCHEMA_VERSION);
+    assert_eq!(sketch.entities.len(), 2);
+    let kept: Vec<&str> = sketch.constraints.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(kept, ["c2"]);
+    assert_eq!(
+        sketch.constraints[0].constraint,
+        Constraint::Vertical {
+            seg: EntityId("s2".into())
+        }
+    );
+    assert_eq!(
+        losses(&report, "dropped"),
+        ["constraint:c1", "constraint:c3"]
+    );
+
+    let saved = serde_json::to_value(&sketch).unwrap();
+    let (again, report) = load_sketch(saved).unwrap();
+    assert!(report.is_lossless());
+    assert_eq!(again.constraints.len(), 1);
+
+    let mut future = v1;
+    future["meta"]["schema_version"] = (SKETCH_SCHEMA_VERSION + 1).into();
+    let err = load_sketch(future).unwrap_err();
+    assert_eq!(err.code, "SERIALIZE_UNSUPPORTED_SCHEMA_VERSION");
+}
@@ -0,0 +1,248 @@
+use craftcad_geom2d::{dist, Pt};
+use craftcad_sketch::model::{
+    ArcEntity, CircleEntity, Constraint, ConstraintRef, Entity, EntityId, Layer, Meta, PointKind,
+    PointRef, SegmentEntity, SketchDoc, Units,
+};
+use craftcad_sketch::ops::align::{self, Align};
+use craftcad_sketch::ops::transform::{self, Transform};
+use craftcad_sketch::ops::{offset, trim_extend};
+use diycad_geom::EpsilonPolicy;
+
+fn p(x: f64, y: f64) -> Pt {
+    Pt { x, y }
+}
+
+fn id(s: &str) -> EntityId {
+    EntityId(s.to_string())
+}
+
+fn segment(name: &str, a: Pt, b: Pt) -> Entity {
+    Entity::Segment(SegmentEntity {
+        id: id(name),
+        layer_id: "L0".into(),
+        a,
+        b,
+    })
+}
+
+fn doc(entities: Vec<Entity>) -> SketchDoc {
+    SketchDoc {
+        id: "sketch".into(),
+        units: Units::Mm,
+        layers: vec![Layer {
+            id: "L0".into(),
+            name: "Default".into(),
+            visible: true,
+            locked: false,
+            order: 0,
+        }],
+        entities,
+        constraints: vec![ConstraintRef {
+            id: "c1".into(),
+            constraint: Constraint::Horizontal { seg: id("s1") },
+        }],
+        meta: Meta {
+            schema_version: 1,
+            created_by: "test".into(),
+            updated_at: "0".into(),
+        },
+    }
+}
+
+fn seg_of(d: &SketchDoc, i: usize) -> (Pt, Pt) {
+    match &d.entities[i] {
+        Entity::Segment(s) => (s.a, s.b),
+        other => panic!("expected segment, got {other:?}"),
+    }
+}
+
+fn close(a: Pt, b: Pt) -> bool {
+    dist(a, b) < 1e-9
+}
+
+fn point_at(d: &SketchDoc, r: &PointRef) -> Pt {
+    let e = d.entities.iter().find(|e| e.id() == &r.entity).unwrap();
+    match (e, r.kind) {
+        (Entity::Segment(s), PointKind::Start) => s.a,
+        (Entity::Segment(s), PointKind::End) => s.b,
+        (Entity::Arc(a), kind) => {
+            let deg = match kind {
+                PointKind::Start => a.start_deg,
+                PointKind::End => a.end_deg,
+                PointKind::Center => return a.c,
+            };
+            let (s, c) = deg.to_radians().sin_cos();
+            p(a.c.x + a.r * c, a.c.y + a.r * s)
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn transform_keeps_ids_and_constraints() {
+    let mut d = doc(vec![
+        segment("s1", p(0.0, 0.0), p(10.0, 0.0)),
+        Entity::Arc(ArcEntity {
+            id: id("a1"),
+            layer_id: "L0".into(),
+            c: p(0.0, 0.0),
+            r: 5.0,
+            start_deg: 0.0,
+            end_deg: 90.0,
+        }),
+    ]);
+    let at = |e: &str, kind| PointRef {
+        entity: id(e),
+        kind,
+    };
+    d.constraints.extend([
+        ConstraintRef {
+            id: "c2".into(),
+            constraint: Constraint::Coincident {
+                p: at("a1", PointKind::Start),
+                q: at("s1", PointKind::End),
+            },
+        },
+        ConstraintRef {
+            id: "c3".into(),
+            constraint: Constraint::Fixed {
+                p: at("a1", PointKind::End),
+                at: p(0.0, 5.0),
+            },
+        },
+    ]);
+    let moved = transform::apply(
+        &d,
+        &[id("s1"), id("a1")],
+        &Transform::Rotate {
+            center: p(0.0, 0.0),
+            deg: 90.0,
+        },
+    )
+    .unwrap();
+    let (a, b) = seg_of(&moved, 0);
+    assert!(close(a, p(0.0, 0.0)) && close(b, p(0.0, 10.0)));
+    assert_eq!(moved.entities[0].id(), &id("s1"));
+    let kept = moved.constraints.iter().zip(&d.constraints);
+    assert!(kept.into_iter().all(|(m, c)| m.constraint == c.constraint));
+
+    // Mirroring across the y axis keeps the arc counter-clockwise.
+    let flipped = transform::apply(
+        &d,
+        &[id("a1")],
+        &Transform::Mirror {
+            a: p(0.0, 0.0),
+            b: p(0.0, 1.0),
+        },
+    )
+    .unwrap();
+    match &flipped.entities[1] {
+        Entity::Arc(a) => {
+            assert!((a.start_deg - 90.0).abs() < 1e-9);
+            assert!((a.end_deg - 180.0).abs() < 1e-9);
+        }
+        other => panic!("expected arc, got {other:?}"),
+    }
+    // Each arc point reference follows its point across the mirror.
+    for (before, after) in d.constraints.iter().zip(&flipped.constraints) {
+        let (mut b, mut a) = (before.constraint.clone(), after.constraint.clone());
+        for (rb, ra) in b.point_refs_mut().into_iter().zip(a.point_refs_mut()) {
+            let q = point_at(&d, rb);
+            let mirrored = if rb.entity == id("a1") {
+                p(-q.x, q.y)
+            } else {
+                q
+            };
+            assert!(
+                close(point_at(&flipped, ra), mirrored),
+                "{} moved",
+                before.id
+            );
+        }
+    }
+
+    let err = transform::apply(
+        &d,
+        &[id("s1")],
+        &Transform::Scale {
+            center: p(0.0, 0.0),
+            factor: 0.0,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code.as_str(), "EDIT_TRANSFORM_WOULD_DEGENERATE");
+}
+
+#[test]
+fn align_left_uses_selection_bounds() {
+    let d = doc(vec![
+        segment("s1", p(2.0, 0.0), p(5.0, 0.0)),
+        Entity::Circle(CircleEntity {
+            id: id("c1"),
+            layer_id: "L0".into(),
+            c: p(10.0, 5.0),
+            r: 1.0,
+        }),
+    ]);
+    let eps = EpsilonPolicy::default();
+    let out = align::apply(&d, &[id("s1"), id("c1")], Align::Left, &eps).unwrap();
+    let (a, _) = seg_of(&out, 0);
+    assert!(close(a, p(2.0, 0.0)));
+    match &out.entities[1] {
+        Entity::Circle(c) => assert!(close(c.c, p(3.0, 5.0))),
+        other => panic!("expected circle, got {other:?}"),
+    }
+    let err = align::apply(&d, &[], Align::Left, &eps).unwrap_err();
+    assert_eq!(err.code.as_str(), "EDIT_NO_SELECTION");
+}
+
+#[test]
+fn offset_adds_entity_next_to_source() {
+    let d = doc(vec![segment("s1", p(0.0, 0.0), p(10.0, 0.0))]);
+    let (out, new_id) = offset::apply(&d, &id("s1"), 2.0, &EpsilonPolicy::default()).unwrap();
+    assert_eq!(out.entities.len(), 2);
+    assert_eq!(out.entities[1].id(), &new_id);
+    assert_ne!(new_id, id("s1"));
+    let (a, b) = seg_of(&out, 1);
+    assert!((a.y.abs() - 2.0).abs() < 1e-9 && (b.y - a.y).abs() < 1e-9);
+    assert_eq!(seg_of(&out, 0), seg_of(&d, 0));
+}
+
+#[test]
+fn trim_and_extend_replace_in_place() {
+    let eps = EpsilonPolicy::default();
+    let d = doc(vec![
+        segment("s1", p(0.0, 0.0), p(10.0, 0.0)),
+        segment("cut", p(5.0, -5.0), p(5.0, 5.0)),
+    ]);
+    let trimmed = trim_extend::trim(&d, &id("s1"), &id("cut"), p(1.0, 0.0), &eps, None).unwrap();
+    let (a, b) = seg_of(&trimmed, 0);
+    assert_eq!(trimmed.entities[0].id(), &id("s1"));
+    assert!(close(a, p(0.0, 0.0)) && close(b, p(5.0, 0.0)));
+
+    let d = doc(vec![
+        segment("s1", p(0.0, 0.0), p(3.0, 0.0)),
+        segment("wall", p(5.0, -5.0), p(5.0, 5.0)),
+    ]);
+    let grown = trim_extend::extend(
+        &d,
+        &id("s1"),
+        &[id("s1"), id("wall")],
+        p(3.0, 0.0),
+        &eps,
+        None,
+    )
+    .unwrap();
+    let (a, b) = seg_of(&grown, 0);
+    assert!(close(a, p(0.0, 0.0)) && close(b, p(5.0, 0.0)));
+    assert_eq!(grown.constraints[0].constraint, d.constraints[0].constraint);
+}
+
+#[test]
+fn locked_layer_is_rejected() {
+    let mut d = doc(vec![segment("s1", p(0.0, 0.0), p(10.0, 0.0))]);
+    d.layers[0].locked = true;
+    let err =
+        transform::apply(&d, &[id("s1")], &Transform::Translate { dx: 1.0, dy: 0.0 }).unwrap_err();
+    assert_eq!(err.code.as_str(), "EDIT_TARGET_LOCKED_OR_HIDDEN");
+}
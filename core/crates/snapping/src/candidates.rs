@@ -0,0 +1,423 @@
+use crate::tracking::Acquired;
+use crate::types::{SnapKind, SnapPoint};
+use craftcad_geom2d::aabb::Aabb;
+use craftcad_geom2d::math::round_step;
+use craftcad_geom2d::{dist, Pt};
+use craftcad_serialize::{Document, Entity};
+use craftcad_sketch::query::SpatialIndex;
+use diycad_geom::{bbox, intersect, project_point, EpsilonPolicy, Geom2D, Vec2};
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+/// Base score of a kind; `resolve` prefers higher scores.
+pub fn kind_score(kind: SnapKind) -> i32 {
+    match kind {
+        SnapKind::Endpoint => 100,
+        SnapKind::Intersection => 90,
+        SnapKind::Center => 80,
+        SnapKind::Quadrant => 70,
+        SnapKind::Midpoint => 60,
+        SnapKind::Tangent | SnapKind::Perpendicular => 50,
+        SnapKind::Extension | SnapKind::Alignment => 45,
+        SnapKind::Angle => 40,
+        SnapKind::Nearest => 20,
+        SnapKind::Grid => 10,
+    }
+}
+
+pub struct SnapQuery {
+    pub cursor: Pt,
+    /// Search radius around the cursor, in world units.
+    pub radius: f64,
+    /// Grid spacing; `None` disables grid snaps.
+    pub grid: Option<f64>,
+    /// Last point of the tool in progress. Tangent and perpendicular snaps
+    /// are taken from it and need it to be set.
+    pub from: Option<Pt>,
+}
+
+/// Document geometry indexed for candidate lookup. Build once per document
+/// revision and query on every cursor move.
+pub struct CandidateSource {
+    geoms: Vec<Geom2D>,
+    index: SpatialIndex,
+}
+
+fn vec2(p: Pt) -> Vec2 {
+    Vec2 { x: p.x, y: p.y }
+}
+
+fn pt(v: Vec2) -> Pt {
+    Pt { x: v.x, y: v.y }
+}
+
+fn polar(c: Vec2, r: f64, a: f64) -> Vec2 {
+    Vec2 {
+        x: c.x + r * a.cos(),
+        y: c.y + r * a.sin(),
+    }
+}
+
+/// Point at parameter `t` of an ellipse rotated by `rot`.
+fn ellipse_at(c: Vec2, rx: f64, ry: f64, rot: f64, t: f64) -> Vec2 {
+    let (s, k) = rot.sin_cos();
+    let (x, y) = (rx * t.cos(), ry * t.sin());
+    Vec2 {
+        x: c.x + k * x - s * y,
+        y: c.y + s * x + k * y,
+    }
+}
+
+/// Swept angle of an arc, in `(0, TAU]`.
+fn sweep(start: f64, end: f64, ccw: bool) -> f64 {
+    let s = if ccw { end - start } else { start - end }.rem_euclid(TAU);
+    if s == 0.0 {
+        TAU
+    } else {
+        s
+    }
+}
+
+fn on_arc(a: f64, start: f64, end: f64, ccw: bool) -> bool {
+    let from_start = if ccw { a - start } else { start - a }.rem_euclid(TAU);
+    from_start <= sweep(start, end, ccw) + 1e-12
+}
+
+fn segments(pts: &[Vec2], closed: bool) -> Vec<(Vec2, Vec2)> {
+    let mut out: Vec<(Vec2, Vec2)> = pts.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed && pts.len() > 2 {
+        out.push((pts[pts.len() - 1], pts[0]));
+    }
+    out
+}
+
+fn endpoints(g: &Geom2D) -> Vec<Vec2> {
+    match g {
+        Geom2D::Line { a, b } | Geom2D::CubicBezier { a, b, .. } => vec![*a, *b],
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ..
+        } => vec![polar(*c, *r, *start_angle), polar(*c, *r, *end_angle)],
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ..
+        } => vec![
+            ellipse_at(*c, *rx, *ry, *rot, *start_angle),
+            ellipse_at(*c, *rx, *ry, *rot, *end_angle),
+        ],
+        Geom2D::Polyline { pts, .. } => pts.clone(),
+        Geom2D::Circle { .. } | Geom2D::Ellipse { .. } => vec![],
+    }
+}
+
+fn midpoints(g: &Geom2D) -> Vec<Vec2> {
+    let mid = |a: Vec2, b: Vec2| Vec2 {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    };
+    match g {
+        Geom2D::Line { a, b } => vec![mid(*a, *b)],
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            let half = sweep(*start_angle, *end_angle, *ccw) / 2.0;
+            let a = if *ccw {
+                start_angle + half
+            } else {
+                start_angle - half
+            };
+            vec![polar(*c, *r, a)]
+        }
+        Geom2D::Polyline { pts, closed } => segments(pts, *closed)
+            .into_iter()
+            .map(|(a, b)| mid(a, b))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn centers(g: &Geom2D) -> Vec<Vec2> {
+    match g {
+        Geom2D::Circle { c, .. }
+        | Geom2D::Arc { c, .. }
+        | Geom2D::Ellipse { c, .. }
+        | Geom2D::EllipticalArc { c, .. } => vec![*c],
+        _ => vec![],
+    }
+}
+
+fn quadrants(g: &Geom2D) -> Vec<Vec2> {
+    let quarter = |k: i32| k as f64 * FRAC_PI_2;
+    match g {
+        Geom2D::Circle { c, r } => (0..4).map(|k| polar(*c, *r, quarter(k))).collect(),
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => (0..4)
+            .map(quarter)
+            .filter(|a| on_arc(*a, *start_angle, *end_angle, *ccw))
+            .map(|a| polar(*c, *r, a))
+            .collect(),
+        Geom2D::Ellipse { c, rx, ry, rot } => (0..4)
+            .map(|k| ellipse_at(*c, *rx, *ry, *rot, quarter(k)))
+            .collect(),
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => (0..4)
+            .map(quarter)
+            .filter(|t| on_arc(*t, *start_angle, *end_angle, *ccw))
+            .map(|t| ellipse_at(*c, *rx, *ry, *rot, t))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Points where a line from `from` meets `g` at a right angle.
+fn perpendicular_feet(g: &Geom2D, from: Vec2) -> Vec<Vec2> {
+    let foot = |a: Vec2, b: Vec2| {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len2 = dx * dx + dy * dy;
+        if len2 <= f64::EPSILON {
+            return None;
+        }
+        let t = ((from.x - a.x) * dx + (from.y - a.y) * dy) / len2;
+        (0.0..=1.0).contains(&t).then_some(Vec2 {
+            x: a.x + t * dx,
+            y: a.y + t * dy,
+        })
+    };
+    let radial = |c: Vec2, r: f64| {
+        let a = (from.y - c.y).atan2(from.x - c.x);
+        [a, a + std::f64::consts::PI].map(|a| (a, polar(c, r, a)))
+    };
+    match g {
+        Geom2D::Line { a, b } => foot(*a, *b).into_iter().collect(),
+        Geom2D::Polyline { pts, closed } => segments(pts, *closed)
+            .into_iter()
+            .filter_map(|(a, b)| foot(a, b))
+            .collect(),
+        Geom2D::Circle { c, r } => radial(*c, *r).into_iter().map(|(_, p)| p).collect(),
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => radial(*c, *r)
+            .into_iter()
+            .filter(|(a, _)| on_arc(*a, *start_angle, *end_angle, *ccw))
+            .map(|(_, p)| p)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Points where a line from `from` touches the circle or arc `g`.
+fn tangent_points(g: &Geom2D, from: Vec2) -> Vec<Vec2> {
+    let (c, r, range) = match g {
+        Geom2D::Circle { c, r } => (*c, *r, None),
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => (*c, *r, Some((*start_angle, *end_angle, *ccw))),
+        _ => return vec![],
+    };
+    let d = (from.x - c.x).hypot(from.y - c.y);
+    if d <= r {
+        return vec![];
+    }
+    let base = (from.y - c.y).atan2(from.x - c.x);
+    let spread = (r / d).acos();
+    [base - spread, base + spread]
+        .into_iter()
+        .filter(|a| range.is_none_or(|(s, e, ccw)| on_arc(*a, s, e, ccw)))
+        .map(|a| polar(c, r, a))
+        .collect()
+}
+
+fn unit(x: f64, y: f64) -> Option<Pt> {
+    let len = x.hypot(y);
+    (len > f64::EPSILON).then(|| Pt {
+        x: x / len,
+        y: y / len,
+    })
+}
+
+/// Open ends of `g` with the unit direction leaving the curve there.
+fn open_ends(g: &Geom2D) -> Vec<(Vec2, Option<Pt>)> {
+    let away = |from: Vec2, to: Vec2| (to, unit(to.x - from.x, to.y - from.y));
+    match g {
+        Geom2D::Line { a, b } => vec![away(*b, *a), away(*a, *b)],
+        Geom2D::Polyline { pts, closed: false } if pts.len() >= 2 => {
+            let n = pts.len();
+            vec![away(pts[1], pts[0]), away(pts[n - 2], pts[n - 1])]
+        }
+        Geom2D::Arc {
+            c,
+            r,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            // Direction of travel at angle `a` is the ccw tangent, flipped for cw.
+            let sign = if *ccw { 1.0 } else { -1.0 };
+            let travel = |a: f64| unit(-a.sin() * sign, a.cos() * sign);
+            let back = travel(*start_angle).map(|d| Pt { x: -d.x, y: -d.y });
+            vec![
+                (polar(*c, *r, *start_angle), back),
+                (polar(*c, *r, *end_angle), travel(*end_angle)),
+            ]
+        }
+        _ => vec![],
+    }
+}
+
+impl CandidateSource {
+    /// Indexes the entities on visible layers of `doc`, block instances
+    /// included; `cell` is the bucket size of the spatial index. Entities
+    /// whose geometry is invalid are skipped.
+    pub fn build(doc: &Document, cell: f64, eps: &EpsilonPolicy) -> Self {
+        let mut geoms = vec![];
+        let mut boxes = vec![];
+        let visible = |id| !doc.layers.iter().any(|l| l.id == id && !l.visible);
+        let mut entities: Vec<Entity> = doc
+            .entities
+            .iter()
+            .filter(|e| visible(e.layer_id))
+            .cloned()
+            .collect();
+        for i in doc.block_instances.iter().filter(|i| visible(i.layer_id)) {
+            let Ok(placed) = craftcad_edit_ops::blocks::instance_entities(doc, i.id) else {
+                continue;
+            };
+            entities.extend(placed.into_iter().filter(|e| visible(e.layer_id)));
+        }
+        for e in &entities {
+            let g = Geom2D::from(&e.geom);
+            let Ok(b) = bbox(&g, eps) else { continue };
+            boxes.push(Aabb {
+                min_x: b.min.x,
+                min_y: b.min.y,
+                max_x: b.max.x,
+                max_y: b.max.y,
+            });
+            geoms.push(g);
+        }
+        Self {
+            geoms,
+            index: SpatialIndex::build(cell, boxes),
+        }
+    }
+
+    /// Every candidate within `q.radius` of the cursor, from the entities
+    /// whose bounds reach that far. Output order is deterministic: entity
+    /// order and kind per entity, then intersections between entities and
+    /// with the line from `q.from` to the cursor, then the grid point.
+    pub fn candidates(&self, q: &SnapQuery, eps: &EpsilonPolicy) -> Vec<SnapPoint> {
+        let mut out = vec![];
+        if !q.radius.is_finite() || q.radius < 0.0 {
+            return out;
+        }
+        let mut keep = |kind: SnapKind, points: Vec<Vec2>| {
+            for p in points {
+                let p = pt(p);
+                if dist(p, q.cursor) <= q.radius {
+                    out.push(SnapPoint {
+                        kind,
+                        pt: p,
+                        score: kind_score(kind),
+                    });
+                }
+            }
+        };
+        let near = self.index.query_aabb(Aabb {
+            min_x: q.cursor.x - q.radius,
+            min_y: q.cursor.y - q.radius,
+            max_x: q.cursor.x + q.radius,
+            max_y: q.cursor.y + q.radius,
+        });
+        let cursor = vec2(q.cursor);
+        for &i in &near {
+            let g = &self.geoms[i];
+            keep(SnapKind::Endpoint, endpoints(g));
+            keep(SnapKind::Midpoint, midpoints(g));
+            keep(SnapKind::Center, centers(g));
+            keep(SnapKind::Quadrant, quadrants(g));
+            if let Ok(hit) = project_point(g, cursor, eps) {
+                keep(SnapKind::Nearest, vec![hit.point]);
+            }
+            if let Some(from) = q.from.map(vec2) {
+                keep(SnapKind::Perpendicular, perpendicular_feet(g, from));
+                keep(SnapKind::Tangent, tangent_points(g, from));
+            }
+        }
+        for (k, &i) in near.iter().enumerate() {
+            for &j in &near[k + 1..] {
+                if let Ok(hits) = intersect(&self.geoms[i], &self.geoms[j], eps) {
+                    keep(SnapKind::Intersection, hits.points);
+                }
+            }
+        }
+        // The line being drawn, from the tool's last point to the cursor.
+        if let Some(from) = q.from.map(vec2) {
+            let band = Geom2D::Line { a: from, b: cursor };
+            for &i in &near {
+                if let Ok(hits) = intersect(&band, &self.geoms[i], eps) {
+                    keep(SnapKind::Intersection, hits.points);
+                }
+            }
+        }
+        if let Some(step) = q.grid.filter(|s| s.is_finite() && *s > 0.0) {
+            keep(
+                SnapKind::Grid,
+                vec![Vec2 {
+                    x: round_step(q.cursor.x, step),
+                    y: round_step(q.cursor.y, step),
+                }],
+            );
+        }
+        out
+    }
+
+    /// Open ends within `radius` of `cursor`, ready to acquire for tracking.
+    /// Each carries the direction its curve would continue in.
+    pub fn extensions(&self, cursor: Pt, radius: f64) -> Vec<Acquired> {
+        let near = self.index.query_aabb(Aabb {
+            min_x: cursor.x - radius,
+            min_y: cursor.y - radius,
+            max_x: cursor.x + radius,
+            max_y: cursor.y + radius,
+        });
+        near.iter()
+            .flat_map(|&i| open_ends(&self.geoms[i]))
+            .map(|(p, extension)| Acquired {
+                pt: pt(p),
+                extension,
+            })
+            .filter(|a| dist(a.pt, cursor) <= radius)
+            .collect()
+    }
+}
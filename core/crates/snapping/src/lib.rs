@@ -1,3 +1,5 @@
+pub mod candidates;
 pub mod index;
 pub mod resolve;
+pub mod tracking;
 pub mod types;
@@ -0,0 +1,223 @@
+use crate::candidates::kind_score;
+use crate::resolve::SnapPolicy;
+use crate::types::{SnapKind, SnapPoint};
+use craftcad_geom2d::{dist, Pt};
+use serde::{Deserialize, Serialize};
+
+/// Acquired points older than this many are forgotten.
+pub const MAX_ACQUIRED: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrackKind {
+    /// Ray from the last point at a multiple of the polar angle step.
+    Polar,
+    /// Ray continuing a curve past an acquired endpoint.
+    Extension,
+    /// Horizontal line through an acquired point.
+    Horizontal,
+    /// Vertical line through an acquired point.
+    Vertical,
+}
+
+/// Construction line for the UI to draw from `origin` through the tracked
+/// point. `dir` is a unit vector; rays only extend forward along it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrackLine {
+    pub kind: TrackKind,
+    pub origin: Pt,
+    pub dir: Pt,
+    pub ray: bool,
+}
+
+/// Point acquired by hovering. `extension` is the unit direction leaving
+/// the curve at `pt`, when `pt` is an open end.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Acquired {
+    pub pt: Pt,
+    #[serde(default)]
+    pub extension: Option<Pt>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackResult {
+    pub snapped: Option<SnapPoint>,
+    /// Lines through `snapped`: one, or two when it is their intersection.
+    pub lines: Vec<TrackLine>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Tracker {
+    acquired: Vec<Acquired>,
+}
+
+fn snap_kind(kind: TrackKind) -> SnapKind {
+    match kind {
+        TrackKind::Polar => SnapKind::Angle,
+        TrackKind::Extension => SnapKind::Extension,
+        TrackKind::Horizontal | TrackKind::Vertical => SnapKind::Alignment,
+    }
+}
+
+impl TrackLine {
+    /// Closest point of the line to `p`, or `None` behind the origin of a ray.
+    fn project(&self, p: Pt) -> Option<Pt> {
+        let t = (p.x - self.origin.x) * self.dir.x + (p.y - self.origin.y) * self.dir.y;
+        if self.ray && t < 0.0 {
+            return None;
+        }
+        Some(self.at(t))
+    }
+
+    fn at(&self, t: f64) -> Pt {
+        Pt {
+            x: self.origin.x + t * self.dir.x,
+            y: self.origin.y + t * self.dir.y,
+        }
+    }
+
+    fn intersect(&self, other: &TrackLine) -> Option<Pt> {
+        let den = self.dir.x * other.dir.y - self.dir.y * other.dir.x;
+        if den.abs() < 1e-9 {
+            return None;
+        }
+        let (dx, dy) = (
+            other.origin.x - self.origin.x,
+            other.origin.y - self.origin.y,
+        );
+        let t = (dx * other.dir.y - dy * other.dir.x) / den;
+        let u = (dx * self.dir.y - dy * self.dir.x) / den;
+        if (self.ray && t < 0.0) || (other.ray && u < 0.0) {
+            return None;
+        }
+        Some(self.at(t))
+    }
+}
+
+impl Tracker {
+    pub fn acquired(&self) -> &[Acquired] {
+        &self.acquired
+    }
+
+    /// Acquires `a`, or releases it when it is already acquired (hovering a
+    /// tracked point again toggles it off).
+    pub fn acquire(&mut self, a: Acquired) {
+        if let Some(i) = self.acquired.iter().position(|x| x.pt == a.pt) {
+            self.acquired.remove(i);
+            return;
+        }
+        if self.acquired.len() == MAX_ACQUIRED {
+            self.acquired.remove(0);
+        }
+        self.acquired.push(a);
+    }
+
+    pub fn clear(&mut self) {
+        self.acquired.clear();
+    }
+
+    /// Tracking lines for the current state: the nearest polar ray from
+    /// `last`, then per acquired point its extension ray and its horizontal
+    /// and vertical lines.
+    pub fn lines(&self, cursor: Pt, last: Option<Pt>, policy: &SnapPolicy) -> Vec<TrackLine> {
+        let mut out = vec![];
+        let step = policy.angle_step_deg;
+        if let Some(last) = last.filter(|_| step.is_finite() && step > 0.0) {
+            if dist(last, cursor) > 0.0 {
+                let deg = (cursor.y - last.y).atan2(cursor.x - last.x).to_degrees();
+                let a = ((deg / step).round() * step).to_radians();
+                out.push(TrackLine {
+                    kind: TrackKind::Polar,
+                    origin: last,
+                    dir: Pt {
+                        x: a.cos(),
+                        y: a.sin(),
+                    },
+                    ray: true,
+                });
+            }
+        }
+        for a in &self.acquired {
+            if let Some(dir) = a.extension {
+                out.push(TrackLine {
+                    kind: TrackKind::Extension,
+                    origin: a.pt,
+                    dir,
+                    ray: true,
+                });
+            }
+            for (kind, dir) in [
+                (TrackKind::Horizontal, Pt { x: 1.0, y: 0.0 }),
+                (TrackKind::Vertical, Pt { x: 0.0, y: 1.0 }),
+            ] {
+                out.push(TrackLine {
+                    kind,
+                    origin: a.pt,
+                    dir,
+                    ray: false,
+                });
+            }
+        }
+        out
+    }
+
+    /// Tracked point within `policy.max_dist` of the cursor. Where two
+    /// tracking lines cross near the cursor their intersection wins;
+    /// otherwise the closest projection onto a single line does.
+    pub fn track(&self, cursor: Pt, last: Option<Pt>, policy: &SnapPolicy) -> TrackResult {
+        let lines = self.lines(cursor, last, policy);
+        let near: Vec<(TrackLine, Pt)> = lines
+            .iter()
+            .filter_map(|l| l.project(cursor).map(|p| (*l, p)))
+            .filter(|(_, p)| dist(*p, cursor) <= policy.max_dist)
+            .collect();
+
+        let mut best: Option<(f64, Pt, Vec<TrackLine>)> = None;
+        for (i, (a, _)) in near.iter().enumerate() {
+            for (b, _) in &near[i + 1..] {
+                // Lines through the same point meet only at that point.
+                if a.origin == b.origin {
+                    continue;
+                }
+                let Some(p) = a.intersect(b) else { continue };
+                let d = dist(p, cursor);
+                if d <= policy.max_dist && best.as_ref().is_none_or(|(bd, _, _)| d < *bd) {
+                    best = Some((d, p, vec![*a, *b]));
+                }
+            }
+        }
+        if let Some((_, p, lines)) = best {
+            let kind = snap_kind(lines[0].kind);
+            return TrackResult {
+                snapped: Some(SnapPoint {
+                    kind,
+                    pt: p,
+                    score: kind_score(kind) + 10,
+                }),
+                lines,
+            };
+        }
+
+        let single = near.iter().min_by(|(_, p), (_, q)| {
+            dist(*p, cursor)
+                .partial_cmp(&dist(*q, cursor))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        match single {
+            Some((line, p)) => {
+                let kind = snap_kind(line.kind);
+                TrackResult {
+                    snapped: Some(SnapPoint {
+                        kind,
+                        pt: *p,
+                        score: kind_score(kind),
+                    }),
+                    lines: vec![*line],
+                }
+            }
+            None => TrackResult {
+                snapped: None,
+                lines: vec![],
+            },
+        }
+    }
+}
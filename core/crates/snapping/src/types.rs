@@ -8,6 +8,12 @@ pub enum SnapKind {
     Center,
     Grid,
     Angle,
+    Tangent,
+    Perpendicular,
+    Nearest,
+    Quadrant,
+    Extension,
+    Alignment,
 }
 
 #[derive(Clone, Copy, Debug)]
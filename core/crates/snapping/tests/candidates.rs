@@ -0,0 +1,178 @@
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{Document, Entity, Geom2D, Layer, ProjectSettings, Vec2};
+use craftcad_snapping::candidates::{CandidateSource, SnapQuery};
+use craftcad_snapping::resolve::{resolve, SnapPolicy};
+use craftcad_snapping::types::{SnapKind, SnapPoint};
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn v(x: f64, y: f64) -> Vec2 {
+    Vec2 { x, y }
+}
+
+fn doc(geoms: Vec<(u128, Geom2D)>) -> Document {
+    let layer = |n: u128, visible: bool| Layer {
+        id: Uuid::from_u128(n),
+        name: format!("L{n}"),
+        visible,
+        locked: false,
+        editable: true,
+        color: None,
+        linetype: "CONTINUOUS".into(),
+        print: true,
+    };
+    Document {
+        schema_version: 3,
+        id: Uuid::from_u128(99),
+        units: "mm".into(),
+        layers: vec![layer(1, true), layer(2, false)],
+        entities: geoms
+            .into_iter()
+            .enumerate()
+            .map(|(i, (l, geom))| Entity {
+                id: Uuid::from_u128(100 + i as u128),
+                layer_id: Uuid::from_u128(l),
+                geom,
+                style: serde_json::json!({}),
+                tags: vec![],
+                meta: BTreeMap::new(),
+            })
+            .collect(),
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    }
+}
+
+fn has(c: &[SnapPoint], kind: SnapKind, x: f64, y: f64) -> bool {
+    c.iter()
+        .any(|s| s.kind == kind && (s.pt.x - x).abs() < 1e-9 && (s.pt.y - y).abs() < 1e-9)
+}
+
+fn query(x: f64, y: f64, radius: f64) -> SnapQuery {
+    SnapQuery {
+        cursor: Pt { x, y },
+        radius,
+        grid: None,
+        from: None,
+    }
+}
+
+#[test]
+fn generates_each_kind_near_the_cursor() {
+    let eps = EpsilonPolicy::default();
+    let d = doc(vec![
+        (
+            1,
+            Geom2D::Line {
+                a: v(-10.0, 0.0),
+                b: v(10.0, 0.0),
+            },
+        ),
+        (
+            1,
+            Geom2D::Circle {
+                c: v(0.0, 0.0),
+                r: 5.0,
+            },
+        ),
+        (
+            2,
+            Geom2D::Line {
+                a: v(-1.0, -1.0),
+                b: v(1.0, 1.0),
+            },
+        ),
+    ]);
+    let src = CandidateSource::build(&d, 4.0, &eps);
+
+    let c = src.candidates(&query(0.0, 0.0, 20.0), &eps);
+    assert!(has(&c, SnapKind::Endpoint, 10.0, 0.0));
+    assert!(has(&c, SnapKind::Midpoint, 0.0, 0.0));
+    assert!(has(&c, SnapKind::Center, 0.0, 0.0));
+    assert!(has(&c, SnapKind::Quadrant, 0.0, 5.0));
+    assert!(has(&c, SnapKind::Intersection, 5.0, 0.0));
+    assert!(has(&c, SnapKind::Intersection, -5.0, 0.0));
+    // The hidden diagonal contributes nothing.
+    assert!(!has(&c, SnapKind::Endpoint, 1.0, 1.0));
+
+    let mut q = query(4.0, 4.0, 3.0);
+    q.grid = Some(2.5);
+    q.from = Some(Pt { x: 10.0, y: 10.0 });
+    let c = src.candidates(&q, &eps);
+    let r = 5.0 / 2f64.sqrt();
+    assert!(has(&c, SnapKind::Nearest, r, r));
+    assert!(has(&c, SnapKind::Perpendicular, r, r));
+    assert!(has(&c, SnapKind::Grid, 5.0, 5.0));
+    assert!(!has(&c, SnapKind::Endpoint, 10.0, 0.0));
+
+    let policy = SnapPolicy {
+        max_dist: 3.0,
+        angle_step_deg: 15.0,
+        prefer: vec![],
+    };
+    let best = resolve(c, q.cursor, &policy).snapped.unwrap();
+    assert_eq!(best.kind, SnapKind::Perpendicular);
+
+    // Tangent from (10, 10) touches the circle at -24.3 degrees.
+    let mut q = query(4.5, -2.0, 1.0);
+    q.from = Some(Pt { x: 10.0, y: 10.0 });
+    let c = src.candidates(&q, &eps);
+    let t = c.iter().find(|s| s.kind == SnapKind::Tangent).unwrap();
+    let (dx, dy) = (t.pt.x - 10.0, t.pt.y - 10.0);
+    assert!((dx * t.pt.x + dy * t.pt.y).abs() < 1e-9);
+}
+
+#[test]
+fn far_entities_are_not_examined() {
+    let eps = EpsilonPolicy::default();
+    let d = doc(vec![
+        (
+            1,
+            Geom2D::Line {
+                a: v(0.0, 0.0),
+                b: v(1.0, 0.0),
+            },
+        ),
+        (
+            1,
+            Geom2D::Line {
+                a: v(100.0, 0.0),
+                b: v(101.0, 0.0),
+            },
+        ),
+    ]);
+    let src = CandidateSource::build(&d, 10.0, &eps);
+    let c = src.candidates(&query(0.5, 0.0, 2.0), &eps);
+    assert!(c.iter().all(|s| s.pt.x < 2.0));
+    assert_eq!(c.iter().filter(|s| s.kind == SnapKind::Endpoint).count(), 2);
+}
+
+#[test]
+fn line_being_drawn_intersects_entities() {
+    let eps = EpsilonPolicy::default();
+    let d = doc(vec![(
+        1,
+        Geom2D::Line {
+            a: v(5.0, -10.0),
+            b: v(5.0, 10.0),
+        },
+    )]);
+    let source = CandidateSource::build(&d, 8.0, &eps);
+    let mut q = query(5.3, 3.0, 1.0);
+    assert!(!source
+        .candidates(&q, &eps)
+        .iter()
+        .any(|s| s.kind == SnapKind::Intersection));
+    q.from = Some(Pt { x: 0.0, y: 0.0 });
+    let c = source.candidates(&q, &eps);
+    assert!(has(&c, SnapKind::Intersection, 5.0, 3.0 * 5.0 / 5.3));
+}
@@ -0,0 +1,132 @@
+use craftcad_geom2d::Pt;
+use craftcad_serialize::{Document, Entity, Geom2D, Layer, ProjectSettings, Vec2};
+use craftcad_snapping::candidates::CandidateSource;
+use craftcad_snapping::resolve::SnapPolicy;
+use craftcad_snapping::tracking::{Acquired, TrackKind, Tracker};
+use craftcad_snapping::types::SnapKind;
+use diycad_geom::EpsilonPolicy;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn p(x: f64, y: f64) -> Pt {
+    Pt { x, y }
+}
+
+fn policy() -> SnapPolicy {
+    SnapPolicy {
+        max_dist: 1.0,
+        angle_step_deg: 15.0,
+        prefer: vec![],
+    }
+}
+
+fn close(a: Pt, b: Pt) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+#[test]
+fn polar_tracking_snaps_to_angle_step() {
+    let r = Tracker::default().track(p(10.0, 0.3), Some(p(0.0, 0.0)), &policy());
+    let s = r.snapped.unwrap();
+    assert_eq!(s.kind, SnapKind::Angle);
+    assert!(close(s.pt, p(10.0, 0.0)));
+    assert_eq!(r.lines[0].kind, TrackKind::Polar);
+
+    // 44 degrees rounds to the 45 degree ray.
+    let a = 44f64.to_radians();
+    let r = Tracker::default().track(
+        p(10.0 * a.cos(), 10.0 * a.sin()),
+        Some(p(0.0, 0.0)),
+        &policy(),
+    );
+    let s = r.snapped.unwrap();
+    assert!((s.pt.x - s.pt.y).abs() < 1e-9);
+}
+
+#[test]
+fn acquired_point_lines_cross_the_polar_ray() {
+    let mut t = Tracker::default();
+    t.acquire(Acquired {
+        pt: p(5.0, 8.0),
+        extension: None,
+    });
+    let r = t.track(p(5.2, 0.1), Some(p(0.0, 0.0)), &policy());
+    assert!(close(r.snapped.unwrap().pt, p(5.0, 0.0)));
+    let kinds: Vec<TrackKind> = r.lines.iter().map(|l| l.kind).collect();
+    assert_eq!(kinds, [TrackKind::Polar, TrackKind::Vertical]);
+
+    // Two acquired points: horizontal from one meets vertical from the other.
+    t.acquire(Acquired {
+        pt: p(20.0, 3.0),
+        extension: None,
+    });
+    let r = t.track(p(5.3, 3.2), None, &policy());
+    assert!(close(r.snapped.unwrap().pt, p(5.0, 3.0)));
+    assert_eq!(r.lines.len(), 2);
+
+    // Acquiring again releases the point.
+    t.acquire(Acquired {
+        pt: p(20.0, 3.0),
+        extension: None,
+    });
+    assert_eq!(t.acquired().len(), 1);
+}
+
+#[test]
+fn extension_line_continues_hovered_endpoint() {
+    let layer = Uuid::from_u128(1);
+    let doc = Document {
+        schema_version: 3,
+        id: Uuid::from_u128(2),
+        units: "mm".into(),
+        layers: vec![Layer {
+            id: layer,
+            name: "L".into(),
+            visible: true,
+            locked: false,
+            editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
+        }],
+        entities: vec![Entity {
+            id: Uuid::from_u128(3),
+            layer_id: layer,
+            geom: Geom2D::Line {
+                a: Vec2 { x: 0.0, y: 0.0 },
+                b: Vec2 { x: 10.0, y: 0.0 },
+            },
+            style: serde_json::json!({}),
+            tags: vec![],
+            meta: BTreeMap::new(),
+        }],
+        parts: vec![],
+        jobs: vec![],
+        materials: vec![],
+        settings: ProjectSettings::default(),
+        used_presets: vec![],
+        used_templates: vec![],
+        wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
+    };
+    let eps = EpsilonPolicy::default();
+    let src = CandidateSource::build(&doc, 5.0, &eps);
+    let ends = src.extensions(p(10.1, 0.0), 0.5);
+    assert_eq!(ends.len(), 1);
+    assert!(close(ends[0].extension.unwrap(), p(1.0, 0.0)));
+
+    let mut t = Tracker::default();
+    t.acquire(ends[0]);
+    let r = t.track(p(15.0, 0.2), None, &policy());
+    let s = r.snapped.unwrap();
+    assert_eq!(s.kind, SnapKind::Extension);
+    assert!(close(s.pt, p(15.0, 0.0)));
+    assert!(close(r.lines[0].origin, p(10.0, 0.0)));
+    // The extension is a ray: nothing behind the line's start.
+    let behind = t.lines(p(-5.0, 0.2), None, &policy());
+    assert!(behind
+        .iter()
+        .any(|l| l.kind == TrackKind::Extension && l.ray));
+}
@@ -0,0 +1,95 @@
+//! Block geometry in world coordinates, for everything that draws,
+//! exports or picks the document.
+
+use crate::{rotate, scale, translate};
+use craftcad_serialize::{
+    BlockDef, BlockInstance, BlockTransform, Document, Entity, Geom2D, Reason, ReasonCode, Result,
+    Vec2,
+};
+use uuid::Uuid;
+
+const ORIGIN: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+/// Maps `geom` from block coordinates (base point `base`) into the space
+/// the transform places it in. Circles and arcs need a uniform scale.
+pub fn place_geom(tr: &BlockTransform, base: &Vec2, geom: &Geom2D) -> Result<Geom2D> {
+    let sx = if tr.mirror { -tr.scale.x } else { tr.scale.x };
+    let g = translate(geom, -base.x, -base.y)?;
+    let g = scale(&g, &ORIGIN, sx, tr.scale.y)?;
+    let g = rotate(&g, &ORIGIN, tr.rotate_rad)?;
+    translate(&g, tr.translate.x, tr.translate.y)
+}
+
+/// The definition `id` among `blocks`.
+pub fn block_def(blocks: &[BlockDef], id: Uuid) -> Result<&BlockDef> {
+    blocks.iter().find(|b| b.id == id).ok_or_else(|| {
+        let mut r = Reason::from_code(ReasonCode::ModelReferenceNotFound);
+        r.params
+            .insert("block_id".into(), serde_json::json!(id.to_string()));
+        r
+    })
+}
+
+fn flatten_into(
+    blocks: &[BlockDef],
+    instance: &BlockInstance,
+    outer: &[&BlockInstance],
+    out: &mut Vec<Entity>,
+) -> Result<()> {
+    if outer.len() > blocks.len() {
+        return Err(Reason::from_code(ReasonCode::EditBlockCycle));
+    }
+    let def = block_def(blocks, instance.block_id)?;
+    let place = |geom: &Geom2D| -> Result<Geom2D> {
+        let mut g = place_geom(&instance.transform, &def.base_point, geom)?;
+        for o in outer.iter().rev() {
+            let base = &block_def(blocks, o.block_id)?.base_point;
+            g = place_geom(&o.transform, base, &g)?;
+        }
+        Ok(g)
+    };
+    for e in &def.entities {
+        out.push(Entity {
+            geom: place(&e.geom)?,
+            ..e.clone()
+        });
+    }
+    let mut chain = outer.to_vec();
+    chain.push(instance);
+    for nested in &def.instances {
+        flatten_into(blocks, nested, &chain, out)?;
+    }
+    Ok(())
+}
+
+/// World geometry of a placed instance with nested blocks resolved.
+/// Entities keep their definition ids, so several instances yield the
+/// same ids.
+pub fn instance_entities(doc: &Document, instance_id: Uuid) -> Result<Vec<Entity>> {
+    let instance = doc
+        .block_instances
+        .iter()
+        .find(|i| i.id == instance_id)
+        .ok_or_else(|| Reason::from_code(ReasonCode::ModelReferenceNotFound))?;
+    let mut out = vec![];
+    flatten_into(&doc.blocks, instance, &[], &mut out)?;
+    Ok(out)
+}
+
+/// Everything the document draws: its own entities, then the world
+/// geometry of each placed instance in order. `shown` filters by layer id;
+/// block geometry needs both its instance's layer and its own shown.
+pub fn drawn_entities(doc: &Document, shown: impl Fn(Uuid) -> bool) -> Result<Vec<Entity>> {
+    let mut out: Vec<Entity> = doc
+        .entities
+        .iter()
+        .filter(|e| shown(e.layer_id))
+        .cloned()
+        .collect();
+    for i in doc.block_instances.iter().filter(|i| shown(i.layer_id)) {
+        let mut placed = vec![];
+        flatten_into(&doc.blocks, i, &[], &mut placed)?;
+        out.extend(placed.into_iter().filter(|e| shown(e.layer_id)));
+    }
+    Ok(out)
+}
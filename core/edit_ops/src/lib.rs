@@ -1,5 +1,7 @@
 #![allow(clippy::result_large_err)]
 
+pub mod blocks;
+
 use craftcad_serialize::{Geom2D, Reason, ReasonCode, Result, Vec2};
 
 const MIN_SCALE: f64 = 1e-12;
@@ -37,6 +39,36 @@ fn scale_point(p: &Vec2, center: &Vec2, sx: f64, sy: f64) -> Vec2 {
     }
 }
 
+/// Ellipse under `diag(sx, sy)`: `diag(sx, sy) R(rot) diag(rx, ry)` is split
+/// into `R(theta) diag(a, b) R(phi)`, so parameter `t` maps to `t + phi`. A
+/// mirroring scale also reverses the parameter (`t -> -t`). Returns
+/// `(rx, ry, rot, map)` with `map` sending an old parameter to the new one.
+fn scale_ellipse(
+    rx: f64,
+    ry: f64,
+    rot: f64,
+    sx: f64,
+    sy: f64,
+) -> (f64, f64, f64, impl Fn(f64) -> f64) {
+    let (s, c) = rot.sin_cos();
+    let mirror = (sx < 0.0) != (sy < 0.0);
+    // Flip the second column when mirroring so the matrix keeps det > 0.
+    let f = if mirror { -1.0 } else { 1.0 };
+    let (m00, m01) = (sx * c * rx, -sx * s * ry * f);
+    let (m10, m11) = (sy * s * rx, sy * c * ry * f);
+    let e = (m00 + m11) / 2.0;
+    let g = (m00 - m11) / 2.0;
+    let h = (m10 + m01) / 2.0;
+    let k = (m10 - m01) / 2.0;
+    let q = (e * e + k * k).sqrt();
+    let r = (g * g + h * h).sqrt();
+    let a1 = h.atan2(g);
+    let a2 = k.atan2(e);
+    let theta = (a2 + a1) / 2.0;
+    let phi = (a2 - a1) / 2.0;
+    (q + r, q - r, theta, move |t: f64| f * t + phi)
+}
+
 pub fn translate(geom: &Geom2D, dx: f64, dy: f64) -> Result<Geom2D> {
     if !valid(dx) || !valid(dy) {
         return Err(Reason::from_code(ReasonCode::EditInvalidNumeric));
@@ -67,6 +99,35 @@ pub fn translate(geom: &Geom2D, dx: f64, dy: f64) -> Result<Geom2D> {
             pts: pts.iter().map(|p| add(p, dx, dy)).collect(),
             closed: *closed,
         },
+        Geom2D::Ellipse { c, rx, ry, rot } => Geom2D::Ellipse {
+            c: add(c, dx, dy),
+            rx: *rx,
+            ry: *ry,
+            rot: *rot,
+        },
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => Geom2D::EllipticalArc {
+            c: add(c, dx, dy),
+            rx: *rx,
+            ry: *ry,
+            rot: *rot,
+            start_angle: *start_angle,
+            end_angle: *end_angle,
+            ccw: *ccw,
+        },
+        Geom2D::CubicBezier { a, c1, c2, b } => Geom2D::CubicBezier {
+            a: add(a, dx, dy),
+            c1: add(c1, dx, dy),
+            c2: add(c2, dx, dy),
+            b: add(b, dx, dy),
+        },
     })
 }
 
@@ -103,6 +164,35 @@ pub fn rotate(geom: &Geom2D, center: &Vec2, angle_rad: f64) -> Result<Geom2D> {
                 .collect(),
             closed: *closed,
         },
+        Geom2D::Ellipse { c, rx, ry, rot } => Geom2D::Ellipse {
+            c: rotate_point(c, center, angle_rad),
+            rx: *rx,
+            ry: *ry,
+            rot: *rot + angle_rad,
+        },
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => Geom2D::EllipticalArc {
+            c: rotate_point(c, center, angle_rad),
+            rx: *rx,
+            ry: *ry,
+            rot: *rot + angle_rad,
+            start_angle: *start_angle,
+            end_angle: *end_angle,
+            ccw: *ccw,
+        },
+        Geom2D::CubicBezier { a, c1, c2, b } => Geom2D::CubicBezier {
+            a: rotate_point(a, center, angle_rad),
+            c1: rotate_point(c1, center, angle_rad),
+            c2: rotate_point(c2, center, angle_rad),
+            b: rotate_point(b, center, angle_rad),
+        },
     })
 }
 
@@ -137,17 +227,61 @@ pub fn scale(geom: &Geom2D, center: &Vec2, sx: f64, sy: f64) -> Result<Geom2D> {
             if (sx.abs() - sy.abs()).abs() > MIN_SCALE {
                 return Err(Reason::from_code(ReasonCode::EditTransformWouldDegenerate));
             }
+            // A negative factor reflects the angles; mirroring (one negative
+            // factor) also reverses the sweep.
+            let map = |a: f64| {
+                if sx < 0.0 || sy < 0.0 {
+                    (sy.signum() * a.sin()).atan2(sx.signum() * a.cos())
+                } else {
+                    a
+                }
+            };
             Geom2D::Arc {
                 c: scale_point(c, center, sx, sy),
                 r: *r * sx.abs(),
-                start_angle: *start_angle,
-                end_angle: *end_angle,
-                ccw: *ccw,
+                start_angle: map(*start_angle),
+                end_angle: map(*end_angle),
+                ccw: *ccw == ((sx < 0.0) == (sy < 0.0)),
             }
         }
         Geom2D::Polyline { pts, closed } => Geom2D::Polyline {
             pts: pts.iter().map(|p| scale_point(p, center, sx, sy)).collect(),
             closed: *closed,
         },
+        Geom2D::Ellipse { c, rx, ry, rot } => {
+            let (rx, ry, rot, _) = scale_ellipse(*rx, *ry, *rot, sx, sy);
+            Geom2D::Ellipse {
+                c: scale_point(c, center, sx, sy),
+                rx,
+                ry,
+                rot,
+            }
+        }
+        Geom2D::EllipticalArc {
+            c,
+            rx,
+            ry,
+            rot,
+            start_angle,
+            end_angle,
+            ccw,
+        } => {
+            let (rx, ry, rot, map) = scale_ellipse(*rx, *ry, *rot, sx, sy);
+            Geom2D::EllipticalArc {
+                c: scale_point(c, center, sx, sy),
+                rx,
+                ry,
+                rot,
+                start_angle: map(*start_angle),
+                end_angle: map(*end_angle),
+                ccw: *ccw == ((sx < 0.0) == (sy < 0.0)),
+            }
+        }
+        Geom2D::CubicBezier { a, c1, c2, b } => Geom2D::CubicBezier {
+            a: scale_point(a, center, sx, sy),
+            c1: scale_point(c1, center, sx, sy),
+            c2: scale_point(c2, center, sx, sy),
+            b: scale_point(b, center, sx, sy),
+        },
     })
 }
@@ -14,7 +14,7 @@ impl Default for DrawingPdfOptions {
 }
 
 pub fn export_drawing_pdf(doc: &Document, options: &DrawingPdfOptions) -> Result<Vec<u8>> {
-    for e in &doc.entities {
+    for e in &craftcad_edit_ops::blocks::drawn_entities(doc, |_| true)? {
         match e.geom {
             Geom2D::Line { .. } | Geom2D::Polyline { .. } => {}
             _ => return Err(Reason::from_code(ReasonCode::ExportUnsupportedEntity)),
@@ -69,17 +69,18 @@ pub fn gauge_length_in_doc_units(units: &str) -> Result<f64> {
     }
 }
 
-fn iter_points(doc: &Document) -> impl Iterator<Item = Vec2> + '_ {
-    let entities = doc.entities.iter().flat_map(|e| match &e.geom {
-        Geom2D::Line { a, b } => vec![a.clone(), b.clone()],
-        Geom2D::Polyline { pts, .. } => pts.clone(),
+fn collect_points(doc: &Document) -> Result<Vec<Vec2>> {
+    let entities = craftcad_edit_ops::blocks::drawn_entities(doc, |_| true)?;
+    let points = entities.into_iter().flat_map(|e| match e.geom {
+        Geom2D::Line { a, b } => vec![a, b],
+        Geom2D::Polyline { pts, .. } => pts,
         _ => vec![],
     });
     let part_outer = doc
         .parts
         .iter()
         .flat_map(|p| p.outline.outer.clone().into_iter());
-    entities.chain(part_outer)
+    Ok(points.chain(part_outer).collect())
 }
 
 pub fn compute_tiled_layout(doc: &Document, options: &TiledPdfOptions) -> Result<TileLayout> {
@@ -90,7 +91,7 @@ pub fn compute_tiled_layout(doc: &Document, options: &TiledPdfOptions) -> Result
     let mut min_y = f64::INFINITY;
     let mut max_x = f64::NEG_INFINITY;
     let mut max_y = f64::NEG_INFINITY;
-    for p in iter_points(doc) {
+    for p in collect_points(doc)? {
         if !p.x.is_finite() || !p.y.is_finite() {
             return Err(Reason::from_code(ReasonCode::ExportUnsupportedEntity));
         }
@@ -140,7 +141,7 @@ fn pdf_escape(s: &str) -> String {
 }
 
 pub fn export_tiled_pdf(doc: &Document, options: &TiledPdfOptions) -> Result<Vec<u8>> {
-    for e in &doc.entities {
+    for e in &craftcad_edit_ops::blocks::drawn_entities(doc, |_| true)? {
         match e.geom {
             Geom2D::Line { .. } | Geom2D::Polyline { .. } => {}
             _ => return Err(Reason::from_code(ReasonCode::ExportUnsupportedEntity)),
@@ -1,4 +1,4 @@
-use craftcad_serialize::{Document, Geom2D, Reason, ReasonCode, Result, Vec2};
+use craftcad_serialize::{Document, Geom2D, Layer, Reason, ReasonCode, Result, Vec2};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,28 +34,56 @@ fn poly_path(pts: &[Vec2], closed: bool, prec: usize) -> String {
     out
 }
 
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `stroke` and `stroke-dasharray` for a layer; nothing for the defaults so
+/// plain drawings export unchanged.
+fn layer_style(layer: Option<&Layer>) -> String {
+    let mut out = String::new();
+    let Some(l) = layer else {
+        return out;
+    };
+    if let Some(c) = &l.color {
+        out.push_str(&format!(" stroke=\"{}\"", xml_escape(c)));
+    }
+    if let Some(d) = craftcad_io_support::svg_dash_array(&l.linetype) {
+        out.push_str(&format!(" stroke-dasharray=\"{d}\""));
+    }
+    out
+}
+
 pub fn export_svg(doc: &Document, options: &SvgExportOptions) -> Result<String> {
     let mut items: Vec<String> = vec![];
     if options.include_entities {
-        let mut entities = doc.entities.clone();
+        let printed = |id| !doc.layers.iter().any(|l| l.id == id && !l.print);
+        let mut entities = craftcad_edit_ops::blocks::drawn_entities(doc, printed)?;
         entities.sort_by_key(|e| e.id);
         for e in entities {
+            let layer = doc.layers.iter().find(|l| l.id == e.layer_id);
+            let style = layer_style(layer);
             match e.geom {
                 Geom2D::Line { a, b } => {
                     items.push(format!(
-                        "<path data-id=\"{}\" d=\"M {} {} L {} {}\" class=\"entity line\" />",
+                        "<path data-id=\"{}\" d=\"M {} {} L {} {}\" class=\"entity line\"{} />",
                         e.id,
                         fmt(a.x, options.precision),
                         fmt(a.y, options.precision),
                         fmt(b.x, options.precision),
-                        fmt(b.y, options.precision)
+                        fmt(b.y, options.precision),
+                        style
                     ));
                 }
                 Geom2D::Polyline { pts, closed } => {
                     items.push(format!(
-                        "<path data-id=\"{}\" d=\"{}\" class=\"entity polyline\" />",
+                        "<path data-id=\"{}\" d=\"{}\" class=\"entity polyline\"{} />",
                         e.id,
-                        poly_path(&pts, closed, options.precision)
+                        poly_path(&pts, closed, options.precision),
+                        style
                     ));
                 }
                 _ => return Err(Reason::from_code(ReasonCode::ExportUnsupportedEntity)),
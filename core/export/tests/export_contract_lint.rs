@@ -28,6 +28,9 @@ fn tiled_defaults_invariants() {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     };
     let layout = compute_tiled_layout(&doc, &TiledPdfOptions::default()).expect("layout");
     assert!(layout.tiles_x >= 1 && layout.tiles_y >= 1);
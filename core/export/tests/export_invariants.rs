@@ -1,10 +1,12 @@
 use craftcad_export::{
-    compute_tiled_layout, export_svg, gauge_length_in_doc_units, Orientation, PageSize,
-    SvgExportOptions, TiledPdfOptions,
+    compute_tiled_layout, export_drawing_pdf, export_svg, export_tiled_pdf,
+    gauge_length_in_doc_units, DrawingPdfOptions, Orientation, PageSize, SvgExportOptions,
+    TiledPdfOptions,
 };
 use craftcad_serialize::{
-    Document, Entity, Geom2D, GrainPolicy, Layer, Material, MaterialCategory, NestConstraints,
-    NestJob, NestObjective, Part, PartRef, Polygon2D, ProjectSettings, SheetDef,
+    BlockDef, BlockInstance, BlockTransform, Document, Entity, Geom2D, GrainPolicy, Layer,
+    Material, MaterialCategory, NestConstraints, NestJob, NestObjective, Part, PartRef, Polygon2D,
+    ProjectSettings, SheetDef,
 };
 use uuid::Uuid;
 
@@ -22,6 +24,9 @@ fn doc(units: &str) -> Document {
             visible: true,
             locked: false,
             editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
         }],
         entities: vec![
             Entity {
@@ -89,11 +94,13 @@ fn doc(units: &str) -> Document {
                 allow_rotate_default: true,
                 no_go_zones: vec![],
                 grain_policy: GrainPolicy::Ignore,
+                guillotine: false,
             },
             objective: NestObjective {
                 w_utilization: 1.0,
                 w_sheet_count: 1.0,
                 w_cut_count: 1.0,
+                w_grain_misaligned: 1.0,
             },
             seed: 1,
             result: None,
@@ -111,6 +118,9 @@ fn doc(units: &str) -> Document {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     }
 }
 
@@ -155,3 +165,75 @@ fn svg_stable_order_and_precision() {
     assert!(idx_poly < idx_line);
     assert!(s.contains("10.00"));
 }
+
+#[test]
+fn svg_honours_layer_colour_linetype_and_print() {
+    let mut d = doc("mm");
+    d.layers[0].color = Some("#FF8000".into());
+    d.layers[0].linetype = "DASHED".into();
+    let opts = SvgExportOptions::default();
+    let s = export_svg(&d, &opts).unwrap();
+    assert_eq!(
+        s.matches("stroke=\"#FF8000\" stroke-dasharray=\"6 3\"")
+            .count(),
+        2
+    );
+
+    d.layers[0].print = false;
+    let s = export_svg(&d, &opts).unwrap();
+    assert!(!s.contains("class=\"entity"));
+    assert!(s.contains("class=\"part outer\""), "parts have no layer");
+}
+
+#[test]
+fn svg_escapes_layer_colour() {
+    let mut d = doc("mm");
+    d.layers[0].color = Some("red\" onload=\"alert(1)\" x=\"<".into());
+    let s = export_svg(&d, &SvgExportOptions::default()).unwrap();
+    assert!(!s.contains("onload=\""));
+    assert!(s.contains("stroke=\"red&quot; onload=&quot;alert(1)&quot; x=&quot;&lt;\""));
+}
+
+#[test]
+fn block_instances_are_exported_in_world_coordinates() {
+    let mut d = doc("mm");
+    let line = d.entities.remove(0);
+    let layer_id = line.layer_id;
+    let block_id = Uuid::new_v4();
+    d.blocks.push(BlockDef {
+        id: block_id,
+        name: "B".into(),
+        base_point: craftcad_serialize::Vec2 { x: 0.0, y: 0.0 },
+        entities: vec![line],
+        instances: vec![],
+    });
+    d.block_instances.push(BlockInstance {
+        id: Uuid::new_v4(),
+        block_id,
+        name: String::new(),
+        layer_id,
+        transform: BlockTransform {
+            translate: craftcad_serialize::Vec2 {
+                x: 1000.0,
+                y: 500.0,
+            },
+            ..Default::default()
+        },
+    });
+
+    let s = export_svg(&d, &SvgExportOptions::default()).unwrap();
+    assert!(
+        s.contains("d=\"M 1000.000 500.000 L 1300.000 500.000\""),
+        "{s}"
+    );
+
+    let opt = TiledPdfOptions::default();
+    let layout = compute_tiled_layout(&d, &opt).unwrap();
+    assert_eq!(layout.bbox_max, (1300.0, 500.0));
+    assert!(export_tiled_pdf(&d, &opt).is_ok());
+    assert!(export_drawing_pdf(&d, &DrawingPdfOptions::default()).is_ok());
+
+    d.layers[0].print = false;
+    let s = export_svg(&d, &SvgExportOptions::default()).unwrap();
+    assert!(!s.contains("class=\"entity"));
+}
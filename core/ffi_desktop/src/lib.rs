@@ -8,8 +8,16 @@
 mod editor_bridge;
 use craftcad_bom::{generate_bom, write_bom_csv, CsvOptions, RoundingPolicy, UnitPolicy};
 use craftcad_commands::commands::advanced_edit::{
-    ChamferCommand, ChamferInput, FilletCommand, FilletInput, MirrorCommand, MirrorInput,
-    PatternCommand, PatternInput, PatternParams,
+    BooleanCommand, BooleanInput, ChamferCommand, ChamferInput, ChamferPolylineCommand,
+    ChamferPolylineInput, FilletCommand, FilletInput, FilletPolylineCommand, FilletPolylineInput,
+    MirrorCommand, MirrorInput, PatternCommand, PatternInput, PatternParams,
+};
+use craftcad_commands::commands::blocks::{
+    instance_contents, instance_entities, DefineBlockCommand, DefineBlockInput, EditBlockCommand,
+    EditBlockInput, ExplodeBlockCommand, ExplodeBlockInput, InsertBlockCommand, InsertBlockInput,
+};
+use craftcad_commands::commands::clipboard::{
+    copy_selection, ClipboardPayload, CutCommand, CutInput, PasteCommand, PasteInput,
 };
 use craftcad_commands::commands::create_line::{CreateLineCommand, CreateLineInput};
 use craftcad_commands::commands::create_part::{
@@ -21,6 +29,8 @@ use craftcad_commands::commands::create_shapes::{
     CreateCircleInput, CreatePolylineCommand, CreatePolylineInput, CreateRectCommand,
     CreateRectInput, PolylineParams, RectParams,
 };
+use craftcad_commands::commands::extend_entity::{ExtendEntityCommand, ExtendEntityInput};
+use craftcad_commands::commands::layers::{LayerCommand, LayerEdit};
 use craftcad_commands::commands::nesting::{
     EditPlacementCommand, EditPlacementInput, PlacementPose, RunNestingCommand, RunNestingInput,
 };
@@ -45,8 +55,17 @@ use craftcad_projection_lite::{
 use craftcad_rules_engine::{
     preflight_rules, run_rules_edge_distance, RuleConfig, RuleReport, RuleSeverity,
 };
-use craftcad_serialize::{load_diycad, Document, Part, Reason, ReasonCode, Vec2};
-use diycad_geom::{intersect, project_point, split_at, EpsilonPolicy, Geom2D, SplitBy};
+use craftcad_serialize::{
+    load_diycad, load_diycad_with_history, save_diycad_with_history, Document, Manifest, Part,
+    Reason, ReasonCode, Vec2,
+};
+use craftcad_snapping::candidates::{CandidateSource, SnapQuery};
+use craftcad_snapping::resolve::{resolve, SnapPolicy};
+use craftcad_snapping::tracking::{Acquired, Tracker};
+use craftcad_snapping::types::SnapPoint;
+use diycad_geom::{
+    intersect, project_point, split_at, BooleanOp, EpsilonPolicy, Geom2D, JoinStyle, SplitBy,
+};
 use diycad_nesting::RunLimits;
 use diycad_project::{load as load_project_file, save as save_project_file, DiycadProject};
 use serde::Serialize;
@@ -87,8 +106,11 @@ pub const EXPORTED_SYMBOLS: &[&str] = &[
     "craftcad_history_free",
     "craftcad_history_apply_fillet",
     "craftcad_history_apply_chamfer",
+    "craftcad_history_apply_fillet_polyline",
+    "craftcad_history_apply_chamfer_polyline",
     "craftcad_history_apply_mirror",
     "craftcad_history_apply_pattern",
+    "craftcad_history_apply_boolean",
     "craftcad_geom_candidates_for_operation",
     "craftcad_history_apply_create_rect",
     "craftcad_history_apply_create_circle",
@@ -97,8 +119,11 @@ pub const EXPORTED_SYMBOLS: &[&str] = &[
     "craftcad_history_apply_create_line",
     "craftcad_history_apply_transform_selection",
     "craftcad_history_apply_offset_entity",
+    "craftcad_history_apply_offset_entity_with_join",
     "craftcad_history_apply_trim_entity",
     "craftcad_history_apply_trim_entity_with_candidate_index",
+    "craftcad_history_apply_extend_entity",
+    "craftcad_history_apply_extend_entity_with_candidate_index",
     "craftcad_history_undo",
     "craftcad_history_redo",
     "craftcad_history_begin_group",
@@ -116,6 +141,26 @@ pub const EXPORTED_SYMBOLS: &[&str] = &[
     "craftcad_ssot_get_part",
     "craftcad_ssot_set_part_name",
     "craftcad_ssot_set_part_quantity",
+    "craftcad_snap_candidates",
+    "craftcad_snap_index_new",
+    "craftcad_snap_index_query",
+    "craftcad_snap_index_free",
+    "craftcad_history_entries",
+    "craftcad_load_diycad_with_history_json",
+    "craftcad_save_diycad_with_history_json",
+    "craftcad_history_nodes",
+    "craftcad_history_jump",
+    "craftcad_history_compare",
+    "craftcad_clipboard_copy",
+    "craftcad_history_apply_cut",
+    "craftcad_history_apply_paste",
+    "craftcad_block_instance_entities",
+    "craftcad_block_instance_contents",
+    "craftcad_history_apply_define_block",
+    "craftcad_history_apply_insert_block",
+    "craftcad_history_apply_explode_block",
+    "craftcad_history_apply_edit_block",
+    "craftcad_history_apply_layer_edit",
 ];
 
 fn reason_json(reason: &Reason) -> serde_json::Value {
@@ -210,7 +255,11 @@ pub unsafe extern "C" fn craftcad_extract_faces(
         Ok(v) => v,
         Err(r) => return encode_err(r),
     };
-    let geoms: Vec<_> = doc.entities.iter().map(|e| e.geom.clone()).collect();
+    let entities = match craftcad_commands::commands::blocks::drawn_entities(&doc, |_| true) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let geoms: Vec<_> = entities.into_iter().map(|e| e.geom).collect();
     match extract_faces(&geoms, &eps) {
         Ok(fs) => {
             let faces = fs
@@ -657,6 +706,170 @@ pub unsafe extern "C" fn craftcad_geom_split_at_t(
     }
 }
 
+#[derive(serde::Deserialize)]
+struct SnapQueryJson {
+    cursor: craftcad_geom2d::Pt,
+    radius: f64,
+    #[serde(default)]
+    grid: Option<f64>,
+    #[serde(default)]
+    from: Option<craftcad_geom2d::Pt>,
+    /// Enables polar/object-snap tracking when set.
+    #[serde(default)]
+    angle_step_deg: Option<f64>,
+    #[serde(default)]
+    acquired: Vec<Acquired>,
+}
+
+fn snap_point_json(p: &SnapPoint) -> serde_json::Value {
+    serde_json::json!({
+        "kind": format!("{:?}", p.kind),
+        "x": p.pt.x,
+        "y": p.pt.y,
+        "score": p.score,
+    })
+}
+
+fn parse_snap_query(
+    query_json: *const c_char,
+    eps_json: *const c_char,
+) -> craftcad_serialize::Result<(SnapQueryJson, EpsilonPolicy)> {
+    let q: SnapQueryJson = serde_json::from_str(&parse_cstr(query_json, "query")?)
+        .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))?;
+    let eps: EpsilonPolicy = serde_json::from_str(&parse_cstr(eps_json, "eps")?)
+        .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))?;
+    Ok((q, eps))
+}
+
+/// Candidates of `source` for `q`, in the JSON shape shared by
+/// [`craftcad_snap_candidates`] and [`craftcad_snap_index_query`].
+fn snap_response(
+    source: &CandidateSource,
+    q: SnapQueryJson,
+    eps: &EpsilonPolicy,
+) -> serde_json::Value {
+    let query = SnapQuery {
+        cursor: q.cursor,
+        radius: q.radius,
+        grid: q.grid,
+        from: q.from,
+    };
+    let policy = SnapPolicy {
+        max_dist: q.radius,
+        angle_step_deg: q.angle_step_deg.unwrap_or(0.0),
+        prefer: vec![],
+    };
+    let res = resolve(source.candidates(&query, eps), query.cursor, &policy);
+    let mut out = serde_json::json!({
+        "snapped": res.snapped.as_ref().map(snap_point_json),
+        "candidates": res.candidates.iter().map(snap_point_json).collect::<Vec<_>>(),
+    });
+    if q.angle_step_deg.is_some() {
+        let mut tracker = Tracker::default();
+        for a in q.acquired {
+            tracker.acquire(a);
+        }
+        let tr = tracker.track(query.cursor, query.from, &policy);
+        out["tracking"] = serde_json::json!({
+            "snapped": tr.snapped.as_ref().map(snap_point_json),
+            "lines": tr.lines,
+        });
+        out["extensions"] = serde_json::json!(source.extensions(query.cursor, q.radius));
+    }
+    out
+}
+
+/// Snap candidates of `doc_json` around the query cursor, resolved in the
+/// deterministic snapping order; `snapped` is the first of them. With
+/// `angle_step_deg` set, `tracking` holds the tracked point and its
+/// construction lines and `extensions` the open ends the UI may acquire.
+/// Indexes the document on every call; pointer moves should query a
+/// [`craftcad_snap_index_new`] handle instead.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_snap_candidates(
+    doc_json: *const c_char,
+    query_json: *const c_char,
+    eps_json: *const c_char,
+) -> *mut c_char {
+    match (|| -> craftcad_serialize::Result<serde_json::Value> {
+        let doc: Document = serde_json::from_str(&parse_cstr(doc_json, "doc")?)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))?;
+        let (q, eps) = parse_snap_query(query_json, eps_json)?;
+        let source = CandidateSource::build(&doc, q.radius.max(1.0) * 4.0, &eps);
+        Ok(snap_response(&source, q, &eps))
+    })() {
+        Ok(v) => encode_ok(v),
+        Err(r) => encode_err(r),
+    }
+}
+
+static SNAP_INDEXES: OnceLock<Mutex<HashMap<u64, CandidateSource>>> = OnceLock::new();
+
+fn snap_indexes() -> &'static Mutex<HashMap<u64, CandidateSource>> {
+    SNAP_INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Indexes `doc_json` for snapping and returns `{"handle": n}`. Build one
+/// per document revision, query it with [`craftcad_snap_index_query`] and
+/// release it with [`craftcad_snap_index_free`]. `cell` is the bucket size
+/// of the spatial index, in world units.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_snap_index_new(
+    doc_json: *const c_char,
+    eps_json: *const c_char,
+    cell: f64,
+) -> *mut c_char {
+    match (|| -> craftcad_serialize::Result<serde_json::Value> {
+        if !cell.is_finite() || cell <= 0.0 {
+            return Err(Reason::from_code(ReasonCode::DrawInvalidNumeric));
+        }
+        let doc: Document = serde_json::from_str(&parse_cstr(doc_json, "doc")?)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))?;
+        let eps: EpsilonPolicy = serde_json::from_str(&parse_cstr(eps_json, "eps")?)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))?;
+        let source = CandidateSource::build(&doc, cell, &eps);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        snap_indexes()
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?
+            .insert(id, source);
+        Ok(serde_json::json!({ "handle": id }))
+    })() {
+        Ok(v) => encode_ok(v),
+        Err(r) => encode_err(r),
+    }
+}
+
+/// [`craftcad_snap_candidates`] against an index built by
+/// [`craftcad_snap_index_new`].
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_snap_index_query(
+    handle: u64,
+    query_json: *const c_char,
+    eps_json: *const c_char,
+) -> *mut c_char {
+    match (|| -> craftcad_serialize::Result<serde_json::Value> {
+        let (q, eps) = parse_snap_query(query_json, eps_json)?;
+        let map = snap_indexes()
+            .lock()
+            .map_err(|_| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        let source = map
+            .get(&handle)
+            .ok_or_else(|| Reason::from_code(ReasonCode::CoreInvariantViolation))?;
+        Ok(snap_response(source, q, &eps))
+    })() {
+        Ok(v) => encode_ok(v),
+        Err(r) => encode_err(r),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn craftcad_snap_index_free(handle: u64) {
+    if let Ok(mut m) = snap_indexes().lock() {
+        m.remove(&handle);
+    }
+}
+
 static HISTORIES: OnceLock<Mutex<HashMap<u64, History>>> = OnceLock::new();
 static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
@@ -809,6 +1022,80 @@ pub unsafe extern "C" fn craftcad_history_apply_chamfer(
     })
 }
 #[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_fillet_polyline(
+    handle: u64,
+    doc_json: *const c_char,
+    fillet_json: *const c_char,
+    _eps_json: *const c_char,
+) -> *mut c_char {
+    #[derive(serde::Deserialize)]
+    struct In {
+        entity_id: String,
+        vertex: Option<usize>,
+        radius: f64,
+    }
+    let i: In = match parse_cstr(fillet_json, "fillet_json").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::DrawInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let entity_id = match Uuid::parse_str(&i.entity_id) {
+        Ok(v) => v,
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::ModelReferenceNotFound)),
+    };
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut c = FilletPolylineCommand::new();
+        c.begin(&CommandContext::default())?;
+        c.update(FilletPolylineInput {
+            entity_id,
+            vertex: i.vertex,
+            radius: i.radius,
+        })?;
+        let d = c.commit()?;
+        d.apply(doc)?;
+        h.push(d);
+        Ok(())
+    })
+}
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_chamfer_polyline(
+    handle: u64,
+    doc_json: *const c_char,
+    chamfer_json: *const c_char,
+    _eps_json: *const c_char,
+) -> *mut c_char {
+    #[derive(serde::Deserialize)]
+    struct In {
+        entity_id: String,
+        vertex: Option<usize>,
+        distance: f64,
+    }
+    let i: In = match parse_cstr(chamfer_json, "chamfer_json").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::DrawInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let entity_id = match Uuid::parse_str(&i.entity_id) {
+        Ok(v) => v,
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::ModelReferenceNotFound)),
+    };
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut c = ChamferPolylineCommand::new();
+        c.begin(&CommandContext::default())?;
+        c.update(ChamferPolylineInput {
+            entity_id,
+            vertex: i.vertex,
+            distance: i.distance,
+        })?;
+        let d = c.commit()?;
+        d.apply(doc)?;
+        h.push(d);
+        Ok(())
+    })
+}
+#[no_mangle]
 pub unsafe extern "C" fn craftcad_history_apply_mirror(
     handle: u64,
     doc_json: *const c_char,
@@ -890,6 +1177,58 @@ pub unsafe extern "C" fn craftcad_history_apply_pattern(
     })
 }
 #[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_boolean(
+    handle: u64,
+    doc_json: *const c_char,
+    boolean_json: *const c_char,
+    eps_json: *const c_char,
+) -> *mut c_char {
+    #[derive(serde::Deserialize)]
+    struct In {
+        a_ids: Vec<String>,
+        b_ids: Vec<String>,
+        op: BooleanOp,
+    }
+    let i: In = match parse_cstr(boolean_json, "boolean_json").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::EditNoSelection))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let eps: EpsilonPolicy = match parse_cstr(eps_json, "eps").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let parse_ids = |ids: Vec<String>| -> std::result::Result<Vec<Uuid>, Reason> {
+        ids.iter()
+            .map(|s| {
+                Uuid::parse_str(s)
+                    .map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+            })
+            .collect()
+    };
+    let (a_ids, b_ids) = match (parse_ids(i.a_ids), parse_ids(i.b_ids)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(r), _) | (_, Err(r)) => return encode_err(r),
+    };
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut c = BooleanCommand::new();
+        c.begin(&CommandContext::default())?;
+        c.update(BooleanInput {
+            a_ids,
+            b_ids,
+            op: i.op,
+            eps,
+        })?;
+        let d = c.commit()?;
+        d.apply(doc)?;
+        h.push(d);
+        Ok(())
+    })
+}
+#[no_mangle]
 pub unsafe extern "C" fn craftcad_geom_candidates_for_operation(
     op_json: *const c_char,
 ) -> *mut c_char {
@@ -1069,6 +1408,254 @@ pub unsafe extern "C" fn craftcad_history_apply_create_line(
     })
 }
 
+#[derive(serde::Deserialize)]
+struct ClipboardSelection {
+    #[serde(default)]
+    entity_ids: Vec<Uuid>,
+    #[serde(default)]
+    part_ids: Vec<Uuid>,
+}
+
+fn parse_clipboard_selection(
+    ptr: *const c_char,
+) -> std::result::Result<ClipboardSelection, Reason> {
+    parse_cstr(ptr, "selection").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+    })
+}
+
+/// Copies `{"entity_ids":[..],"part_ids":[..]}` of the document into a
+/// clipboard payload. Cut is this followed by `craftcad_history_apply_cut`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_clipboard_copy(
+    doc_json: *const c_char,
+    selection_json: *const c_char,
+) -> *mut c_char {
+    let doc: Document = match parse_cstr(doc_json, "doc_json").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    }) {
+        Ok(d) => d,
+        Err(r) => return encode_err(r),
+    };
+    let sel = match parse_clipboard_selection(selection_json) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    match copy_selection(&doc, &sel.entity_ids, &sel.part_ids) {
+        Ok(payload) => encode_ok(payload),
+        Err(r) => encode_err(r),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_cut(
+    handle: u64,
+    doc_json: *const c_char,
+    selection_json: *const c_char,
+) -> *mut c_char {
+    let sel = match parse_clipboard_selection(selection_json) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut cmd = CutCommand::new();
+        cmd.begin(&CommandContext::default())?;
+        cmd.update(CutInput {
+            entity_ids: sel.entity_ids,
+            part_ids: sel.part_ids,
+        })?;
+        let delta = cmd.commit()?;
+        delta.apply(doc)?;
+        h.push(delta);
+        Ok(())
+    })
+}
+
+/// Pastes a payload from `craftcad_clipboard_copy`, possibly taken from
+/// another document, moved by `offset_json` (`{"x":..,"y":..}`).
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_paste(
+    handle: u64,
+    doc_json: *const c_char,
+    payload_json: *const c_char,
+    offset_json: *const c_char,
+) -> *mut c_char {
+    let payload: ClipboardPayload = match parse_cstr(payload_json, "payload").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let offset: Vec2 = match parse_cstr(offset_json, "offset").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::EditInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut cmd = PasteCommand::new();
+        cmd.begin(&CommandContext::default())?;
+        cmd.update(PasteInput { payload, offset })?;
+        let delta = cmd.commit()?;
+        delta.apply(doc)?;
+        h.push(delta);
+        Ok(())
+    })
+}
+
+fn parse_doc_and_instance(
+    doc_json: *const c_char,
+    instance_id_uuid: *const c_char,
+) -> std::result::Result<(Document, Uuid), Reason> {
+    let doc: Document = parse_cstr(doc_json, "doc_json").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    })?;
+    let id = parse_cstr(instance_id_uuid, "instance_id").and_then(|s| {
+        Uuid::parse_str(&s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+    })?;
+    Ok((doc, id))
+}
+
+/// World geometry of a block instance with nested blocks resolved.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_block_instance_entities(
+    doc_json: *const c_char,
+    instance_id_uuid: *const c_char,
+) -> *mut c_char {
+    match parse_doc_and_instance(doc_json, instance_id_uuid)
+        .and_then(|(doc, id)| instance_entities(&doc, id))
+    {
+        Ok(entities) => encode_ok(serde_json::json!({ "entities": entities })),
+        Err(r) => encode_err(r),
+    }
+}
+
+/// One level of a block instance in world coordinates, for edit in place
+/// through `craftcad_history_apply_edit_block` with `via_instance`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_block_instance_contents(
+    doc_json: *const c_char,
+    instance_id_uuid: *const c_char,
+) -> *mut c_char {
+    match parse_doc_and_instance(doc_json, instance_id_uuid)
+        .and_then(|(doc, id)| instance_contents(&doc, id))
+    {
+        Ok((entities, instances)) => encode_ok(serde_json::json!({
+            "entities": entities,
+            "instances": instances,
+        })),
+        Err(r) => encode_err(r),
+    }
+}
+
+fn parse_command_input<T: serde::de::DeserializeOwned>(
+    ptr: *const c_char,
+) -> std::result::Result<T, Reason> {
+    parse_cstr(ptr, "input").and_then(|s| {
+        serde_json::from_str(&s).map_err(|e| {
+            let mut r = Reason::from_code(ReasonCode::SerializePackageCorrupted);
+            r.debug
+                .insert("error".into(), serde_json::json!(e.to_string()));
+            r
+        })
+    })
+}
+
+fn apply_command<C: Command>(
+    handle: u64,
+    doc_json: *const c_char,
+    mut cmd: C,
+    input: C::Input,
+) -> *mut c_char {
+    with_history_doc(handle, doc_json, |h, doc| {
+        cmd.begin(&CommandContext::default())?;
+        cmd.update(input)?;
+        let delta = cmd.commit()?;
+        delta.apply(doc)?;
+        h.push(delta);
+        Ok(())
+    })
+}
+
+/// Moves entities and instances into a new block and places it once:
+/// `{"name","base_point","entity_ids","instance_ids","layer_id"}`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_define_block(
+    handle: u64,
+    doc_json: *const c_char,
+    input_json: *const c_char,
+) -> *mut c_char {
+    match parse_command_input::<DefineBlockInput>(input_json) {
+        Ok(input) => apply_command(handle, doc_json, DefineBlockCommand::new(), input),
+        Err(r) => encode_err(r),
+    }
+}
+
+/// Places a block: `{"block_id","name","layer_id","transform"}`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_insert_block(
+    handle: u64,
+    doc_json: *const c_char,
+    input_json: *const c_char,
+) -> *mut c_char {
+    match parse_command_input::<InsertBlockInput>(input_json) {
+        Ok(input) => apply_command(handle, doc_json, InsertBlockCommand::new(), input),
+        Err(r) => encode_err(r),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_explode_block(
+    handle: u64,
+    doc_json: *const c_char,
+    instance_id_uuid: *const c_char,
+) -> *mut c_char {
+    let instance_id = match parse_cstr(instance_id_uuid, "instance_id").and_then(|s| {
+        Uuid::parse_str(&s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    apply_command(
+        handle,
+        doc_json,
+        ExplodeBlockCommand::new(),
+        ExplodeBlockInput { instance_id },
+    )
+}
+
+/// Replaces a block's contents:
+/// `{"block_id","entities","instances","via_instance"}`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_edit_block(
+    handle: u64,
+    doc_json: *const c_char,
+    input_json: *const c_char,
+) -> *mut c_char {
+    match parse_command_input::<EditBlockInput>(input_json) {
+        Ok(input) => apply_command(handle, doc_json, EditBlockCommand::new(), input),
+        Err(r) => encode_err(r),
+    }
+}
+
+/// Creates, renames, deletes, reorders, merges or restyles a layer, or moves
+/// entities between layers: `{"op":"Create","name"}`, `{"op":"Delete",
+/// "layer_id","reassign_to"}`, `{"op":"MoveEntities","ids","layer_id"}`, ...
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_layer_edit(
+    handle: u64,
+    doc_json: *const c_char,
+    edit_json: *const c_char,
+) -> *mut c_char {
+    match parse_command_input::<LayerEdit>(edit_json) {
+        Ok(edit) => apply_command(handle, doc_json, LayerCommand::new(), edit),
+        Err(r) => encode_err(r),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn craftcad_history_apply_transform_selection(
     handle: u64,
@@ -1129,6 +1716,42 @@ pub unsafe extern "C" fn craftcad_history_apply_offset_entity(
     entity_id_uuid: *const c_char,
     dist: f64,
     eps_json: *const c_char,
+) -> *mut c_char {
+    apply_offset_entity(
+        handle,
+        doc_json,
+        entity_id_uuid,
+        dist,
+        JoinStyle::default(),
+        eps_json,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_offset_entity_with_join(
+    handle: u64,
+    doc_json: *const c_char,
+    entity_id_uuid: *const c_char,
+    dist: f64,
+    join_json: *const c_char,
+    eps_json: *const c_char,
+) -> *mut c_char {
+    let join: JoinStyle = match parse_cstr(join_json, "join").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    apply_offset_entity(handle, doc_json, entity_id_uuid, dist, join, eps_json)
+}
+
+unsafe fn apply_offset_entity(
+    handle: u64,
+    doc_json: *const c_char,
+    entity_id_uuid: *const c_char,
+    dist: f64,
+    join: JoinStyle,
+    eps_json: *const c_char,
 ) -> *mut c_char {
     let entity_id = match parse_cstr(entity_id_uuid, "entity_id").and_then(|s| {
         Uuid::parse_str(&s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
@@ -1149,6 +1772,7 @@ pub unsafe extern "C" fn craftcad_history_apply_offset_entity(
         cmd.update(OffsetEntityInput {
             entity_id,
             dist,
+            join,
             eps,
         })?;
         let delta = cmd.commit()?;
@@ -1233,6 +1857,98 @@ pub unsafe extern "C" fn craftcad_history_apply_trim_entity_with_candidate_index
         Ok(())
     })
 }
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_extend_entity(
+    handle: u64,
+    doc_json: *const c_char,
+    target_id_uuid: *const c_char,
+    boundary_json: *const c_char,
+    pick_point_json: *const c_char,
+    eps_json: *const c_char,
+) -> *mut c_char {
+    craftcad_history_apply_extend_entity_with_candidate_index(
+        handle,
+        doc_json,
+        target_id_uuid,
+        boundary_json,
+        pick_point_json,
+        eps_json,
+        -1,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_history_apply_extend_entity_with_candidate_index(
+    handle: u64,
+    doc_json: *const c_char,
+    target_id_uuid: *const c_char,
+    boundary_json: *const c_char,
+    pick_point_json: *const c_char,
+    eps_json: *const c_char,
+    candidate_index: i32,
+) -> *mut c_char {
+    #[derive(serde::Deserialize)]
+    struct BoundaryIds {
+        ids: Vec<String>,
+    }
+
+    let target_id = match parse_cstr(target_id_uuid, "target_id").and_then(|s| {
+        Uuid::parse_str(&s).map_err(|_| Reason::from_code(ReasonCode::ModelReferenceNotFound))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let boundary: BoundaryIds = match parse_cstr(boundary_json, "boundary").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::EditInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let boundary_ids = {
+        let mut ids = Vec::with_capacity(boundary.ids.len());
+        for s in boundary.ids {
+            let id = match Uuid::parse_str(&s) {
+                Ok(v) => v,
+                Err(_) => return encode_err(Reason::from_code(ReasonCode::ModelReferenceNotFound)),
+            };
+            ids.push(id);
+        }
+        ids
+    };
+    let pick_point: Vec2 = match parse_cstr(pick_point_json, "pick_point").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let eps: EpsilonPolicy = match parse_cstr(eps_json, "eps").and_then(|s| {
+        serde_json::from_str(&s).map_err(|_| Reason::from_code(ReasonCode::GeomInvalidNumeric))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+
+    with_history_doc(handle, doc_json, |h, doc| {
+        let mut cmd = ExtendEntityCommand::new();
+        cmd.begin(&CommandContext::default())?;
+        cmd.update(ExtendEntityInput {
+            entity_id: target_id,
+            boundary_ids,
+            pick_point,
+            eps,
+            candidate_index: if candidate_index < 0 {
+                None
+            } else {
+                Some(candidate_index as usize)
+            },
+        })?;
+        let delta = cmd.commit()?;
+        delta.apply(doc)?;
+        h.push(delta);
+        Ok(())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn craftcad_history_undo(handle: u64, doc_json: *const c_char) -> *mut c_char {
     with_history_doc(handle, doc_json, |h, doc| h.undo(doc))
@@ -1276,6 +1992,143 @@ pub extern "C" fn craftcad_history_end_group(handle: u64) -> *mut c_char {
     encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation))
 }
 
+#[no_mangle]
+pub extern "C" fn craftcad_history_entries(handle: u64) -> *mut c_char {
+    let map = match histories().lock() {
+        Ok(m) => m,
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    };
+    match map.get(&handle) {
+        Some(h) => encode_ok(h.entries()),
+        None => encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn craftcad_history_nodes(handle: u64) -> *mut c_char {
+    let map = match histories().lock() {
+        Ok(m) => m,
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    };
+    match map.get(&handle) {
+        Some(h) => encode_ok(serde_json::json!({"current": h.current(), "nodes": h.nodes()})),
+        None => encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn craftcad_history_jump(
+    handle: u64,
+    doc_json: *const c_char,
+    node: u64,
+) -> *mut c_char {
+    with_history_doc(handle, doc_json, |h, doc| h.jump_to(node as usize, doc))
+}
+
+#[no_mangle]
+pub extern "C" fn craftcad_history_compare(
+    handle: u64,
+    doc_json: *const c_char,
+    node_a: u64,
+    node_b: u64,
+) -> *mut c_char {
+    let doc: Document = match parse_cstr(doc_json, "doc_json").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    }) {
+        Ok(d) => d,
+        Err(r) => return encode_err(r),
+    };
+    let map = match histories().lock() {
+        Ok(m) => m,
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    };
+    match map
+        .get(&handle)
+        .map(|h| h.compare(node_a as usize, node_b as usize, &doc))
+    {
+        Some(Ok(diff)) => encode_ok(diff),
+        Some(Err(r)) => encode_err(r),
+        None => encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    }
+}
+
+/// Opens a package and a new history handle holding its saved journal.
+/// A journal that cannot be restored leaves the handle empty and adds a
+/// `SERIALIZE_HISTORY_DROPPED` entry to `warnings`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_load_diycad_with_history_json(
+    path_utf8: *const c_char,
+) -> *mut c_char {
+    let path = match parse_cstr(path_utf8, "path") {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let package = match load_diycad_with_history(Path::new(&path)) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let mut warnings = package.warnings;
+    let (history, dropped) = History::restore(package.history);
+    warnings.extend(dropped);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    match histories().lock() {
+        Ok(mut m) => {
+            m.insert(id, history);
+        }
+        Err(_) => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+    }
+    encode_ok(serde_json::json!({
+        "manifest": package.manifest,
+        "document": package.document,
+        "history_handle": id,
+        "warnings": warnings
+    }))
+}
+
+/// Saves a package with the journal of history `handle`.
+#[no_mangle]
+pub unsafe extern "C" fn craftcad_save_diycad_with_history_json(
+    path_utf8: *const c_char,
+    manifest_json: *const c_char,
+    doc_json: *const c_char,
+    handle: u64,
+) -> *mut c_char {
+    let path = match parse_cstr(path_utf8, "path") {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let manifest: Manifest = match parse_cstr(manifest_json, "manifest_json").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let doc: Document = match parse_cstr(doc_json, "doc_json").and_then(|s| {
+        serde_json::from_str(&s)
+            .map_err(|_| Reason::from_code(ReasonCode::SerializePackageCorrupted))
+    }) {
+        Ok(v) => v,
+        Err(r) => return encode_err(r),
+    };
+    let journal = {
+        let map = match histories().lock() {
+            Ok(m) => m,
+            Err(_) => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+        };
+        match map.get(&handle).map(History::journal) {
+            Some(Ok(j)) => j,
+            Some(Err(r)) => return encode_err(r),
+            None => return encode_err(Reason::from_code(ReasonCode::CoreInvariantViolation)),
+        }
+    };
+    match save_diycad_with_history(Path::new(&path), &manifest, &doc, Some(&journal)) {
+        Ok(()) => encode_ok(serde_json::json!({})),
+        Err(r) => encode_err(r),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn craftcad_export_diagnostic_pack(
     doc_json: *const c_char,
@@ -1882,7 +2735,7 @@ pub unsafe extern "C" fn craftcad_export_preflight_check(project_path_utf8: *con
             0
         }
         Err(err) => {
-            set_last_error(serde_json::to_string(&err.report).unwrap_or_else(|_| err.reason_code));
+            set_last_error(serde_json::to_string(&err.report).unwrap_or(err.reason_code));
             10
         }
     }
@@ -19,6 +19,9 @@ fn sample_doc() -> Document {
             visible: true,
             locked: false,
             editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
         }],
         entities: vec![],
         parts: vec![],
@@ -28,6 +31,9 @@ fn sample_doc() -> Document {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     }
 }
 
@@ -80,3 +86,115 @@ fn history_apply_undo_redo_roundtrip() {
 
     craftcad_ffi_desktop::craftcad_history_free(h);
 }
+
+#[test]
+fn snap_candidates_resolve_endpoint_first() {
+    let mut doc = sample_doc();
+    doc.entities.push(craftcad_serialize::Entity {
+        id: Uuid::new_v4(),
+        layer_id: doc.layers[0].id,
+        geom: craftcad_serialize::Geom2D::Line {
+            a: craftcad_serialize::Vec2 { x: 0.0, y: 0.0 },
+            b: craftcad_serialize::Vec2 { x: 10.0, y: 0.0 },
+        },
+        style: serde_json::json!({}),
+        tags: vec![],
+        meta: Default::default(),
+    });
+    let doc_s = CString::new(serde_json::to_string(&doc).expect("doc json")).expect("cstring");
+    let query = CString::new("{\"cursor\":{\"x\":9.5,\"y\":0.2},\"radius\":2}").expect("cstring");
+    let eps = CString::new(
+        "{\"eq_dist\":1e-6,\"snap_dist\":1e-2,\"intersect_tol\":1e-6,\"area_tol\":1e-6}",
+    )
+    .expect("cstring");
+    let res = unsafe {
+        take(craftcad_ffi_desktop::craftcad_snap_candidates(
+            doc_s.as_ptr(),
+            query.as_ptr(),
+            eps.as_ptr(),
+        ))
+    };
+    let v: serde_json::Value = serde_json::from_str(&res).expect("envelope");
+    assert_eq!(v["ok"], true);
+    assert_eq!(v["data"]["snapped"]["kind"], "Endpoint");
+    assert_eq!(v["data"]["snapped"]["x"], 10.0);
+}
+
+#[test]
+fn snap_candidates_report_tracking_lines() {
+    let doc = sample_doc();
+    let doc_s = CString::new(serde_json::to_string(&doc).expect("doc json")).expect("cstring");
+    let query = CString::new(
+        "{\"cursor\":{\"x\":5.2,\"y\":0.1},\"radius\":1,\"from\":{\"x\":0,\"y\":0},\
+         \"angle_step_deg\":15,\"acquired\":[{\"pt\":{\"x\":5,\"y\":8}}]}",
+    )
+    .expect("cstring");
+    let eps = CString::new(
+        "{\"eq_dist\":1e-6,\"snap_dist\":1e-2,\"intersect_tol\":1e-6,\"area_tol\":1e-6}",
+    )
+    .expect("cstring");
+    let res = unsafe {
+        take(craftcad_ffi_desktop::craftcad_snap_candidates(
+            doc_s.as_ptr(),
+            query.as_ptr(),
+            eps.as_ptr(),
+        ))
+    };
+    let v: serde_json::Value = serde_json::from_str(&res).expect("envelope");
+    assert_eq!(v["ok"], true);
+    let tracking = &v["data"]["tracking"];
+    assert_eq!(tracking["snapped"]["kind"], "Angle");
+    assert_eq!(tracking["lines"][1]["kind"], "Vertical");
+}
+
+#[test]
+fn snap_index_is_built_once_and_snaps_to_the_rubber_band() {
+    let mut doc = sample_doc();
+    doc.entities.push(craftcad_serialize::Entity {
+        id: Uuid::new_v4(),
+        layer_id: doc.layers[0].id,
+        geom: craftcad_serialize::Geom2D::Line {
+            a: craftcad_serialize::Vec2 { x: 5.0, y: -10.0 },
+            b: craftcad_serialize::Vec2 { x: 5.0, y: 10.0 },
+        },
+        style: serde_json::json!({}),
+        tags: vec![],
+        meta: Default::default(),
+    });
+    let doc_s = CString::new(serde_json::to_string(&doc).expect("doc json")).expect("cstring");
+    let eps = CString::new(
+        "{\"eq_dist\":1e-6,\"snap_dist\":1e-2,\"intersect_tol\":1e-6,\"area_tol\":1e-6}",
+    )
+    .expect("cstring");
+    let built = unsafe {
+        take(craftcad_ffi_desktop::craftcad_snap_index_new(
+            doc_s.as_ptr(),
+            eps.as_ptr(),
+            8.0,
+        ))
+    };
+    let v: serde_json::Value = serde_json::from_str(&built).expect("envelope");
+    let h = v["data"]["handle"].as_u64().expect("handle");
+    let query = |q: &str| {
+        let q = CString::new(q).expect("cstring");
+        let res = unsafe {
+            take(craftcad_ffi_desktop::craftcad_snap_index_query(
+                h,
+                q.as_ptr(),
+                eps.as_ptr(),
+            ))
+        };
+        serde_json::from_str::<serde_json::Value>(&res).expect("envelope")
+    };
+
+    let v = query("{\"cursor\":{\"x\":5.3,\"y\":3},\"radius\":1}");
+    assert_eq!(v["data"]["snapped"]["kind"], "Nearest");
+    // Drawing from the origin, the line being drawn crosses x = 5 near the cursor.
+    let v = query("{\"cursor\":{\"x\":5.3,\"y\":3},\"radius\":1,\"from\":{\"x\":0,\"y\":0}}");
+    assert_eq!(v["data"]["snapped"]["kind"], "Intersection");
+    assert_eq!(v["data"]["snapped"]["x"], 5.0);
+
+    craftcad_ffi_desktop::craftcad_snap_index_free(h);
+    let v = query("{\"cursor\":{\"x\":5.3,\"y\":3},\"radius\":1}");
+    assert_eq!(v["ok"], false);
+}
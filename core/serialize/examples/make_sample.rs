@@ -18,6 +18,9 @@ fn main() {
             visible: true,
             locked: false,
             editable: true,
+            color: None,
+            linetype: "CONTINUOUS".into(),
+            print: true,
         }],
         entities: vec![Entity {
             id: Uuid::new_v4(),
@@ -37,6 +40,9 @@ fn main() {
         used_presets: vec![],
         used_templates: vec![],
         wizard_runs: vec![],
+        selection_sets: vec![],
+        blocks: vec![],
+        block_instances: vec![],
     };
     let manifest = create_manifest("CraftCAD", "0.1.0");
     save_diycad(Path::new(&out), &manifest, &doc).expect("save sample");
@@ -16,6 +16,8 @@ use zip::{write::FileOptions, ZipArchive, ZipWriter};
 const MANIFEST_PATH: &str = "manifest.json";
 const DOC_PATH: &str = "data/document.json";
 const ASSETS_DIR: &str = "assets/";
+/// Undo/redo journal stored next to the document.
+pub const HISTORY_PATH: &str = "data/history.json";
 
 pub type Result<T> = std::result::Result<T, Reason>;
 
@@ -41,7 +43,9 @@ pub enum ReasonCode {
     SerializeSchemaValidationFailed,
     SerializePackageCorrupted,
     SerializeUnsupportedSchemaVersion,
+    SerializeHistoryDropped,
     ModelReferenceNotFound,
+    ModelConversionLossy,
     GeomInvalidNumeric,
     GeomNoIntersection,
     GeomIntersectionAmbiguous,
@@ -54,17 +58,32 @@ pub enum ReasonCode {
     GeomOffsetSelfIntersection,
     GeomOffsetNotSupported,
     GeomTrimNoIntersection,
+    GeomBooleanEmptyResult,
     EditAmbiguousTarget,
     EditTrimAmbiguousCandidate,
     EditNoSelection,
+    EditSelectionSetNameInvalid,
+    EditBlockNameInvalid,
+    EditBlockCycle,
+    EditLayerNameInvalid,
+    EditLayerInUse,
+    EditLayerStyleInvalid,
     EditTargetLockedOrHidden,
     EditInvalidNumeric,
     EditTransformWouldDegenerate,
     DrawInvalidNumeric,
     DrawConstraintConflict,
+    DrawConstraintNotConverged,
+    DrawConstraintTargetInvalid,
+    DrawConstraintRedundant,
+    DrawConstraintUnderConstrained,
     DrawInsufficientInput,
+    DrawInputSyntax,
+    DrawInputUnknownUnit,
+    DrawInputUnknownVariable,
     EditFilletRadiusTooLarge,
     EditChamferDistanceTooLarge,
+    EditFilletUnsupportedGeometry,
     EditMirrorAxisInvalid,
     EditPatternInvalidParams,
     EditAmbiguousCandidate,
@@ -85,6 +104,7 @@ pub enum ReasonCode {
     NestGrainConstraintBlocksFit,
     NestNoFeasiblePositionWithMarginAndKerf,
     NestNoGoZoneBlocksFit,
+    NestNoSheetForMaterial,
     NestStoppedByTimeLimit,
     NestStoppedByIterationLimit,
     NestInternalInfeasible,
@@ -96,7 +116,9 @@ impl ReasonCode {
             Self::SerializeSchemaValidationFailed => "SERIALIZE_SCHEMA_VALIDATION_FAILED",
             Self::SerializePackageCorrupted => "SERIALIZE_PACKAGE_CORRUPTED",
             Self::SerializeUnsupportedSchemaVersion => "SERIALIZE_UNSUPPORTED_SCHEMA_VERSION",
+            Self::SerializeHistoryDropped => "SERIALIZE_HISTORY_DROPPED",
             Self::ModelReferenceNotFound => "MODEL_REFERENCE_NOT_FOUND",
+            Self::ModelConversionLossy => "MODEL_CONVERSION_LOSSY",
             Self::GeomInvalidNumeric => "GEOM_INVALID_NUMERIC",
             Self::GeomNoIntersection => "GEOM_NO_INTERSECTION",
             Self::GeomIntersectionAmbiguous => "GEOM_INTERSECTION_AMBIGUOUS",
@@ -109,17 +131,32 @@ impl ReasonCode {
             Self::GeomOffsetSelfIntersection => "GEOM_OFFSET_SELF_INTERSECTION",
             Self::GeomOffsetNotSupported => "GEOM_OFFSET_NOT_SUPPORTED",
             Self::GeomTrimNoIntersection => "GEOM_TRIM_NO_INTERSECTION",
+            Self::GeomBooleanEmptyResult => "GEOM_BOOLEAN_EMPTY_RESULT",
             Self::EditAmbiguousTarget => "EDIT_AMBIGUOUS_TARGET",
             Self::EditTrimAmbiguousCandidate => "EDIT_TRIM_AMBIGUOUS_CANDIDATE",
             Self::EditNoSelection => "EDIT_NO_SELECTION",
+            Self::EditSelectionSetNameInvalid => "EDIT_SELECTION_SET_NAME_INVALID",
+            Self::EditBlockNameInvalid => "EDIT_BLOCK_NAME_INVALID",
+            Self::EditBlockCycle => "EDIT_BLOCK_CYCLE",
+            Self::EditLayerNameInvalid => "EDIT_LAYER_NAME_INVALID",
+            Self::EditLayerInUse => "EDIT_LAYER_IN_USE",
+            Self::EditLayerStyleInvalid => "EDIT_LAYER_STYLE_INVALID",
             Self::EditTargetLockedOrHidden => "EDIT_TARGET_LOCKED_OR_HIDDEN",
             Self::EditInvalidNumeric => "EDIT_INVALID_NUMERIC",
             Self::EditTransformWouldDegenerate => "EDIT_TRANSFORM_WOULD_DEGENERATE",
             Self::DrawInvalidNumeric => "DRAW_INVALID_NUMERIC",
             Self::DrawConstraintConflict => "DRAW_CONSTRAINT_CONFLICT",
+            Self::DrawConstraintNotConverged => "DRAW_CONSTRAINT_NOT_CONVERGED",
+            Self::DrawConstraintTargetInvalid => "DRAW_CONSTRAINT_TARGET_INVALID",
+            Self::DrawConstraintRedundant => "DRAW_CONSTRAINT_REDUNDANT",
+            Self::DrawConstraintUnderConstrained => "DRAW_CONSTRAINT_UNDER_CONSTRAINED",
             Self::DrawInsufficientInput => "DRAW_INSUFFICIENT_INPUT",
+            Self::DrawInputSyntax => "DRAW_INPUT_SYNTAX",
+            Self::DrawInputUnknownUnit => "DRAW_INPUT_UNKNOWN_UNIT",
+            Self::DrawInputUnknownVariable => "DRAW_INPUT_UNKNOWN_VARIABLE",
             Self::EditFilletRadiusTooLarge => "EDIT_FILLET_RADIUS_TOO_LARGE",
             Self::EditChamferDistanceTooLarge => "EDIT_CHAMFER_DISTANCE_TOO_LARGE",
+            Self::EditFilletUnsupportedGeometry => "EDIT_FILLET_UNSUPPORTED_GEOMETRY",
             Self::EditMirrorAxisInvalid => "EDIT_MIRROR_AXIS_INVALID",
             Self::EditPatternInvalidParams => "EDIT_PATTERN_INVALID_PARAMS",
             Self::EditAmbiguousCandidate => "EDIT_AMBIGUOUS_CANDIDATE",
@@ -142,6 +179,7 @@ impl ReasonCode {
                 "NEST_NO_FEASIBLE_POSITION_WITH_MARGIN_AND_KERF"
             }
             Self::NestNoGoZoneBlocksFit => "NEST_NO_GO_ZONE_BLOCKS_FIT",
+            Self::NestNoSheetForMaterial => "NEST_NO_SHEET_FOR_MATERIAL",
             Self::NestStoppedByTimeLimit => "NEST_STOPPED_BY_TIME_LIMIT",
             Self::NestStoppedByIterationLimit => "NEST_STOPPED_BY_ITERATION_LIMIT",
             Self::NestInternalInfeasible => "NEST_INTERNAL_INFEASIBLE",
@@ -164,6 +202,12 @@ pub struct Manifest {
     pub assets_path: String,
     #[serde(default)]
     pub settings_digest: Option<String>,
+    /// Set when the package carries an undo/redo journal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_path: Option<String>,
+    /// SHA-256 of the document.json the journal was recorded against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_doc_digest: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -217,6 +261,64 @@ pub struct WizardRunRecord {
     pub created_at_unix_ms: i64,
 }
 
+/// Named selection saved with the document.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SelectionSet {
+    pub name: String,
+    pub entity_ids: Vec<Uuid>,
+}
+
+fn unit_scale() -> Vec2 {
+    Vec2 { x: 1.0, y: 1.0 }
+}
+
+/// Placement of a block: mirror (`x -> -x`) when `mirror` is set, scale,
+/// rotate, then move the block's base point to `translate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTransform {
+    pub translate: Vec2,
+    #[serde(default)]
+    pub rotate_rad: f64,
+    #[serde(default = "unit_scale")]
+    pub scale: Vec2,
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+impl Default for BlockTransform {
+    fn default() -> Self {
+        Self {
+            translate: Vec2 { x: 0.0, y: 0.0 },
+            rotate_rad: 0.0,
+            scale: unit_scale(),
+            mirror: false,
+        }
+    }
+}
+
+/// Named placement of a block definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockInstance {
+    pub id: Uuid,
+    pub block_id: Uuid,
+    #[serde(default)]
+    pub name: String,
+    pub layer_id: Uuid,
+    pub transform: BlockTransform,
+}
+
+/// Reusable group of entities, stored in block coordinates. `instances`
+/// places other blocks inside this one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockDef {
+    pub id: Uuid,
+    pub name: String,
+    pub base_point: Vec2,
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub instances: Vec<BlockInstance>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectSettings {
     #[serde(default)]
@@ -242,7 +344,26 @@ pub struct Document {
     pub used_templates: Vec<UsedTemplateRef>,
     #[serde(default)]
     pub wizard_runs: Vec<WizardRunRecord>,
+    #[serde(default)]
+    pub selection_sets: Vec<SelectionSet>,
+    #[serde(default)]
+    pub blocks: Vec<BlockDef>,
+    #[serde(default)]
+    pub block_instances: Vec<BlockInstance>,
+}
+fn continuous() -> String {
+    "CONTINUOUS".into()
 }
+fn is_continuous(linetype: &str) -> bool {
+    linetype == "CONTINUOUS"
+}
+fn yes() -> bool {
+    true
+}
+fn is_yes(v: &bool) -> bool {
+    *v
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layer {
     pub id: Uuid,
@@ -250,6 +371,15 @@ pub struct Layer {
     pub visible: bool,
     pub locked: bool,
     pub editable: bool,
+    /// `#RRGGBB`; exporters fall back to black when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Line type name as in `mapping_rules.json` (`CONTINUOUS`, `DASHED`, `DOTTED`, ...).
+    #[serde(default = "continuous", skip_serializing_if = "is_continuous")]
+    pub linetype: String,
+    /// Whether exporters write the layer's geometry (DXF plot flag).
+    #[serde(default = "yes", skip_serializing_if = "is_yes")]
+    pub print: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vec2 {
@@ -278,6 +408,29 @@ pub enum Geom2D {
         pts: Vec<Vec2>,
         closed: bool,
     },
+    Ellipse {
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+    },
+    /// `start_angle`/`end_angle` are parametric angles in the ellipse frame
+    /// (`c + R(rot) * (rx cos t, ry sin t)`), not polar angles.
+    EllipticalArc {
+        c: Vec2,
+        rx: f64,
+        ry: f64,
+        rot: f64,
+        start_angle: f64,
+        end_angle: f64,
+        ccw: bool,
+    },
+    CubicBezier {
+        a: Vec2,
+        c1: Vec2,
+        c2: Vec2,
+        b: Vec2,
+    },
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
@@ -323,6 +476,9 @@ pub struct NestConstraints {
     pub no_go_zones: Vec<NoGoZone>,
     #[serde(default)]
     pub grain_policy: GrainPolicy,
+    /// Only produce layouts a panel saw can cut with edge-to-edge cuts.
+    #[serde(default)]
+    pub guillotine: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "PascalCase")]
@@ -348,6 +504,14 @@ pub struct NestObjective {
     pub w_utilization: f64,
     pub w_sheet_count: f64,
     pub w_cut_count: f64,
+    /// Score cost of each part placed across the grain under
+    /// `GrainPolicy::Prefer`.
+    #[serde(default = "default_w_grain_misaligned")]
+    pub w_grain_misaligned: f64,
+}
+
+fn default_w_grain_misaligned() -> f64 {
+    1.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -379,8 +543,25 @@ pub struct BBox {
 pub struct NestMetrics {
     pub utilization_per_sheet: Vec<f64>,
     pub sheet_count_used: u32,
+    /// Exact number of saw cuts in guillotine mode, else `placements * 4`.
     pub cut_count_estimate: u32,
+    /// Total length of the saw cuts in `cut_sequence`; guillotine mode only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guillotine_cut_length: Option<f64>,
     pub score: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub per_material: Vec<MaterialMetrics>,
+}
+
+/// Metrics for the sheets of one material, ordered by `material_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialMetrics {
+    pub material_id: Uuid,
+    pub sheet_count_used: u32,
+    /// Part area over the area of the used sheets of this material.
+    pub utilization: f64,
+    pub placed_count: u32,
+    pub unplaced_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -403,6 +584,29 @@ pub struct NestResultV1 {
     pub placements: Vec<Placement>,
     pub metrics: NestMetrics,
     pub per_part_status: Vec<PartPlacementStatus>,
+    /// Saw cuts in the order they are made; guillotine mode only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cut_sequence: Vec<Cut>,
+}
+
+/// `Rip` runs along the sheet's x axis (with the grain), `Crosscut`
+/// across it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum CutKind {
+    Rip,
+    Crosscut,
+}
+
+/// One straight saw cut, edge to edge across the panel it splits. `kerf`
+/// is the blade width.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cut {
+    pub sheet_instance_index: u32,
+    pub kind: CutKind,
+    pub from: Vec2,
+    pub to: Vec2,
+    pub kerf: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -518,10 +722,22 @@ pub fn normalize_document_json(mut val: serde_json::Value) -> serde_json::Value
         if !m.contains_key("wizard_runs") {
             m.insert("wizard_runs".to_string(), serde_json::json!([]));
         }
+        if !m.contains_key("selection_sets") {
+            m.insert("selection_sets".to_string(), serde_json::json!([]));
+        }
+        if !m.contains_key("blocks") {
+            m.insert("blocks".to_string(), serde_json::json!([]));
+        }
+        if !m.contains_key("block_instances") {
+            m.insert("block_instances".to_string(), serde_json::json!([]));
+        }
 
+        // v2 added the asset fields defaulted above; v3 added the Ellipse,
+        // EllipticalArc and CubicBezier entities, which older documents
+        // cannot contain, so both upgrades are a version bump only.
         if let Some(sv) = m.get("schema_version").and_then(|v| v.as_u64()) {
-            if sv == 1 {
-                m.insert("schema_version".to_string(), serde_json::json!(2));
+            if sv == 1 || sv == 2 {
+                m.insert("schema_version".to_string(), serde_json::json!(3));
             }
         }
 
@@ -577,17 +793,37 @@ pub fn create_manifest(app_name: &str, app_version: &str) -> Manifest {
         document_path: DOC_PATH.to_string(),
         assets_path: ASSETS_DIR.to_string(),
         settings_digest: None,
+        history_path: None,
+        history_doc_digest: None,
     }
 }
 
 pub fn save_diycad(path: &Path, manifest: &Manifest, doc: &Document) -> Result<()> {
+    save_diycad_with_history(path, manifest, doc, None)
+}
+
+/// Saves the package with an optional undo/redo journal. The journal is
+/// opaque here; `craftcad_commands::History` produces and reads it.
+pub fn save_diycad_with_history(
+    path: &Path,
+    manifest: &Manifest,
+    doc: &Document,
+    history: Option<&serde_json::Value>,
+) -> Result<()> {
+    let d_json = serde_json::to_vec_pretty(doc).map_err(|e| {
+        Reason::from_code(ReasonCode::SerializePackageCorrupted)
+            .with_debug("doc_serialize", e.to_string())
+    })?;
+    let mut manifest = manifest.clone();
+    manifest.history_path = history.map(|_| HISTORY_PATH.to_string());
+    manifest.history_doc_digest = history.map(|_| sha256_hex(&d_json));
     let f = std::fs::File::create(path).map_err(|e| {
         Reason::from_code(ReasonCode::SerializePackageCorrupted).with_debug("io", e.to_string())
     })?;
     let mut zip = ZipWriter::new(f);
     let opt = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
 
-    let m_json = serde_json::to_vec_pretty(manifest).map_err(|e| {
+    let m_json = serde_json::to_vec_pretty(&manifest).map_err(|e| {
         Reason::from_code(ReasonCode::SerializePackageCorrupted)
             .with_debug("manifest_serialize", e.to_string())
     })?;
@@ -600,10 +836,6 @@ pub fn save_diycad(path: &Path, manifest: &Manifest, doc: &Document) -> Result<(
     })?;
 
     zip.add_directory("data/", opt).ok();
-    let d_json = serde_json::to_vec_pretty(doc).map_err(|e| {
-        Reason::from_code(ReasonCode::SerializePackageCorrupted)
-            .with_debug("doc_serialize", e.to_string())
-    })?;
     zip.start_file(DOC_PATH, opt).map_err(|e| {
         Reason::from_code(ReasonCode::SerializePackageCorrupted).with_debug("zip", e.to_string())
     })?;
@@ -612,6 +844,21 @@ pub fn save_diycad(path: &Path, manifest: &Manifest, doc: &Document) -> Result<(
             .with_debug("zip_write", e.to_string())
     })?;
 
+    if let Some(history) = history {
+        let h_json = serde_json::to_vec(history).map_err(|e| {
+            Reason::from_code(ReasonCode::SerializePackageCorrupted)
+                .with_debug("history_serialize", e.to_string())
+        })?;
+        zip.start_file(HISTORY_PATH, opt).map_err(|e| {
+            Reason::from_code(ReasonCode::SerializePackageCorrupted)
+                .with_debug("zip", e.to_string())
+        })?;
+        zip.write_all(&h_json).map_err(|e| {
+            Reason::from_code(ReasonCode::SerializePackageCorrupted)
+                .with_debug("zip_write", e.to_string())
+        })?;
+    }
+
     zip.add_directory(ASSETS_DIR, opt).ok();
 
     zip.finish().map_err(|e| {
@@ -622,6 +869,53 @@ pub fn save_diycad(path: &Path, manifest: &Manifest, doc: &Document) -> Result<(
 }
 
 pub fn load_diycad(path: &Path) -> Result<(Manifest, Document)> {
+    let p = load_diycad_with_history(path)?;
+    Ok((p.manifest, p.document))
+}
+
+/// A package as loaded by [`load_diycad_with_history`].
+#[derive(Debug)]
+pub struct LoadedPackage {
+    pub manifest: Manifest,
+    pub document: Document,
+    /// The undo/redo journal, when one was saved and is usable.
+    pub history: Option<serde_json::Value>,
+    /// `SERIALIZE_HISTORY_DROPPED` when a saved journal was left out.
+    pub warnings: Vec<Reason>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut h = Sha256::new();
+    h.update(bytes);
+    hex::encode(h.finalize())
+}
+
+fn history_dropped(key: &str, detail: String) -> Reason {
+    Reason::from_code(ReasonCode::SerializeHistoryDropped).with_debug(key, detail)
+}
+
+/// Reads the journal at `h_path`. Undo history is optional, so any problem
+/// with it, or a journal recorded against other document bytes, drops it
+/// with a warning instead of failing the load.
+fn read_history(
+    zip: &mut ZipArchive<std::fs::File>,
+    manifest: &Manifest,
+    h_path: &str,
+    doc_raw: &str,
+) -> Result<serde_json::Value> {
+    let digest = sha256_hex(doc_raw.as_bytes());
+    if manifest.history_doc_digest.as_deref() != Some(digest.as_str()) {
+        return Err(history_dropped("document_digest", digest));
+    }
+    let hf = zip
+        .by_name(h_path)
+        .map_err(|e| history_dropped("missing_history", e.to_string()))?;
+    serde_json::from_reader(hf).map_err(|e| history_dropped("history_read", e.to_string()))
+}
+
+/// Loads the package and its undo/redo journal, if one was saved. A journal
+/// that is missing, unreadable or stale is dropped with a warning.
+pub fn load_diycad_with_history(path: &Path) -> Result<LoadedPackage> {
     let f = std::fs::File::open(path).map_err(|e| {
         Reason::from_code(ReasonCode::SerializePackageCorrupted).with_debug("io", e.to_string())
     })?;
@@ -673,14 +967,28 @@ pub fn load_diycad(path: &Path) -> Result<(Manifest, Document)> {
             .with_debug("doc_decode", e.to_string())
     })?;
 
-    Ok((manifest, doc))
+    let mut warnings = vec![];
+    let history = match manifest.history_path.clone() {
+        Some(h_path) => match read_history(&mut zip, &manifest, &h_path, &d_raw) {
+            Ok(v) => Some(v),
+            Err(r) => {
+                warnings.push(r);
+                None
+            }
+        },
+        None => None,
+    };
+
+    Ok(LoadedPackage {
+        manifest,
+        document: doc,
+        history,
+        warnings,
+    })
 }
 
 pub fn digest_settings_json(settings_json: &serde_json::Value) -> String {
-    let bytes = serde_json::to_vec(settings_json).unwrap_or_default();
-    let mut h = Sha256::new();
-    h.update(bytes);
-    hex::encode(h.finalize())
+    sha256_hex(&serde_json::to_vec(settings_json).unwrap_or_default())
 }
 
 trait ReasonExt {
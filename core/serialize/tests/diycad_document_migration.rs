@@ -1,8 +1,8 @@
 #[test]
-fn migrate_v1_document_to_v2_adds_asset_fields() {
+fn migrate_v1_document_to_latest_adds_asset_fields() {
     let s = std::fs::read_to_string("../../tests/golden/diycad_document_v1.json").unwrap();
     let v = craftcad_serialize::validate_document_json_str(&s).unwrap();
-    assert_eq!(v.get("schema_version").and_then(|x| x.as_u64()), Some(2));
+    assert_eq!(v.get("schema_version").and_then(|x| x.as_u64()), Some(3));
     assert_eq!(v.get("used_presets").unwrap().as_array().unwrap().len(), 0);
     assert_eq!(
         v.get("used_templates").unwrap().as_array().unwrap().len(),
@@ -32,5 +32,5 @@ fn normalize_document_json_adds_defaults() {
     assert!(v.get("used_presets").is_some());
     assert!(v.get("used_templates").is_some());
     assert!(v.get("wizard_runs").is_some());
-    assert_eq!(v.get("schema_version").and_then(|x| x.as_u64()), Some(2));
+    assert_eq!(v.get("schema_version").and_then(|x| x.as_u64()), Some(3));
 }
@@ -47,3 +47,53 @@ fn old_doc_without_materials_is_normalized() {
     assert!(val.get("materials").is_some());
     assert_eq!(val.get("materials").unwrap().as_array().unwrap().len(), 0);
 }
+
+#[test]
+fn document_schema_accepts_curve_entities_and_rejects_bad_radius() {
+    let entity = |geom: serde_json::Value| {
+        serde_json::json!({
+          "id": "00000000-0000-4000-8000-000000000003",
+          "layer_id": "00000000-0000-4000-8000-000000000002",
+          "geom": geom,
+          "style": {},
+          "tags": [],
+          "meta": {}
+        })
+    };
+    let doc = |entities: Vec<serde_json::Value>| {
+        serde_json::json!({
+          "schema_version": 2,
+          "id": "00000000-0000-4000-8000-000000000001",
+          "units": "mm",
+          "layers": [],
+          "entities": entities,
+          "parts": [],
+          "jobs": []
+        })
+        .to_string()
+    };
+    let p = |x: f64, y: f64| serde_json::json!({"x": x, "y": y});
+    let ok = doc(vec![
+        entity(serde_json::json!({"type":"Ellipse","c":p(0.0,0.0),"rx":5.0,"ry":2.0,"rot":0.3})),
+        entity(serde_json::json!({
+          "type":"EllipticalArc","c":p(0.0,0.0),"rx":5.0,"ry":2.0,"rot":0.0,
+          "start_angle":0.0,"end_angle":1.5,"ccw":true
+        })),
+        entity(serde_json::json!({
+          "type":"CubicBezier","a":p(0.0,0.0),"c1":p(1.0,2.0),"c2":p(3.0,2.0),"b":p(4.0,0.0)
+        })),
+    ]);
+    let val = validate_document_json_str(&ok).expect("curves should validate");
+    assert_eq!(val["schema_version"], serde_json::json!(3));
+    let decoded: craftcad_serialize::Document = serde_json::from_value(val).expect("decode");
+    assert!(matches!(
+        decoded.entities[2].geom,
+        craftcad_serialize::Geom2D::CubicBezier { .. }
+    ));
+
+    let bad = doc(vec![entity(
+        serde_json::json!({"type":"Ellipse","c":p(0.0,0.0),"rx":0.0,"ry":2.0,"rot":0.0}),
+    )]);
+    let err = validate_document_json_str(&bad).expect_err("zero radius");
+    assert_eq!(err.code, "SERIALIZE_SCHEMA_VALIDATION_FAILED");
+}